@@ -4,6 +4,10 @@ from PyInstaller.utils.hooks import collect_all, collect_dynamic_libs
 datas = [('noises_cleanup_marker.txt', '.')]
 binaries = []
 hiddenimports = ['transformers', 'diffusers', 'uvicorn', 'fastapi', 'torchsde', 'psutil', 'acestep']
+# uvicorn picks its WebSocket implementation by name at startup (/ws/...).
+hiddenimports += ['websockets', 'uvicorn.protocols.websockets.websockets_impl']
+# main.py imports grpc_server (and grpcio) only for --grpc-port.
+hiddenimports += ['grpc_server', 'grpc']
 
 # ---------------------------------------------------------------------------
 # PyTorch is NOT bundled.  It is downloaded at runtime by cuda_setup.py
@@ -1,5 +1,6 @@
 import os
 import sys
+import tempfile
 from pathlib import Path
 import torch
 
@@ -13,9 +14,25 @@ if torch.cuda.is_available():
     vram = torch.cuda.get_device_properties(0).total_memory / 1024**3
     print(f"[Config] GPU: {gpu}  |  VRAM: {vram:.1f} GB")
 
-DEVICE = "cuda" if torch.cuda.is_available() else "cpu"
+# The app passes the user's device choice (cuda:0, cuda:1, cpu, mps) in
+# NOISES_DEVICE; without it, use the first GPU if there is one.
+DEVICE = os.environ.get("NOISES_DEVICE") or ("cuda" if torch.cuda.is_available() else "cpu")
+if DEVICE.startswith("cuda") and not torch.cuda.is_available():
+    print(f"[Config] {DEVICE} requested but CUDA is not available, falling back to CPU")
+    DEVICE = "cpu"
+elif DEVICE == "mps" and not torch.backends.mps.is_available():
+    print("[Config] mps requested but not available, falling back to CPU")
+    DEVICE = "cpu"
+if DEVICE.startswith("cuda:"):
+    torch.cuda.set_device(DEVICE)
 print(f"[Config] Device: {DEVICE}")
 
+# Set by the app's low-VRAM mode: keep only the part of a model that's running
+# on the GPU. Slower, but fits cards around 6 GB.
+LOW_VRAM = os.environ.get("NOISES_LOW_VRAM") == "1"
+if LOW_VRAM:
+    print("[Config] Low-VRAM mode")
+
 # ---------------------------------------------------------------------------
 # Paths — Use ~/Music/Noises so we never write inside the app bundle
 # ---------------------------------------------------------------------------
@@ -31,6 +48,11 @@ ONESHOTS_DIR = SAMPLES_DIR / "oneshots"
 LOOPS_DIR.mkdir(parents=True, exist_ok=True)
 ONESHOTS_DIR.mkdir(parents=True, exist_ok=True)
 
+# Reference clips the app uploads (/upload/reference) for a later generate call
+# to condition on. They're scratch copies, so they live in the temp folder
+# rather than the library.
+REFERENCES_DIR = Path(tempfile.gettempdir()) / "noises_references"
+
 # Audio Settings
 # Note: Each model uses its own native sample rate internally.
 # ACE-Step outputs at 48kHz stereo, Stable Audio at 44.1kHz.
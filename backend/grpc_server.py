@@ -0,0 +1,157 @@
+"""
+Optional gRPC service for generations, for an app on another machine.
+
+One server-streaming method, /noises.Generator/Generate: the request is the
+same JSON /generate takes, and the reply streams the sampling steps and then
+every output as raw audio in chunks, so the app doesn't have to fetch the
+files afterwards. The messages are small enough to encode by hand, which
+keeps grpcio the only dependency (no generated code, no protobuf package);
+src-tauri/src/grpc.rs has them as a .proto.
+
+Started by main.py when --grpc-port is given and grpcio is installed.
+"""
+
+import json
+import queue
+import threading
+from concurrent import futures
+
+# Small enough to pass every receiver's default message limit (4 MiB).
+FRAME_BYTES = 1024 * 1024
+
+# More than one waiting call only queues up behind the generation lock; the
+# app hears RESOURCE_EXHAUSTED past this and retries later.
+MAX_CONCURRENT_CALLS = 4
+
+
+def _varint(n):
+    out = bytearray()
+    while True:
+        low, n = n & 0x7F, n >> 7
+        if n:
+            out.append(low | 0x80)
+        else:
+            out.append(low)
+            return bytes(out)
+
+
+def _int_field(number, value):
+    return _varint(number << 3) + _varint(int(value))
+
+
+def _bytes_field(number, data):
+    return _varint(number << 3 | 2) + _varint(len(data)) + data
+
+
+def progress_message(step, total):
+    return _bytes_field(1, _int_field(1, step) + _int_field(2, total))
+
+
+def frame_message(index, file_name, data, last):
+    frame = (
+        _int_field(1, index)
+        + _bytes_field(2, file_name.encode())
+        + _bytes_field(3, data)
+        + _int_field(4, last)
+    )
+    return _bytes_field(2, frame)
+
+
+def _read_varint(data, pos):
+    value = shift = 0
+    while True:
+        if pos >= len(data):
+            raise ValueError("truncated message")
+        byte = data[pos]
+        pos += 1
+        value |= (byte & 0x7F) << shift
+        shift += 7
+        if not byte & 0x80:
+            return value, pos
+
+
+def config_from_request(data):
+    """The config_json of a GenerateRequest, skipping fields it doesn't know."""
+    pos = 0
+    config = "{}"
+    while pos < len(data):
+        key, pos = _read_varint(data, pos)
+        number, wire = key >> 3, key & 7
+        if wire == 0:
+            _, pos = _read_varint(data, pos)
+        elif wire == 2:
+            length, pos = _read_varint(data, pos)
+            if pos + length > len(data):
+                raise ValueError("truncated message")
+            if number == 1:
+                config = data[pos:pos + length].decode()
+            pos += length
+        elif wire == 1:
+            pos += 8
+        elif wire == 5:
+            pos += 4
+        else:
+            raise ValueError(f"unsupported wire type {wire}")
+    return json.loads(config)
+
+
+def _frames(index, path):
+    name = path.rsplit("/", 1)[-1].rsplit("\\", 1)[-1]
+    with open(path, "rb") as f:
+        chunk = f.read(FRAME_BYTES)
+        while True:
+            following = f.read(FRAME_BYTES)
+            yield frame_message(index, name, chunk, not following)
+            if not following:
+                return
+            chunk = following
+
+
+def serve(host, port, generate):
+    """
+    Starts the server on its own threads. `generate(config, on_progress)` runs
+    a generation, calling on_progress(step, total) as it goes, and returns the
+    output paths; it raises ValueError for a request it won't run.
+    """
+    import grpc
+
+    def run(request, context):
+        try:
+            config = config_from_request(request)
+        except ValueError as e:
+            context.abort(grpc.StatusCode.INVALID_ARGUMENT, f"Invalid request: {e}")
+        events = queue.Queue()
+
+        def work():
+            try:
+                paths = generate(config, lambda step, total: events.put(("progress", step, total)))
+                events.put(("done", paths))
+            except Exception as e:
+                events.put(("failed", e))
+
+        threading.Thread(target=work, daemon=True).start()
+        while True:
+            event = events.get()
+            if event[0] == "progress":
+                yield progress_message(event[1], event[2])
+            elif event[0] == "done":
+                for index, path in enumerate(event[1]):
+                    yield from _frames(index, path)
+                return
+            else:
+                error = event[1]
+                code = grpc.StatusCode.INVALID_ARGUMENT if isinstance(error, ValueError) else grpc.StatusCode.INTERNAL
+                context.abort(code, str(error))
+
+    handler = grpc.method_handlers_generic_handler("noises.Generator", {
+        "Generate": grpc.unary_stream_rpc_method_handler(run),
+    })
+    server = grpc.server(
+        futures.ThreadPoolExecutor(max_workers=MAX_CONCURRENT_CALLS),
+        handlers=[handler],
+        maximum_concurrent_rpcs=MAX_CONCURRENT_CALLS,
+    )
+    server.add_insecure_port(f"{host}:{port}")
+    server.start()
+    print(f"gRPC service on {host}:{port}")
+    return server
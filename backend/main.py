@@ -1,4 +1,5 @@
 import sys
+import asyncio
 import multiprocessing
 
 if __name__ == "__main__":
@@ -12,7 +13,9 @@ import threading
 import argparse
 import psutil
 import shutil
+import uuid
 from contextlib import asynccontextmanager
+from pathlib import Path
 
 # === CUDA BOOTSTRAP =========================================================
 # Must run BEFORE any `import torch` anywhere in the app.
@@ -36,6 +39,11 @@ if "--setup-torch" in sys.argv:
         print(f"Setup failed: {e}", file=sys.stderr)
         sys.exit(1)
 
+# --device cpu is how the app says it found no usable GPU. It has to be in
+# the environment before config.py picks the device on import.
+if "--device" in sys.argv[:-1]:
+    os.environ["NOISES_DEVICE"] = sys.argv[sys.argv.index("--device") + 1]
+
 load_torch()
 # =============================================================================
 
@@ -70,7 +78,8 @@ def _clean_old_mei_dirs():
         print(f"Cleanup warning: {e}")
 
 import uvicorn
-from fastapi import FastAPI, HTTPException
+from fastapi import FastAPI, HTTPException, UploadFile, File, WebSocket, WebSocketDisconnect
+from fastapi.responses import StreamingResponse
 from fastapi.middleware.cors import CORSMiddleware
 from pydantic import BaseModel
 
@@ -96,7 +105,7 @@ from models.acestep import ACEStepModel
 from models.stable_audio import StableAudioOpenModel
 from audio.postprocess import normalize_audio, fade_audio
 from audio.utils import save_wav, get_next_filename
-from config import LOOPS_DIR, ONESHOTS_DIR, DEVICE
+from config import LOOPS_DIR, ONESHOTS_DIR, REFERENCES_DIR, DEVICE
 
 def _get_lock_file_path():
     return os.path.join(tempfile.gettempdir(), "noises_backend.lock")
@@ -181,11 +190,13 @@ def shutdown():
 def health_check():
     return {
         "status": "ok",
+        # Lets the app tell us apart from anything else on the port.
+        "service": "noises",
         "device": DEVICE,
         "cuda_available": torch.cuda.is_available(),
         "torch_version": torch.__version__,
         "cuda_version": torch.version.cuda,
-        "gpu_name": torch.cuda.get_device_name(0) if torch.cuda.is_available() else None,
+        "gpu_name": torch.cuda.get_device_name() if DEVICE.startswith("cuda") else None,
     }
 
 class GenerateRequest(BaseModel):
@@ -202,88 +213,260 @@ class GenerateRequest(BaseModel):
     seed: int | None = None
     scheduler_type: str = "euler"
     cfg_type: str = "apg"
+    # Root of the active workspace; the app sends it so outputs land in the
+    # right library. Same samples/ layout as config.py underneath.
+    output_dir: str | None = None
+    # An id from /upload/reference: songs start from that clip instead of
+    # noise, keeping reference_strength of it.
+    reference_audio: str | None = None
+    reference_strength: float = 0.5
+
+# Audio the app may hand in as a reference; anything else is stored as .wav
+# and left to the model to reject.
+REFERENCE_EXTENSIONS = {".wav", ".mp3", ".flac", ".ogg", ".m4a", ".aiff", ".aif"}
+UPLOAD_CHUNK_BYTES = 1024 * 1024
+
+# Streams the multipart "file" part to REFERENCES_DIR under a fresh id, which
+# generate takes as reference_audio. Written to a temp name and renamed, so a
+# dropped upload never leaves a half file behind that id.
+@app.post("/upload/reference")
+def upload_reference(file: UploadFile = File(...)):
+    ext = Path(file.filename or "").suffix.lower()
+    if ext not in REFERENCE_EXTENSIONS:
+        ext = ".wav"
+    ref_id = uuid.uuid4().hex + ext
+    REFERENCES_DIR.mkdir(parents=True, exist_ok=True)
+    path = REFERENCES_DIR / ref_id
+    partial = REFERENCES_DIR / f".{ref_id}.part"
+    try:
+        with open(partial, "wb") as out:
+            shutil.copyfileobj(file.file, out, UPLOAD_CHUNK_BYTES)
+        os.replace(partial, path)
+    except OSError as e:
+        partial.unlink(missing_ok=True)
+        raise HTTPException(status_code=500, detail=f"Failed to save the reference: {e}")
+    print(f"[Upload] Reference saved as {path}")
+    return {"status": "success", "id": ref_id, "path": str(path)}
+
+def reference_path(ref_id):
+    # Only ids this sidecar handed out, so a request can't point it elsewhere.
+    path = REFERENCES_DIR / Path(ref_id).name
+    if Path(ref_id).name != ref_id or not path.is_file():
+        raise HTTPException(status_code=400, detail=f"Unknown reference audio {ref_id!r}; upload it first")
+    return str(path)
+
+def output_entry(filename, path, audio, sr):
+    # Paths and metadata only; the app reads the audio from disk.
+    return {
+        "file": filename,
+        "path": str(path),
+        # save_wav writes (frames, channels), so the length is the frame count.
+        "duration": len(audio) / int(sr),
+        "sample_rate": int(sr),
+    }
 
-@app.post("/generate")
-async def generate(req: GenerateRequest):
+# One generation at a time: the models take turns on the GPU (each unloads the
+# other), and the app queues jobs anyway.
+_generation_lock = threading.Lock()
+
+# Sockets on /ws/progress, and the event loop they live on; generations run
+# on worker threads and hand their events over to it.
+_progress_sockets = set()
+_progress_lock = threading.Lock()
+_progress_loop = None
+# A gRPC call's own listener, on the thread running its generation.
+_progress_local = threading.local()
+
+def publish_progress(event):
+    listener = getattr(_progress_local, "listener", None)
+    if listener:
+        listener(event)
+    with _progress_lock:
+        sockets = list(_progress_sockets)
+    for ws in sockets:
+        asyncio.run_coroutine_threadsafe(ws.send_json(event), _progress_loop)
+
+def _generate_files(req: GenerateRequest):
+    with _generation_lock:
+        publish_progress({"event": "started", "type": req.type, "steps": req.steps})
+        try:
+            files = _run_generation(req)
+        except Exception as e:
+            publish_progress({"event": "failed", "error": str(getattr(e, "detail", e))})
+            raise
+        publish_progress({"event": "finished", "files": [f["path"] for f in files]})
+        return files
+
+def _generate_for_grpc(config, on_progress):
+    # grpc_server.serve's generate: a bad request (pydantic's errors are
+    # ValueErrors too) or a 4xx becomes INVALID_ARGUMENT.
+    req = GenerateRequest(**config)
+    _progress_local.listener = lambda event: event["event"] == "step" and on_progress(event["step"], event["total"])
     try:
-        req_type = req.type.lower().replace("-", "")
-        generated_files = []
-
-        if "loop" in req_type:
-            # --- STABLE AUDIO (Better for short loops & samples) ---
-            duration = req.length if req.length else 2.5
-            full_prompt = req.prompt
-            if req.bpm: full_prompt += f", {req.bpm} bpm"
-            if req.key: full_prompt += f", {req.key}"
-
-            print(f"Generating Loop: {full_prompt}, {duration}s")
-            # Free ACE-Step VRAM before loading Stable Audio
-            if acestep.is_loaded:
-                acestep.unload()
-            raw_results = stable_audio.generate(
-                prompt=full_prompt,
-                negative_prompt=req.negative_prompt or "",
-                duration_seconds=duration,
-                num_inference_steps=req.steps,
-                guidance_scale=req.guidance,
-                seed=req.seed,
-            )
-            for i, (audio, sr) in enumerate(raw_results):
-                audio = normalize_audio(audio, target_db=-10.0)
-                audio = fade_audio(audio, sr, fade_out_ms=100)
-                bpm_part = f"_{req.bpm}bpm" if req.bpm else ""
-                safe_key = (req.key or 'Key').replace(" ", "_")
-                filename = get_next_filename(LOOPS_DIR, f"loop{bpm_part}_{safe_key}")
-                path = LOOPS_DIR / filename
-                save_wav(audio, sr, path)
-                generated_files.append({"file": filename, "path": str(path)})
-            
-            # Unload Stable Audio immediately after generation to free GPU memory
+        files = _generate_files(req)
+    except HTTPException as e:
+        if e.status_code < 500:
+            raise ValueError(e.detail)
+        raise RuntimeError(e.detail)
+    finally:
+        _progress_local.listener = None
+    return [f["path"] for f in files]
+
+def _run_generation(req: GenerateRequest):
+    req_type = req.type.lower().replace("-", "")
+    generated_files = []
+    loops_dir, oneshots_dir = LOOPS_DIR, ONESHOTS_DIR
+    if req.output_dir:
+        samples_dir = Path(req.output_dir) / "samples"
+        loops_dir, oneshots_dir = samples_dir / "loops", samples_dir / "oneshots"
+
+    reference = reference_path(req.reference_audio) if req.reference_audio else None
+    if reference and "loop" in req_type:
+        raise HTTPException(status_code=400, detail="Reference audio only works for songs, not loops")
+
+    if "loop" in req_type:
+        # --- STABLE AUDIO (Better for short loops & samples) ---
+        duration = req.length if req.length else 2.5
+        full_prompt = req.prompt
+        if req.bpm: full_prompt += f", {req.bpm} bpm"
+        if req.key: full_prompt += f", {req.key}"
+
+        print(f"Generating Loop: {full_prompt}, {duration}s")
+        # Free ACE-Step VRAM before loading Stable Audio
+        if acestep.is_loaded:
+            acestep.unload()
+        raw_results = stable_audio.generate(
+            prompt=full_prompt,
+            negative_prompt=req.negative_prompt or "",
+            duration_seconds=duration,
+            num_inference_steps=req.steps,
+            guidance_scale=req.guidance,
+            seed=req.seed,
+            progress_callback=lambda step, total: publish_progress(
+                {"event": "step", "step": step, "total": total}
+            ),
+        )
+        for i, (audio, sr) in enumerate(raw_results):
+            audio = normalize_audio(audio, target_db=-10.0)
+            audio = fade_audio(audio, sr, fade_out_ms=100)
+            bpm_part = f"_{req.bpm}bpm" if req.bpm else ""
+            safe_key = (req.key or 'Key').replace(" ", "_")
+            loops_dir.mkdir(parents=True, exist_ok=True)
+            filename = get_next_filename(loops_dir, f"loop{bpm_part}_{safe_key}")
+            path = loops_dir / filename
+            save_wav(audio, sr, path)
+            generated_files.append(output_entry(filename, path, audio, sr))
+        
+        # Unload Stable Audio immediately after generation to free GPU memory
+        stable_audio.unload()
+
+    else:
+        # --- ACE-STEP (Better for full songs with vocals) ---
+        duration = req.length if req.length else 30.0
+        full_prompt = req.prompt
+        if req.key: full_prompt += f", {req.key}"
+
+        print(f"Generating Full Song: {full_prompt}, {duration}s")
+        # Free Stable Audio VRAM before loading ACE-Step
+        if stable_audio.is_loaded:
             stable_audio.unload()
+        raw_results = acestep.generate(
+            prompt=full_prompt,
+            lyrics=req.lyrics or "",
+            duration_seconds=duration,
+            steps=req.steps,
+            guidance_scale=req.guidance,
+            seed=req.seed,
+            scheduler_type=req.scheduler_type,
+            cfg_type=req.cfg_type,
+            reference_audio=reference,
+            reference_strength=req.reference_strength,
+        )
+        for i, (audio, sr) in enumerate(raw_results):
+            audio = normalize_audio(audio, target_db=-10.0)
+            audio = fade_audio(audio, sr, fade_out_ms=2000)
+            oneshots_dir.mkdir(parents=True, exist_ok=True)
+            filename = get_next_filename(oneshots_dir, "song")
+            path = oneshots_dir / filename
+            save_wav(audio, sr, path)
+            generated_files.append(output_entry(filename, path, audio, sr))
+        
+        # Unload ACE-Step immediately after generation to free GPU memory
+        acestep.unload()
 
-        else:
-            # --- ACE-STEP (Better for full songs with vocals) ---
-            duration = req.length if req.length else 30.0
-            full_prompt = req.prompt
-            if req.key: full_prompt += f", {req.key}"
-
-            print(f"Generating Full Song: {full_prompt}, {duration}s")
-            # Free Stable Audio VRAM before loading ACE-Step
-            if stable_audio.is_loaded:
-                stable_audio.unload()
-            raw_results = acestep.generate(
-                prompt=full_prompt,
-                lyrics=req.lyrics or "",
-                duration_seconds=duration,
-                steps=req.steps,
-                guidance_scale=req.guidance,
-                seed=req.seed,
-                scheduler_type=req.scheduler_type,
-                cfg_type=req.cfg_type,
-            )
-            for i, (audio, sr) in enumerate(raw_results):
-                audio = normalize_audio(audio, target_db=-10.0)
-                audio = fade_audio(audio, sr, fade_out_ms=2000)
-                filename = get_next_filename(ONESHOTS_DIR, "song")
-                path = ONESHOTS_DIR / filename
-                save_wav(audio, sr, path)
-                generated_files.append({"file": filename, "path": str(path)})
-            
-            # Unload ACE-Step immediately after generation to free GPU memory
-            acestep.unload()
+    if not generated_files:
+        raise RuntimeError("Generation failed")
+    return generated_files
 
-        if not generated_files:
-            raise HTTPException(500, "Generation failed")
+def _success(generated_files):
+    return {
+        "status": "success",
+        "files": generated_files,
+        "path": generated_files[0]["path"]
+    }
+
+# Plain def: FastAPI runs it on its thread pool, so /health (and the rest)
+# still answer while the models work.
+@app.post("/generate")
+def generate(req: GenerateRequest):
+    try:
+        return _success(_generate_files(req))
+    except HTTPException:
+        raise
+    except Exception as e:
+        print(f"Error during generation: {e}")
+        raise HTTPException(status_code=500, detail=str(e))
 
-        return {
-            "status": "success",
-            "files": generated_files,
-            "path": generated_files[0]["path"]
-        }
+STREAM_CHUNK_BYTES = 64 * 1024
 
+# The same generation, answered with the audio itself as a chunked audio/wav
+# body instead of JSON; X-Noises-Output says where the library copy was saved.
+# Both models make a clip in one pass, so the stream starts once sampling is
+# done. With more than one output there's no single stream, and the usual
+# JSON comes back instead.
+@app.post("/generate/stream")
+def generate_stream(req: GenerateRequest):
+    try:
+        generated_files = _generate_files(req)
+    except HTTPException:
+        raise
     except Exception as e:
         print(f"Error during generation: {e}")
         raise HTTPException(status_code=500, detail=str(e))
+    if len(generated_files) != 1:
+        return _success(generated_files)
+
+    path = generated_files[0]["path"]
+    def chunks():
+        with open(path, "rb") as f:
+            while chunk := f.read(STREAM_CHUNK_BYTES):
+                yield chunk
+    return StreamingResponse(chunks(), media_type="audio/wav", headers={"X-Noises-Output": path})
+
+# Realtime channels for the app's WebSocket bridge (ws_bridge.rs). "progress"
+# pushes the running generation's events as JSON: started, step (Stable Audio
+# reports every few sampling steps; ACE-Step only starts and finishes),
+# finished with the output paths, or failed. "ping" is answered with "pong".
+# Other channels are closed with 4404.
+@app.websocket("/ws/{channel}")
+async def websocket_channel(ws: WebSocket, channel: str):
+    global _progress_loop
+    await ws.accept()
+    if channel != "progress":
+        await ws.close(code=4404, reason=f"No such channel: {channel}")
+        return
+    _progress_loop = asyncio.get_running_loop()
+    with _progress_lock:
+        _progress_sockets.add(ws)
+    try:
+        while True:
+            if await ws.receive_text() == "ping":
+                await ws.send_text("pong")
+    except WebSocketDisconnect:
+        pass
+    finally:
+        with _progress_lock:
+            _progress_sockets.discard(ws)
 
 if __name__ == "__main__":
     _ensure_single_instance()
@@ -292,6 +475,10 @@ if __name__ == "__main__":
     parser = argparse.ArgumentParser()
     parser.add_argument("--parent-pid", type=int, help="PID of the parent process to monitor")
     parser.add_argument("--setup-torch", action="store_true", help="Install PyTorch for this GPU (called by installer)")
+    parser.add_argument("--port", type=int, default=8000, help="Port to listen on (the app picks another when 8000 is taken)")
+    parser.add_argument("--device", help="Compute device, overriding NOISES_DEVICE (the app passes cpu when there's no GPU)")
+    parser.add_argument("--grpc-port", type=int, help="Also take generations over gRPC on this port (needs grpcio)")
+    parser.add_argument("--grpc-host", default="127.0.0.1", help="Address for --grpc-port; 0.0.0.0 takes them from other machines")
     args, _ = parser.parse_known_args()
 
     def _monitor_parent(pid):
@@ -321,5 +508,13 @@ if __name__ == "__main__":
 
     threading.Thread(target=watch_stdin, daemon=True).start()
 
-    print("Backend starting on http://127.0.0.1:8000")
-    uvicorn.run(app, host="127.0.0.1", port=8000)
+    grpc_service = None
+    if args.grpc_port:
+        try:
+            import grpc_server
+            grpc_service = grpc_server.serve(args.grpc_host, args.grpc_port, _generate_for_grpc)
+        except ImportError:
+            print("grpcio isn't installed, so --grpc-port is ignored")
+
+    print(f"Backend starting on http://127.0.0.1:{args.port}")
+    uvicorn.run(app, host="127.0.0.1", port=args.port)
@@ -13,19 +13,15 @@ class ACEStepModel:
         self.device = DEVICE
         self.sample_rate = 48000  # ACE-Step native output sample rate
         self.is_loaded = False
-        self.dtype = "bfloat16" if torch.cuda.is_available() and torch.cuda.is_bf16_supported() else "float32"
+        self.dtype = "bfloat16" if self.device.startswith("cuda") and torch.cuda.is_bf16_supported() else "float32"
 
     def load(self):
         if self.is_loaded and self.pipe is not None:
             return
 
-        if self.device != "cuda":
-            raise RuntimeError(
-                "ACE-Step requires an NVIDIA GPU with CUDA support. "
-                f"Detected device: {self.device}. "
-                "If you have an NVIDIA GPU, ensure CUDA drivers are installed "
-                "and torch.cuda.is_available() returns True."
-            )
+        # Without CUDA (the app's CPU fallback, or cpu/mps picked in settings)
+        # it still runs, just many times slower.
+        on_cuda = self.device.startswith("cuda")
 
         try:
             print(f"Loading ACE-Step ({self.dtype})...")
@@ -34,17 +30,22 @@ class ACEStepModel:
             # cpu_offload=True: moves model parts to CPU when not in use (~8GB peak VRAM)
             # overlapped_decode=True: more memory-efficient decoding for longer audio
             self.pipe = ACEStepPipeline(
+                device_id=int(self.device.split(":")[1]) if ":" in self.device else 0,
                 dtype=self.dtype,
                 torch_compile=False,  # Enable on Linux/WSL for speed
-                cpu_offload=True,
+                cpu_offload=on_cuda,
                 overlapped_decode=True,
             )
+            # The pipeline takes the GPU whenever there is one; the checkpoint
+            # is only loaded on first use, so this still decides where it goes.
+            if not on_cuda:
+                self.pipe.device = torch.device(self.device)
             # The pipeline auto-downloads models from HuggingFace
             # (ACE-Step/ACE-Step-v1-3.5B) to ~/.cache/ace-step/checkpoints
             # if not already present. Loading checkpoint is deferred to first __call__.
 
             self.is_loaded = True
-            print(f"ACE-Step loaded on {self.device} (cpu_offload=True).")
+            print(f"ACE-Step loaded on {self.device} (cpu_offload={on_cuda}).")
 
         except ImportError:
             print("ACE-Step not found. Install with: pip install git+https://github.com/ace-step/ACE-Step.git")
@@ -78,6 +79,8 @@ class ACEStepModel:
         seed: Optional[int] = None,
         scheduler_type: str = "euler",
         cfg_type: str = "apg",
+        reference_audio: Optional[str] = None,
+        reference_strength: float = 0.5,
     ) -> List[Tuple[np.ndarray, int]]:
         """
         Generate music with ACE-Step.
@@ -89,6 +92,8 @@ class ACEStepModel:
         - guidance_scale: 15.0 default (higher = more prompt adherence)
         - scheduler_type: "euler" recommended
         - cfg_type: "apg" (Adaptive Prompt Guidance) recommended
+        - reference_audio: path of a clip to start from (audio2audio)
+        - reference_strength: how much of the reference survives, 0-1
 
         Returns a list of (audio_ndarray, sample_rate) tuples.
         Audio is (samples, channels) float32 numpy array at 48kHz stereo.
@@ -113,6 +118,9 @@ class ACEStepModel:
                     cfg_type=cfg_type,
                     save_path=tmp_dir,
                     batch_size=1,
+                    audio2audio_enable=reference_audio is not None,
+                    ref_audio_input=reference_audio,
+                    ref_audio_strength=reference_strength,
                 )
 
                 # output = ["/path/to/output_0.wav", ..., {params_json_dict}]
@@ -2,7 +2,7 @@ import torch
 import numpy as np
 from typing import List, Tuple, Optional, Callable
 from diffusers.pipelines.stable_audio.pipeline_stable_audio import StableAudioPipeline
-from config import DEVICE
+from config import DEVICE, LOW_VRAM
 
 
 class StableAudioOpenModel:
@@ -16,31 +16,29 @@ class StableAudioOpenModel:
         if self.is_loaded and self.pipe is not None:
             return
 
-        if self.device != "cuda":
-            raise RuntimeError(
-                "Stable Audio requires an NVIDIA GPU with CUDA support. "
-                f"Detected device: {self.device}. "
-                "If you have an NVIDIA GPU, ensure CUDA drivers are installed "
-                "and torch.cuda.is_available() returns True."
-            )
-
+        # Runs on CUDA, Apple Silicon (compute_device "mps") or, many times
+        # slower, the CPU the app falls back to without a supported GPU.
         try:
             print("Loading Stable Audio Open...")
             try:
                 self.pipe = StableAudioPipeline.from_pretrained(
                     "stabilityai/stable-audio-open-1.0",
-                    torch_dtype=torch.float16 if self.device == "cuda" else torch.float32,
+                    torch_dtype=torch.float16 if self.device.startswith("cuda") else torch.float32,
                     local_files_only=True
                 )
             except (OSError, ValueError):
                 print("Model not found locally, downloading Stable Audio Open...")
                 self.pipe = StableAudioPipeline.from_pretrained(
                     "stabilityai/stable-audio-open-1.0",
-                    torch_dtype=torch.float16 if self.device == "cuda" else torch.float32
+                    torch_dtype=torch.float16 if self.device.startswith("cuda") else torch.float32
                 )
-            self.pipe = self.pipe.to(self.device)
+            if LOW_VRAM and self.device.startswith("cuda"):
+                # Moves each component onto the GPU only while it runs.
+                self.pipe.enable_model_cpu_offload(device=self.device)
+            else:
+                self.pipe = self.pipe.to(self.device)
 
-            if self.device == "cuda":
+            if self.device.startswith("cuda"):
                 self.pipe.enable_attention_slicing()
 
             self.is_loaded = True
@@ -80,7 +78,7 @@ class StableAudioOpenModel:
             self.load()
         
         # Ensure model is on GPU (may have been moved to CPU by unload())
-        if self.pipe.device.type != self.device and self.device == "cuda":
+        if self.device.startswith("cuda") and self.pipe.device.type != "cuda":
             self.pipe = self.pipe.to(self.device)
 
         try:
@@ -1,12 +1,18 @@
 import React, { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { TitleBar } from './components/TitleBar';
 import { MegaInput } from './components/MegaInput';
 import { AudioPlayer } from './components/AudioPlayer';
+import { ModelManager } from './components/ModelManager';
 import { Button } from './components/Button';
-import { AlertCircle, Plus, Trash2, Tag } from 'lucide-react';
+import { TaskList } from './components/TaskList';
+import { AlertCircle, Download, Layers, Plus, Trash2, Tag, X } from 'lucide-react';
 import { motion, AnimatePresence } from 'framer-motion';
 
+// Variations per seed sweep.
+const SWEEP_COUNT = 4;
+
 function App() {
   const [config, setConfig] = useState({
     prompt: "",
@@ -21,36 +27,106 @@ function App() {
     seed: "",
     schedulerType: "euler",
     cfgType: "apg",
+    provider: "local",
+    renderTarget: false,
   });
 
   const [presets, setPresets] = useState([]);
+  const [presetsLoaded, setPresetsLoaded] = useState(false);
   const [generating, setGenerating] = useState(false);
   const [result, setResult] = useState(null);
   const [error, setError] = useState(null);
   const [backendReady, setBackendReady] = useState(false);
+  const [warmingUp, setWarmingUp] = useState(false);
+  const [closeRequest, setCloseRequest] = useState(null);
+  const [update, setUpdate] = useState(null);
+  const [crashReport, setCrashReport] = useState(null);
+  const [showModels, setShowModels] = useState(false);
+  const [updateProgress, setUpdateProgress] = useState(null);
+  const [adjusted, setAdjusted] = useState(null);
+  const [portNotice, setPortNotice] = useState(null);
+  const [integrityFailed, setIntegrityFailed] = useState(false);
+  const [engineProblem, setEngineProblem] = useState(null);
+  const [interruptedNotice, setInterruptedNotice] = useState(null);
+  const [gpuWait, setGpuWait] = useState(null);
+  const [providers, setProviders] = useState([]);
+  const [providerWait, setProviderWait] = useState(null);
+  const [renderTarget, setRenderTarget] = useState(null);
+  const [renderNotice, setRenderNotice] = useState(null);
+  const [thermal, setThermal] = useState(null);
+  const [undo, setUndo] = useState(null);
+  const [cpuFallback, setCpuFallback] = useState(false);
+  const [packNotice, setPackNotice] = useState(null);
+  const [sweep, setSweep] = useState(null);
 
-  // Check if we have any saved cool stuff.
+  // Check if we have any saved cool stuff. Presets belong to the active
+  // workspace; ones from before workspaces (localStorage) move into the first
+  // workspace that has none saved.
   useEffect(() => {
-    const saved = localStorage.getItem('user_presets');
-    if (saved) {
-        try {
-            setPresets(JSON.parse(saved));
-        } catch (e) {
-            console.error("Failed to load presets", e);
+    const load = async () => {
+      setPresetsLoaded(false);
+      try {
+        let saved = await invoke('get_workspace_presets');
+        const legacy = localStorage.getItem('user_presets');
+        if (saved === null && legacy) {
+          saved = JSON.parse(legacy);
+          await invoke('save_workspace_presets', { presets: saved });
+          localStorage.removeItem('user_presets');
         }
-    }
+        setPresets(saved || []);
+      } catch (e) {
+        console.error("Failed to load presets", e);
+      }
+      setPresetsLoaded(true);
+    };
+    load();
+    const unlisteners = [
+      listen('workspace-changed', () => {
+        setResult(null);
+        load();
+      }),
+      listen('profile-imported', load),
+    ];
+    return () => { unlisteners.forEach(u => u.then(fn => fn())); };
   }, []);
 
   // Save presets when changed (don't lose the cool stuff!)
   useEffect(() => {
-      localStorage.setItem('user_presets', JSON.stringify(presets));
+      if (!presetsLoaded) return;
+      invoke('save_workspace_presets', { presets }).catch(e => console.error("Failed to save presets", e));
   }, [presets]);
 
+  // Clicking a "Sound ready" notification brings the new file up in the player.
+  useEffect(() => {
+    const unlisten = listen('select-sound', ({ payload }) => {
+      setError(null);
+      setResult({ file: payload.file, path: payload.path });
+    });
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
+
+  // noises://generate?... prefills the form, noises://open?sound=... shows a sound.
+  useEffect(() => {
+    const applyLink = (link) => {
+      if (link.action === 'prefill') {
+        setConfig(prev => ({ ...prev, ...link.config }));
+      } else if (link.action === 'open-sound') {
+        setError(null);
+        setResult({ file: link.file, path: link.path });
+      }
+    };
+    const unlisten = listen('deep-link', ({ payload }) => applyLink(payload));
+    unlisten.then(() => invoke('take_pending_deep_links')).then(links => links.forEach(applyLink));
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
+
   useEffect(() => {
     const interval = setInterval(async () => {
       try {
-        const ready = await invoke('check_backend_health');
-        if (ready) {
+        // With lazy start the engine isn't spawned until the first generation,
+        // so "idle" is as good as ready here.
+        const stage = await invoke('backend_stage');
+        if (stage !== 'starting') {
             setBackendReady(true);
             clearInterval(interval);
         }
@@ -59,15 +135,225 @@ function App() {
     return () => clearInterval(interval);
   }, []);
 
-  const handleGenerate = async (e) => {
-    if(e) e.preventDefault();
-    if (!config.prompt) return;
-    
-    setGenerating(true);
-    setError(null);
-    setResult(null);
+  useEffect(() => {
+    const unlisteners = [
+      listen('backend-warming-up', () => setWarmingUp(true)),
+      listen('backend-ready', () => setWarmingUp(false)),
+      // Changing the compute device restarts the engine.
+      listen('backend-restarting', () => setWarmingUp(true)),
+      // A warm-up that timed out ends the job without `backend-ready`.
+      listen('job-finished', () => setWarmingUp(false)),
+    ];
+    return () => { unlisteners.forEach(u => u.then(fn => fn())); };
+  }, []);
+
+  // Start the form on the default model's type (same values as MegaInput's toggle).
+  useEffect(() => {
+    invoke('list_models').then(models => {
+        if (models.find(m => m.is_default)?.generation_type === 'one-shot') {
+            setConfig(prev => ({ ...prev, type: 'one-shot', length: 30, steps: 60, guidance: 15.0 }));
+        }
+    }).catch(e => console.error("Failed to load models", e));
+  }, []);
+
+  // The engine's port was taken when it started (sidecar.rs); say what happened.
+  useEffect(() => {
+    const unlisten = listen('backend-port-in-use', ({ payload }) => {
+      setPortNotice(payload.used === payload.port
+        ? `An AI Engine left over from an earlier run had port ${payload.port} and was replaced`
+        : `Port ${payload.port} is in use by ${payload.owner === 'noises' ? 'an old AI Engine' : 'another program'}, the AI Engine uses ${payload.used} instead`);
+    });
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
+
+  // The bundled backend doesn't match this build (sidecar.rs), so it won't be started.
+  useEffect(() => {
+    const unlisten = listen('backend-integrity-failed', () => setIntegrityFailed(true));
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
+
+  // The port check behind the health check (backend.rs) tells a hung engine
+  // from one that's gone. A closed port before the first healthy answer is
+  // just the engine starting.
+  useEffect(() => {
+    let wasHealthy = false;
+    const unlisten = listen('backend-status', ({ payload }) => {
+      if (payload.status === 'healthy') {
+        wasHealthy = true;
+        setEngineProblem(null);
+      } else if (payload.status === 'unresponsive') {
+        setEngineProblem('The AI Engine is running but not responding');
+      } else if (payload.status === 'closed' && wasHealthy) {
+        setEngineProblem('The AI Engine stopped');
+      }
+    });
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
+
+  // No GPU found, so the engine runs on the CPU (sidecar.rs). The event may
+  // have gone out before this window loaded, hence the command as well.
+  useEffect(() => {
+    const unlisten = listen('cpu-fallback', () => setCpuFallback(true));
+    invoke('cpu_fallback_active').then(active => { if (active) setCpuFallback(true); }).catch(() => { });
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
+
+  // A finished sweep shows its first sound in the player.
+  useEffect(() => {
+    const unlisten = listen('batch-progress', ({ payload }) => {
+      setSweep(payload);
+      if (payload.done === payload.total && payload.outputs.length > 0) {
+        const path = payload.outputs[0];
+        setResult({ file: path.split(/[\\/]/).pop(), path });
+      }
+    });
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
+
+  // Sound packs opened with Noises are imported in the background (sound_pack.rs).
+  useEffect(() => {
+    const unlisteners = [
+      listen('sound-pack-imported', ({ payload }) => {
+        const parts = [`${payload.imported.length} sounds from "${payload.name}" added to the library`];
+        if (payload.duplicates) parts.push(`${payload.duplicates} already there`);
+        if (payload.skipped.length) parts.push(`${payload.skipped.length} skipped`);
+        setPackNotice({ error: false, text: parts.join(', ') });
+      }),
+      listen('sound-pack-failed', ({ payload }) => {
+        setPackNotice({ error: true, text: `Couldn't import sound pack: ${payload.error}` });
+      }),
+    ];
+    return () => { unlisteners.forEach(u => u.then(fn => fn())); };
+  }, []);
+
+  // A generation the engine crashed under is sent again (jobs.rs).
+  useEffect(() => {
+    const unlisten = listen('job-interrupted', ({ payload }) => {
+      setInterruptedNotice(payload.requeued
+        ? `The AI Engine stopped during "${payload.prompt}", generating it again`
+        : `The AI Engine kept stopping during "${payload.prompt}", so it was given up`);
+    });
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
+
+  // A job that wouldn't fit in the free VRAM (a game open) waits for it
+  // (jobs.rs). The notice goes once no job is waiting any more.
+  useEffect(() => {
+    const gb = bytes => (bytes / 1024 ** 3).toFixed(1);
+    const unlisteners = [
+      listen('job-waiting-for-gpu', ({ payload }) => {
+        setGpuWait(`Waiting for GPU memory for "${payload.prompt}": ${gb(payload.free)} GB free, about ${gb(payload.needed)} GB needed`);
+      }),
+      listen('jobs-changed', ({ payload }) => {
+        if (!payload.active.some(job => job.state === 'waiting-for-gpu')) setGpuWait(null);
+      }),
+    ];
+    return () => { unlisteners.forEach(u => u.then(fn => fn())); };
+  }, []);
+
+  // Remote generation hosts from Settings, picked per job (providers.rs).
+  // A job sent to one shows as waiting until the provider starts on it.
+  useEffect(() => {
+    invoke('get_all_settings').then(s => setProviders(s.remote_providers)).catch(() => {});
+    const unlisteners = [
+      listen('settings-changed', ({ payload }) => {
+        if (payload.key === 'remote_providers') setProviders(payload.value);
+      }),
+      listen('jobs-changed', ({ payload }) => {
+        const waiting = payload.active.find(job => job.state === 'waiting-for-provider');
+        setProviderWait(waiting ? `Waiting for the provider to start "${waiting.prompt}"` : null);
+      }),
+    ];
+    return () => { unlisteners.forEach(u => u.then(fn => fn())); };
+  }, []);
+
+  // Marked generations are copied to the render target path (render_target.rs).
+  useEffect(() => {
+    invoke('get_all_settings').then(s => setRenderTarget(s.render_target)).catch(() => {});
+    const unlisteners = [
+      listen('settings-changed', ({ payload }) => {
+        if (payload.key === 'render_target') setRenderTarget(payload.value);
+      }),
+      listen('render-target-updated', ({ payload }) => {
+        setRenderNotice(payload.error
+          ? { text: payload.error, error: true }
+          : { text: `Updated ${payload.path}` });
+      }),
+    ];
+    return () => { unlisteners.forEach(u => u.then(fn => fn())); };
+  }, []);
+
+  // The queue holds new jobs while the GPU is over the temperature limit (thermal.rs).
+  useEffect(() => {
+    const unlisten = listen('thermal-status', ({ payload }) => {
+      setThermal(payload.paused ? payload : null);
+    });
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
+
+  // Deleting moves the file to a holding folder (trash.rs); offer it back.
+  useEffect(() => {
+    const unlisten = listen('undo-available', ({ payload }) => setUndo(payload));
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
+
+  const restoreDeleted = () => {
+    const { id, file } = undo;
+    setUndo(null);
+    invoke('restore_deleted', { id })
+      .then(path => setResult({ file: path.split(/[\\/]/).pop() || file, path }))
+      .catch(e => setError(String(e)));
+  };
+
+  // Low-VRAM mode caps requests that wouldn't fit (low_vram.rs); say what changed.
+  useEffect(() => {
+    const unlisten = listen('request-adjusted', ({ payload }) => {
+      setAdjusted(payload.adjustments.map(a => `${a.field} ${a.requested} → ${a.used}`).join(', '));
+    });
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
+
+  // After a crash the app relaunches itself; say so instead of starting over silently.
+  useEffect(() => {
+    invoke('take_crash_report').then(setCrashReport).catch(e => console.error("Failed to read crash report", e));
+  }, []);
+
+  // Look for a new release once per launch; offline is fine, just stay quiet.
+  useEffect(() => {
+    invoke('check_for_updates').then(setUpdate).catch(e => {
+        // Offline mode (proxy.rs) refuses on purpose; nothing to warn about.
+        if (!String(e).startsWith('offline:')) console.warn("Update check failed", e);
+    });
+    const unlisten = listen('update-download-progress', ({ payload }) => {
+        setUpdateProgress(payload.total ? Math.round(payload.downloaded / payload.total * 100) : 0);
+    });
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
 
+  const installUpdate = async () => {
+    setUpdateProgress(0);
     try {
+        // Restarts the app when it's done.
+        await invoke('install_update');
+    } catch (err) {
+        setUpdateProgress(null);
+        setError(typeof err === 'string' ? err : err.message);
+    }
+  };
+
+  // Closing while sounds are generating asks first (see close_guard.rs).
+  useEffect(() => {
+    const unlisten = listen('close-requested-with-active-jobs', ({ payload }) => setCloseRequest(payload));
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
+
+  const resolveClose = (choice) => {
+    setCloseRequest(null);
+    invoke('resolve_close_request', { choice }).catch(e => console.error("Failed to close", e));
+  };
+
+  // The request for the backend from the form.
+  const buildPayload = () => {
       const payload = {
         type: config.type,
         prompt: config.prompt,
@@ -76,6 +362,8 @@ function App() {
         length: Number(config.length),
         variations: 1
       };
+      if (config.provider && config.provider !== 'local') payload.provider = config.provider;
+      if (config.renderTarget && renderTarget) payload.render_target = true;
 
       if (config.type === 'loop') {
         // Stable Audio - loops & short samples
@@ -92,6 +380,22 @@ function App() {
          payload.cfg_type = config.cfgType;
          if (config.seed !== "") payload.seed = Number(config.seed);
       }
+      return payload;
+  };
+
+  // `parent` is the sound this one is made from (Regenerate), for the
+  // lineage in the history (history.rs).
+  const handleGenerate = async (e, parent) => {
+    if(e) e.preventDefault();
+    if (!config.prompt) return;
+    
+    setGenerating(true);
+    setError(null);
+    setResult(null);
+
+    try {
+      const payload = buildPayload();
+      if (parent) payload.parent = parent;
 
       const data = await invoke('generate_audio', { config: payload });
       
@@ -102,13 +406,23 @@ function App() {
       });
 
     } catch (err) {
-      // invoke() throws strings directly, not Error objects
-      setError(typeof err === 'string' ? err : err.message);
+      // invoke() throws strings directly, not Error objects. Cancelling
+      // (TaskList) isn't an error worth showing.
+      if (err !== 'Cancelled') setError(typeof err === 'string' ? err : err.message);
     } finally {
       setGenerating(false);
     }
   };
 
+  // Seed sweep: the same prompt a few times with consecutive seeds, run in
+  // the background (jobs.rs). Progress comes as batch-progress.
+  const handleSweep = () => {
+    if (!config.prompt) return;
+    setError(null);
+    invoke('enqueue_variations', { config: buildPayload(), count: SWEEP_COUNT })
+        .catch(err => setError(typeof err === 'string' ? err : err.message));
+  };
+
   const handleAddPreset = () => {
       if (!config.prompt) return;
       const newPreset = {
@@ -156,7 +470,7 @@ function App() {
 
   return (
     <div className="flex flex-col h-screen bg-background text-gray-200 overflow-hidden font-sans relative">
-        <TitleBar />
+        <TitleBar showLibrary showWorkspaces onOpenModels={() => setShowModels(true)} />
       
         {/* Fancy glowing background blobs. */}
         <div className="absolute top-[-20%] left-[20%] w-[600px] h-[600px] bg-purple-900/10 rounded-full blur-[120px] pointer-events-none" />
@@ -173,6 +487,107 @@ function App() {
                         <AlertCircle size={12} /> Connecting to AI Engine...
                     </div>
                 )}
+                {warmingUp && (
+                    <div className="inline-flex items-center gap-2 px-3 py-1 rounded-full bg-yellow-500/10 text-yellow-500 text-xs font-medium border border-yellow-500/20">
+                        <AlertCircle size={12} /> Warming up AI Engine...
+                    </div>
+                )}
+                {crashReport && (
+                    <div className="inline-flex items-center gap-2 px-3 py-1 rounded-full bg-red-500/10 text-red-300 text-xs font-medium border border-red-500/20">
+                        <AlertCircle size={12} /> Noises crashed and was restarted.
+                        <button onClick={() => invoke('show_in_folder', { path: crashReport.path }).catch(e => setError(String(e)))} className="underline hover:text-white">Show report</button>
+                        <button onClick={() => setCrashReport(null)} className="hover:text-white" title="Dismiss"><X size={12} /></button>
+                    </div>
+                )}
+                {adjusted && (
+                    <div className="inline-flex items-center gap-2 px-3 py-1 rounded-full bg-yellow-500/10 text-yellow-300 text-xs font-medium border border-yellow-500/20">
+                        <AlertCircle size={12} /> Low-VRAM mode: {adjusted}
+                        <button onClick={() => setAdjusted(null)} className="hover:text-white" title="Dismiss"><X size={12} /></button>
+                    </div>
+                )}
+                {portNotice && (
+                    <div className="inline-flex items-center gap-2 px-3 py-1 rounded-full bg-yellow-500/10 text-yellow-300 text-xs font-medium border border-yellow-500/20">
+                        <AlertCircle size={12} /> {portNotice}
+                        <button onClick={() => setPortNotice(null)} className="hover:text-white" title="Dismiss"><X size={12} /></button>
+                    </div>
+                )}
+                {integrityFailed && (
+                    <div className="inline-flex items-center gap-2 px-3 py-1 rounded-full bg-red-500/10 text-red-300 text-xs font-medium border border-red-500/20">
+                        <AlertCircle size={12} /> The AI Engine's files are damaged or from another version. Reinstall Noises to fix them
+                    </div>
+                )}
+                {engineProblem && (
+                    <div className="inline-flex items-center gap-2 px-3 py-1 rounded-full bg-red-500/10 text-red-300 text-xs font-medium border border-red-500/20">
+                        <AlertCircle size={12} /> {engineProblem}
+                        <button onClick={() => setEngineProblem(null)} className="hover:text-white" title="Dismiss"><X size={12} /></button>
+                    </div>
+                )}
+                {cpuFallback && (
+                    <div className="inline-flex items-center gap-2 px-3 py-1 rounded-full bg-yellow-500/10 text-yellow-300 text-xs font-medium border border-yellow-500/20">
+                        <AlertCircle size={12} /> No supported GPU found, so the AI Engine runs on the CPU. Generations will take much longer
+                        <button onClick={() => setCpuFallback(false)} className="hover:text-white" title="Dismiss"><X size={12} /></button>
+                    </div>
+                )}
+                {sweep && (
+                    <div className="inline-flex items-center gap-2 px-3 py-1 rounded-full bg-primary/10 text-primary text-xs font-medium border border-primary/20">
+                        <Layers size={12} /> Seed sweep: {sweep.done} of {sweep.total} done{sweep.failed > 0 && `, ${sweep.failed} failed`}
+                        {sweep.done === sweep.total && (
+                            <button onClick={() => setSweep(null)} className="hover:text-white" title="Dismiss"><X size={12} /></button>
+                        )}
+                    </div>
+                )}
+                {packNotice && (
+                    <div className={`inline-flex items-center gap-2 px-3 py-1 rounded-full text-xs font-medium border ${packNotice.error ? 'bg-red-500/10 text-red-300 border-red-500/20' : 'bg-primary/10 text-primary border-primary/20'}`}>
+                        <AlertCircle size={12} /> {packNotice.text}
+                        <button onClick={() => setPackNotice(null)} className="hover:text-white" title="Dismiss"><X size={12} /></button>
+                    </div>
+                )}
+                {renderNotice && (
+                    <div className={`inline-flex items-center gap-2 px-3 py-1 rounded-full text-xs font-medium border ${renderNotice.error ? 'bg-red-500/10 text-red-300 border-red-500/20' : 'bg-primary/10 text-primary border-primary/20'}`}>
+                        <AlertCircle size={12} /> {renderNotice.text}
+                        <button onClick={() => setRenderNotice(null)} className="hover:text-white" title="Dismiss"><X size={12} /></button>
+                    </div>
+                )}
+                {undo && (
+                    <div className="inline-flex items-center gap-2 px-3 py-1 rounded-full bg-primary/10 text-primary text-xs font-medium border border-primary/20">
+                        <Trash2 size={12} /> Deleted {undo.file}
+                        <button onClick={restoreDeleted} className="underline hover:text-white">Undo</button>
+                        <button onClick={() => setUndo(null)} className="hover:text-white" title="Dismiss"><X size={12} /></button>
+                    </div>
+                )}
+                {thermal && (
+                    <div className="inline-flex items-center gap-2 px-3 py-1 rounded-full bg-red-500/10 text-red-300 text-xs font-medium border border-red-500/20">
+                        <AlertCircle size={12} /> GPU at {thermal.temperature} °C, queue paused to cool down
+                    </div>
+                )}
+                {gpuWait && (
+                    <div className="inline-flex items-center gap-2 px-3 py-1 rounded-full bg-yellow-500/10 text-yellow-300 text-xs font-medium border border-yellow-500/20">
+                        <AlertCircle size={12} /> {gpuWait}
+                    </div>
+                )}
+                {providerWait && (
+                    <div className="inline-flex items-center gap-2 px-3 py-1 rounded-full bg-primary/10 text-primary text-xs font-medium border border-primary/20">
+                        <AlertCircle size={12} /> {providerWait}
+                    </div>
+                )}
+                {interruptedNotice && (
+                    <div className="inline-flex items-center gap-2 px-3 py-1 rounded-full bg-yellow-500/10 text-yellow-300 text-xs font-medium border border-yellow-500/20">
+                        <AlertCircle size={12} /> {interruptedNotice}
+                        <button onClick={() => setInterruptedNotice(null)} className="hover:text-white" title="Dismiss"><X size={12} /></button>
+                    </div>
+                )}
+                {update && (
+                    <button
+                        onClick={installUpdate}
+                        disabled={updateProgress !== null}
+                        className="inline-flex items-center gap-2 px-3 py-1 rounded-full bg-primary/10 text-primary text-xs font-medium border border-primary/20 hover:bg-primary/20 disabled:hover:bg-primary/10 transition-colors"
+                    >
+                        <Download size={12} />
+                        {updateProgress === null
+                            ? `Update ${update.version} available, install now`
+                            : `Downloading update... ${updateProgress}%`}
+                    </button>
+                )}
             </div>
 
             <div className="w-full mb-8 animate-in fade-in slide-in-from-bottom-8 duration-700 delay-100">
@@ -180,10 +595,16 @@ function App() {
                     config={config} 
                     setConfig={setConfig} 
                     onGenerate={handleGenerate}
+                    onSweep={handleSweep}
                     generating={generating}
                     backendReady={backendReady}
+                    providers={providers}
+                    renderTarget={renderTarget}
                     onAddContext={handleEnhanceContext}
                 />
+                <div className="mt-3">
+                    <TaskList />
+                </div>
             </div>
 
             {/* Presets and Results Section */}
@@ -214,7 +635,7 @@ function App() {
                             <AudioPlayer 
                                 filePath={result.path} 
                                 fileName={result.file}
-                                onRegenerate={handleGenerate}
+                                onRegenerate={() => handleGenerate(null, result.path)}
                                 onDelete={() => setResult(null)}
                             />
                         </motion.div>
@@ -256,6 +677,27 @@ function App() {
             </div>
 
         </main>
+
+        {showModels && <ModelManager onClose={() => setShowModels(false)} />}
+
+        {closeRequest && (
+            <div className="absolute inset-0 z-[60] flex items-center justify-center bg-black/60">
+                <div className="w-full max-w-sm bg-panel rounded-xl border border-gray-700/50 p-5 shadow-lg space-y-4">
+                    <div>
+                        <h2 className="text-base font-semibold text-white">
+                            {closeRequest.active.length === 1 ? 'A sound is still generating' : `${closeRequest.active.length} sounds are still generating`}
+                        </h2>
+                        <p className="text-sm text-gray-400 mt-1">Closing now would stop the AI engine and lose them.</p>
+                    </div>
+                    <div className="flex flex-col gap-2">
+                        <Button onClick={() => resolveClose('finish')}>Finish, then quit</Button>
+                        <Button variant="secondary" onClick={() => resolveClose('background')}>Keep running in the tray</Button>
+                        <Button variant="destructive" onClick={() => resolveClose('cancel')}>Quit now</Button>
+                        <Button variant="ghost" onClick={() => resolveClose('stay')}>Cancel</Button>
+                    </div>
+                </div>
+            </div>
+        )}
     </div>
   );
 }
@@ -1,9 +1,7 @@
 import React, { useState, useEffect, useRef } from 'react';
-import { Play, Pause, Volume2, RefreshCw, VolumeX, FolderOpen, GripVertical, Trash2 } from 'lucide-react';
+import { Play, Pause, Volume2, RefreshCw, VolumeX, FolderOpen, GripVertical, Trash2, Share2, SquarePen, Send } from 'lucide-react';
 import { Button } from './Button';
-import { readFile } from '@tauri-apps/plugin-fs';
-import { invoke } from '@tauri-apps/api/core';
-import { startDrag } from '@crabnebula/tauri-plugin-drag';
+import { invoke, convertFileSrc } from '@tauri-apps/api/core';
 // Import a transparent 1x1 pixel base64 image or similar if needed, 
 // but try passing undefined first, or a known path.
 // The error says "missing required key image".
@@ -16,6 +14,14 @@ export function AudioPlayer({ filePath, fileName, onRegenerate, onDelete }) {
   const audioRef = useRef(null);
   const requestRef = useRef();
   const [src, setSrc] = useState(null);
+  const [processors, setProcessors] = useState([]);
+
+  // Processors from plugins.rs, as "plugin/processor" options.
+  useEffect(() => {
+    invoke('list_plugins')
+      .then(plugins => setProcessors(plugins.flatMap(p => p.processors.map(proc => ({ plugin: p.id, ...proc })))))
+      .catch(err => console.error("Failed to list plugins:", err));
+  }, []);
 
   useEffect(() => {
     // Linear animation loop for smooth progress bar
@@ -37,28 +43,10 @@ export function AudioPlayer({ filePath, fileName, onRegenerate, onDelete }) {
   }, [isPlaying]);
 
   useEffect(() => {
-    async function loadAudio() {
-        if (!filePath) return;
-        setSrc(null); // Reset
-        try {
-            console.log("Reading file:", filePath);
-            // Read file as binary (Uint8Array)
-            const content = await readFile(filePath, {
-                // Ensure we are using the baseDir if needed, but absolute path is preferred usually
-            });
-            // Create Blob and URL
-            const blob = new Blob([content], { type: 'audio/wav' }); 
-            const url = URL.createObjectURL(blob);
-            setSrc(url);
-            console.log("Audio loaded successfully into Blob URL");
-            
-            // Cleanup previous URL
-            return () => URL.revokeObjectURL(url);
-        } catch (err) {
-            console.error("Failed to load audio file via readFile:", err);
-        }
-    }
-    loadAudio();
+    if (!filePath) return;
+    // Served by the Rust `noises://` protocol with Range support, so large WAVs
+    // seek instantly instead of being read into a Blob first.
+    setSrc(convertFileSrc(`audio/${filePath}`, 'noises'));
   }, [filePath]);
 
   useEffect(() => {
@@ -101,18 +89,73 @@ export function AudioPlayer({ filePath, fileName, onRegenerate, onDelete }) {
           await invoke('show_in_folder', { path: filePath });
       } catch (err) {
           console.error("Failed to open folder:", err);
+          alert('Failed to open folder: ' + err);
+      }
+  };
+
+  const handleShare = async () => {
+      try {
+          await invoke('share_file', { path: filePath });
+      } catch (err) {
+          console.error("Failed to share file:", err);
+          alert('Failed to share file: ' + err);
+      }
+  };
+
+  // Posts the generation this file came from to the team's Discord webhook
+  // (discord.rs), with its prompt and settings.
+  const handleDiscord = async () => {
+      try {
+          const entry = (await invoke('list_history')).find(e => e.outputs.includes(filePath));
+          if (!entry) {
+              alert('Only generated sounds can be posted to Discord');
+              return;
+          }
+          await invoke('share_to_discord', { id: entry.id });
+      } catch (err) {
+          console.error("Failed to post to Discord:", err);
+          alert('Failed to post to Discord: ' + err);
+      }
+  };
+
+  const handleProcess = async (value) => {
+      const proc = processors.find(p => `${p.plugin}/${p.id}` === value);
+      if (!proc) return;
+      try {
+          const output = await invoke('run_processor', { plugin: proc.plugin, processor: proc.id, path: filePath, params: proc.params });
+          await invoke('show_in_folder', { path: output });
+      } catch (err) {
+          console.error("Processor failed:", err);
+          alert('Processing failed: ' + err);
+      }
+  };
+
+  // Audacity, RX, ... from Settings, or whatever the system edits audio with (editor.rs).
+  const handleEdit = async () => {
+      try {
+          await invoke('open_in_editor', { path: filePath });
+      } catch (err) {
+          console.error("Failed to open editor:", err);
+          alert('Failed to open in editor: ' + err);
       }
   };
 
   const handleDelete = async () => {
       if (!confirm('Are you sure you want to delete this file?')) return;
       
-      try {
-          await invoke('delete_file', { path: filePath });
-          if (onDelete) onDelete();
-      } catch (err) {
-          console.error("Failed to delete file:", err);
-          alert('Failed to delete file: ' + err);
+      for (;;) {
+          try {
+              await invoke('delete_file', { path: filePath });
+              if (onDelete) onDelete();
+              return;
+          } catch (err) {
+              console.error("Failed to delete file:", err);
+              // Open in another program (a DAW, usually): say which and offer another go.
+              const inUse = String(err).match(/^FileInUse: (.*)$/s);
+              if (inUse && confirm(inUse[1] + '\n\nTry again?')) continue;
+              if (!inUse) alert('Failed to delete file: ' + err);
+              return;
+          }
       }
   };
 
@@ -120,13 +163,8 @@ export function AudioPlayer({ filePath, fileName, onRegenerate, onDelete }) {
       console.log("Drag started for:", filePath);
       
       try {
-          // Use a transparent 1x1 pixel image as the drag icon to avoid path issues
-          const emptyIcon = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAQAAAC1HAwCAAAAC0lEQVR42mNkYAAAAAYAAjCB0C8AAAAASUVORK5CYII=";
-          
-          await startDrag({
-              item: [filePath],
-              icon: emptyIcon 
-          });
+          // The drag image (waveform + file count) is rendered on the Rust side
+          await invoke('start_drag_out', { paths: [filePath] });
       } catch (err) {
           console.error("Drag failed:", err);
       }
@@ -164,6 +202,26 @@ export function AudioPlayer({ filePath, fileName, onRegenerate, onDelete }) {
                  <Button size="sm" variant="ghost" onClick={handleOpenFolder} title="Show in Folder">
                     <FolderOpen size={16} />
                  </Button>
+                 <Button size="sm" variant="ghost" onClick={handleEdit} title="Open in editor">
+                    <SquarePen size={16} />
+                 </Button>
+                 <Button size="sm" variant="ghost" onClick={handleShare} title="Share">
+                    <Share2 size={16} />
+                 </Button>
+                 <Button size="sm" variant="ghost" onClick={handleDiscord} title="Post to Discord">
+                    <Send size={16} />
+                 </Button>
+                 {processors.length > 0 && (
+                     <select
+                        value=""
+                        onChange={e => handleProcess(e.target.value)}
+                        title="Process with a plugin"
+                        className="h-8 px-1 rounded bg-transparent text-xs text-gray-400 hover:text-white focus:outline-none"
+                     >
+                        <option value="">Process...</option>
+                        {processors.map(p => <option key={`${p.plugin}/${p.id}`} value={`${p.plugin}/${p.id}`}>{p.label}</option>)}
+                     </select>
+                 )}
                  {onRegenerate && (
                      <Button size="sm" variant="ghost" onClick={onRegenerate} title="Regenerate">
                         <RefreshCw size={16} />
@@ -0,0 +1,299 @@
+import React, { useEffect, useMemo, useRef, useState } from 'react';
+import { Play, Square, FolderOpen, ArrowUpRight, RefreshCw, Tag, Package, Layers, Pencil, Link, Upload, FolderInput, LayoutGrid } from 'lucide-react';
+import { convertFileSrc, invoke } from '@tauri-apps/api/core';
+import { listen, emitTo } from '@tauri-apps/api/event';
+import { TitleBar } from './TitleBar';
+
+const KINDS = [
+  { id: 'all', label: 'All' },
+  { id: 'loop', label: 'Loops' },
+  { id: 'oneshot', label: 'One-shots' },
+  { id: 'import', label: 'Imports' },
+  { id: 'recording', label: 'Recordings' },
+];
+
+const formatSize = (bytes) => bytes >= 1024 * 1024
+  ? `${(bytes / (1024 * 1024)).toFixed(1)} MB`
+  : `${Math.max(1, Math.round(bytes / 1024))} KB`;
+
+// Rendered in the detachable library window (index.html?view=library).
+export function LibraryView() {
+  const [items, setItems] = useState([]);
+  const [kind, setKind] = useState('all');
+  const [filter, setFilter] = useState('');
+  const [playing, setPlaying] = useState(null);
+  const [editingTags, setEditingTags] = useState(null);
+  const [scan, setScan] = useState(null);
+  const listRef = useRef(null);
+
+  const refresh = () => {
+    invoke('list_library').then(setItems).catch(e => console.error("Failed to load library", e));
+  };
+
+  // Checks every file again, for changes the watcher missed; only changed ones are re-read.
+  const rescan = () => {
+    invoke('refresh_library').then(setItems).catch(e => console.error("Failed to rescan library", e));
+  };
+
+  useEffect(() => {
+    refresh();
+    // Same event streams as the main window: new results show up here too.
+    const unlisteners = [
+      listen('job-finished', refresh),
+      listen('sound-pack-imported', refresh),
+      listen('undo-available', refresh),
+      listen('sound-restored', refresh),
+      listen('workspace-changed', refresh),
+      listen('library-changed', refresh),
+      // Only sent for big libraries (library.rs).
+      listen('library-scan-progress', ({ payload }) => {
+        setScan(payload.done < payload.total ? payload : null);
+      }),
+      listen('playback-state', ({ payload }) => {
+        setPlaying(payload.state === 'stopped' ? null : payload.path);
+      }),
+    ];
+    return () => { unlisteners.forEach(u => u.then(fn => fn())); };
+  }, []);
+
+  const visible = useMemo(() => {
+    const needle = filter.trim().toLowerCase();
+    return items.filter(item =>
+      (kind === 'all' || item.kind === kind) &&
+      (!needle || item.file.toLowerCase().includes(needle) || item.tags.some(t => t.toLowerCase().includes(needle)))
+    );
+  }, [items, kind, filter]);
+
+  // Tell the backend which rows are on screen, so their thumbnails render
+  // before the rest of the library (waveform.rs).
+  useEffect(() => {
+    const onScreen = new Set();
+    let timer = null;
+    const observer = new IntersectionObserver(entries => {
+      entries.forEach(entry => {
+        if (entry.isIntersecting) onScreen.add(entry.target.dataset.path);
+        else onScreen.delete(entry.target.dataset.path);
+      });
+      clearTimeout(timer);
+      timer = setTimeout(() => {
+        invoke('set_visible_sounds', { paths: [...onScreen] }).catch(() => {});
+      }, 150);
+    }, { root: listRef.current });
+    listRef.current?.querySelectorAll('[data-path]').forEach(row => observer.observe(row));
+    return () => { clearTimeout(timer); observer.disconnect(); };
+  }, [visible]);
+
+  const togglePlay = (item) => {
+    if (playing === item.path) {
+      invoke('stop_audio').catch(e => console.error("Stop failed", e));
+    } else {
+      invoke('play_audio', { path: item.path }).catch(e => console.error("Playback failed", e));
+    }
+  };
+
+  // Tags are per workspace (workspaces.rs); typed as a comma-separated list.
+  const saveTags = (item, text) => {
+    setEditingTags(null);
+    const tags = text.split(',').map(t => t.trim()).filter(Boolean);
+    invoke('set_sound_tags', { path: item.path, tags }).then(refresh).catch(e => console.error("Failed to save tags", e));
+  };
+
+  // Bundles what the filter shows into a .noisespack in Downloads (sound_pack.rs).
+  const exportPack = () => {
+    const name = prompt(`Name for a pack of ${visible.length} sounds`, filter.trim());
+    if (name === null) return;
+    const license = prompt('License (optional, e.g. CC-BY-4.0)', '') || null;
+    invoke('export_sound_pack', { paths: visible.map(item => item.path), info: { name, license } })
+      .then(path => invoke('show_in_folder', { path }))
+      .catch(e => alert('Failed to export sound pack: ' + e));
+  };
+
+  // Copies what the filter shows into a Wwise or FMOD Studio project, with a
+  // manifest to import them by (middleware.rs).
+  const exportToMiddleware = () => {
+    const project = prompt(`Wwise or FMOD Studio project folder for ${visible.length} sounds`);
+    if (!project) return;
+    invoke('export_to_middleware', { paths: visible.map(item => item.path), project: project.trim() })
+      .then(result => invoke('show_in_folder', { path: result.manifest }))
+      .catch(e => alert('Failed to export: ' + e));
+  };
+
+  // Puts what the filter shows on free pads of an SP-404 or MPC layout, as
+  // 16-bit 44.1 kHz WAV (samplers.rs).
+  const exportForSampler = () => {
+    const layout = prompt('Sampler: sp404 or mpc', 'sp404')?.trim().toLowerCase();
+    if (!layout) return;
+    if (layout !== 'sp404' && layout !== 'mpc') {
+      alert('Unknown sampler: ' + layout);
+      return;
+    }
+    const destination = prompt(`SD card or folder for ${visible.length} sounds`);
+    if (!destination) return;
+    invoke('export_for_sampler', { paths: visible.map(item => item.path), layout, destination: destination.trim() })
+      .then(result => invoke('show_in_folder', { path: result.files[0] }))
+      .catch(e => alert('Failed to export: ' + e));
+  };
+
+  // Downloads into Imports, tagged "reference" (import.rs). Progress shows as
+  // a download task.
+  const importFromUrl = () => {
+    const url = prompt('Link to a WAV, MP3 or FLAC file');
+    if (!url) return;
+    invoke('import_from_url', { url })
+      .then(refresh)
+      .catch(e => { if (e !== 'Cancelled') alert('Failed to import: ' + e); });
+  };
+
+  // Tags, stems and history follow the file (filenames.rs).
+  const rename = (item) => {
+    const name = prompt('New name', item.file.replace(/\.[^.]+$/, ''));
+    if (!name) return;
+    invoke('rename_sound', { path: item.path, name })
+      .then(refresh)
+      .catch(e => alert('Failed to rename: ' + e));
+  };
+
+  // Publishes the generation the file came from (freesound.rs). Its tags go
+  // along; Freesound wants at least three.
+  const publish = async (item) => {
+    try {
+      const entry = (await invoke('list_history')).find(e => e.outputs.includes(item.path));
+      if (!entry) {
+        alert('Only generated sounds can be published');
+        return;
+      }
+      const tags = prompt('Freesound tags (comma-separated, at least 3 with the sound\'s own)', item.tags.join(', '));
+      if (tags === null) return;
+      const license = prompt('License: cc0, by or by-nc', 'cc0');
+      if (!license) return;
+      await invoke('upload_to_freesound', {
+        id: entry.id,
+        metadata: { tags: tags.split(',').map(t => t.trim()).filter(Boolean), license: license.trim().toLowerCase() },
+      });
+      alert('Uploaded; it goes public once Freesound has moderated it');
+    } catch (e) {
+      alert('Failed to publish: ' + e);
+    }
+  };
+
+  // Copies the sound and its stems into one folder in Downloads (stems.rs).
+  const exportStems = (item) => {
+    invoke('export_stems', { path: item.path })
+      .then(path => invoke('show_in_folder', { path }))
+      .catch(e => alert('Failed to export stems: ' + e));
+  };
+
+  // Shows the sound in the main window's player, like a notification click does.
+  const openInMain = (item) => {
+    emitTo('main', 'select-sound', { file: item.file, path: item.path });
+  };
+
+  return (
+    <div className="flex flex-col h-screen bg-background text-gray-200 overflow-hidden font-sans">
+      <TitleBar />
+      <div className="pt-10 px-6 pb-3 flex items-center gap-3 border-b border-gray-800">
+        <h1 className="text-lg font-semibold mr-2">Library</h1>
+        <div className="flex gap-1">
+          {KINDS.map(k => (
+            <button
+              key={k.id}
+              onClick={() => setKind(k.id)}
+              className={`px-3 py-1 rounded-full text-xs transition-colors ${kind === k.id ? 'bg-primary text-white' : 'bg-gray-800/50 text-gray-400 hover:bg-gray-800'}`}
+            >
+              {k.label}
+            </button>
+          ))}
+        </div>
+        <input
+          value={filter}
+          onChange={e => setFilter(e.target.value)}
+          placeholder="Filter by name or tag"
+          className="ml-auto h-8 w-48 px-3 rounded-md bg-gray-900 border border-gray-800 text-sm focus:outline-none focus:border-primary"
+        />
+        <button onClick={importFromUrl} title="Import from URL" className="p-1.5 text-gray-400 hover:text-white transition-colors">
+          <Link size={14} />
+        </button>
+        <button onClick={exportPack} disabled={visible.length === 0} title="Export as sound pack" className="p-1.5 text-gray-400 hover:text-white disabled:opacity-40 transition-colors">
+          <Package size={14} />
+        </button>
+        <button onClick={exportToMiddleware} disabled={visible.length === 0} title="Export to Wwise or FMOD" className="p-1.5 text-gray-400 hover:text-white disabled:opacity-40 transition-colors">
+          <FolderInput size={14} />
+        </button>
+        <button onClick={exportForSampler} disabled={visible.length === 0} title="Export for a hardware sampler" className="p-1.5 text-gray-400 hover:text-white disabled:opacity-40 transition-colors">
+          <LayoutGrid size={14} />
+        </button>
+        <button onClick={rescan} title="Rescan" className="p-1.5 text-gray-400 hover:text-white transition-colors">
+          <RefreshCw size={14} />
+        </button>
+      </div>
+      <div ref={listRef} className="flex-1 overflow-y-auto custom-scrollbar px-4 py-2">
+        {scan && (
+          <div className="text-center text-xs text-gray-500 mt-2">Scanning {scan.done} of {scan.total} sounds...</div>
+        )}
+        {visible.length === 0 && !scan && (
+          <div className="text-center text-sm text-gray-600 italic mt-12">No sounds here yet.</div>
+        )}
+        {visible.map(item => (
+          <div key={item.path} data-path={item.path} className="group flex items-center gap-3 px-2 py-2 rounded-md hover:bg-gray-800/50">
+            <button
+              onClick={() => togglePlay(item)}
+              className="h-8 w-8 shrink-0 rounded-full bg-gray-800 group-hover:bg-primary text-white flex items-center justify-center transition-colors"
+            >
+              {playing === item.path ? <Square size={12} /> : <Play size={14} />}
+            </button>
+            <div className="min-w-0 flex-1">
+              <div className="text-sm truncate">{item.file}</div>
+              <div className="text-xs text-gray-500">
+                {item.kind} · {formatSize(item.size)} · {new Date(item.modified).toLocaleString()}
+                {item.stems.length > 0 && ` · ${item.stems.length} stems`}
+                {item.stem_of && ` · stem of ${item.stem_of.split(/[\\/]/).pop()}`}
+              </div>
+              {editingTags === item.path ? (
+                <input
+                  autoFocus
+                  defaultValue={item.tags.join(', ')}
+                  onBlur={e => saveTags(item, e.target.value)}
+                  onKeyDown={e => { if (e.key === 'Enter') e.target.blur(); if (e.key === 'Escape') setEditingTags(null); }}
+                  placeholder="drums, dark, 808"
+                  className="mt-1 h-6 w-full px-2 rounded bg-gray-900 border border-gray-800 text-xs focus:outline-none focus:border-primary"
+                />
+              ) : item.tags.length > 0 && (
+                <div className="mt-1 flex flex-wrap gap-1">
+                  {item.tags.map(t => (
+                    <span key={t} className="px-1.5 py-0.5 rounded bg-gray-800 text-[10px] text-gray-400">{t}</span>
+                  ))}
+                </div>
+              )}
+            </div>
+            <img
+              src={`${convertFileSrc('thumb/' + item.path, 'noises')}?w=160&h=24`}
+              loading="lazy"
+              alt=""
+              className="h-6 w-40 shrink-0 opacity-70"
+            />
+            {item.stems.length > 0 && (
+              <button onClick={() => exportStems(item)} title="Export stems as folder" className="p-1.5 text-gray-500 hover:text-white opacity-0 group-hover:opacity-100 transition-opacity">
+                <Layers size={14} />
+              </button>
+            )}
+            <button onClick={() => publish(item)} title="Publish to Freesound" className="p-1.5 text-gray-500 hover:text-white opacity-0 group-hover:opacity-100 transition-opacity">
+              <Upload size={14} />
+            </button>
+            <button onClick={() => rename(item)} title="Rename" className="p-1.5 text-gray-500 hover:text-white opacity-0 group-hover:opacity-100 transition-opacity">
+              <Pencil size={14} />
+            </button>
+            <button onClick={() => setEditingTags(item.path)} title="Edit tags" className="p-1.5 text-gray-500 hover:text-white opacity-0 group-hover:opacity-100 transition-opacity">
+              <Tag size={14} />
+            </button>
+            <button onClick={() => openInMain(item)} title="Open in main window" className="p-1.5 text-gray-500 hover:text-white opacity-0 group-hover:opacity-100 transition-opacity">
+              <ArrowUpRight size={14} />
+            </button>
+            <button onClick={() => invoke('show_in_folder', { path: item.path }).catch(e => console.error("Failed to open folder", e))} title="Show in folder" className="p-1.5 text-gray-500 hover:text-white opacity-0 group-hover:opacity-100 transition-opacity">
+              <FolderOpen size={14} />
+            </button>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
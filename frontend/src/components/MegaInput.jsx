@@ -1,14 +1,17 @@
 import React, { useState } from 'react';
-import { Settings2, Sliders, Wand2, X } from 'lucide-react';
+import { Settings2, Sliders, Wand2, Dices, Layers, X } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
 import { motion, AnimatePresence } from 'framer-motion';
 import { Button } from './Button';
 import { Input, Select } from './Input';
 import { cn } from '../lib/utils';
 
-export function MegaInput({ config, setConfig, onGenerate, generating, backendReady, onAddContext }) {
+export function MegaInput({ config, setConfig, onGenerate, onSweep, generating, backendReady, providers = [], renderTarget = null, onAddContext }) {
   const [expanded, setExpanded] = useState(false);
   const [showAdvanced, setShowAdvanced] = useState(false);
   const [showContext, setShowContext] = useState(false);
+  // A remote provider doesn't need the local engine to be up.
+  const ready = backendReady || (config.provider || 'local') !== 'local';
 
   const toggleExpand = () => setExpanded(!expanded);
   const toggleAdvanced = () => setShowAdvanced(!showAdvanced);
@@ -32,6 +35,13 @@ export function MegaInput({ config, setConfig, onGenerate, generating, backendRe
     { label: "Energetic / Upbeat", prompt: ", energetic, fast tempo, dynamic, uplifting", negative: "slow, calm, mellow" }
   ];
 
+  // Made up offline from word lists (suggest.rs); songs get song prompts.
+  const surpriseMe = () => {
+      invoke('suggest_prompt', { category: config.type === 'loop' ? null : 'song' })
+          .then(prompt => setConfig({ ...config, prompt }))
+          .catch(e => console.error("Failed to suggest a prompt", e));
+  };
+
   const handleContextClick = (opt) => {
       onAddContext(opt);
       setShowContext(false);
@@ -74,6 +84,13 @@ export function MegaInput({ config, setConfig, onGenerate, generating, backendRe
                  </div>
 
                  <div className="flex gap-2 relative">
+                     <button
+                        onClick={surpriseMe}
+                        title="Surprise me"
+                        className="text-gray-400 hover:text-primary transition-colors p-2 rounded-full hover:bg-gray-800"
+                     >
+                         <Dices size={16} />
+                     </button>
                      <div className="relative">
                         <button 
                             onClick={() => setShowContext(!showContext)}
@@ -277,10 +294,43 @@ export function MegaInput({ config, setConfig, onGenerate, generating, backendRe
             </AnimatePresence>
 
             {/* Generate Button Area - Same Background Color - Fixed Radius */}
-            <div className="p-2 flex justify-end bg-panel rounded-b-xl">
+            <div className="p-2 flex justify-end gap-2 bg-panel rounded-b-xl">
+                {renderTarget && (
+                    <label className="flex items-center gap-1.5 text-xs text-gray-400 px-1" title={`Copy the result to ${renderTarget}`}>
+                        <input
+                            type="checkbox"
+                            checked={!!config.renderTarget}
+                            onChange={(e) => setConfig({ ...config, renderTarget: e.target.checked })}
+                        />
+                        To render target
+                    </label>
+                )}
+                {providers.length > 0 && (
+                    <select
+                        value={config.provider || 'local'}
+                        onChange={(e) => setConfig({ ...config, provider: e.target.value })}
+                        title="Where this generation runs"
+                        className="bg-[#252525] border-transparent rounded-lg text-sm px-2.5 focus:outline-none focus:ring-1 focus:ring-primary/50"
+                    >
+                        <option value="local">This computer</option>
+                        {providers.map(p => <option key={p.name} value={p.name}>{p.name}</option>)}
+                    </select>
+                )}
+                {onSweep && (
+                    <Button
+                        variant="secondary"
+                        onClick={onSweep}
+                        disabled={!ready || !config.prompt}
+                        size="lg"
+                        title="Generate a few variations with different seeds"
+                        className="rounded-lg px-4"
+                    >
+                        <Layers size={18} />
+                    </Button>
+                )}
                 <Button 
                     onClick={onGenerate} 
-                    disabled={generating || !backendReady}
+                    disabled={generating || !ready}
                     isLoading={generating}
                     size="lg"
                     className="rounded-lg px-8 w-full md:w-auto font-bold tracking-wide"
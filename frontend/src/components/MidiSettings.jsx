@@ -0,0 +1,133 @@
+import React, { useEffect, useState } from 'react';
+import { X } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { Button } from './Button';
+
+const describeTrigger = (t) => `${t.kind === 'note' ? 'Note' : 'CC'} ${t.number}${t.channel ? ` (ch ${t.channel})` : ''}`;
+
+const describeAction = (a, presets) => {
+  if (a.type === 'play_sound') return `Play sound #${a.index}`;
+  if (a.type === 'generate_preset') {
+    const preset = presets.find(p => p.id === a.preset);
+    return `Generate "${preset ? preset.label : 'missing preset'}"`;
+  }
+  return 'Stop playback';
+};
+
+// MIDI input and its note/CC mappings (midi.rs). "Map a control" waits for
+// the next key or knob, then asks what it should do.
+export function MidiSettings({ onError }) {
+  const [enabled, setEnabled] = useState(false);
+  const [input, setInput] = useState(null);
+  const [inputs, setInputs] = useState([]);
+  const [mappings, setMappings] = useState([]);
+  const [presets, setPresets] = useState([]);
+  const [learning, setLearning] = useState(false);
+  const [pending, setPending] = useState(null);
+  const [action, setAction] = useState({ type: 'play_sound', index: 1 });
+
+  useEffect(() => {
+    invoke('get_all_settings').then(s => {
+      setEnabled(s.midi_enabled);
+      setInput(s.midi_input);
+      setMappings(s.midi_mappings);
+    }).catch(e => onError(String(e)));
+    invoke('list_midi_inputs').then(setInputs).catch(e => onError(String(e)));
+    invoke('get_workspace_presets').then(p => setPresets(p || [])).catch(e => onError(String(e)));
+  }, []);
+
+  useEffect(() => {
+    if (!learning) return;
+    const unlisten = listen('midi-input', ({ payload }) => {
+      setPending({ kind: payload.kind, channel: payload.channel, number: payload.number });
+      setLearning(false);
+    });
+    return () => { unlisten.then(fn => fn()); };
+  }, [learning]);
+
+  const save = (key, value) => {
+    invoke('set_setting', { key, value }).catch(e => onError(String(e)));
+  };
+
+  const map = (trigger, nextAction) => {
+    invoke('set_midi_mapping', { trigger, action: nextAction })
+      .then(setMappings)
+      .catch(e => onError(String(e)));
+  };
+
+  const confirmPending = () => {
+    map(pending, action);
+    setPending(null);
+  };
+
+  return (
+    <div className="space-y-1">
+      <label className="flex items-center gap-2 text-xs text-gray-400">
+        <input
+          type="checkbox"
+          checked={enabled}
+          onChange={e => { setEnabled(e.target.checked); save('midi_enabled', e.target.checked); }}
+        />
+        MIDI triggers from
+        <select
+          value={input || ''}
+          onChange={e => { const value = e.target.value || null; setInput(value); save('midi_input', value); }}
+          className="h-6 px-1 rounded bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+        >
+          <option value="">Every input</option>
+          {inputs.map(name => <option key={name} value={name}>{name}</option>)}
+        </select>
+      </label>
+
+      {enabled && (
+        <div className="space-y-1 pl-5">
+          {mappings.map(m => (
+            <div key={`${m.trigger.kind}-${m.trigger.channel}-${m.trigger.number}`} className="flex items-center gap-2 text-[11px] text-gray-400">
+              <span className="w-28 font-mono">{describeTrigger(m.trigger)}</span>
+              <span className="flex-1 truncate">{describeAction(m.action, presets)}</span>
+              <button onClick={() => map(m.trigger, null)} className="text-gray-600 hover:text-red-400" title="Remove mapping">
+                <X size={10} />
+              </button>
+            </div>
+          ))}
+
+          {pending ? (
+            <div className="flex items-center gap-2 text-[11px] text-gray-400">
+              <span className="w-28 font-mono">{describeTrigger(pending)}</span>
+              <select
+                value={action.type === 'generate_preset' ? `preset-${action.preset}` : action.type}
+                onChange={e => {
+                  const value = e.target.value;
+                  if (value.startsWith('preset-')) setAction({ type: 'generate_preset', preset: Number(value.slice(7)) });
+                  else if (value === 'play_sound') setAction({ type: 'play_sound', index: 1 });
+                  else setAction({ type: 'stop_playback' });
+                }}
+                className="h-6 px-1 rounded bg-gray-900/50 border border-gray-800 text-xs text-gray-200"
+              >
+                <option value="play_sound">Play sound #</option>
+                {presets.map(p => <option key={p.id} value={`preset-${p.id}`}>Generate "{p.label}"</option>)}
+                <option value="stop_playback">Stop playback</option>
+              </select>
+              {action.type === 'play_sound' && (
+                <input
+                  type="number"
+                  min="1"
+                  value={action.index}
+                  onChange={e => setAction({ type: 'play_sound', index: Math.max(1, Number(e.target.value)) })}
+                  className="w-14 h-6 px-2 rounded bg-gray-900/50 border border-gray-800 text-xs text-gray-200"
+                />
+              )}
+              <Button size="sm" onClick={confirmPending}>Map</Button>
+              <Button size="sm" variant="ghost" onClick={() => setPending(null)}>Cancel</Button>
+            </div>
+          ) : (
+            <Button size="sm" variant="ghost" onClick={() => setLearning(!learning)}>
+              {learning ? 'Press a key or move a control...' : 'Map a control'}
+            </Button>
+          )}
+        </div>
+      )}
+    </div>
+  );
+}
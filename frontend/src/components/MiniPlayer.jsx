@@ -0,0 +1,86 @@
+import React, { useEffect, useState } from 'react';
+import { Play, Pause, Square, Pin, PinOff, X } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+
+const fileName = (path) => path ? path.split(/[\\/]/).pop() : null;
+
+// Rendered in the always-on-top mini player window (index.html?view=mini).
+// Everything comes from the same Rust events the main window gets.
+export function MiniPlayer() {
+  const [playback, setPlayback] = useState({ state: 'stopped', path: null });
+  const [queue, setQueue] = useState({ active: [], recent: [], batch_total: 0, batch_done: 0 });
+  const [onTop, setOnTop] = useState(true);
+
+  useEffect(() => {
+    invoke('queue_status').then(setQueue).catch(e => console.error("Failed to load queue", e));
+    const unlisteners = [
+      listen('playback-state', ({ payload }) => setPlayback(payload)),
+      listen('jobs-changed', ({ payload }) => setQueue(payload)),
+    ];
+    return () => { unlisteners.forEach(u => u.then(fn => fn())); };
+  }, []);
+
+  const playOrToggle = () => {
+    if (playback.state === 'stopped') {
+      const last = playback.path || queue.recent[0];
+      if (last) invoke('play_audio', { path: last }).catch(e => console.error("Playback failed", e));
+    } else {
+      invoke('toggle_audio').catch(e => console.error("Toggle failed", e));
+    }
+  };
+
+  const toggleOnTop = () => {
+    const next = !onTop;
+    invoke('set_mini_player_on_top', { enabled: next })
+      .then(() => setOnTop(next))
+      .catch(e => console.error("Failed to change always-on-top", e));
+  };
+
+  const title = fileName(playback.path) || fileName(queue.recent[0]) || 'Nothing played yet';
+  const status = queue.active.length === 0
+    ? 'Idle'
+    : queue.batch_total > 1
+      ? `Generating ${queue.batch_done + 1} of ${queue.batch_total}`
+      : `Generating "${queue.active[0].prompt}"`;
+
+  return (
+    <div className="h-screen w-screen flex flex-col bg-gray-950 text-gray-200 border border-gray-800 rounded-lg overflow-hidden select-none">
+      <div data-tauri-drag-region className="flex items-center justify-between h-6 px-2 text-[10px] uppercase tracking-wider text-gray-500">
+        <span data-tauri-drag-region>Noises</span>
+        <div className="flex items-center gap-1">
+          <button onClick={toggleOnTop} title={onTop ? "Unpin" : "Keep on top"} className="p-0.5 hover:text-white transition-colors">
+            {onTop ? <Pin size={12} /> : <PinOff size={12} />}
+          </button>
+          <button onClick={() => invoke('close_mini_player')} className="p-0.5 hover:text-red-400 transition-colors">
+            <X size={12} />
+          </button>
+        </div>
+      </div>
+      <div className="flex-1 flex items-center gap-3 px-3">
+        <button
+          onClick={playOrToggle}
+          className="h-9 w-9 shrink-0 rounded-full bg-primary hover:bg-primary-hover text-white flex items-center justify-center transition-colors"
+        >
+          {playback.state === 'playing' ? <Pause size={16} /> : <Play size={16} />}
+        </button>
+        <button
+          onClick={() => invoke('stop_audio')}
+          disabled={playback.state === 'stopped'}
+          className="h-7 w-7 shrink-0 rounded-md bg-gray-800 hover:bg-gray-700 text-gray-300 flex items-center justify-center disabled:opacity-40 transition-colors"
+        >
+          <Square size={12} />
+        </button>
+        <div className="min-w-0 flex-1">
+          <div className="text-sm truncate">{title}</div>
+          <div className="text-xs text-gray-500 truncate">{status}</div>
+          {queue.batch_total > 1 && queue.active.length > 0 && (
+            <div className="mt-1 h-1 rounded bg-gray-800 overflow-hidden">
+              <div className="h-full bg-primary" style={{ width: `${(queue.batch_done / queue.batch_total) * 100}%` }} />
+            </div>
+          )}
+        </div>
+      </div>
+    </div>
+  );
+}
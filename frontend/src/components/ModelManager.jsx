@@ -0,0 +1,620 @@
+import React, { useEffect, useRef, useState } from 'react';
+import { Download, Trash2, Star, X, FolderOpen, Eraser } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { Button } from './Button';
+import { MidiSettings } from './MidiSettings';
+import { SyncSettings } from './SyncSettings';
+
+const formatSize = (bytes) => bytes >= 1024 * 1024 * 1024
+  ? `${(bytes / (1024 * 1024 * 1024)).toFixed(1)} GB`
+  : `${Math.round(bytes / (1024 * 1024))} MB`;
+
+const formatDate = (millis) => millis ? new Date(millis).toLocaleDateString() : 'never';
+
+// Checkpoints unused for this long are what "Purge unused" removes.
+const PURGE_DAYS = 30;
+
+// Download, delete and pick the default checkpoint (see models.rs).
+export function ModelManager({ onClose }) {
+  const [models, setModels] = useState([]);
+  const [progress, setProgress] = useState({});
+  const [error, setError] = useState(null);
+  const [token, setToken] = useState('');
+  const [tokenSaved, setTokenSaved] = useState(false);
+  const [proxyMode, setProxyMode] = useState('system');
+  const [proxyUrl, setProxyUrl] = useState('');
+  const [profileStatus, setProfileStatus] = useState(null);
+  const [telemetry, setTelemetry] = useState(false);
+  const [lowVram, setLowVram] = useState(false);
+  const [offline, setOffline] = useState(false);
+  const [api, setApi] = useState({ enabled: false, port: 8765, token: null });
+  const [osc, setOsc] = useState({ enabled: false, port: 9000 });
+  const [watchFolder, setWatchFolder] = useState('');
+  const [hook, setHook] = useState('');
+  const [thermalLimit, setThermalLimit] = useState('');
+  const [promptNames, setPromptNames] = useState(false);
+  const [engineMetadata, setEngineMetadata] = useState(false);
+  const [editor, setEditor] = useState('');
+  const [renderTarget, setRenderTarget] = useState('');
+  const [cueDevice, setCueDevice] = useState('');
+  const [outputDevices, setOutputDevices] = useState([]);
+  const [cache, setCache] = useState([]);
+  const [webhook, setWebhook] = useState('');
+  const [webhookSaved, setWebhookSaved] = useState(false);
+  const [freesound, setFreesound] = useState({ available: false, connected: false });
+  const [providers, setProviders] = useState([]);
+  const [providerKeys, setProviderKeys] = useState({ replicate: '', runpod: '' });
+  const profileInput = useRef(null);
+
+  const refresh = () => {
+    invoke('list_models').then(setModels).catch(e => setError(String(e)));
+    invoke('list_cached_checkpoints').then(setCache).catch(e => setError(String(e)));
+  };
+
+  useEffect(() => {
+    refresh();
+    invoke('get_all_settings').then(s => {
+      setProxyMode(s.proxy_mode);
+      setProxyUrl(s.proxy_url || '');
+      setTelemetry(s.telemetry);
+      setLowVram(s.low_vram);
+      setOffline(s.offline);
+      setApi(prev => ({ ...prev, enabled: s.api_enabled, port: s.api_port }));
+      setOsc({ enabled: s.osc_enabled, port: s.osc_port });
+      setWatchFolder(s.watch_folder || '');
+      setHook(s.post_generation_hook || '');
+      setThermalLimit(s.thermal_limit ?? '');
+      setPromptNames(s.prompt_file_names);
+      setEngineMetadata(s.engine_metadata);
+      setEditor(s.external_editor || '');
+      setRenderTarget(s.render_target || '');
+      setCueDevice(s.cue_device || '');
+      setProviders(s.remote_providers);
+    }).catch(e => setError(String(e)));
+    invoke('get_secret', { key: 'huggingface-token' }).then(t => setToken(t || '')).catch(e => setError(String(e)));
+    invoke('get_secret', { key: 'api-token' }).then(t => setApi(prev => ({ ...prev, token: t }))).catch(e => setError(String(e)));
+    invoke('get_secret', { key: 'discord-webhook' }).then(w => setWebhook(w || '')).catch(e => setError(String(e)));
+    invoke('freesound_status').then(setFreesound).catch(e => setError(String(e)));
+    invoke('list_output_devices').then(setOutputDevices).catch(e => setError(String(e)));
+    invoke('get_secret', { key: 'replicate-token' }).then(k => setProviderKeys(prev => ({ ...prev, replicate: k || '' }))).catch(e => setError(String(e)));
+    invoke('get_secret', { key: 'runpod-api-key' }).then(k => setProviderKeys(prev => ({ ...prev, runpod: k || '' }))).catch(e => setError(String(e)));
+    const unlisteners = [
+      listen('model-download-progress', ({ payload }) => {
+        setProgress(prev => ({ ...prev, [payload.model]: payload }));
+      }),
+      listen('model-download-finished', ({ payload }) => {
+        setProgress(prev => { const next = { ...prev }; delete next[payload.model]; return next; });
+        if (payload.error) setError(payload.error);
+        refresh();
+      }),
+      listen('freesound-connected', ({ payload }) => {
+        if (payload.error) setError(payload.error);
+        invoke('freesound_status').then(setFreesound).catch(e => setError(String(e)));
+      }),
+    ];
+    return () => { unlisteners.forEach(u => u.then(fn => fn())); };
+  }, []);
+
+  const run = (command, id) => {
+    setError(null);
+    invoke(command, { id }).then(refresh).catch(e => setError(String(e)));
+  };
+
+  const purge = () => {
+    if (!confirm(`Delete model checkpoints that haven't been used in ${PURGE_DAYS} days? The default model is kept.`)) return;
+    setError(null);
+    invoke('purge_unused_models', { days: PURGE_DAYS })
+      .then(report => {
+        alert(report.removed.length ? `Freed ${formatSize(report.freed)}` : 'Nothing to purge');
+        refresh();
+      })
+      .catch(e => setError(String(e)));
+  };
+
+  // Kept in the OS keychain (secrets.rs), used for downloads and by the backend.
+  const saveToken = () => {
+    setError(null);
+    invoke('set_secret', { key: 'huggingface-token', value: token })
+      .then(() => setTokenSaved(true))
+      .catch(e => setError(String(e)));
+  };
+
+  // "Post to Discord" in the player sends generations here (discord.rs).
+  const saveWebhook = () => {
+    setError(null);
+    invoke('set_secret', { key: 'discord-webhook', value: webhook })
+      .then(() => setWebhookSaved(true))
+      .catch(e => setError(String(e)));
+  };
+
+  // Remote hosts a generation can be sent to instead of this computer
+  // (providers.rs). The model there has to take the backend's parameters.
+  const saveProviders = (next) => {
+    setError(null);
+    invoke('set_setting', { key: 'remote_providers', value: next })
+      .then(() => setProviders(next))
+      .catch(e => setError(String(e)));
+  };
+
+  const addProvider = () => {
+    const kind = prompt('Provider: replicate, runpod or grpc', 'replicate')?.trim().toLowerCase();
+    if (!kind) return;
+    if (!['replicate', 'runpod', 'grpc'].includes(kind)) {
+      setError('Unknown provider: ' + kind);
+      return;
+    }
+    const field = { replicate: 'version', runpod: 'endpoint_id', grpc: 'url' }[kind];
+    const target = prompt({
+      replicate: 'Model version id',
+      runpod: 'Endpoint id',
+      grpc: 'Backend address (started with --grpc-port)',
+    }[kind], kind === 'grpc' ? 'http://127.0.0.1:50051' : '');
+    if (!target?.trim()) return;
+    const name = prompt('Name to pick it by', { replicate: 'Replicate', runpod: 'RunPod', grpc: 'gRPC' }[kind]);
+    if (!name?.trim()) return;
+    const provider = { name: name.trim(), kind, [field]: target.trim() };
+    saveProviders([...providers.filter(p => p.name !== provider.name), provider]);
+  };
+
+  const saveProviderKey = (kind) => {
+    const key = kind === 'replicate' ? 'replicate-token' : 'runpod-api-key';
+    invoke('set_secret', { key, value: providerKeys[kind] }).catch(e => setError(String(e)));
+  };
+
+  // Model downloads and update checks go through this (proxy.rs).
+  const saveProxy = async (mode, url) => {
+    setError(null);
+    try {
+      await invoke('set_setting', { key: 'proxy_url', value: url.trim() || null });
+      await invoke('set_setting', { key: 'proxy_mode', value: mode });
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  // Settings, presets and hotkeys in one file for another machine (profile.rs).
+  const exportProfile = () => {
+    setError(null);
+    invoke('export_profile')
+      .then(path => { setProfileStatus(`Saved to ${path}`); return invoke('show_in_folder', { path }); })
+      .catch(e => setError(String(e)));
+  };
+
+  // Every generation in this workspace, for spreadsheets and scripts (history.rs).
+  const exportHistory = (format) => {
+    setError(null);
+    invoke('export_history', { format })
+      .then(path => { setProfileStatus(`Saved to ${path}`); return invoke('show_in_folder', { path }); })
+      .catch(e => setError(String(e)));
+  };
+
+  const importProfile = async (file) => {
+    setError(null);
+    try {
+      const report = await invoke('import_profile', { contents: await file.text() });
+      const summary = `Imported ${report.settings} settings, ${report.presets} presets, ${report.hotkeys} hotkeys`;
+      setProfileStatus(report.skipped.length ? `${summary}. Skipped: ${report.skipped.join('; ')}` : summary);
+      const s = await invoke('get_all_settings');
+      setProxyMode(s.proxy_mode);
+      setProxyUrl(s.proxy_url || '');
+      refresh();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  // Local HTTP API for scripts and editor plugins (http_api.rs). The token is
+  // made the first time it's turned on.
+  const saveSetting = async (key, value) => {
+    setError(null);
+    try {
+      await invoke('set_setting', { key, value });
+      if (key === 'api_enabled' && value && !api.token) {
+        // Give the server a moment to create it.
+        setTimeout(() => invoke('get_secret', { key: 'api-token' }).then(t => setApi(prev => ({ ...prev, token: t }))), 500);
+      }
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const resetApiToken = () => {
+    invoke('reset_api_token').then(t => setApi(prev => ({ ...prev, token: t }))).catch(e => setError(String(e)));
+  };
+
+  const total = models.reduce((sum, m) => sum + m.size, 0);
+
+  return (
+    <div className="absolute inset-0 z-[60] flex items-center justify-center bg-black/60">
+      <div className="w-full max-w-md bg-panel rounded-xl border border-gray-700/50 p-5 shadow-lg space-y-4">
+        <div className="flex items-center justify-between">
+          <div>
+            <h2 className="text-base font-semibold text-white">Models</h2>
+            <p className="text-xs text-gray-500">{formatSize(total)} on disk</p>
+          </div>
+          <button onClick={onClose} className="text-gray-500 hover:text-white"><X size={16} /></button>
+        </div>
+
+        <div className="space-y-2">
+          {models.map(model => {
+            const p = progress[model.id];
+            return (
+              <div key={model.id} className="p-3 rounded-lg bg-gray-900/50 border border-gray-800 space-y-2">
+                <div className="flex items-center gap-2">
+                  <div className="flex-1 min-w-0">
+                    <div className="text-sm text-gray-200 truncate">{model.name}</div>
+                    <div className="text-[11px] text-gray-500">
+                      {model.generation_type === 'loop' ? 'Loops' : 'Full songs'} · {model.installed ? formatSize(model.size) : 'Not downloaded'}
+                      {model.installed && ` · last used ${formatDate(cache.find(c => c.model === model.id)?.last_used)}`}
+                    </div>
+                  </div>
+                  <button
+                    onClick={() => run('set_default_model', model.id)}
+                    title={model.is_default ? 'Default model' : 'Make default'}
+                    className={model.is_default ? 'text-primary' : 'text-gray-600 hover:text-gray-300'}
+                  >
+                    <Star size={14} fill={model.is_default ? 'currentColor' : 'none'} />
+                  </button>
+                  {model.installed ? (
+                    <Button size="sm" variant="ghost" onClick={() => run('delete_model', model.id)} title="Delete">
+                      <Trash2 size={14} />
+                    </Button>
+                  ) : (
+                    <Button size="sm" variant="secondary" disabled={model.downloading || !!p} onClick={() => run('download_model', model.id)}>
+                      <Download size={14} className="mr-1" /> Download
+                    </Button>
+                  )}
+                </div>
+                {p && (
+                  <div className="space-y-1">
+                    <div className="h-1 rounded bg-gray-800 overflow-hidden">
+                      <div className="h-full bg-primary transition-all" style={{ width: `${p.total ? (p.downloaded / p.total) * 100 : 0}%` }} />
+                    </div>
+                    <div className="text-[10px] text-gray-500 truncate">{formatSize(p.downloaded)} / {formatSize(p.total)} · {p.file}</div>
+                  </div>
+                )}
+              </div>
+            );
+          })}
+        </div>
+
+        {/* The whole cache, other programs' checkpoints included (models.rs). */}
+        <div className="space-y-1">
+          <div className="flex items-center gap-2">
+            <label className="flex-1 text-xs text-gray-400">Model cache · {formatSize(cache.reduce((sum, c) => sum + c.size, 0))}</label>
+            <Button size="sm" variant="ghost" onClick={() => invoke('reveal_model_cache').catch(e => setError(String(e)))} title="Show cache folder">
+              <FolderOpen size={14} />
+            </Button>
+            <Button size="sm" variant="ghost" onClick={purge} title={`Purge models unused for ${PURGE_DAYS} days`}>
+              <Eraser size={14} />
+            </Button>
+          </div>
+          {cache.filter(c => !c.model).map(c => (
+            <div key={c.path} className="flex gap-2 text-[11px] text-gray-500">
+              <span className="flex-1 truncate" title={c.path}>{c.repo}</span>
+              <span>{formatSize(c.size)} · {formatDate(c.downloaded)}</span>
+            </div>
+          ))}
+        </div>
+
+        <div className="space-y-1">
+          <label className="text-xs text-gray-400">Hugging Face token (for gated models)</label>
+          <div className="flex gap-2">
+            <input
+              type="password"
+              value={token}
+              onChange={e => { setToken(e.target.value); setTokenSaved(false); }}
+              placeholder="hf_..."
+              className="flex-1 h-8 px-2 rounded-md bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+            />
+            <Button size="sm" variant="secondary" onClick={saveToken}>{tokenSaved ? 'Saved' : 'Save'}</Button>
+          </div>
+        </div>
+
+        <div className="space-y-1">
+          <label className="text-xs text-gray-400">Discord webhook (for Post to Discord)</label>
+          <div className="flex gap-2">
+            <input
+              type="password"
+              value={webhook}
+              onChange={e => { setWebhook(e.target.value); setWebhookSaved(false); }}
+              placeholder="https://discord.com/api/webhooks/..."
+              className="flex-1 h-8 px-2 rounded-md bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+            />
+            <Button size="sm" variant="secondary" onClick={saveWebhook}>{webhookSaved ? 'Saved' : 'Save'}</Button>
+          </div>
+        </div>
+
+        {/* Sign-in happens in the browser and comes back as noises://freesound (freesound.rs). */}
+        {freesound.available && (
+          <div className="flex items-center gap-2">
+            <label className="flex-1 text-xs text-gray-400">
+              Freesound {freesound.connected ? '· connected' : '(for publishing from the library)'}
+            </label>
+            {freesound.connected ? (
+              <Button size="sm" variant="ghost" onClick={() => invoke('disconnect_freesound').then(() => setFreesound({ available: true, connected: false })).catch(e => setError(String(e)))}>
+                Disconnect
+              </Button>
+            ) : (
+              <Button size="sm" variant="secondary" onClick={() => invoke('connect_freesound').catch(e => setError(String(e)))}>
+                Connect
+              </Button>
+            )}
+          </div>
+        )}
+
+        <div className="space-y-1">
+          <div className="flex items-center gap-2">
+            <label className="flex-1 text-xs text-gray-400">Remote generation</label>
+            <Button size="sm" variant="ghost" onClick={addProvider}>Add</Button>
+          </div>
+          {providers.map(p => (
+            <div key={p.name} className="flex items-center gap-2 text-[11px] text-gray-500">
+              <span className="flex-1 truncate">{p.name} · {p.version ?? p.endpoint_id ?? p.url}</span>
+              <button onClick={() => saveProviders(providers.filter(q => q.name !== p.name))} title="Remove" className="text-gray-600 hover:text-gray-300">
+                <Trash2 size={12} />
+              </button>
+            </div>
+          ))}
+          {[...new Set(providers.map(p => p.kind))].filter(kind => kind !== 'grpc').map(kind => (
+            <input
+              key={kind}
+              type="password"
+              value={providerKeys[kind]}
+              onChange={e => setProviderKeys(prev => ({ ...prev, [kind]: e.target.value }))}
+              onBlur={() => saveProviderKey(kind)}
+              placeholder={kind === 'replicate' ? 'Replicate API token' : 'RunPod API key'}
+              className="w-full h-7 px-2 rounded-md bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+            />
+          ))}
+        </div>
+
+        <div className="space-y-1">
+          <label className="text-xs text-gray-400">Proxy</label>
+          <div className="flex gap-2">
+            <select
+              value={proxyMode}
+              onChange={e => { setProxyMode(e.target.value); saveProxy(e.target.value, proxyUrl); }}
+              className="h-8 px-2 rounded-md bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+            >
+              <option value="system">System</option>
+              <option value="off">None</option>
+              <option value="manual">Manual</option>
+            </select>
+            {proxyMode === 'manual' && (
+              <input
+                value={proxyUrl}
+                onChange={e => setProxyUrl(e.target.value)}
+                onBlur={() => saveProxy(proxyMode, proxyUrl)}
+                placeholder="http://proxy.example.com:8080"
+                className="flex-1 h-8 px-2 rounded-md bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+              />
+            )}
+          </div>
+        </div>
+
+        <div className="space-y-1">
+          <label className="flex items-center gap-2 text-xs text-gray-400">
+            <input
+              type="checkbox"
+              checked={api.enabled}
+              onChange={e => { setApi(prev => ({ ...prev, enabled: e.target.checked })); saveSetting('api_enabled', e.target.checked); }}
+            />
+            Local API for other tools on port
+            <input
+              type="number"
+              value={api.port}
+              onChange={e => setApi(prev => ({ ...prev, port: e.target.value }))}
+              onBlur={() => saveSetting('api_port', Number(api.port))}
+              className="w-20 h-6 px-2 rounded bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+            />
+          </label>
+          {api.enabled && api.token && (
+            <div className="flex gap-2 items-center">
+              <code className="flex-1 truncate text-[10px] text-gray-500 select-all">{api.token}</code>
+              <Button size="sm" variant="ghost" onClick={resetApiToken}>Reset token</Button>
+            </div>
+          )}
+        </div>
+
+        {/* /noises/generate, /noises/play and /noises/stop (osc.rs). */}
+        <label className="flex items-center gap-2 text-xs text-gray-400">
+          <input
+            type="checkbox"
+            checked={osc.enabled}
+            onChange={e => { setOsc(prev => ({ ...prev, enabled: e.target.checked })); saveSetting('osc_enabled', e.target.checked); }}
+          />
+          OSC triggers on port
+          <input
+            type="number"
+            value={osc.port}
+            onChange={e => setOsc(prev => ({ ...prev, port: e.target.value }))}
+            onBlur={() => saveSetting('osc_port', Number(osc.port))}
+            className="w-20 h-6 px-2 rounded bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+          />
+        </label>
+
+        <MidiSettings onError={setError} />
+
+        {/* Drop rain.txt or rain.json in here and get rain.wav back (watch_folder.rs). */}
+        <label className="flex items-center gap-2 text-xs text-gray-400">
+          Watch folder
+          <input
+            value={watchFolder}
+            onChange={e => setWatchFolder(e.target.value)}
+            onBlur={() => saveSetting('watch_folder', watchFolder.trim() || null)}
+            placeholder="Off"
+            className="flex-1 h-6 px-2 rounded bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+          />
+        </label>
+
+        {/* "Open in editor" in the player; empty uses the system's choice (editor.rs). */}
+        <label className="flex items-center gap-2 text-xs text-gray-400">
+          Audio editor
+          <input
+            value={editor}
+            onChange={e => setEditor(e.target.value)}
+            onBlur={() => saveSetting('external_editor', editor.trim() || null)}
+            placeholder="System default"
+            className="flex-1 h-6 px-2 rounded bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+          />
+        </label>
+
+        {/* Generations ticked "To render target" are copied here, e.g. a game's hot-reloaded asset (render_target.rs). */}
+        <label className="flex items-center gap-2 text-xs text-gray-400">
+          Render target
+          <input
+            value={renderTarget}
+            onChange={e => setRenderTarget(e.target.value)}
+            onBlur={() => saveSetting('render_target', renderTarget.trim() || null)}
+            placeholder="Assets/Audio/latest.wav"
+            className="flex-1 h-6 px-2 rounded bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+          />
+        </label>
+
+        {/* Previews also play here, e.g. headphones next to a stream mix (playback.rs). */}
+        <label className="flex items-center gap-2 text-xs text-gray-400">
+          Cue output
+          <select
+            value={cueDevice}
+            onChange={e => {
+              const device = e.target.value;
+              setError(null);
+              invoke('set_cue_device', { device: device || null })
+                .then(() => setCueDevice(device))
+                .catch(e => setError(String(e)));
+            }}
+            className="flex-1 h-6 px-2 rounded bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+          >
+            <option value="">Off</option>
+            {[...new Set([...outputDevices, cueDevice].filter(Boolean))].map(name => (
+              <option key={name} value={name}>{name}</option>
+            ))}
+          </select>
+        </label>
+
+        {/* Gets the output paths as arguments, prompt and params as NOISES_* variables (hooks.rs). */}
+        <label className="flex items-center gap-2 text-xs text-gray-400" title="Runs confined: it can only change files in this workspace, and is stopped after the hook timeout">
+          After each generation run
+          <input
+            value={hook}
+            onChange={e => setHook(e.target.value)}
+            onBlur={() => saveSetting('post_generation_hook', hook.trim() || null)}
+            placeholder="Nothing"
+            className="flex-1 h-6 px-2 rounded bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+          />
+        </label>
+
+        <SyncSettings onError={setError} />
+
+        {/* Blocks downloads, update checks and usage counters (proxy.rs). */}
+        <label className="flex items-center gap-2 text-xs text-gray-400">
+          <input
+            type="checkbox"
+            checked={offline}
+            onChange={e => {
+              const enabled = e.target.checked;
+              setOffline(enabled);
+              invoke('set_setting', { key: 'offline', value: enabled }).catch(err => setError(String(err)));
+            }}
+          />
+          Offline mode (never connect to the internet)
+        </label>
+
+        {/* Restarts the engine with offloading on and caps length/steps (low_vram.rs). */}
+        <label className="flex items-center gap-2 text-xs text-gray-400">
+          <input
+            type="checkbox"
+            checked={lowVram}
+            onChange={e => {
+              const enabled = e.target.checked;
+              setLowVram(enabled);
+              invoke('set_setting', { key: 'low_vram', value: enabled }).catch(err => setError(String(err)));
+            }}
+          />
+          Low-VRAM mode (for GPUs with 6 GB or less; slower, shorter sounds)
+        </label>
+
+        {/* "Rain on a tin roof.wav" instead of song_001.wav (filenames.rs). */}
+        <label className="flex items-center gap-2 text-xs text-gray-400">
+          <input
+            type="checkbox"
+            checked={promptNames}
+            onChange={e => {
+              const enabled = e.target.checked;
+              setPromptNames(enabled);
+              saveSetting('prompt_file_names', enabled);
+            }}
+          />
+          Name new sounds after their prompt
+        </label>
+
+        {/* A .import for Godot, <file>.noises.json for Unity, next to exports into a project (game_engines.rs). */}
+        <label className="flex items-center gap-2 text-xs text-gray-400">
+          <input
+            type="checkbox"
+            checked={engineMetadata}
+            onChange={e => {
+              const enabled = e.target.checked;
+              setEngineMetadata(enabled);
+              saveSetting('engine_metadata', enabled);
+            }}
+          />
+          Add Godot/Unity import settings to exports into a game project
+        </label>
+
+        {/* Holds new jobs while the GPU is this hot, until it's 10 °C cooler (thermal.rs). NVIDIA only. */}
+        <label className="flex items-center gap-2 text-xs text-gray-400">
+          Pause the queue above
+          <input
+            type="number"
+            value={thermalLimit}
+            placeholder="off"
+            onChange={e => setThermalLimit(e.target.value)}
+            onBlur={() => saveSetting('thermal_limit', thermalLimit === '' ? null : Number(thermalLimit))}
+            className="w-16 h-6 px-2 rounded bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+          />
+          °C GPU temperature
+        </label>
+
+        {/* Off unless turned on here; only counters, never prompts or files (telemetry.rs). */}
+        <label className="flex items-center gap-2 text-xs text-gray-400">
+          <input
+            type="checkbox"
+            checked={telemetry}
+            onChange={e => {
+              const enabled = e.target.checked;
+              setTelemetry(enabled);
+              invoke('set_setting', { key: 'telemetry', value: enabled }).catch(err => setError(String(err)));
+            }}
+          />
+          Send anonymous usage counters (generation counts, durations, error types)
+        </label>
+
+        <div className="space-y-1">
+          <label className="text-xs text-gray-400">Profile (settings, presets and hotkeys)</label>
+          <div className="flex gap-2">
+            <Button size="sm" variant="secondary" onClick={exportProfile}>Export</Button>
+            <Button size="sm" variant="secondary" onClick={() => profileInput.current?.click()}>Import</Button>
+            <input
+              ref={profileInput}
+              type="file"
+              accept=".json,application/json"
+              className="hidden"
+              onChange={e => { const file = e.target.files?.[0]; e.target.value = ''; if (file) importProfile(file); }}
+            />
+          </div>
+          <label className="text-xs text-gray-400">Generation history</label>
+          <div className="flex gap-2">
+            <Button size="sm" variant="secondary" onClick={() => exportHistory('csv')}>Export CSV</Button>
+            <Button size="sm" variant="secondary" onClick={() => exportHistory('json')}>Export JSON</Button>
+          </div>
+          {profileStatus && <p className="text-[11px] text-gray-500 break-all">{profileStatus}</p>}
+        </div>
+
+        {error && <p className="text-xs text-red-300">{error}</p>}
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,117 @@
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { Button } from './Button';
+
+const EMPTY = {
+  s3: { kind: 's3', endpoint: 'https://s3.amazonaws.com', region: 'us-east-1', bucket: '', prefix: '', access_key_id: '' },
+  webdav: { kind: 'webdav', url: '', username: '' },
+};
+
+const inputClass = 'flex-1 h-6 px-2 rounded bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary';
+
+// Library sync to S3 or WebDAV (sync.rs). New generations upload on their
+// own; "Sync now" also pulls down what other machines added.
+export function SyncSettings({ onError }) {
+  const [remote, setRemote] = useState(null);
+  const [password, setPassword] = useState('');
+  const [progress, setProgress] = useState(null);
+  const [status, setStatus] = useState(null);
+
+  useEffect(() => {
+    invoke('get_all_settings').then(s => setRemote(s.sync)).catch(e => onError(String(e)));
+    invoke('get_secret', { key: 'sync-password' }).then(p => setPassword(p || '')).catch(e => onError(String(e)));
+    const unlisten = listen('sync-progress', ({ payload }) => setProgress(payload));
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
+
+  const field = (key, label, type = 'text') => (
+    <label className="flex items-center gap-2 text-[11px] text-gray-400">
+      <span className="w-24">{label}</span>
+      <input
+        type={type}
+        value={remote[key] || ''}
+        onChange={e => setRemote({ ...remote, [key]: e.target.value })}
+        className={inputClass}
+      />
+    </label>
+  );
+
+  const save = async () => {
+    try {
+      await invoke('set_secret', { key: 'sync-password', value: password });
+      await invoke('set_setting', { key: 'sync', value: remote });
+      setStatus('Saved');
+    } catch (e) {
+      onError(String(e));
+    }
+  };
+
+  const syncNow = async () => {
+    setStatus(null);
+    try {
+      const report = await invoke('sync_now');
+      const summary = `Uploaded ${report.uploaded}, downloaded ${report.downloaded}`;
+      setStatus(report.conflicts.length
+        ? `${summary}. Changed on both sides (remote copy saved as .remote): ${report.conflicts.join(', ')}`
+        : summary);
+    } catch (e) {
+      onError(String(e));
+    }
+    setProgress(null);
+  };
+
+  return (
+    <div className="space-y-1">
+      <label className="flex items-center gap-2 text-xs text-gray-400">
+        Sync library to
+        <select
+          value={remote ? remote.kind : ''}
+          onChange={e => {
+            const kind = e.target.value;
+            setRemote(kind ? EMPTY[kind] : null);
+            if (!kind) invoke('set_setting', { key: 'sync', value: null }).catch(err => onError(String(err)));
+          }}
+          className="h-6 px-1 rounded bg-gray-900/50 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+        >
+          <option value="">Nothing</option>
+          <option value="s3">S3-compatible storage</option>
+          <option value="webdav">WebDAV</option>
+        </select>
+      </label>
+
+      {remote && (
+        <div className="space-y-1 pl-5">
+          {remote.kind === 's3' ? (
+            <>
+              {field('endpoint', 'Endpoint')}
+              {field('region', 'Region')}
+              {field('bucket', 'Bucket')}
+              {field('prefix', 'Folder')}
+              {field('access_key_id', 'Access key ID')}
+            </>
+          ) : (
+            <>
+              {field('url', 'Folder URL')}
+              {field('username', 'Username')}
+            </>
+          )}
+          <label className="flex items-center gap-2 text-[11px] text-gray-400">
+            <span className="w-24">{remote.kind === 's3' ? 'Secret key' : 'Password'}</span>
+            <input type="password" value={password} onChange={e => setPassword(e.target.value)} className={inputClass} />
+          </label>
+          <div className="flex gap-2">
+            <Button size="sm" variant="secondary" onClick={save}>Save</Button>
+            <Button size="sm" variant="secondary" onClick={syncNow} disabled={!!progress}>Sync now</Button>
+          </div>
+          {progress && (
+            <p className="text-[11px] text-gray-500 truncate">
+              {progress.done}/{progress.total} {progress.direction === 'upload' ? 'Uploaded' : 'Downloaded'} {progress.file}
+            </p>
+          )}
+          {status && <p className="text-[11px] text-gray-500 break-all">{status}</p>}
+        </div>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,52 @@
+import React, { useEffect, useState } from 'react';
+import { X } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+
+const KIND_LABELS = {
+  generation: 'Generating',
+  processing: 'Processing',
+  download: 'Downloading',
+  sync: 'Syncing',
+};
+
+const percent = (task) => task.total ? ` ${Math.round(task.done / task.total * 100)}%` : '';
+
+// Everything long-running in the backend (tasks.rs), each with a way to stop it.
+export function TaskList() {
+  const [tasks, setTasks] = useState([]);
+
+  useEffect(() => {
+    invoke('list_tasks').then(setTasks).catch(e => console.error("Failed to load tasks", e));
+    const remove = ({ payload }) => setTasks(prev => prev.filter(t => t.id !== payload.id));
+    const unlisteners = [
+      listen('task-progress', ({ payload }) => setTasks(prev => {
+        const others = prev.filter(t => t.id !== payload.id);
+        return prev.length === others.length ? [...prev, payload] : prev.map(t => t.id === payload.id ? payload : t);
+      })),
+      listen('task-finished', remove),
+      listen('task-cancelled', remove),
+    ];
+    return () => { unlisteners.forEach(u => u.then(fn => fn())); };
+  }, []);
+
+  if (tasks.length === 0) return null;
+
+  return (
+    <div className="w-full space-y-1">
+      {tasks.map(task => (
+        <div key={task.id} className="flex items-center gap-2 px-3 py-1.5 rounded-md bg-gray-900/50 border border-gray-800 text-xs text-gray-400">
+          <span className="shrink-0 text-gray-500">{KIND_LABELS[task.kind]}{percent(task)}</span>
+          <span className="flex-1 truncate">{task.label}</span>
+          <button
+            onClick={() => invoke('cancel_task', { taskId: task.id }).catch(e => console.error("Failed to cancel", e))}
+            title="Cancel"
+            className="p-0.5 text-gray-500 hover:text-white transition-colors"
+          >
+            <X size={12} />
+          </button>
+        </div>
+      ))}
+    </div>
+  );
+}
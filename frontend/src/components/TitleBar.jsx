@@ -1,8 +1,10 @@
 import React, { useEffect, useState } from 'react';
-import { Minus, Square, X, Maximize2 } from 'lucide-react';
+import { Minus, Square, X, Maximize2, Library, HardDrive } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
 import { getCurrentWindow } from '@tauri-apps/api/window';
+import { WorkspaceSwitcher } from './WorkspaceSwitcher';
 
-export function TitleBar() {
+export function TitleBar({ showLibrary = false, showWorkspaces = false, onOpenModels }) {
   const [appWindow, setAppWindow] = useState(null);
 
   useEffect(() => {
@@ -33,9 +35,27 @@ export function TitleBar() {
   return (
     <div className="fixed top-0 left-0 right-0 h-8 flex items-center justify-between bg-transparent z-50 select-none">
       <div data-tauri-drag-region className="flex-1 h-full flex items-center px-3 gap-2 text-xs font-medium text-gray-500">
-        {/* Placeholder for icon or title if needed, or keeping it clean */}
+        {showWorkspaces && <WorkspaceSwitcher />}
       </div>
       <div className="flex items-center h-full z-50">
+        {onOpenModels && (
+          <button
+            onClick={onOpenModels}
+            title="Manage models"
+            className="h-full w-10 flex items-center justify-center text-gray-400 hover:bg-gray-800 hover:text-white transition-colors"
+          >
+            <HardDrive size={15} />
+          </button>
+        )}
+        {showLibrary && (
+          <button
+            onClick={() => invoke('open_library_window')}
+            title="Open library in a new window"
+            className="h-full w-10 flex items-center justify-center text-gray-400 hover:bg-gray-800 hover:text-white transition-colors"
+          >
+            <Library size={15} />
+          </button>
+        )}
         <button 
           onClick={minimize}
           className="h-full w-10 flex items-center justify-center text-gray-400 hover:bg-gray-800 hover:text-white transition-colors"
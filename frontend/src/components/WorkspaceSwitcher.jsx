@@ -0,0 +1,75 @@
+import React, { useEffect, useState } from 'react';
+import { Plus, Check, X } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+
+// Picks the active workspace (workspaces.rs). Each has its own output folder,
+// history, presets and tags.
+export function WorkspaceSwitcher() {
+  const [list, setList] = useState(null);
+  const [creating, setCreating] = useState(false);
+  const [name, setName] = useState('');
+  const [error, setError] = useState(null);
+
+  const refresh = () => {
+    invoke('list_workspaces').then(setList).catch(e => setError(String(e)));
+  };
+
+  useEffect(() => {
+    refresh();
+    const unlisten = listen('workspace-changed', refresh);
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
+
+  const switchTo = (id) => {
+    setError(null);
+    invoke('switch_workspace', { id }).catch(e => setError(String(e)));
+  };
+
+  const create = async () => {
+    setError(null);
+    try {
+      const workspace = await invoke('create_workspace', { name, root: null });
+      setCreating(false);
+      setName('');
+      await invoke('switch_workspace', { id: workspace.id });
+    } catch (e) {
+      setError(String(e));
+    }
+    refresh();
+  };
+
+  if (!list) return null;
+
+  return (
+    <div className="flex items-center gap-1" title={error || undefined}>
+      {creating ? (
+        <>
+          <input
+            autoFocus
+            value={name}
+            onChange={e => setName(e.target.value)}
+            onKeyDown={e => { if (e.key === 'Enter') create(); if (e.key === 'Escape') setCreating(false); }}
+            placeholder="Workspace name"
+            className="h-6 w-36 px-2 rounded bg-gray-900 border border-gray-800 text-xs text-gray-200 focus:outline-none focus:border-primary"
+          />
+          <button onClick={create} className="p-1 text-gray-500 hover:text-white"><Check size={12} /></button>
+          <button onClick={() => setCreating(false)} className="p-1 text-gray-500 hover:text-white"><X size={12} /></button>
+        </>
+      ) : (
+        <>
+          <select
+            value={list.active}
+            onChange={e => switchTo(e.target.value)}
+            className={`h-6 px-1 rounded bg-transparent border border-transparent hover:border-gray-800 text-xs focus:outline-none ${error ? 'text-red-300' : 'text-gray-400'}`}
+          >
+            {list.workspaces.map(w => <option key={w.id} value={w.id}>{w.name}</option>)}
+          </select>
+          <button onClick={() => setCreating(true)} title="New workspace" className="p-1 text-gray-500 hover:text-white">
+            <Plus size={12} />
+          </button>
+        </>
+      )}
+    </div>
+  );
+}
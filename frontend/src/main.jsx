@@ -1,10 +1,19 @@
 import React from 'react'
 import ReactDOM from 'react-dom/client'
 import App from './App.jsx'
+import { MiniPlayer } from './components/MiniPlayer.jsx'
+import { LibraryView } from './components/LibraryView.jsx'
+import { followSystemTheme } from './theme.js'
 import './styles.css'
 
+// Secondary windows load the same bundle with ?view=... (see mini_player.rs
+// and library_window.rs).
+const view = new URLSearchParams(window.location.search).get('view')
+
+followSystemTheme()
+
 ReactDOM.createRoot(document.getElementById('root')).render(
   <React.StrictMode>
-    <App />
+    {view === 'mini' ? <MiniPlayer /> : view === 'library' ? <LibraryView /> : <App />}
   </React.StrictMode>,
 )
@@ -0,0 +1,27 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+
+// Darkens a "#rrggbb" color for hover states.
+const shade = (hex, amount) => {
+  const n = parseInt(hex.slice(1), 16);
+  const channel = (shift) => Math.max(0, Math.round(((n >> shift) & 0xff) * (1 - amount)));
+  return `#${[16, 8, 0].map(s => channel(s).toString(16).padStart(2, '0')).join('')}`;
+};
+
+const apply = ({ theme, accent }) => {
+  const root = document.documentElement;
+  root.dataset.theme = theme;
+  if (accent) {
+    root.style.setProperty('--accent-color', accent);
+    root.style.setProperty('--accent-hover', shade(accent, 0.12));
+  } else {
+    root.style.removeProperty('--accent-color');
+    root.style.removeProperty('--accent-hover');
+  }
+};
+
+// Follows the OS dark/light mode and accent color live (see theme.rs).
+export function followSystemTheme() {
+  invoke('get_system_theme').then(apply).catch(e => console.error("Failed to read system theme", e));
+  listen('system-theme-changed', ({ payload }) => apply(payload));
+}
@@ -1,3 +1,39 @@
+use std::io::Read;
+
+use sha2::{Digest, Sha256};
+
 fn main() {
+  bake_backend_digest();
   tauri_build::build()
 }
+
+// The SHA-256 of the sidecar that gets bundled, for sidecar.rs to check
+// before it runs it. An empty placeholder (a dev checkout without a built
+// backend) gets no digest, and nothing is checked.
+fn bake_backend_digest() {
+  let target = std::env::var("TARGET").unwrap_or_default();
+  let extension = if target.contains("windows") { ".exe" } else { "" };
+  let path = format!("binaries/backend-{}{}", target, extension);
+  println!("cargo:rerun-if-changed={}", path);
+
+  let Ok(mut file) = std::fs::File::open(&path) else {
+    return;
+  };
+  let mut hasher = Sha256::new();
+  let mut buffer = vec![0u8; 1 << 20];
+  let mut len = 0;
+  loop {
+    match file.read(&mut buffer) {
+      Ok(0) => break,
+      Ok(n) => {
+        hasher.update(&buffer[..n]);
+        len += n;
+      }
+      Err(e) => panic!("Failed to read {}: {}", path, e),
+    }
+  }
+  if len > 0 {
+    let digest: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    println!("cargo:rustc-env=NOISES_BACKEND_SHA256={}", digest);
+  }
+}
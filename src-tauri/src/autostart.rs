@@ -0,0 +1,67 @@
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_autostart::ManagerExt;
+
+use crate::jobs::JobManager;
+use crate::tray;
+
+// Passed by the login item so we come up in the tray instead of on screen.
+pub const MINIMIZED_FLAG: &str = "--minimized";
+
+// Registry Run key on Windows, a launch agent (the plugin's default) on macOS,
+// an XDG autostart entry on Linux.
+pub fn plugin<R: Runtime>() -> tauri::plugin::TauriPlugin<R> {
+    tauri_plugin_autostart::Builder::new()
+        .arg(MINIMIZED_FLAG)
+        .build()
+}
+
+pub fn launched_minimized() -> bool {
+    std::env::args().any(|arg| arg == MINIMIZED_FLAG)
+}
+
+// Called from setup. The main window starts hidden (tauri.conf.json) and is
+// only shown here, unless this is a login launch, which stays in the tray.
+pub fn apply_launch_flags(app: &AppHandle) {
+    if launched_minimized() {
+        println!("[Autostart] Started minimized to the tray");
+        return;
+    }
+    tray::show_main_window(app);
+}
+
+pub fn is_enabled(app: &AppHandle) -> bool {
+    app.autolaunch().is_enabled().unwrap_or(false)
+}
+
+fn set_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    let autolaunch = app.autolaunch();
+    let result = if enabled {
+        autolaunch.enable()
+    } else {
+        autolaunch.disable()
+    };
+    result.map_err(|e| format!("Failed to update login item: {}", e))?;
+    println!(
+        "[Autostart] Start at login {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    tray::refresh(app, &app.state::<JobManager>().status());
+    Ok(())
+}
+
+// Tray check item; the mark has already toggled, so follow the OS state instead.
+pub fn toggle(app: &AppHandle) {
+    if let Err(e) = set_enabled(app, !is_enabled(app)) {
+        eprintln!("[Autostart] {}", e);
+    }
+}
+
+#[tauri::command]
+pub fn get_autostart(app: AppHandle) -> bool {
+    is_enabled(&app)
+}
+
+#[tauri::command]
+pub fn set_autostart(app: AppHandle, enabled: bool) -> Result<(), String> {
+    set_enabled(&app, enabled)
+}
@@ -0,0 +1,520 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::{Value, json};
+use tauri::{AppHandle, Emitter};
+
+use crate::crash;
+use crate::filenames;
+use crate::import;
+use crate::paths;
+use crate::settings;
+use crate::stems;
+
+// The backend's usual port. When something else has it, sidecar.rs starts
+// the backend on a free one instead and records it here.
+pub const DEFAULT_PORT: u16 = 8000;
+static PORT: AtomicU16 = AtomicU16::new(DEFAULT_PORT);
+
+pub fn port() -> u16 {
+    PORT.load(Ordering::Relaxed)
+}
+
+pub fn set_port(port: u16) {
+    PORT.store(port, Ordering::Relaxed);
+}
+
+pub fn url() -> String {
+    format!("http://127.0.0.1:{}", port())
+}
+
+// 10 min for GPU inference
+pub const GENERATE_TIMEOUT: Duration = Duration::from_secs(600);
+// The same models take many times as long on a CPU.
+const CPU_GENERATE_TIMEOUT: Duration = Duration::from_secs(3600);
+
+// Set by sidecar.rs when it started the backend on the CPU for lack of a GPU.
+static CPU_FALLBACK: AtomicBool = AtomicBool::new(false);
+
+pub fn set_cpu_fallback(on: bool) {
+    CPU_FALLBACK.store(on, Ordering::Relaxed);
+}
+
+pub fn is_cpu_fallback() -> bool {
+    CPU_FALLBACK.load(Ordering::Relaxed)
+}
+
+// For generation requests; everything else keeps the client's timeout.
+pub fn generate_timeout() -> Duration {
+    if is_cpu_fallback() {
+        CPU_GENERATE_TIMEOUT
+    } else {
+        GENERATE_TIMEOUT
+    }
+}
+
+// A refused connection answers on the spot; the timeouts are for a backend
+// that's up but stuck.
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(300);
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(3);
+
+// Leading code of the error for a backend that's up but can't take the
+// request yet (429 queue full, 503 model loading), followed by how long it
+// asked us to wait: "BackendBusy: retry in 5 s: <detail>".
+pub const BUSY_ERROR: &str = "BackendBusy";
+// When the backend doesn't say how long (or says it as a date).
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(5);
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+// A busy backend gets this long in all before the job fails after all,
+// enough for the slowest model to load.
+const MAX_BUSY_WAIT: Duration = Duration::from_secs(600);
+
+// How long check_backend_health answers from the last check, so a UI polling
+// every second or two doesn't turn into a request each time.
+const HEALTH_TTL: Duration = Duration::from_secs(2);
+
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+// One client (and its connection pool) for everything that talks to the
+// backend. The backend is local, so the proxy settings (proxy.rs) never
+// apply; a system proxy that doesn't exempt 127.0.0.1 would otherwise break
+// everything.
+pub fn generate_client() -> Result<reqwest::Client, String> {
+    if let Some(client) = CLIENT.get() {
+        return Ok(client.clone());
+    }
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .timeout(GENERATE_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    Ok(CLIENT.get_or_init(|| client).clone())
+}
+
+// What's on a local port, going by a raw connect and then its /health answer.
+// The connect is what tells a dead backend (Closed) from a hung one
+// (Unresponsive): a process that's stuck still has its socket open.
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Probe {
+    // A backend of ours, maybe one left over from an earlier run.
+    Healthy,
+    // Nothing listening.
+    Closed,
+    // Something accepts connections but doesn't answer /health properly.
+    Unresponsive,
+    // Something else entirely (another dev server, say).
+    Foreign,
+}
+
+pub async fn probe(port: u16) -> Probe {
+    // Nothing listening is by far the common case, and a bare connect finds
+    // that out without building a request.
+    let connect = tokio::net::TcpStream::connect(("127.0.0.1", port));
+    if !matches!(
+        tokio::time::timeout(CONNECT_TIMEOUT, connect).await,
+        Ok(Ok(_))
+    ) {
+        return Probe::Closed;
+    }
+    let Ok(client) = generate_client() else {
+        return Probe::Unresponsive;
+    };
+    let request = client
+        .get(format!("http://127.0.0.1:{}/health", port))
+        .timeout(HEALTH_TIMEOUT);
+    let Ok(res) = request.send().await else {
+        return Probe::Unresponsive;
+    };
+    if !res.status().is_success() {
+        return Probe::Unresponsive;
+    }
+    let Ok(info) = res.text().await else {
+        return Probe::Unresponsive;
+    };
+    // Older backends don't send "service", but they all report torch.
+    let ours = serde_json::from_str::<Value>(&info).is_ok_and(|health| {
+        health.get("service").and_then(|s| s.as_str()) == Some("noises")
+            || health.get("torch_version").is_some()
+    });
+    if !ours {
+        return Probe::Foreign;
+    }
+    // Device and torch/CUDA versions, for crash reports.
+    crash::record_backend_info(info);
+    Probe::Healthy
+}
+
+// Always asks the backend; for waiting on startup and shutdown. The UI goes
+// through HealthCache instead. Something else answering on the port doesn't
+// count.
+pub async fn is_healthy() -> bool {
+    probe(port()).await == Probe::Healthy
+}
+
+#[derive(Clone, Serialize)]
+struct BackendStatus {
+    status: Probe,
+    port: u16,
+}
+
+// Tells the UI when the backend's port changes state, as `backend-status`
+// {status, port}, so it can say whether the engine died or hung.
+pub fn announce(app: &AppHandle, status: Probe) {
+    static LAST: Mutex<Option<Probe>> = Mutex::new(None);
+    if LAST.lock().unwrap().replace(status) == Some(status) {
+        return;
+    }
+    let _ = app.emit(
+        "backend-status",
+        BackendStatus {
+            status,
+            port: port(),
+        },
+    );
+}
+
+// Asks a backend we didn't spawn (so can't kill) to exit.
+pub async fn request_shutdown(port: u16) {
+    if let Ok(client) = generate_client() {
+        let _ = client
+            .post(format!("http://127.0.0.1:{}/shutdown", port))
+            .timeout(HEALTH_TIMEOUT)
+            .send()
+            .await;
+    }
+}
+
+#[derive(Default)]
+pub struct HealthCache {
+    last: Mutex<Option<(Instant, bool)>>,
+}
+
+impl HealthCache {
+    pub async fn check(&self, app: &AppHandle) -> bool {
+        if let Some((at, healthy)) = *self.last.lock().unwrap()
+            && at.elapsed() < HEALTH_TTL
+        {
+            return healthy;
+        }
+        let status = probe(port()).await;
+        announce(app, status);
+        let healthy = status == Probe::Healthy;
+        *self.last.lock().unwrap() = Some((Instant::now(), healthy));
+        healthy
+    }
+}
+
+// Limits on a generation request. The real ones are a few hundred bytes; the
+// caps are there so stale or broken frontend state (a decoded file ending up
+// in the form, say) is turned away here instead of stalling the backend.
+// Audio goes through upload_reference_audio, never inside the request.
+const MAX_CONFIG_BYTES: usize = 64 * 1024;
+// Room for a full song's lyrics.
+const MAX_TEXT_CHARS: usize = 20_000;
+const MAX_DEPTH: usize = 4;
+const MAX_ITEMS: usize = 256;
+
+// Checked before a generation is queued, so a bad request never becomes a job.
+pub fn validate_config(config: &Value) -> Result<(), String> {
+    let fields = config
+        .as_object()
+        .ok_or("The generation request isn't an object")?;
+    for key in ["type", "prompt"] {
+        if !fields.get(key).is_some_and(|v| v.is_string()) {
+            return Err(format!("The generation request has no {}", key));
+        }
+    }
+    let size = serde_json::to_vec(config).map(|b| b.len()).unwrap_or(0);
+    if size > MAX_CONFIG_BYTES {
+        return Err(format!(
+            "The generation request is too large ({} KB, at most {} KB). Reference audio has to be uploaded, not sent inline",
+            size / 1024,
+            MAX_CONFIG_BYTES / 1024
+        ));
+    }
+    check_value("request", config, 0)
+}
+
+fn check_value(name: &str, value: &Value, depth: usize) -> Result<(), String> {
+    if depth > MAX_DEPTH {
+        return Err(format!("{} is nested too deeply", name));
+    }
+    match value {
+        Value::String(s) if s.chars().count() > MAX_TEXT_CHARS => Err(format!(
+            "{} is too long (at most {} characters)",
+            name, MAX_TEXT_CHARS
+        )),
+        Value::Array(items) if items.len() > MAX_ITEMS => Err(format!(
+            "{} has too many items (at most {})",
+            name, MAX_ITEMS
+        )),
+        Value::Array(items) => items
+            .iter()
+            .try_for_each(|item| check_value(name, item, depth + 1)),
+        Value::Object(fields) if fields.len() > MAX_ITEMS => Err(format!(
+            "{} has too many fields (at most {})",
+            name, MAX_ITEMS
+        )),
+        Value::Object(fields) => fields
+            .iter()
+            .try_for_each(|(key, item)| check_value(key, item, depth + 1)),
+        _ => Ok(()),
+    }
+}
+
+// The reply is a few file paths on this machine. A backend elsewhere is a
+// "grpc" provider instead (grpc.rs), which streams the audio back.
+pub async fn generate(config: &serde_json::Value) -> Result<serde_json::Value, String> {
+    let client = generate_client()?;
+
+    let res = client
+        .post(format!("{}/generate", url()))
+        .timeout(generate_timeout())
+        .json(config)
+        .send()
+        .await
+        .map_err(|e| format!("Backend request failed: {}", e))?;
+
+    if !res.status().is_success() {
+        return Err(error_from_response(res).await);
+    }
+
+    res.json::<serde_json::Value>()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))
+}
+
+// The backend writes every output to disk and answers with paths, never the
+// audio itself. Before anything uses them, each file has to exist, hold audio
+// that decodes (see check_output), and sit in the active workspace's
+// library; one saved elsewhere (an older backend that ignores output_dir) is
+// moved into the matching library folder, and renamed after the prompt if
+// the user asked for that (filenames.rs). Stems are split out (stems.rs).
+// Each entry gains `size`, and `duration` when the backend didn't send one.
+pub fn settle_outputs(app: &AppHandle, config: &Value, mut result: Value) -> Result<Value, String> {
+    let is_loop = config
+        .get("type")
+        .and_then(|t| t.as_str())
+        .is_some_and(|t| t.contains("loop"));
+    let prompt = config
+        .get("prompt")
+        .and_then(|p| p.as_str())
+        .filter(|p| !p.trim().is_empty());
+    let rename = settings::get(app).prompt_file_names;
+    let library = paths::output_dir(app)?;
+    let library = paths::canonical(&library).unwrap_or(library);
+    let files = result
+        .get_mut("files")
+        .and_then(|f| f.as_array_mut())
+        .ok_or("The backend answered without a file list")?;
+
+    for file in files.iter_mut() {
+        let path = file
+            .get("path")
+            .and_then(|p| p.as_str())
+            .map(PathBuf::from)
+            .ok_or("The backend answered with a file without a path")?;
+        check_output(&path)?;
+        // Canonical only for the check; the UI keeps the path it was given.
+        let canonical = paths::canonical(&path)
+            .map_err(|_| format!("The backend's output is missing: {}", path.display()))?;
+
+        let path = if canonical.starts_with(&library) {
+            path
+        } else {
+            let dir = if is_loop {
+                paths::loops_dir(app)?
+            } else {
+                paths::oneshots_dir(app)?
+            };
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create output folder: {}", e))?;
+            let target = match path.file_name() {
+                Some(name) if !dir.join(name).exists() => dir.join(name),
+                _ => {
+                    let extension = path
+                        .extension()
+                        .map(|ext| format!(".{}", ext.to_string_lossy()))
+                        .unwrap_or_default();
+                    let prefix = if is_loop { "loop" } else { "song" };
+                    dir.join(paths::next_filename(&dir, prefix, &extension))
+                }
+            };
+            paths::move_file(&path, &target)?;
+            println!(
+                "[Backend] Moved {} into the library",
+                target.file_name().unwrap_or_default().to_string_lossy()
+            );
+            target
+        };
+
+        let path = match prompt.filter(|_| rename) {
+            Some(prompt) => filenames::name_after_prompt(&path, prompt)?,
+            None => path,
+        };
+        stems::settle(app, &path, file)?;
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        file["file"] = json!(path.file_name().unwrap_or_default().to_string_lossy());
+        file["path"] = json!(path.to_string_lossy());
+        file["size"] = json!(size);
+        if file.get("duration").is_none()
+            && let Some(duration) = wav_duration(&path)
+        {
+            file["duration"] = json!(duration);
+        }
+    }
+
+    if let Some(first) = files.first().and_then(|f| f.get("path")).cloned() {
+        result["path"] = first;
+    }
+    Ok(result)
+}
+
+// A "successful" generation can still point at nothing, a zero-byte file or
+// one cut off mid-write, so the file is opened and its first sample decoded.
+// That only reads the header and the first block, not the whole file.
+pub fn check_output(path: &Path) -> Result<(), String> {
+    let meta = std::fs::metadata(path)
+        .map_err(|_| format!("The backend's output is missing: {}", path.display()))?;
+    if !meta.is_file() || !import::is_audio_file(path) {
+        return Err(format!(
+            "The backend's output isn't audio: {}",
+            path.display()
+        ));
+    }
+    if meta.len() == 0 {
+        return Err(format!("The backend's output is empty: {}", path.display()));
+    }
+    let file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open the backend's output: {}", e))?;
+    let mut decoder = rodio::Decoder::new(std::io::BufReader::new(file)).map_err(|e| {
+        format!(
+            "The backend's output can't be decoded: {} ({})",
+            path.display(),
+            e
+        )
+    })?;
+    if decoder.next().is_none() {
+        return Err(format!(
+            "The backend's output has no audio in it: {}",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+fn wav_duration(path: &Path) -> Option<f64> {
+    let reader = hound::WavReader::open(path).ok()?;
+    let spec = reader.spec();
+    Some(reader.duration() as f64 / spec.sample_rate as f64)
+}
+
+pub async fn error_from_response(res: reqwest::Response) -> String {
+    let status = res.status();
+    let busy = matches!(status.as_u16(), 429 | 503).then(|| {
+        res.headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_RETRY_AFTER)
+            .min(MAX_RETRY_AFTER)
+    });
+    let body = res.text().await.unwrap_or_default();
+    // Try to extract FastAPI's "detail" field
+    let message = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|err_json| Some(err_json.get("detail")?.as_str()?.to_string()))
+        .unwrap_or_else(|| format!("Backend error ({}): {}", status, body));
+    match busy {
+        Some(wait) => format!("{}: retry in {} s: {}", BUSY_ERROR, wait.as_secs(), message),
+        None => message,
+    }
+}
+
+// How long a BackendBusy error asks to wait; None for any other error.
+fn retry_after(error: &str) -> Option<Duration> {
+    let rest = error
+        .strip_prefix(BUSY_ERROR)?
+        .strip_prefix(": retry in ")?;
+    let (secs, _) = rest.split_once(" s")?;
+    secs.parse().ok().map(Duration::from_secs)
+}
+
+// Runs a backend request again for as long as the backend says it's busy, so
+// a full queue or a loading model delays the job instead of failing it. The
+// wait happens inside the job's task, so cancelling still works.
+pub async fn retry_while_busy<T, F: Future<Output = Result<T, String>>>(
+    label: &str,
+    mut request: impl FnMut() -> F,
+) -> Result<T, String> {
+    let started = Instant::now();
+    loop {
+        let result = request().await;
+        let Err(e) = &result else {
+            return result;
+        };
+        let Some(wait) = retry_after(e) else {
+            return result;
+        };
+        if started.elapsed() + wait > MAX_BUSY_WAIT {
+            return result;
+        }
+        println!(
+            "[Backend] Busy, retrying \"{}\" in {} s",
+            label,
+            wait.as_secs()
+        );
+        tokio::time::sleep(wait).await;
+    }
+}
+
+// Streams a reference file straight from disk to the backend as multipart/form-data,
+// so a 100 MB WAV never has to be base64-encoded into the JSON config. Returns the
+// backend's JSON answer; its `id` goes into a later config as `reference_audio`.
+#[tauri::command]
+pub async fn upload_reference_audio(
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<serde_json::Value, String> {
+    let path = paths::normalize(&path);
+    let file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|e| format!("Failed to open reference audio: {}", e))?;
+    let len = file
+        .metadata()
+        .await
+        .map_err(|e| format!("Failed to read reference audio: {}", e))?
+        .len();
+
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "reference.wav".to_string());
+    let body = reqwest::Body::wrap_stream(tokio_util::io::ReaderStream::new(file));
+    let part = reqwest::multipart::Part::stream_with_length(body, len)
+        .file_name(file_name)
+        .mime_str(crate::protocol::content_type(&path))
+        .map_err(|e| format!("Failed to build upload: {}", e))?;
+    let form = reqwest::multipart::Form::new().part("file", part);
+
+    crate::sidecar::ensure_running(&app).await?;
+    let res = generate_client()?
+        .post(format!("{}/upload/reference", url()))
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|e| format!("Upload failed: {}", e))?;
+
+    if !res.status().is_success() {
+        return Err(error_from_response(res).await);
+    }
+
+    res.json::<serde_json::Value>()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))
+}
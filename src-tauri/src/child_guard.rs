@@ -0,0 +1,108 @@
+use std::process::{Child, Command};
+
+// Keeps the backend from outliving us. Killing it on RunEvent::Exit only
+// works when we exit normally; after a crash or a kill from the task manager
+// the OS has to do it:
+//   Windows  the child joins a Job Object that kills everything in it when
+//            its last handle (ours, held until we die) closes
+//   Linux    the child asks for SIGKILL when we die (PR_SET_PDEATHSIG)
+//   unix     the child leads its own process group, so kill() also reaches
+//            whatever it started (the packaged backend is a launcher plus
+//            the real Python process)
+// macOS has no death signal; there the backend's own --parent-pid watch is
+// what's left.
+
+// Call on the command before spawning it.
+#[cfg(unix)]
+pub fn tie_to_app(cmd: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    #[cfg(target_os = "linux")]
+    let parent = std::process::id() as libc::pid_t;
+    // Runs in the forked child, so only async-signal-safe calls.
+    unsafe {
+        cmd.pre_exec(move || {
+            libc::setpgid(0, 0);
+            #[cfg(target_os = "linux")]
+            {
+                // The signal follows the thread that forked us, which is a
+                // runtime worker that lives as long as the app does.
+                libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+                // We may have died before the prctl above took effect.
+                if libc::getppid() != parent {
+                    libc::_exit(1);
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(windows)]
+pub fn tie_to_app(_cmd: &mut Command) {}
+
+// Call right after spawning.
+#[cfg(windows)]
+pub fn adopt(child: &Child) {
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::JobObjects::AssignProcessToJobObject;
+
+    let Some(job) = job() else {
+        return;
+    };
+    if let Err(e) = unsafe { AssignProcessToJobObject(job, HANDLE(child.as_raw_handle())) } {
+        eprintln!("[Tauri] Failed to tie the backend to the app: {}", e);
+    }
+}
+
+#[cfg(unix)]
+pub fn adopt(_child: &Child) {}
+
+// One job for the app's lifetime. Never closed on purpose: the OS closes it
+// when we exit, however that happens, and takes the backend with it.
+#[cfg(windows)]
+fn job() -> Option<windows::Win32::Foundation::HANDLE> {
+    use std::sync::OnceLock;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::JobObjects::{
+        CreateJobObjectW, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JobObjectExtendedLimitInformation, SetInformationJobObject,
+    };
+    use windows::core::PCWSTR;
+
+    // HANDLE isn't Send, so the raw value is what's kept.
+    static JOB: OnceLock<Option<usize>> = OnceLock::new();
+    let raw = JOB.get_or_init(|| unsafe {
+        let job = match CreateJobObjectW(None, PCWSTR::null()) {
+            Ok(job) => job,
+            Err(e) => {
+                eprintln!("[Tauri] Failed to create a job object: {}", e);
+                return None;
+            }
+        };
+        let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+        info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        if let Err(e) = SetInformationJobObject(
+            job,
+            JobObjectExtendedLimitInformation,
+            &info as *const _ as *const std::ffi::c_void,
+            std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        ) {
+            eprintln!("[Tauri] Failed to set up the job object: {}", e);
+            return None;
+        }
+        Some(job.0 as usize)
+    });
+    raw.map(|raw| HANDLE(raw as *mut std::ffi::c_void))
+}
+
+// Kills the child (and on unix its process group) and reaps it.
+pub fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+    let _ = child.kill(); // Sends SIGKILL / TerminateProcess
+    let _ = child.wait();
+}
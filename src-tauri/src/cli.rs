@@ -0,0 +1,301 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use serde_json::{Value, json};
+use tauri::{AppHandle, Manager};
+
+use crate::hotkeys::HotkeyStore;
+use crate::jobs::{self, JobManager};
+use crate::library;
+use crate::mcp;
+use crate::models::{self, ModelManager};
+use crate::paths;
+use crate::power::PowerGuard;
+use crate::profile;
+use crate::settings::SettingsStore;
+use crate::sidecar::{self, Sidecar};
+use crate::tasks::TaskRegistry;
+use crate::workspaces::WorkspaceStore;
+
+const USAGE: &str = "\
+Usage:
+  noises generate --prompt <text> [--type loop|one-shot] [--count <n>]
+                  [--length <seconds>] [--steps <n>] [--seed <n>] [--out <dir>]
+  noises list
+  noises export-profile [--out <dir>]
+  noises mcp
+
+Results are printed to stdout as JSON; progress and logs go to stderr.
+`generate` goes through the same queue as the app, so it lands in the history
+and runs the post-generation hooks; --out gets a copy, the library keeps its own.
+`export-profile` saves settings, presets and hotkeys (no sounds) to a
+noises-profile_NNN.json, for importing on another machine.
+`mcp` serves the Model Context Protocol on stdio for LLM agents and editors.";
+
+pub struct GenerateArgs {
+    prompt: String,
+    kind: Option<String>,
+    count: u32,
+    length: Option<f64>,
+    steps: Option<u32>,
+    seed: Option<u64>,
+    out: Option<PathBuf>,
+}
+
+pub enum Command {
+    Generate(GenerateArgs),
+    List,
+    ExportProfile { out: Option<PathBuf> },
+    Mcp,
+}
+
+// Anything that isn't one of our subcommands (file paths from "Open with",
+// noises:// links, --autostart, --crashed) is left to the GUI.
+pub fn parse(args: &[String]) -> Option<Result<Command, String>> {
+    let (name, rest) = args.split_first()?;
+    if !matches!(
+        name.as_str(),
+        "generate" | "list" | "export-profile" | "mcp"
+    ) {
+        return None;
+    }
+    Some(parse_command(name, rest))
+}
+
+fn number<T: std::str::FromStr>(key: &str, value: Option<String>) -> Result<Option<T>, String> {
+    value
+        .map(|v| v.parse().map_err(|_| format!("--{} expects a number", key)))
+        .transpose()
+}
+
+fn parse_command(name: &str, args: &[String]) -> Result<Command, String> {
+    let mut options = std::collections::HashMap::new();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let key = flag
+            .strip_prefix("--")
+            .ok_or_else(|| format!("Unexpected argument: {}", flag))?;
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for --{}", key))?;
+        options.insert(key.to_string(), value.clone());
+    }
+    let mut take = |key: &str| options.remove(key);
+
+    let command = match name {
+        "generate" => Command::Generate(GenerateArgs {
+            prompt: take("prompt").ok_or("generate needs --prompt")?,
+            kind: take("type"),
+            count: number("count", take("count"))?.unwrap_or(1).max(1),
+            length: number("length", take("length"))?,
+            steps: number("steps", take("steps"))?,
+            seed: number("seed", take("seed"))?,
+            out: take("out").map(PathBuf::from),
+        }),
+        "export-profile" => Command::ExportProfile {
+            out: take("out").map(PathBuf::from),
+        },
+        "mcp" => Command::Mcp,
+        _ => Command::List,
+    };
+    match options.keys().next() {
+        Some(unknown) => Err(format!("Unknown option --{}", unknown)),
+        None => Ok(command),
+    }
+}
+
+// Runs a subcommand against the same settings, workspace and backend as the
+// GUI, without its windows, tray or plugins, and returns the exit code.
+pub fn run(command: Result<Command, String>, mut context: tauri::Context) -> i32 {
+    let mut results = redirect_logs();
+    let command = match command {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+
+    context.config_mut().app.windows.clear();
+    let app = match tauri::Builder::default()
+        .plugin(tauri_plugin_shell::init())
+        .build(context)
+    {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("Failed to start: {}", e);
+            return 1;
+        }
+    };
+    let app = app.handle().clone();
+    app.manage(SettingsStore::load(&app));
+    app.manage(WorkspaceStore::load(&app));
+    app.manage(HotkeyStore::load(&app));
+    app.manage(Sidecar::default());
+    // What jobs::generate needs, so generations from here (and from `noises
+    // mcp`) are queued, recorded and hooked like the app's.
+    app.manage(PowerGuard::new());
+    app.manage(TaskRegistry::default());
+    app.manage(ModelManager::default());
+    app.manage(JobManager::new(&app));
+
+    if let Command::Mcp = command {
+        let result = tauri::async_runtime::block_on(mcp::serve_stdio(&app, results));
+        app.state::<Sidecar>().kill();
+        return match result {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                1
+            }
+        };
+    }
+
+    let result = tauri::async_runtime::block_on(async {
+        match command {
+            Command::Generate(args) => generate(&app, args).await,
+            Command::Mcp => unreachable!(),
+            Command::List => serde_json::to_value(library::scan(&app))
+                .map_err(|e| format!("Failed to serialize library: {}", e)),
+            Command::ExportProfile { out } => {
+                let dir = match out {
+                    Some(dir) => dir,
+                    None => std::env::current_dir()
+                        .map_err(|e| format!("Failed to resolve current folder: {}", e))?,
+                };
+                profile::export(&app, &dir).map(|path| json!({ "path": path }))
+            }
+        }
+    });
+    app.state::<Sidecar>().kill();
+
+    match result {
+        Ok(value) => {
+            let json = serde_json::to_string_pretty(&value).unwrap_or_default();
+            let _ = writeln!(results, "{}", json);
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    }
+}
+
+async fn generate(app: &AppHandle, args: GenerateArgs) -> Result<Value, String> {
+    let GenerateArgs {
+        prompt,
+        kind,
+        count,
+        length,
+        steps,
+        seed,
+        out,
+    } = args;
+    let kind = kind.unwrap_or_else(|| models::default_generation_type(app).to_string());
+    if let Some(out) = &out {
+        std::fs::create_dir_all(out)
+            .map_err(|e| format!("Failed to create {}: {}", out.display(), e))?;
+    }
+
+    eprintln!("Starting the AI engine...");
+    sidecar::start(app).await?;
+
+    let mut files = Vec::new();
+    for i in 0..count {
+        let mut config = json!({ "type": kind, "prompt": prompt, "variations": 1 });
+        if let Some(length) = length {
+            config["length"] = json!(length);
+        }
+        if let Some(steps) = steps {
+            config["steps"] = json!(steps);
+        }
+        // Consecutive seeds, so a batch is reproducible but not four copies.
+        if let Some(seed) = seed {
+            config["seed"] = json!(seed.wrapping_add(i as u64));
+        }
+
+        eprintln!("[{}/{}] Generating \"{}\"...", i + 1, count, prompt);
+        let started = Instant::now();
+        let result = jobs::generate(app, config).await?;
+        eprintln!(
+            "[{}/{}] Done in {:.1}s",
+            i + 1,
+            count,
+            started.elapsed().as_secs_f64()
+        );
+
+        let outputs = result
+            .get("files")
+            .and_then(|f| f.as_array())
+            .cloned()
+            .unwrap_or_default();
+        for file in outputs {
+            let Some(path) = file.get("path").and_then(|p| p.as_str()).map(PathBuf::from) else {
+                continue;
+            };
+            // The library keeps its copy, like a generation from the app.
+            let path = match &out {
+                Some(out) => {
+                    let target = out.join(path.file_name().unwrap_or_default());
+                    paths::copy_atomic(&path, &target)
+                        .map_err(|e| format!("Failed to copy to {}: {}", target.display(), e))?;
+                    target
+                }
+                None => path,
+            };
+            files.push(json!({
+                "file": path.file_name().map(|n| n.to_string_lossy().into_owned()),
+                "path": path,
+            }));
+        }
+    }
+    Ok(Value::Array(files))
+}
+
+// Log lines from everywhere else (println!) would mix into the JSON, so once
+// we know this is a CLI run, stdout is pointed at stderr and the original
+// stdout is kept for the results alone.
+#[cfg(unix)]
+fn redirect_logs() -> Box<dyn Write + Send> {
+    use std::os::fd::FromRawFd;
+
+    let _ = std::io::stdout().flush();
+    // SAFETY: plain fd juggling on our own standard streams; the dup'ed fd
+    // is owned by the File from here on.
+    unsafe {
+        let original = libc::dup(libc::STDOUT_FILENO);
+        if original < 0 || libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) < 0 {
+            return Box::new(std::io::stdout());
+        }
+        Box::new(std::fs::File::from_raw_fd(original))
+    }
+}
+
+// Release builds are GUI-subsystem apps with no console of their own, so
+// borrow the terminal we were started from first.
+#[cfg(windows)]
+fn redirect_logs() -> Box<dyn Write + Send> {
+    use std::os::windows::io::FromRawHandle;
+    use windows::Win32::System::Console::{
+        ATTACH_PARENT_PROCESS, AttachConsole, GetStdHandle, STD_ERROR_HANDLE, STD_OUTPUT_HANDLE,
+        SetStdHandle,
+    };
+
+    // SAFETY: only swaps our own standard handles; the original stdout
+    // handle is owned by the File from here on.
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+        let (Ok(original), Ok(stderr)) = (
+            GetStdHandle(STD_OUTPUT_HANDLE),
+            GetStdHandle(STD_ERROR_HANDLE),
+        ) else {
+            return Box::new(std::io::stdout());
+        };
+        if original.is_invalid() || SetStdHandle(STD_OUTPUT_HANDLE, stderr).is_err() {
+            return Box::new(std::io::stdout());
+        }
+        Box::new(std::fs::File::from_raw_handle(original.0))
+    }
+}
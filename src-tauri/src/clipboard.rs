@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+use serde_json::json;
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::import;
+use crate::jobs;
+use crate::models;
+use crate::telemetry;
+
+// What's on the clipboard, as far as we care: files copied in Explorer/Finder
+// (or a Linux file manager) win over text.
+#[derive(Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ClipboardContents {
+    Files { paths: Vec<String> },
+    Text { text: String },
+    Empty,
+}
+
+// The clipboard plugin only does text and images, so file lists go through
+// arboard directly.
+fn read_files() -> Vec<PathBuf> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get().file_list())
+        .unwrap_or_default()
+}
+
+pub fn read(app: &AppHandle) -> ClipboardContents {
+    let files = read_files();
+    if !files.is_empty() {
+        return ClipboardContents::Files {
+            paths: files
+                .into_iter()
+                .map(|p| p.to_string_lossy().into_owned())
+                .collect(),
+        };
+    }
+    match app.clipboard().read_text() {
+        Ok(text) if !text.trim().is_empty() => ClipboardContents::Text {
+            text: text.trim().to_string(),
+        },
+        _ => ClipboardContents::Empty,
+    }
+}
+
+#[tauri::command]
+pub async fn read_clipboard(app: AppHandle) -> Result<ClipboardContents, String> {
+    tauri::async_runtime::spawn_blocking(move || read(&app))
+        .await
+        .map_err(|e| format!("Failed to read clipboard: {}", e))
+}
+
+// Tray item and hotkey: the copied text becomes a loop prompt.
+pub fn generate_from_clipboard(app: &AppHandle) {
+    let prompt = match read(app) {
+        ClipboardContents::Text { text } => text,
+        ClipboardContents::Files { .. } => {
+            eprintln!("[Clipboard] Clipboard holds files, not a prompt");
+            return;
+        }
+        ClipboardContents::Empty => {
+            eprintln!("[Clipboard] Clipboard has no text to use as a prompt");
+            return;
+        }
+    };
+
+    // Same request the form sends, for whichever model is the default, leaving
+    // everything else to the backend defaults.
+    telemetry::record_feature(app, "clipboard-generate");
+    let kind = models::default_generation_type(app);
+    let config = json!({ "type": kind, "prompt": prompt, "variations": 1 });
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = jobs::generate(&app, config).await {
+            eprintln!("[Clipboard] Generation failed: {}", e);
+        }
+    });
+}
+
+// Tray item and hotkey: audio files copied in a file manager go into the
+// library, and the last one is shown in the player.
+pub fn import_from_clipboard(app: &AppHandle) {
+    let app = app.clone();
+    // Copies can be large; keep them off the main thread.
+    tauri::async_runtime::spawn_blocking(move || {
+        let files: Vec<PathBuf> = read_files()
+            .into_iter()
+            .filter(|f| import::is_audio_file(f))
+            .collect();
+        if files.is_empty() {
+            eprintln!("[Clipboard] No copied audio files to import");
+            return;
+        }
+        import::open_files(&app, files);
+    });
+}
@@ -0,0 +1,103 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State, Window, WindowEvent};
+
+use crate::jobs::{JobInfo, JobManager, QueueStatus};
+use crate::tray;
+
+// Closing the main window quits, and quitting kills the backend mid-render.
+// While generations are running the close is held back and the frontend gets
+// `close-requested-with-active-jobs` to ask what to do instead.
+#[derive(Default)]
+pub struct CloseGuard {
+    // Set by "finish": quit as soon as the queue drains.
+    quit_when_idle: AtomicBool,
+}
+
+#[derive(Clone, Serialize)]
+struct CloseRequested {
+    active: Vec<JobInfo>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CloseChoice {
+    // Hide the window, quit once the running jobs are done.
+    Finish,
+    // Quit now; the running jobs are lost.
+    Cancel,
+    // Hide the window and keep running in the tray.
+    Background,
+    // Never mind, stay open.
+    Stay,
+}
+
+// Hooked up in main.rs via on_window_event. Close-to-tray already keeps the
+// jobs alive, so only a real close is intercepted.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    let WindowEvent::CloseRequested { api, .. } = event else {
+        return;
+    };
+    let app = window.app_handle();
+    if window.label() != "main" || tray::closes_to_tray(app) {
+        return;
+    }
+    let status = app.state::<JobManager>().status();
+    if status.active.is_empty() {
+        return;
+    }
+    api.prevent_close();
+    println!(
+        "[CloseGuard] Close requested with {} job(s) running",
+        status.active.len()
+    );
+    let _ = app.emit(
+        "close-requested-with-active-jobs",
+        CloseRequested {
+            active: status.active,
+        },
+    );
+}
+
+// Called by jobs.rs whenever the queue changes.
+pub fn on_jobs_changed(app: &AppHandle, status: &QueueStatus) {
+    let Some(guard) = app.try_state::<CloseGuard>() else {
+        return;
+    };
+    if status.active.is_empty() && guard.quit_when_idle.load(Ordering::Relaxed) {
+        println!("[CloseGuard] Queue finished, quitting");
+        app.exit(0);
+    }
+}
+
+#[tauri::command]
+pub fn resolve_close_request(
+    app: AppHandle,
+    guard: State<'_, CloseGuard>,
+    choice: CloseChoice,
+) -> Result<(), String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or("Main window is not available")?;
+    match choice {
+        CloseChoice::Finish => {
+            if app.state::<JobManager>().status().active.is_empty() {
+                app.exit(0);
+                return Ok(());
+            }
+            guard.quit_when_idle.store(true, Ordering::Relaxed);
+            let _ = window.hide();
+            println!("[CloseGuard] Hidden, quitting when the queue is empty");
+        }
+        // The Exit handler in main.rs shuts the backend down.
+        CloseChoice::Cancel => app.exit(0),
+        CloseChoice::Background => {
+            guard.quit_when_idle.store(false, Ordering::Relaxed);
+            let _ = window.hide();
+            println!("[CloseGuard] Hidden, still running in the tray");
+        }
+        CloseChoice::Stay => guard.quit_when_idle.store(false, Ordering::Relaxed),
+    }
+    Ok(())
+}
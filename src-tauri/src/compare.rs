@@ -0,0 +1,363 @@
+use std::path::{Path, PathBuf};
+
+use rodio::{Decoder, Source};
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::history;
+use crate::waveform::AudioReader;
+
+// Both sounds are cut into frames of this many samples (mono mix); each gives
+// one loudness value and one spectral centroid. About 46 ms at 44.1 kHz.
+const FRAME: usize = 2048;
+
+// Points per loudness curve. Both curves share one time axis, as long as the
+// longer sound, so point N of one lines up with point N of the other.
+const POINTS: usize = 200;
+
+// Frames quieter than this are left out of the centroid stats; the
+// "brightness" of near-silence is noise.
+const SILENCE_DB: f32 = -60.0;
+
+// What silence reads as, instead of minus infinity (which JSON can't carry).
+const FLOOR_DB: f32 = -96.0;
+
+#[derive(Serialize)]
+pub struct Comparison {
+    pub a: SoundAnalysis,
+    pub b: SoundAnalysis,
+    // Seconds per point of the loudness curves.
+    pub resolution: f64,
+    // b minus a, so positive means b is longer / louder / brighter.
+    pub duration_diff: f64,
+    pub loudness_diff: f32,
+    pub centroid_diff: Option<f32>,
+}
+
+#[derive(Serialize)]
+pub struct SoundAnalysis {
+    pub id: String,
+    pub path: String,
+    pub duration: f64,
+    // dBFS over the whole sound.
+    pub peak_db: f32,
+    pub rms_db: f32,
+    // dBFS per point; null past the end of the sound.
+    pub loudness: Vec<Option<f32>>,
+    // None for a silent sound.
+    pub centroid: Option<CentroidStats>,
+}
+
+// Spectral centroid in Hz, over the frames that aren't silent.
+#[derive(Serialize)]
+pub struct CentroidStats {
+    pub mean: f32,
+    pub median: f32,
+    pub p10: f32,
+    pub p90: f32,
+}
+
+// Per-frame measurements of one file, before they're put on the shared axis.
+struct Frames {
+    sample_rate: u32,
+    samples: usize,
+    peak: f32,
+    sum_sq: f64,
+    mean_squares: Vec<f32>,
+    centroids: Vec<f32>,
+}
+
+impl Frames {
+    fn duration(&self) -> f64 {
+        self.samples as f64 / self.sample_rate.max(1) as f64
+    }
+
+    fn frame_seconds(&self) -> f64 {
+        FRAME as f64 / self.sample_rate.max(1) as f64
+    }
+
+    fn add(&mut self, frame: &[f32], spectrum: &mut Spectrum) {
+        let sum_sq: f64 = frame.iter().map(|s| (*s as f64) * (*s as f64)).sum();
+        let mean_square = sum_sq / frame.len() as f64;
+        self.samples += frame.len();
+        self.sum_sq += sum_sq;
+        self.peak = frame.iter().fold(self.peak, |peak, s| peak.max(s.abs()));
+        self.mean_squares.push(mean_square as f32);
+        if db(mean_square) >= SILENCE_DB
+            && let Some(centroid) = spectrum.centroid(frame, self.sample_rate)
+        {
+            self.centroids.push(centroid);
+        }
+    }
+}
+
+fn db(mean_square: f64) -> f32 {
+    if mean_square <= 0.0 {
+        return FLOOR_DB;
+    }
+    ((10.0 * mean_square.log10()) as f32).max(FLOOR_DB)
+}
+
+fn analyze(path: &Path) -> Result<Frames, String> {
+    let reader =
+        AudioReader::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let source = Decoder::new(reader)
+        .map_err(|e| format!("Failed to decode {}: {}", path.display(), e))?
+        .convert_samples::<f32>();
+    let channels = source.channels().max(1);
+    let mut frames = Frames {
+        sample_rate: source.sample_rate(),
+        samples: 0,
+        peak: 0.0,
+        sum_sq: 0.0,
+        mean_squares: Vec::new(),
+        centroids: Vec::new(),
+    };
+    let mut spectrum = Spectrum::new();
+    let mut buffer = Vec::with_capacity(FRAME);
+    let mut frame_sum = 0.0f32;
+    let mut channel = 0u16;
+
+    for sample in source {
+        frame_sum += sample;
+        channel += 1;
+        if channel < channels {
+            continue;
+        }
+        buffer.push(frame_sum / channels as f32);
+        frame_sum = 0.0;
+        channel = 0;
+        if buffer.len() == FRAME {
+            frames.add(&buffer, &mut spectrum);
+            buffer.clear();
+        }
+    }
+    if !buffer.is_empty() {
+        frames.add(&buffer, &mut spectrum);
+    }
+    Ok(frames)
+}
+
+// Scratch space for the FFT, set up once per file.
+struct Spectrum {
+    window: Vec<f32>,
+    re: Vec<f32>,
+    im: Vec<f32>,
+}
+
+impl Spectrum {
+    fn new() -> Self {
+        // Hann, so a frame's edges don't smear energy into the highs.
+        let window = (0..FRAME)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / (FRAME - 1) as f32).cos())
+            .collect();
+        Spectrum {
+            window,
+            re: vec![0.0; FRAME],
+            im: vec![0.0; FRAME],
+        }
+    }
+
+    // The magnitude-weighted mean frequency. A short last frame is padded
+    // with zeros.
+    fn centroid(&mut self, frame: &[f32], sample_rate: u32) -> Option<f32> {
+        for i in 0..FRAME {
+            self.re[i] = frame.get(i).map_or(0.0, |s| s * self.window[i]);
+            self.im[i] = 0.0;
+        }
+        fft(&mut self.re, &mut self.im);
+        let bin_hz = sample_rate as f32 / FRAME as f32;
+        let (mut weighted, mut total) = (0.0f32, 0.0f32);
+        for k in 1..FRAME / 2 {
+            let magnitude = (self.re[k] * self.re[k] + self.im[k] * self.im[k]).sqrt();
+            weighted += k as f32 * bin_hz * magnitude;
+            total += magnitude;
+        }
+        (total > 0.0).then(|| weighted / total)
+    }
+}
+
+// In-place iterative radix-2 FFT; the length is a power of two (FRAME).
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * std::f64::consts::PI / len as f64;
+        let (step_re, step_im) = (angle.cos(), angle.sin());
+        for start in (0..n).step_by(len) {
+            let (mut w_re, mut w_im) = (1.0f64, 0.0f64);
+            for k in 0..len / 2 {
+                let (a, b) = (start + k, start + k + len / 2);
+                let (wr, wi) = (w_re as f32, w_im as f32);
+                let t_re = re[b] * wr - im[b] * wi;
+                let t_im = re[b] * wi + im[b] * wr;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+                (w_re, w_im) = (
+                    w_re * step_re - w_im * step_im,
+                    w_re * step_im + w_im * step_re,
+                );
+            }
+        }
+        len <<= 1;
+    }
+}
+
+// The mean loudness of the frames under each point, on an axis of
+// `resolution` seconds per point.
+fn loudness_curve(frames: &Frames, resolution: f64) -> Vec<Option<f32>> {
+    let per_frame = frames.frame_seconds();
+    let count = frames.mean_squares.len();
+    (0..POINTS)
+        .map(|p| {
+            let start = (p as f64 * resolution / per_frame).floor() as usize;
+            if start >= count {
+                return None;
+            }
+            let end =
+                (((p + 1) as f64 * resolution / per_frame).ceil() as usize).clamp(start + 1, count);
+            let slice = &frames.mean_squares[start..end];
+            let mean = slice.iter().map(|m| *m as f64).sum::<f64>() / slice.len() as f64;
+            Some(db(mean))
+        })
+        .collect()
+}
+
+fn centroid_stats(centroids: &[f32]) -> Option<CentroidStats> {
+    if centroids.is_empty() {
+        return None;
+    }
+    let mut sorted = centroids.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let percentile = |p: f32| sorted[((sorted.len() - 1) as f32 * p).round() as usize];
+    Some(CentroidStats {
+        mean: sorted.iter().sum::<f32>() / sorted.len() as f32,
+        median: percentile(0.5),
+        p10: percentile(0.1),
+        p90: percentile(0.9),
+    })
+}
+
+fn summarize(id: &str, path: &Path, frames: &Frames, resolution: f64) -> SoundAnalysis {
+    SoundAnalysis {
+        id: id.to_string(),
+        path: path.to_string_lossy().into_owned(),
+        duration: frames.duration(),
+        peak_db: db((frames.peak as f64).powi(2)),
+        rms_db: db(frames.sum_sq / frames.samples.max(1) as f64),
+        loudness: loudness_curve(frames, resolution),
+        centroid: centroid_stats(&frames.centroids),
+    }
+}
+
+// A generation's first output; that's the one the player shows.
+fn output_of(entries: &[history::HistoryEntry], id: &str) -> Result<PathBuf, String> {
+    let entry = entries
+        .iter()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| format!("Unknown generation: {}", id))?;
+    let output = entry
+        .outputs
+        .first()
+        .map(PathBuf::from)
+        .ok_or_else(|| format!("Generation {} has no sound", id))?;
+    if !output.is_file() {
+        return Err(format!("File not found: {}", output.display()));
+    }
+    Ok(output)
+}
+
+pub fn compare(app: &AppHandle, id_a: &str, id_b: &str) -> Result<Comparison, String> {
+    let entries = history::load(app);
+    let (path_a, path_b) = (output_of(&entries, id_a)?, output_of(&entries, id_b)?);
+    let (frames_a, frames_b) = (analyze(&path_a)?, analyze(&path_b)?);
+
+    let longest = frames_a.duration().max(frames_b.duration());
+    let resolution = longest / POINTS as f64;
+    let a = summarize(id_a, &path_a, &frames_a, resolution);
+    let b = summarize(id_b, &path_b, &frames_b, resolution);
+    Ok(Comparison {
+        duration_diff: b.duration - a.duration,
+        loudness_diff: b.rms_db - a.rms_db,
+        centroid_diff: a
+            .centroid
+            .as_ref()
+            .zip(b.centroid.as_ref())
+            .map(|(a, b)| b.mean - a.mean),
+        resolution,
+        a,
+        b,
+    })
+}
+
+// Side by side stats for two generations (A/B while tuning a prompt).
+// Decodes both files, so it runs off the main thread.
+#[tauri::command]
+pub async fn compare_generations(
+    app: AppHandle,
+    id_a: String,
+    id_b: String,
+) -> Result<Comparison, String> {
+    tauri::async_runtime::spawn_blocking(move || compare(&app, &id_a, &id_b))
+        .await
+        .map_err(|e| format!("Comparison failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn magnitudes(re: &[f32], im: &[f32]) -> Vec<f32> {
+        re.iter().zip(im).map(|(r, i)| r.hypot(*i)).collect()
+    }
+
+    #[test]
+    fn a_sine_lands_in_its_bin() {
+        let n = 64;
+        let mut re: Vec<f32> = (0..n)
+            .map(|i| (2.0 * std::f32::consts::PI * 5.0 * i as f32 / n as f32).sin())
+            .collect();
+        let mut im = vec![0.0; n];
+        fft(&mut re, &mut im);
+        let spectrum = magnitudes(&re, &im);
+        for (bin, magnitude) in spectrum.iter().enumerate() {
+            let expected = if bin == 5 || bin == n - 5 {
+                n as f32 / 2.0
+            } else {
+                0.0
+            };
+            assert!(
+                (magnitude - expected).abs() < 1e-3,
+                "bin {}: {}",
+                bin,
+                magnitude
+            );
+        }
+        // sin is -i/2 at the positive frequency.
+        assert!(im[5] < 0.0);
+    }
+
+    #[test]
+    fn an_impulse_is_flat() {
+        let mut re = vec![0.0; 16];
+        re[0] = 1.0;
+        let mut im = vec![0.0; 16];
+        fft(&mut re, &mut im);
+        assert!(magnitudes(&re, &im).iter().all(|m| (m - 1.0).abs() < 1e-6));
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::paths;
+use crate::sidecar::Sidecar;
+
+pub const CRASHED_FLAG: &str = "--crashed";
+
+// Lines of backend output kept for the report.
+const LOG_TAIL_LINES: usize = 200;
+
+// A relaunched app that crashes again this soon is left down instead of
+// relaunching forever.
+const CRASH_LOOP_WINDOW: Duration = Duration::from_secs(30);
+
+// What the report needs that the panic hook can't safely go and ask for.
+struct CrashContext {
+    backend_log: VecDeque<String>,
+    // Last /health answer: device, torch/CUDA versions, GPU.
+    backend_info: Option<String>,
+}
+
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    backend_log: VecDeque::new(),
+    backend_info: None,
+});
+
+// The report left behind by the previous run, for the frontend to mention.
+#[derive(Default)]
+pub struct CrashState {
+    previous: Mutex<Option<CrashReport>>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct CrashReport {
+    path: String,
+    // The panic message, first line of the report's "Panic" section.
+    message: String,
+}
+
+pub fn record_backend_line(line: &str) {
+    let mut context = CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+    if context.backend_log.len() == LOG_TAIL_LINES {
+        context.backend_log.pop_front();
+    }
+    context.backend_log.push_back(line.trim_end().to_string());
+}
+
+pub fn record_backend_info(info: String) {
+    CONTEXT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .backend_info = Some(info);
+}
+
+fn reports_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_data_dir()
+        .ok()
+        .map(|dir| dir.join("crashes"))
+}
+
+// Called first thing in setup. Any panic after this writes a report, takes the
+// backend down with us and starts a fresh instance with --crashed.
+pub fn install(app: &AppHandle) {
+    let Some(dir) = reports_dir(app) else {
+        eprintln!("[Crash] No app data directory, crash reports disabled");
+        return;
+    };
+    let version = app.package_info().version.to_string();
+    let started = Instant::now();
+    let app = app.clone();
+
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+
+        match write_report(&dir, &version, info) {
+            Ok(path) => eprintln!("[Crash] Report written to {}", path.display()),
+            Err(e) => eprintln!("[Crash] Failed to write report: {}", e),
+        }
+        // The backend also watches our PID, but don't leave it holding the
+        // GPU until it notices.
+        if let Some(sidecar) = app.try_state::<Sidecar>() {
+            sidecar.kill_after_crash();
+        }
+
+        if launched_after_crash() && started.elapsed() < CRASH_LOOP_WINDOW {
+            eprintln!("[Crash] Crashed again right after relaunch, giving up");
+        } else {
+            relaunch();
+        }
+        std::process::exit(101);
+    }));
+}
+
+fn write_report(
+    dir: &std::path::Path,
+    version: &str,
+    info: &PanicHookInfo,
+) -> Result<PathBuf, String> {
+    let now = time::OffsetDateTime::now_utc();
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "(no message)".to_string());
+    let location = info
+        .location()
+        .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+        .unwrap_or_else(|| "(unknown)".to_string());
+    let thread = std::thread::current();
+
+    // Whoever panicked may hold the lock; a report without the log beats none.
+    let (backend_info, backend_log) = match CONTEXT.try_lock() {
+        Ok(context) => (
+            context.backend_info.clone(),
+            Vec::from(context.backend_log.clone()),
+        ),
+        Err(_) => (None, Vec::new()),
+    };
+
+    let mut report = String::new();
+    let _ = writeln!(report, "Noises {} crash report", version);
+    let _ = writeln!(
+        report,
+        "Time: {}",
+        now.format(&time::format_description::well_known::Rfc3339)
+            .unwrap_or_default()
+    );
+    let _ = writeln!(
+        report,
+        "OS: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(
+        report,
+        "Backend: {}",
+        backend_info.as_deref().unwrap_or("(never answered)")
+    );
+    let _ = writeln!(report, "\n== Panic\n{}", message);
+    let _ = writeln!(
+        report,
+        "at {} on thread {}",
+        location,
+        thread.name().unwrap_or("(unnamed)")
+    );
+    let _ = writeln!(
+        report,
+        "\n== Backtrace\n{}",
+        std::backtrace::Backtrace::force_capture()
+    );
+    let _ = writeln!(report, "== Backend log (last {} lines)", backend_log.len());
+    for line in backend_log {
+        let _ = writeln!(report, "{}", line);
+    }
+
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create crash folder: {}", e))?;
+    let path = dir.join(format!("crash-{}.txt", now.unix_timestamp()));
+    paths::write_atomic(&path, report)
+        .map_err(|e| format!("Failed to save crash report: {}", e))?;
+    Ok(path)
+}
+
+pub fn launched_after_crash() -> bool {
+    std::env::args().any(|arg| arg == CRASHED_FLAG)
+}
+
+// Same arguments as this run (so --minimized etc. carry over), plus --crashed.
+fn relaunch() {
+    let exe = match tauri::utils::platform::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            eprintln!("[Crash] Can't find our executable to relaunch: {}", e);
+            return;
+        }
+    };
+    let args = std::env::args()
+        .skip(1)
+        .filter(|arg| arg != CRASHED_FLAG)
+        .chain([CRASHED_FLAG.to_string()]);
+    match std::process::Command::new(exe).args(args).spawn() {
+        Ok(_) => eprintln!("[Crash] Relaunching"),
+        Err(e) => eprintln!("[Crash] Failed to relaunch: {}", e),
+    }
+}
+
+// Called from setup after a --crashed launch: picks up the newest report so
+// the frontend can say what happened.
+pub fn load_previous(app: &AppHandle) -> CrashState {
+    let state = CrashState::default();
+    if !launched_after_crash() {
+        return state;
+    }
+    let newest = reports_dir(app)
+        .and_then(|dir| std::fs::read_dir(dir).ok())
+        .and_then(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+                .max()
+        });
+    let Some(path) = newest else {
+        return state;
+    };
+    let message = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|report| {
+            report
+                .split_once("== Panic\n")
+                .and_then(|(_, rest)| rest.lines().next().map(str::to_string))
+        })
+        .unwrap_or_default();
+    println!(
+        "[Crash] Recovered from a crash, report at {}",
+        path.display()
+    );
+    *state.previous.lock().unwrap() = Some(CrashReport {
+        path: path.to_string_lossy().into_owned(),
+        message,
+    });
+    state
+}
+
+// Hands out the previous run's report once; None after that, or when the last
+// run didn't crash.
+#[tauri::command]
+pub fn take_crash_report(state: tauri::State<'_, CrashState>) -> Option<CrashReport> {
+    state.previous.lock().unwrap().take()
+}
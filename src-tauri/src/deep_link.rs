@@ -0,0 +1,300 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::freesound;
+use crate::import;
+use crate::protocol;
+use crate::tray;
+
+// Form fields a link may prefill, named like the frontend's config.
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Prefill {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prompt: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    negative_prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lyrics: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bpm: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    length: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    steps: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    guidance: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+}
+
+// Emitted as `deep-link`, e.g. { "action": "prefill", "config": { "prompt": ... } }.
+#[derive(Clone, Serialize)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum DeepLink {
+    // noises://generate?prompt=dusty+vinyl+drums&type=loop&bpm=90
+    Prefill { config: Prefill },
+    // noises://open?sound=samples/loops/loop_001.wav (library id or absolute path)
+    OpenSound { file: String, path: String },
+}
+
+// Links that arrive before the webview is listening (the one we were launched
+// with, usually) wait here until the frontend asks for them.
+#[derive(Default)]
+pub struct DeepLinkState {
+    inner: Mutex<Pending>,
+}
+
+#[derive(Default)]
+struct Pending {
+    frontend_ready: bool,
+    links: Vec<DeepLink>,
+}
+
+pub fn init(app: &AppHandle) {
+    // Installers register the scheme; dev builds on Windows/Linux have to do it themselves.
+    #[cfg(all(debug_assertions, any(target_os = "windows", target_os = "linux")))]
+    if let Err(e) = app.deep_link().register_all() {
+        eprintln!("[DeepLink] Failed to register URL scheme: {}", e);
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            open_url(&handle, &url);
+        }
+    });
+
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in urls {
+            open_url(app, &url);
+        }
+    }
+}
+
+fn open_url(app: &AppHandle, url: &Url) {
+    // macOS hands "Open with" files to us as file:// URLs through the same event.
+    if url.scheme() == "file" {
+        if let Ok(path) = url.to_file_path() {
+            import::open_files(app, vec![path]);
+        }
+        return;
+    }
+
+    let resolve = |sound: &str| protocol::resolve_library_path(app, sound).ok();
+    let link = match parse(url, resolve) {
+        Ok(link) => link,
+        Err(e) => {
+            eprintln!("[DeepLink] Ignoring {}: {}", url, e);
+            return;
+        }
+    };
+    println!("[DeepLink] {}", url);
+    match link {
+        Link::Ui(link) => deliver(app, link),
+        Link::Play(path) => tray::play(app, path.to_string_lossy().into_owned()),
+        Link::Reveal(path) => {
+            if let Err(e) = crate::show_in_folder(path.to_string_lossy().into_owned()) {
+                eprintln!("[DeepLink] {}", e);
+            }
+        }
+        Link::FreesoundLogin { code, state } => {
+            tray::show_main_window(app);
+            freesound::finish_login(app, code, state);
+        }
+    }
+}
+
+// Emits `deep-link`, or queues it if the frontend hasn't picked up the pending ones yet.
+pub fn deliver(app: &AppHandle, link: DeepLink) {
+    tray::show_main_window(app);
+
+    let state = app.state::<DeepLinkState>();
+    let mut pending = state.inner.lock().unwrap();
+    if pending.frontend_ready {
+        let _ = app.emit("deep-link", link);
+    } else {
+        pending.links.push(link);
+    }
+}
+
+// What a link asks for. Play and Reveal (used by the jump list) and the
+// Freesound sign-in coming back are handled right here; everything else goes
+// to the frontend.
+enum Link {
+    Ui(DeepLink),
+    Play(PathBuf),
+    Reveal(PathBuf),
+    // noises://freesound?code=...&state=...
+    FreesoundLogin {
+        code: Option<String>,
+        state: Option<String>,
+    },
+}
+
+// `resolve` turns a sound parameter into its path, or None when it isn't in
+// the library.
+fn parse(url: &Url, resolve: impl Fn(&str) -> Option<PathBuf>) -> Result<Link, String> {
+    // noises://generate?... puts the action in the host, noises:generate?... in the path.
+    let action = url
+        .host_str()
+        .unwrap_or_else(|| url.path())
+        .trim_matches('/')
+        .to_ascii_lowercase();
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.into_owned())
+            .filter(|v| !v.is_empty())
+    };
+    // Links come from anywhere, so they only get to point inside the library.
+    let sound = || {
+        let sound = param("sound").ok_or("missing sound")?;
+        resolve(&sound).ok_or_else(|| format!("{} is not in the library", sound))
+    };
+
+    match action.as_str() {
+        "generate" => {
+            let config = Prefill {
+                prompt: param("prompt"),
+                kind: param("type"),
+                negative_prompt: param("negative_prompt"),
+                lyrics: param("lyrics"),
+                bpm: param("bpm").and_then(|v| v.parse().ok()),
+                key: param("key"),
+                length: param("length").and_then(|v| v.parse().ok()),
+                steps: param("steps").and_then(|v| v.parse().ok()),
+                guidance: param("guidance").and_then(|v| v.parse().ok()),
+                seed: param("seed").and_then(|v| v.parse().ok()),
+            };
+            if config.prompt.is_none() {
+                return Err("missing prompt".to_string());
+            }
+            Ok(Link::Ui(DeepLink::Prefill { config }))
+        }
+        "open" => {
+            let path = sound()?;
+            Ok(Link::Ui(DeepLink::OpenSound {
+                file: path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                path: path.to_string_lossy().into_owned(),
+            }))
+        }
+        "play" => Ok(Link::Play(sound()?)),
+        "reveal" => Ok(Link::Reveal(sound()?)),
+        "freesound" => Ok(Link::FreesoundLogin {
+            code: param("code"),
+            state: param("state"),
+        }),
+        other => Err(format!("unknown action {:?}", other)),
+    }
+}
+
+// Called once by the frontend after it starts listening for `deep-link`.
+#[tauri::command]
+pub fn take_pending_deep_links(state: State<'_, DeepLinkState>) -> Vec<DeepLink> {
+    let mut pending = state.inner.lock().unwrap();
+    pending.frontend_ready = true;
+    std::mem::take(&mut pending.links)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library(sound: &str) -> Option<PathBuf> {
+        sound
+            .starts_with("samples/")
+            .then(|| PathBuf::from("/library").join(sound))
+    }
+
+    fn parse_str(url: &str) -> Result<Link, String> {
+        parse(&Url::parse(url).unwrap(), library)
+    }
+
+    fn prefill(url: &str) -> Prefill {
+        match parse_str(url) {
+            Ok(Link::Ui(DeepLink::Prefill { config })) => config,
+            _ => panic!("{} isn't a prefill", url),
+        }
+    }
+
+    #[test]
+    fn generate_links_prefill_the_form() {
+        let config = prefill(
+            "noises://generate?prompt=dusty+vinyl%20drums&type=loop&bpm=90&length=4.5&seed=-3",
+        );
+        assert_eq!(config.prompt.as_deref(), Some("dusty vinyl drums"));
+        assert_eq!(config.kind.as_deref(), Some("loop"));
+        assert_eq!(config.bpm, Some(90));
+        assert_eq!(config.length, Some(4.5));
+        assert_eq!(config.seed, Some(-3));
+        assert_eq!(config.key, None);
+    }
+
+    #[test]
+    fn the_action_can_be_in_the_path_and_in_any_case() {
+        assert!(prefill("noises:generate?prompt=pad").prompt.is_some());
+        assert!(prefill("noises://GENERATE/?prompt=pad").prompt.is_some());
+    }
+
+    #[test]
+    fn unparsable_numbers_are_left_out() {
+        let config = prefill("noises://generate?prompt=pad&bpm=fast&steps=-1&guidance=");
+        assert_eq!(config.bpm, None);
+        assert_eq!(config.steps, None);
+        assert_eq!(config.guidance, None);
+    }
+
+    #[test]
+    fn generate_needs_a_prompt() {
+        assert!(parse_str("noises://generate?type=loop").is_err());
+        assert!(parse_str("noises://generate?prompt=").is_err());
+    }
+
+    #[test]
+    fn sounds_have_to_be_in_the_library() {
+        match parse_str("noises://open?sound=samples/loops/loop_001.wav") {
+            Ok(Link::Ui(DeepLink::OpenSound { file, path })) => {
+                assert_eq!(file, "loop_001.wav");
+                assert_eq!(
+                    PathBuf::from(path),
+                    PathBuf::from("/library/samples/loops/loop_001.wav")
+                );
+            }
+            _ => panic!("not an open link"),
+        }
+        assert!(matches!(
+            parse_str("noises://play?sound=samples/kick.wav"),
+            Ok(Link::Play(_))
+        ));
+        assert!(parse_str("noises://reveal?sound=/etc/passwd").is_err());
+        assert!(parse_str("noises://play").is_err());
+    }
+
+    #[test]
+    fn freesound_logins_keep_their_code_and_state() {
+        match parse_str("noises://freesound?code=abc&state=xyz") {
+            Ok(Link::FreesoundLogin { code, state }) => {
+                assert_eq!(code.as_deref(), Some("abc"));
+                assert_eq!(state.as_deref(), Some("xyz"));
+            }
+            _ => panic!("not a Freesound login"),
+        }
+    }
+
+    #[test]
+    fn unknown_actions_are_refused() {
+        assert!(parse_str("noises://delete?sound=samples/kick.wav").is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde_json::{Value, json};
+use tauri::AppHandle;
+
+use crate::history;
+use crate::protocol;
+use crate::proxy;
+use crate::secrets::{self, Secret};
+use crate::telemetry;
+
+// Discord's upload limit for a server without boosts, for all files of a
+// message together, and its cap on attachments and message length.
+const MAX_UPLOAD_BYTES: u64 = 10 * 1024 * 1024;
+const MAX_FILES: usize = 10;
+const MAX_CONTENT_CHARS: usize = 2000;
+
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+// The request settings worth showing next to a candidate, in this order.
+const PARAMS: &[&str] = &["type", "length", "steps", "cfg_scale", "seed", "variations"];
+
+const WEBHOOK_HOSTS: &[&str] = &[
+    "discord.com",
+    "discordapp.com",
+    "canary.discord.com",
+    "ptb.discord.com",
+];
+
+// The webhook URL carries its own token, so it's kept with the other secrets.
+fn webhook(app: &AppHandle) -> Result<reqwest::Url, String> {
+    let url = secrets::get(app, Secret::DiscordWebhook)?
+        .ok_or("Set a Discord webhook in Settings first")?;
+    let url = reqwest::Url::parse(&url).map_err(|_| "The Discord webhook isn't a valid URL")?;
+    let is_webhook = url.scheme() == "https"
+        && url
+            .host_str()
+            .is_some_and(|host| WEBHOOK_HOSTS.contains(&host))
+        && url.path().starts_with("/api/webhooks/");
+    if !is_webhook {
+        return Err(
+            "The Discord webhook should look like https://discord.com/api/webhooks/...".to_string(),
+        );
+    }
+    Ok(url)
+}
+
+// **prompt**, then the model and the settings the request had, e.g.
+// `stable-audio-open · type: loop · length: 8 · seed: 1234`.
+fn message(entry: &history::HistoryEntry) -> String {
+    let mut details = vec![entry.model.clone()];
+    for key in PARAMS {
+        let value = match entry.params.get(*key) {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => continue,
+            Some(other) => other.to_string(),
+        };
+        details.push(format!("{}: {}", key, value));
+    }
+    let details = format!("`{}`", details.join(" · ").replace('`', "'"));
+    // Markdown in the prompt is escaped rather than rendered.
+    let prompt: String = entry
+        .prompt
+        .chars()
+        .flat_map(|c| {
+            let escape = matches!(c, '*' | '_' | '~' | '`' | '|' | '>' | '\\');
+            escape.then_some('\\').into_iter().chain([c])
+        })
+        .collect();
+    let room = MAX_CONTENT_CHARS.saturating_sub(details.chars().count() + 6);
+    let prompt = if prompt.chars().count() > room {
+        let cut: String = prompt.chars().take(room.saturating_sub(1)).collect();
+        format!("{}…", cut.trim_end_matches('\\'))
+    } else {
+        prompt
+    };
+    format!("**{}**\n{}", prompt, details)
+}
+
+// Posts a generation's files with its prompt and settings to the webhook
+// from the `discord-webhook` secret, for a team channel that collects
+// candidates.
+#[tauri::command]
+pub async fn share_to_discord(app: AppHandle, id: String) -> Result<(), String> {
+    let entry = history::load(&app)
+        .into_iter()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| format!("Unknown generation: {}", id))?;
+    let files: Vec<PathBuf> = entry
+        .outputs
+        .iter()
+        .map(PathBuf::from)
+        .filter(|path| path.is_file())
+        .take(MAX_FILES)
+        .collect();
+    if files.is_empty() {
+        return Err("The files of that generation aren't there any more".to_string());
+    }
+    let size: u64 = files
+        .iter()
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum();
+    if size > MAX_UPLOAD_BYTES {
+        return Err(format!(
+            "Too big for Discord ({:.1} MB, the limit is {} MB)",
+            size as f64 / (1024.0 * 1024.0),
+            MAX_UPLOAD_BYTES / (1024 * 1024)
+        ));
+    }
+    let url = webhook(&app)?;
+    telemetry::record_feature(&app, "discord");
+
+    // Mentions stay off, so an @everyone in a prompt doesn't ping anybody.
+    let payload = json!({
+        "content": message(&entry),
+        "allowed_mentions": { "parse": [] },
+    });
+    let mut form = reqwest::multipart::Form::new().text("payload_json", payload.to_string());
+    for (i, path) in files.iter().enumerate() {
+        let bytes = tokio::fs::read(path)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let part = reqwest::multipart::Part::bytes(bytes)
+            .file_name(
+                path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+            .mime_str(protocol::content_type(path))
+            .map_err(|e| format!("Failed to build upload: {}", e))?;
+        form = form.part(format!("files[{}]", i), part);
+    }
+
+    let res = proxy::client_builder(&app)?
+        .timeout(TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?
+        .post(url)
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach Discord: {}", e))?;
+    let status = res.status();
+    if !status.is_success() {
+        let body = res.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|error| Some(error.get("message")?.as_str()?.to_string()))
+            .unwrap_or(body);
+        return Err(format!(
+            "Discord refused the post ({}): {}",
+            status, message
+        ));
+    }
+    println!("[Discord] Posted {} files from {}", files.len(), entry.id);
+    Ok(())
+}
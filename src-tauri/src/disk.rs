@@ -0,0 +1,101 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::models;
+use crate::paths;
+
+// Leading code of the error for a volume without room for what's about to be
+// written; the rest gives both sizes.
+pub const DISK_FULL_ERROR: &str = "DiskFull";
+
+// Kept free on top of the estimate, since it can be off and the rest of the
+// system needs room too.
+const HEADROOM: u64 = 64 * 1024 * 1024;
+
+// Bytes the current user may still write on the volume `path` is on. The
+// path itself needn't exist yet; its nearest existing folder is asked.
+pub fn available(path: &Path) -> Option<u64> {
+    let dir = path.ancestors().find(|p| p.is_dir())?;
+    free_space(dir)
+}
+
+#[cfg(unix)]
+fn free_space(dir: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: a NUL-terminated path and an out-pointer to a local.
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+fn free_space(dir: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+    use windows::core::PCWSTR;
+
+    let wide: Vec<u16> = dir.as_os_str().encode_wide().chain([0]).collect();
+    let mut free = 0u64;
+    // SAFETY: a NUL-terminated path and an out-pointer to a local.
+    unsafe { GetDiskFreeSpaceExW(PCWSTR(wide.as_ptr()), Some(&mut free), None, None) }.ok()?;
+    Some(free)
+}
+
+fn megabytes(bytes: u64) -> String {
+    format!("{:.0} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+// Fails with DiskFull when `dir`'s volume has less than `needed` bytes (plus
+// some headroom) free, instead of finding a truncated file afterwards. When
+// the free space can't be read it's left to the write itself.
+pub fn ensure_space(dir: &Path, needed: u64) -> Result<(), String> {
+    let Some(free) = available(dir) else {
+        return Ok(());
+    };
+    let required = needed.saturating_add(HEADROOM);
+    if free >= required {
+        return Ok(());
+    }
+    eprintln!(
+        "[Disk] {} needs {} bytes, {} available",
+        dir.display(),
+        required,
+        free
+    );
+    Err(format!(
+        "{}: Not enough disk space for {}: {} needed, {} free (required {} bytes, available {} bytes)",
+        DISK_FULL_ERROR,
+        dir.display(),
+        megabytes(required),
+        megabytes(free),
+        required,
+        free
+    ))
+}
+
+// Before a generation goes to the backend: room in the folder it writes to
+// (the config's output_dir, or the active workspace's) for the estimated
+// files.
+pub fn ensure_space_for_generation(app: &AppHandle, config: &Value) -> Result<(), String> {
+    let dir = match config.get("output_dir").and_then(|d| d.as_str()) {
+        Some(dir) => PathBuf::from(dir),
+        None => paths::output_dir(app)?,
+    };
+    ensure_space(&dir, models::output_bytes(config))
+}
+
+// Before copying files somewhere: room for all of them in `dir`.
+pub fn ensure_space_for_files(dir: &Path, files: &[PathBuf]) -> Result<(), String> {
+    let needed = files
+        .iter()
+        .filter_map(|file| std::fs::metadata(file).ok())
+        .map(|meta| meta.len())
+        .sum();
+    ensure_space(dir, needed)
+}
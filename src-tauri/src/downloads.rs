@@ -0,0 +1,402 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::http::{StatusCode, header};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_util::sync::CancellationToken;
+
+use crate::proxy;
+use crate::tasks::{self, Task, TaskKind};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+// Model weights are multi-gigabyte, so there's no overall timeout, only for connecting.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadStatus {
+    Downloading,
+    Paused,
+    Verifying,
+    Completed,
+    Failed,
+}
+
+#[derive(Clone, Serialize)]
+pub struct DownloadInfo {
+    pub id: String,
+    pub url: String,
+    pub dest: String,
+    pub status: DownloadStatus,
+    pub downloaded: u64,
+    pub total: Option<u64>,
+    pub error: Option<String>,
+}
+
+struct Download {
+    info: DownloadInfo,
+    sha256: Option<String>,
+    // Sent as a bearer token, e.g. for gated Hugging Face files.
+    bearer: Option<String>,
+    // Present while a transfer task is running.
+    token: Option<CancellationToken>,
+}
+
+// Resumable downloads: data goes to `<dest>.part` and is renamed into place
+// only after the (optional) SHA-256 matches. Pausing keeps the .part file so
+// resuming continues with a Range request.
+#[derive(Default)]
+pub struct DownloadManager {
+    downloads: Mutex<HashMap<String, Download>>,
+}
+
+impl DownloadManager {
+    fn update(&self, app: &AppHandle, id: &str, f: impl FnOnce(&mut DownloadInfo)) {
+        let info = {
+            let mut downloads = self.downloads.lock().unwrap();
+            let Some(download) = downloads.get_mut(id) else {
+                return;
+            };
+            f(&mut download.info);
+            download.info.clone()
+        };
+        let _ = app.emit("download-progress", info);
+    }
+
+    // Registers the transfer as a task (so cancel_task pauses it, like
+    // pause_download), or None if the download is already running.
+    fn begin(&self, app: &AppHandle, id: &str) -> Option<Task> {
+        let mut downloads = self.downloads.lock().unwrap();
+        let download = downloads.get_mut(id)?;
+        if download.token.is_some() {
+            return None;
+        }
+        let name = Path::new(&download.info.dest)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| id.to_string());
+        let task = tasks::begin(app, TaskKind::Download, &name);
+        download.token = Some(task.token());
+        download.info.status = DownloadStatus::Downloading;
+        download.info.error = None;
+        Some(task)
+    }
+}
+
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+#[tauri::command]
+pub fn start_download(
+    app: AppHandle,
+    id: String,
+    url: String,
+    dest: String,
+    sha256: Option<String>,
+) -> Result<(), String> {
+    start(&app, id, url, dest, sha256, None)
+}
+
+// Also used by models.rs, which keeps an eye on its files through info().
+pub fn start(
+    app: &AppHandle,
+    id: String,
+    url: String,
+    dest: String,
+    sha256: Option<String>,
+    bearer: Option<String>,
+) -> Result<(), String> {
+    let manager = app.state::<DownloadManager>();
+    {
+        let mut downloads = manager.downloads.lock().unwrap();
+        if downloads.get(&id).is_some_and(|d| d.token.is_some()) {
+            return Err(format!("Download {} is already running", id));
+        }
+        downloads.insert(
+            id.clone(),
+            Download {
+                info: DownloadInfo {
+                    id: id.clone(),
+                    url,
+                    dest,
+                    status: DownloadStatus::Paused,
+                    downloaded: 0,
+                    total: None,
+                    error: None,
+                },
+                sha256: sha256.map(|s| s.to_lowercase()),
+                bearer,
+                token: None,
+            },
+        );
+    }
+    spawn_transfer(app.clone(), &manager, id)
+}
+
+pub fn info(app: &AppHandle, id: &str) -> Option<DownloadInfo> {
+    let manager = app.state::<DownloadManager>();
+    let downloads = manager.downloads.lock().unwrap();
+    downloads.get(id).map(|d| d.info.clone())
+}
+
+#[tauri::command]
+pub fn pause_download(manager: State<'_, DownloadManager>, id: String) -> Result<(), String> {
+    let downloads = manager.downloads.lock().unwrap();
+    let download = downloads
+        .get(&id)
+        .ok_or_else(|| format!("Unknown download: {}", id))?;
+    if let Some(token) = &download.token {
+        token.cancel();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn resume_download(
+    app: AppHandle,
+    manager: State<'_, DownloadManager>,
+    id: String,
+) -> Result<(), String> {
+    spawn_transfer(app, &manager, id)
+}
+
+// Stops the transfer and throws away the partial data.
+#[tauri::command]
+pub fn cancel_download(manager: State<'_, DownloadManager>, id: String) -> Result<(), String> {
+    let download = manager
+        .downloads
+        .lock()
+        .unwrap()
+        .remove(&id)
+        .ok_or_else(|| format!("Unknown download: {}", id))?;
+    if let Some(token) = download.token {
+        token.cancel();
+    }
+    let _ = std::fs::remove_file(part_path(Path::new(&download.info.dest)));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_downloads(manager: State<'_, DownloadManager>) -> Vec<DownloadInfo> {
+    let downloads = manager.downloads.lock().unwrap();
+    downloads.values().map(|d| d.info.clone()).collect()
+}
+
+fn spawn_transfer(app: AppHandle, manager: &DownloadManager, id: String) -> Result<(), String> {
+    let (url, dest, sha256, bearer) = {
+        let downloads = manager.downloads.lock().unwrap();
+        let download = downloads
+            .get(&id)
+            .ok_or_else(|| format!("Unknown download: {}", id))?;
+        if download.info.status == DownloadStatus::Completed {
+            return Ok(());
+        }
+        (
+            download.info.url.clone(),
+            PathBuf::from(&download.info.dest),
+            download.sha256.clone(),
+            download.bearer.clone(),
+        )
+    };
+    let task = manager
+        .begin(&app, &id)
+        .ok_or_else(|| format!("Download {} is already running", id))?;
+
+    tauri::async_runtime::spawn(async move {
+        let result = transfer(
+            &app,
+            &id,
+            &url,
+            &dest,
+            sha256.as_deref(),
+            bearer.as_deref(),
+            &task,
+        )
+        .await;
+        let manager = app.state::<DownloadManager>();
+        if let Some(download) = manager.downloads.lock().unwrap().get_mut(&id) {
+            download.token = None;
+        }
+
+        match result {
+            Ok(true) => {
+                println!("[Downloads] {} finished: {}", id, dest.display());
+                manager.update(&app, &id, |info| info.status = DownloadStatus::Completed);
+            }
+            Ok(false) => {
+                manager.update(&app, &id, |info| info.status = DownloadStatus::Paused);
+            }
+            Err(e) => {
+                eprintln!("[Downloads] {} failed: {}", id, e);
+                manager.update(&app, &id, |info| {
+                    info.status = DownloadStatus::Failed;
+                    info.error = Some(e);
+                });
+            }
+        }
+    });
+    Ok(())
+}
+
+// Ok(true) when the file is complete and verified, Ok(false) when paused.
+async fn transfer(
+    app: &AppHandle,
+    id: &str,
+    url: &str,
+    dest: &Path,
+    sha256: Option<&str>,
+    bearer: Option<&str>,
+    task: &Task,
+) -> Result<bool, String> {
+    let manager = app.state::<DownloadManager>();
+    let part = part_path(dest);
+    if let Some(parent) = dest.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create download folder: {}", e))?;
+    }
+
+    let existing = tokio::fs::metadata(&part)
+        .await
+        .map(|m| m.len())
+        .unwrap_or(0);
+
+    let client = proxy::client_builder(app)?
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let mut request = client.get(url);
+    if let Some(bearer) = bearer {
+        request = request.bearer_auth(bearer);
+    }
+    if existing > 0 {
+        request = request.header(header::RANGE, format!("bytes={}-", existing));
+    }
+    let mut res = request
+        .send()
+        .await
+        .map_err(|e| format!("Download request failed: {}", e))?;
+
+    // 206 means the server honoured the range; a plain 200 means start over.
+    let resumed = res.status() == StatusCode::PARTIAL_CONTENT;
+    if res.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        // The .part file already holds everything, go straight to verification.
+    } else if !res.status().is_success() {
+        return Err(format!("Download failed ({})", res.status()));
+    }
+
+    let mut hasher = Sha256::new();
+    let mut downloaded = 0u64;
+    let mut file = if resumed || res.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        // Hash what we already have so the final checksum covers the whole file.
+        if sha256.is_some() {
+            hash_file(&part, &mut hasher).await?;
+        }
+        downloaded = existing;
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&part)
+            .await
+            .map_err(|e| format!("Failed to open partial download: {}", e))?
+    } else {
+        tokio::fs::File::create(&part)
+            .await
+            .map_err(|e| format!("Failed to create download file: {}", e))?
+    };
+
+    let total = if res.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        Some(existing)
+    } else {
+        res.content_length().map(|len| len + downloaded)
+    };
+    manager.update(app, id, |info| {
+        info.downloaded = downloaded;
+        info.total = total;
+    });
+    task.progress(downloaded, total);
+
+    let token = task.token();
+    if res.status() != StatusCode::RANGE_NOT_SATISFIABLE {
+        let mut last_emit = Instant::now();
+        loop {
+            let chunk = tokio::select! {
+                _ = token.cancelled() => {
+                    let _ = file.flush().await;
+                    return Ok(false);
+                }
+                chunk = res.chunk() => chunk.map_err(|e| format!("Download interrupted: {}", e))?,
+            };
+            let Some(chunk) = chunk else { break };
+
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| format!("Failed to write download: {}", e))?;
+            if sha256.is_some() {
+                hasher.update(&chunk);
+            }
+            downloaded += chunk.len() as u64;
+
+            if last_emit.elapsed() >= PROGRESS_INTERVAL {
+                manager.update(app, id, |info| info.downloaded = downloaded);
+                task.progress(downloaded, total);
+                last_emit = Instant::now();
+            }
+        }
+    }
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to write download: {}", e))?;
+    drop(file);
+
+    if let Some(expected) = sha256 {
+        manager.update(app, id, |info| {
+            info.downloaded = downloaded;
+            info.status = DownloadStatus::Verifying;
+        });
+        let actual = to_hex(&hasher.finalize());
+        if actual != expected {
+            // A corrupt .part would poison every later resume.
+            let _ = tokio::fs::remove_file(&part).await;
+            return Err(format!(
+                "Checksum mismatch (expected {}, got {})",
+                expected, actual
+            ));
+        }
+    }
+
+    tokio::fs::rename(&part, dest)
+        .await
+        .map_err(|e| format!("Failed to finish download: {}", e))?;
+    manager.update(app, id, |info| info.downloaded = downloaded);
+    Ok(true)
+}
+
+async fn hash_file(path: &Path, hasher: &mut Sha256) -> Result<(), String> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("Failed to read partial download: {}", e))?;
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file
+            .read(&mut buf)
+            .await
+            .map_err(|e| format!("Failed to read partial download: {}", e))?;
+        if n == 0 {
+            return Ok(());
+        }
+        hasher.update(&buf[..n]);
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,233 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, WebviewWindow};
+use tokio::sync::oneshot;
+
+use crate::loop_metadata;
+use crate::paths;
+use crate::waveform::{self, WAVE_COLOR};
+
+// Drag preview: a card with the first file's waveform, the cards of further
+// files stacked behind it, and a count badge when there's more than one.
+const IMAGE_WIDTH: usize = 176;
+const IMAGE_HEIGHT: usize = 76;
+const CARD_WIDTH: usize = 160;
+const CARD_HEIGHT: usize = 56;
+const CARD_PADDING: usize = 6;
+const STACK_OFFSET: usize = 4;
+const BADGE_RADIUS: usize = 12;
+
+const CARD_COLOR: [u8; 4] = [24, 24, 32, 235];
+const BACK_CARD_COLOR: [u8; 4] = [48, 40, 64, 220];
+const BADGE_TEXT_COLOR: [u8; 4] = [255, 255, 255, 255];
+
+// 3x5 glyphs for the badge, one row per byte (low 3 bits, left to right).
+const GLYPHS: [[u8; 5]; 11] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+    // '+'
+    [0b000, 0b010, 0b111, 0b010, 0b000],
+];
+const GLYPH_SCALE: usize = 2;
+
+#[derive(Clone, Serialize)]
+struct DragOutFinished {
+    dropped: bool,
+}
+
+struct Canvas {
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new() -> Self {
+        Canvas {
+            pixels: vec![0; IMAGE_WIDTH * IMAGE_HEIGHT * 4],
+        }
+    }
+
+    fn put(&mut self, x: usize, y: usize, color: [u8; 4]) {
+        if x < IMAGE_WIDTH && y < IMAGE_HEIGHT {
+            let i = (y * IMAGE_WIDTH + x) * 4;
+            self.pixels[i..i + 4].copy_from_slice(&color);
+        }
+    }
+
+    fn rounded_rect(&mut self, x0: usize, y0: usize, w: usize, h: usize, color: [u8; 4]) {
+        let r = 6.0f32;
+        for y in 0..h {
+            for x in 0..w {
+                // Distance into the corner square, zero along the straight edges.
+                let dx = (r - x as f32 - 0.5)
+                    .max(x as f32 + 0.5 - (w as f32 - r))
+                    .max(0.0);
+                let dy = (r - y as f32 - 0.5)
+                    .max(y as f32 + 0.5 - (h as f32 - r))
+                    .max(0.0);
+                if dx * dx + dy * dy <= r * r {
+                    self.put(x0 + x, y0 + y, color);
+                }
+            }
+        }
+    }
+
+    fn circle(&mut self, cx: usize, cy: usize, radius: usize, color: [u8; 4]) {
+        let r = radius as f32;
+        for y in cy.saturating_sub(radius)..=cy + radius {
+            for x in cx.saturating_sub(radius)..=cx + radius {
+                let dx = x as f32 - cx as f32;
+                let dy = y as f32 - cy as f32;
+                if dx * dx + dy * dy <= r * r {
+                    self.put(x, y, color);
+                }
+            }
+        }
+    }
+
+    // Draws `text` (digits and '+') centred on (cx, cy).
+    fn label(&mut self, cx: usize, cy: usize, text: &str, color: [u8; 4]) {
+        let glyph_w = 3 * GLYPH_SCALE;
+        let glyph_h = 5 * GLYPH_SCALE;
+        let count = text.chars().count();
+        let total_w = count * glyph_w + count.saturating_sub(1) * GLYPH_SCALE;
+        let mut x0 = cx.saturating_sub(total_w / 2);
+        let y0 = cy.saturating_sub(glyph_h / 2);
+
+        for ch in text.chars() {
+            let glyph = match ch {
+                '0'..='9' => GLYPHS[ch as usize - '0' as usize],
+                _ => GLYPHS[10],
+            };
+            for (row, bits) in glyph.iter().enumerate() {
+                for col in 0..3 {
+                    if bits & (0b100 >> col) == 0 {
+                        continue;
+                    }
+                    for sy in 0..GLYPH_SCALE {
+                        for sx in 0..GLYPH_SCALE {
+                            self.put(
+                                x0 + col * GLYPH_SCALE + sx,
+                                y0 + row * GLYPH_SCALE + sy,
+                                color,
+                            );
+                        }
+                    }
+                }
+            }
+            x0 += glyph_w + GLYPH_SCALE;
+        }
+    }
+}
+
+fn render_preview(app: &AppHandle, files: &[PathBuf]) -> Result<Vec<u8>, String> {
+    let mut canvas = Canvas::new();
+    let behind = files.len().saturating_sub(1).min(2);
+    let card_y = STACK_OFFSET * 2 + BADGE_RADIUS / 2;
+
+    // Furthest card first so the front one overwrites it.
+    for k in (1..=behind).rev() {
+        canvas.rounded_rect(
+            STACK_OFFSET * k,
+            card_y - STACK_OFFSET * k,
+            CARD_WIDTH,
+            CARD_HEIGHT,
+            BACK_CARD_COLOR,
+        );
+    }
+    canvas.rounded_rect(0, card_y, CARD_WIDTH, CARD_HEIGHT, CARD_COLOR);
+
+    let wave_w = CARD_WIDTH - CARD_PADDING * 2;
+    let wave_h = CARD_HEIGHT - CARD_PADDING * 2;
+    // No waveform (undecodable file) still leaves a usable card.
+    if let Ok(peaks) = waveform::cached_peaks(app, &files[0], wave_w) {
+        waveform::draw_peaks(
+            &mut canvas.pixels,
+            IMAGE_WIDTH,
+            (CARD_PADDING, card_y + CARD_PADDING, wave_w, wave_h),
+            &peaks,
+            WAVE_COLOR,
+        );
+    }
+
+    if files.len() > 1 {
+        let text = if files.len() > 99 {
+            "99+".to_string()
+        } else {
+            files.len().to_string()
+        };
+        let cx = IMAGE_WIDTH - BADGE_RADIUS - 1;
+        canvas.circle(cx, BADGE_RADIUS, BADGE_RADIUS, WAVE_COLOR);
+        canvas.label(cx, BADGE_RADIUS, &text, BADGE_TEXT_COLOR);
+    }
+
+    waveform::encode_png(&canvas.pixels, IMAGE_WIDTH as u32, IMAGE_HEIGHT as u32)
+}
+
+// Drags one or more files out of the window (into a DAW, Explorer, Finder...)
+// with a preview rendered from the audio. Emits `drag-out-finished` when the
+// drop completes or is cancelled.
+#[tauri::command]
+pub async fn start_drag_out(
+    app: AppHandle,
+    window: WebviewWindow,
+    paths: Vec<String>,
+) -> Result<(), String> {
+    let files: Vec<PathBuf> = paths.iter().map(|p| paths::normalize(p)).collect();
+    if files.is_empty() {
+        return Err("Nothing to drag".to_string());
+    }
+    if let Some(missing) = files.iter().find(|f| !f.is_file()) {
+        return Err(format!("File not found: {}", missing.display()));
+    }
+
+    let preview_app = app.clone();
+    let preview_files = files.clone();
+    let image = tauri::async_runtime::spawn_blocking(move || {
+        // Before the drop, so the DAW reads the tempo on import.
+        for file in &preview_files {
+            loop_metadata::tag_for_daw(&preview_app, file);
+        }
+        render_preview(&preview_app, &preview_files)
+    })
+    .await
+    .map_err(|e| format!("Failed to render drag image: {}", e))??;
+
+    // The OS drag APIs have to be driven from the main thread (and on Windows
+    // block it until the drop), so don't wait for them on a runtime thread.
+    let (tx, rx) = oneshot::channel();
+    let drop_app = app.clone();
+    app.run_on_main_thread(move || {
+        #[cfg(target_os = "linux")]
+        let target = window.gtk_window();
+        #[cfg(not(target_os = "linux"))]
+        let target = tauri::Result::Ok(window.clone());
+
+        let result = match target {
+            Ok(target) => drag::start_drag(
+                &target,
+                drag::DragItem::Files(files),
+                drag::Image::Raw(image),
+                move |result, _| {
+                    let dropped = matches!(result, drag::DragResult::Dropped);
+                    let _ = drop_app.emit("drag-out-finished", DragOutFinished { dropped });
+                },
+                drag::Options::default(),
+            )
+            .map_err(|e| format!("Failed to start drag: {}", e)),
+            Err(e) => Err(format!("Failed to start drag: {}", e)),
+        };
+        let _ = tx.send(result);
+    })
+    .map_err(|e| format!("Failed to start drag: {}", e))?;
+
+    rx.await.map_err(|_| "Drag was interrupted".to_string())?
+}
@@ -0,0 +1,133 @@
+use std::path::Path;
+use std::process::Command;
+
+use tauri::AppHandle;
+
+use crate::paths;
+use crate::settings;
+
+// Starts the program and reaps it in the background; an editor usually
+// outlives the call.
+fn spawn(mut cmd: Command, what: &str) -> Result<(), String> {
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", what, e))?;
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+// The `external_editor` setting: an executable, or on macOS an .app bundle
+// (which can't be run directly, so it goes through `open -a`).
+fn open_with(editor: &Path, path: &Path) -> Result<(), String> {
+    let name = editor
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| editor.display().to_string());
+    #[cfg(target_os = "macos")]
+    if editor.extension().is_some_and(|e| e == "app") {
+        let mut cmd = Command::new("open");
+        cmd.arg("-a").arg(editor).arg(path);
+        return spawn(cmd, &name);
+    }
+    if !editor.exists() {
+        return Err(format!(
+            "The external editor isn't there any more: {}",
+            editor.display()
+        ));
+    }
+    let mut cmd = Command::new(editor);
+    cmd.arg(path);
+    spawn(cmd, &name)
+}
+
+// True when the shell found a program for the verb and started it.
+#[cfg(windows)]
+fn shell_execute(target: &std::ffi::OsStr, verb: windows::core::PCWSTR) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::Win32::UI::Shell::ShellExecuteW;
+    use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+    use windows::core::PCWSTR;
+
+    let wide: Vec<u16> = target.encode_wide().chain([0]).collect();
+    // SAFETY: NUL-terminated strings that outlive the call.
+    let result = unsafe {
+        ShellExecuteW(
+            None,
+            verb,
+            PCWSTR(wide.as_ptr()),
+            PCWSTR::null(),
+            PCWSTR::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+    // Anything above 32 is success.
+    result.0 as isize > 32
+}
+
+// Whatever the system offers for editing the file: the "edit" verb on
+// Windows (audio files have it when an editor registered one), else "open".
+#[cfg(windows)]
+fn open_default(path: &Path) -> Result<(), String> {
+    use windows::core::w;
+
+    if [w!("edit"), w!("open")]
+        .into_iter()
+        .any(|verb| shell_execute(path.as_os_str(), verb))
+    {
+        return Ok(());
+    }
+    Err("No program is set up to edit this file; choose an editor in Settings".to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn open_default(path: &Path) -> Result<(), String> {
+    let mut cmd = Command::new("open");
+    cmd.arg(path);
+    spawn(cmd, "the default app")
+}
+
+#[cfg(target_os = "linux")]
+fn open_default(path: &Path) -> Result<(), String> {
+    let mut cmd = Command::new("xdg-open");
+    cmd.arg(path);
+    spawn(cmd, "xdg-open")
+}
+
+// A web page in the default browser, e.g. a sign-in page.
+pub fn open_url(url: &str) -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        if shell_execute(url.as_ref(), windows::core::w!("open")) {
+            return Ok(());
+        }
+        Err("Failed to open the browser".to_string())
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let mut cmd = Command::new("open");
+        cmd.arg(url);
+        spawn(cmd, "the browser")
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let mut cmd = Command::new("xdg-open");
+        cmd.arg(url);
+        spawn(cmd, "xdg-open")
+    }
+}
+
+// Sends a sound to an audio editor (Audacity, RX, ...) for the fixes the app
+// doesn't do: the one from the `external_editor` setting, or the system's.
+#[tauri::command]
+pub fn open_in_editor(app: AppHandle, path: String) -> Result<(), String> {
+    let path = paths::normalize(&path);
+    if !path.is_file() {
+        return Err(format!("{} doesn't exist anymore", path.display()));
+    }
+    match settings::get(&app).external_editor {
+        Some(editor) => open_with(&editor, &path),
+        None => open_default(&path),
+    }
+}
@@ -0,0 +1,64 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::{Serialize, Serializer};
+
+// Errors a command can tell apart before turning them into the message the
+// frontend shows. They reach it as that message, the same plain string every
+// other command rejects with, so callers doing String(e) keep working.
+#[derive(Debug)]
+pub enum AppError {
+    // A path from the frontend that isn't there (anymore).
+    NotFound(PathBuf),
+    // A program the app hands off to (the file manager) didn't start.
+    Launch {
+        program: &'static str,
+        error: std::io::Error,
+    },
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound(path) => write!(f, "{} doesn't exist anymore", path.display()),
+            AppError::Launch { program, error } => {
+                write!(f, "Failed to open the {}: {}", program, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_as_its_message() {
+        let error = AppError::NotFound(PathBuf::from("kick.wav"));
+        assert_eq!(
+            serde_json::to_string(&error).unwrap(),
+            "\"kick.wav doesn't exist anymore\""
+        );
+    }
+
+    #[test]
+    fn launch_errors_name_the_program() {
+        let error = AppError::Launch {
+            program: "file manager",
+            error: std::io::Error::from(std::io::ErrorKind::NotFound),
+        };
+        assert!(
+            error
+                .to_string()
+                .starts_with("Failed to open the file manager: ")
+        );
+    }
+}
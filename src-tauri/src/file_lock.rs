@@ -0,0 +1,114 @@
+use std::path::Path;
+use std::time::Duration;
+
+// Leading code of the error for a file another program has open (a DAW
+// playing the sample, Explorer's preview), so the frontend can tell it from
+// a real failure and offer to try again.
+pub const FILE_IN_USE_ERROR: &str = "FileInUse";
+
+// Programs often let go of a file quickly (a preview pane, a virus scan), so
+// a few short retries get most of those through without bothering the user.
+const RETRIES: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+// ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION. Unix locks are advisory,
+// so there deletes and renames never fail this way.
+fn is_in_use(e: &std::io::Error) -> bool {
+    cfg!(windows) && matches!(e.raw_os_error(), Some(32 | 33))
+}
+
+// Runs a file operation (delete, rename), retrying while another program has
+// the file open. Gives up with a FileInUse error naming the program when
+// Windows can tell which one it is.
+pub async fn retry_while_in_use<F: Future<Output = std::io::Result<()>>>(
+    path: &Path,
+    what: &str,
+    mut op: impl FnMut() -> F,
+) -> Result<(), String> {
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Ok(()) => return Ok(()),
+            Err(e) if is_in_use(&e) && attempt < RETRIES => {
+                attempt += 1;
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+            Err(e) if is_in_use(&e) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let holder = locking_process(path)
+                    .map(|p| format!("open in {}", p))
+                    .unwrap_or_else(|| "open in another program".to_string());
+                return Err(format!(
+                    "{}: Failed to {} {}: it's {}. Close it there and try again",
+                    FILE_IN_USE_ERROR, what, name, holder
+                ));
+            }
+            Err(e) => return Err(format!("Failed to {} file: {}", what, e)),
+        }
+    }
+}
+
+// Asks the Restart Manager who holds the file, the same thing Explorer does
+// for its "file in use" dialog. Best effort: None when it can't say.
+#[cfg(windows)]
+fn locking_process(path: &Path) -> Option<String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::Win32::Foundation::{ERROR_MORE_DATA, ERROR_SUCCESS};
+    use windows::Win32::System::RestartManager::{
+        CCH_RM_SESSION_KEY, RM_PROCESS_INFO, RmEndSession, RmGetList, RmRegisterResources,
+        RmStartSession,
+    };
+    use windows::core::{PCWSTR, PWSTR};
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+    let mut key = [0u16; CCH_RM_SESSION_KEY as usize + 1];
+    let mut session = 0u32;
+    unsafe {
+        if RmStartSession(&mut session, None, PWSTR(key.as_mut_ptr())) != ERROR_SUCCESS {
+            return None;
+        }
+        let names = (|| {
+            let files = [PCWSTR(wide.as_ptr())];
+            if RmRegisterResources(session, Some(&files), None, None) != ERROR_SUCCESS {
+                return None;
+            }
+            // Asked twice: once for the count, then with room for everyone
+            // (more may have opened it in between, hence the loop).
+            let mut list: Vec<RM_PROCESS_INFO> = Vec::new();
+            loop {
+                let mut needed = 0u32;
+                let mut count = list.len() as u32;
+                let mut reasons = 0u32;
+                let buffer = (!list.is_empty()).then_some(list.as_mut_ptr());
+                match RmGetList(session, &mut needed, &mut count, buffer, &mut reasons) {
+                    ERROR_SUCCESS => {
+                        list.truncate(count as usize);
+                        break;
+                    }
+                    ERROR_MORE_DATA => list.resize(needed as usize, RM_PROCESS_INFO::default()),
+                    _ => return None,
+                }
+            }
+            let names: Vec<String> = list
+                .iter()
+                .map(|info| {
+                    let end = info
+                        .strAppName
+                        .iter()
+                        .position(|&c| c == 0)
+                        .unwrap_or(info.strAppName.len());
+                    String::from_utf16_lossy(&info.strAppName[..end])
+                })
+                .filter(|name| !name.is_empty())
+                .collect();
+            (!names.is_empty()).then(|| names.join(", "))
+        })();
+        let _ = RmEndSession(session);
+        names
+    }
+}
+
+#[cfg(not(windows))]
+fn locking_process(_path: &Path) -> Option<String> {
+    None
+}
@@ -0,0 +1,318 @@
+use std::path::{Path, PathBuf};
+
+use tauri::AppHandle;
+
+use crate::file_lock;
+use crate::history;
+use crate::paths;
+use crate::workspaces;
+
+// Longest name (without extension) made from a prompt. Long enough to tell
+// sounds apart in a file list, short of any file system's limit (255 bytes)
+// even with a suffix and an extension.
+const PROMPT_NAME_CHARS: usize = 60;
+const MAX_NAME_CHARS: usize = 120;
+const MAX_NAME_BYTES: usize = 200;
+
+// Device names Windows reserves in every folder, with any extension.
+const RESERVED: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+// Latin letters with marks, as plain ASCII; other scripts are kept as they
+// are, since every file system the app runs on takes them.
+fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'ç' | 'ć' | 'č' => "c",
+        'Ç' | 'Ć' | 'Č' => "C",
+        'ď' | 'đ' | 'ð' => "d",
+        'Ď' | 'Đ' | 'Ð' => "D",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' | 'ě' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ę' | 'Ě' => "E",
+        'ğ' => "g",
+        'Ğ' => "G",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'ı' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ī' | 'İ' => "I",
+        'ł' | 'ľ' | 'ĺ' => "l",
+        'Ł' | 'Ľ' | 'Ĺ' => "L",
+        'ñ' | 'ń' | 'ň' => "n",
+        'Ñ' | 'Ń' | 'Ň' => "N",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' | 'Ő' => "O",
+        'ŕ' | 'ř' => "r",
+        'Ŕ' | 'Ř' => "R",
+        'ś' | 'š' | 'ş' | 'ș' => "s",
+        'Ś' | 'Š' | 'Ş' | 'Ș' => "S",
+        'ť' | 'ţ' | 'ț' => "t",
+        'Ť' | 'Ţ' | 'Ț' => "T",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => "u",
+        'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ū' | 'Ů' | 'Ű' => "U",
+        'ý' | 'ÿ' => "y",
+        'Ý' | 'Ÿ' => "Y",
+        'ź' | 'ż' | 'ž' => "z",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        'ß' => "ss",
+        'æ' => "ae",
+        'Æ' => "AE",
+        'œ' => "oe",
+        'Œ' => "OE",
+        'þ' => "th",
+        'Þ' => "Th",
+        '‘' | '’' | '‚' | '′' => "'",
+        '–' | '—' | '‐' | '−' => "-",
+        _ => return None,
+    })
+}
+
+// Characters Windows refuses (the strictest of the lot), and / everywhere.
+fn is_forbidden(c: char) -> bool {
+    matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') || c.is_control()
+}
+
+// Combining accents, as left behind by text in decomposed form (e + ́).
+fn is_combining_mark(c: char) -> bool {
+    matches!(c, '\u{0300}'..='\u{036f}')
+}
+
+// A file name (without extension) that's valid on Windows, macOS and Linux
+// alike, so a library can move between them: accents transliterated,
+// forbidden characters dropped, runs of spaces collapsed, no trailing dots
+// or spaces, no reserved device names, and a bounded length. Falls back to
+// "sound".
+pub fn sanitize(name: &str) -> String {
+    let mut clean = String::with_capacity(name.len());
+    for c in name.chars() {
+        if let Some(ascii) = transliterate(c) {
+            clean.push_str(ascii);
+        } else if is_forbidden(c) || c.is_whitespace() {
+            clean.push(' ');
+        } else if !is_combining_mark(c) {
+            clean.push(c);
+        }
+    }
+    let mut clean = clean.split_whitespace().collect::<Vec<_>>().join(" ");
+    truncate(&mut clean, MAX_NAME_CHARS);
+    let clean = clean.trim_matches(|c: char| c == '.' || c == ' ' || c == '-');
+    if clean.is_empty() {
+        return "sound".to_string();
+    }
+    // Windows goes by what's before the first dot: nul.old.wav is NUL too.
+    let device = clean.split('.').next().unwrap_or_default();
+    if RESERVED.contains(&device.to_lowercase().as_str()) {
+        return format!("{}_{}", device, &clean[device.len()..]);
+    }
+    clean.to_string()
+}
+
+// To at most `chars` characters and MAX_NAME_BYTES bytes, on a character
+// boundary.
+fn truncate(name: &mut String, chars: usize) {
+    let mut end = 0;
+    for (count, (i, c)) in name.char_indices().enumerate() {
+        if count == chars || i + c.len_utf8() > MAX_NAME_BYTES {
+            break;
+        }
+        end = i + c.len_utf8();
+    }
+    name.truncate(end);
+}
+
+// "Footsteps on gravel, slow, close up" -> "Footsteps on gravel slow close
+// up", cut at a word near PROMPT_NAME_CHARS.
+pub fn from_prompt(prompt: &str) -> String {
+    let without_commas: String = prompt
+        .chars()
+        .map(|c| if matches!(c, ',' | ';') { ' ' } else { c })
+        .collect();
+    let mut name = sanitize(&without_commas);
+    if name.chars().count() > PROMPT_NAME_CHARS {
+        truncate(&mut name, PROMPT_NAME_CHARS);
+        if let Some(space) = name.rfind(' ') {
+            name.truncate(space);
+        }
+    }
+    sanitize(&name)
+}
+
+// `dir`/`name`.`extension` when it's free, or name-1, name-2, ... when not.
+// The extension goes without its dot; empty for a folder.
+pub fn unique(dir: &Path, name: &str, extension: &str) -> PathBuf {
+    unique_among(dir, name, extension, &[])
+}
+
+// The same, also skipping names `claimed` for files not written yet.
+pub fn unique_among(dir: &Path, name: &str, extension: &str, claimed: &[PathBuf]) -> PathBuf {
+    let taken = |path: &PathBuf| path.exists() || claimed.contains(path);
+    let file = |suffix: String| {
+        if extension.is_empty() {
+            dir.join(format!("{}{}", name, suffix))
+        } else {
+            dir.join(format!("{}{}.{}", name, suffix, extension))
+        }
+    };
+    let candidate = file(String::new());
+    if !taken(&candidate) {
+        return candidate;
+    }
+    (1..)
+        .map(|n| file(format!("-{}", n)))
+        .find(|p| !taken(p))
+        .unwrap_or(candidate)
+}
+
+// Whether `from` -> `to` only changes the case of the name, with no other
+// file taking that exact name. On a case-insensitive file system (Windows,
+// macOS by default) `to` exists as soon as `from` does, so only the folder's
+// listing tells this file from another one.
+fn is_case_change(dir: &Path, from: &Path, to: &Path) -> bool {
+    let (Some(from_name), Some(to_name)) = (from.file_name(), to.file_name()) else {
+        return false;
+    };
+    if from_name == to_name
+        || from_name.to_string_lossy().to_lowercase() != to_name.to_string_lossy().to_lowercase()
+    {
+        return false;
+    }
+    std::fs::read_dir(dir)
+        .is_ok_and(|entries| !entries.flatten().any(|entry| entry.file_name() == to_name))
+}
+
+fn extension_of(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+// Renames a generation's output after its prompt, in the same folder.
+pub fn name_after_prompt(path: &Path, prompt: &str) -> Result<PathBuf, String> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let target = unique(dir, &from_prompt(prompt), &extension_of(path));
+    paths::move_file(path, &target)?;
+    Ok(target)
+}
+
+// Renames a sound in the library, keeping its extension and folder. Its tags,
+// stems and history entries follow it. Returns the new path.
+#[tauri::command]
+pub async fn rename_sound(app: AppHandle, path: String, name: String) -> Result<String, String> {
+    let from = paths::normalize(&path);
+    if !from.is_file() {
+        return Err(format!("File not found: {}", from.display()));
+    }
+    let dir = from.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let name = sanitize(&name);
+    if from.file_stem().is_some_and(|stem| *stem == *name) {
+        return Ok(path);
+    }
+    let extension = extension_of(&from);
+    let exact = match extension.as_str() {
+        "" => dir.join(&name),
+        extension => dir.join(format!("{}.{}", name, extension)),
+    };
+    // kick -> Kick renames in place rather than making Kick-1.
+    let to = if is_case_change(&dir, &from, &exact) {
+        exact
+    } else {
+        unique(&dir, &name, &extension)
+    };
+    file_lock::retry_while_in_use(&from, "rename", || {
+        std::future::ready(std::fs::rename(&from, &to))
+    })
+    .await?;
+    workspaces::move_sound(&app, &from, &to)?;
+    history::move_output(&app, &from.to_string_lossy(), &to.to_string_lossy())?;
+    println!(
+        "[Files] Renamed {} to {}",
+        from.display(),
+        to.file_name().unwrap_or_default().to_string_lossy()
+    );
+    Ok(to.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserved_device_names_are_escaped() {
+        assert_eq!(sanitize("con"), "con_");
+        assert_eq!(sanitize("COM1"), "COM1_");
+        assert_eq!(sanitize("nul.old"), "nul_.old");
+        assert_eq!(sanitize("aux "), "aux_");
+        assert_eq!(sanitize("console"), "console");
+        assert_eq!(sanitize("com10"), "com10");
+    }
+
+    #[test]
+    fn forbidden_characters_and_edges_go() {
+        assert_eq!(sanitize("rain: heavy/light?"), "rain heavy light");
+        assert_eq!(sanitize("  a \t\n b  "), "a b");
+        assert_eq!(sanitize("ends here..."), "ends here");
+        assert_eq!(sanitize("-dash-"), "dash");
+        assert_eq!(sanitize("***"), "sound");
+        assert_eq!(sanitize(""), "sound");
+    }
+
+    #[test]
+    fn accents_become_ascii() {
+        assert_eq!(sanitize("Crème brûlée"), "Creme brulee");
+        assert_eq!(sanitize("Cre\u{0300}me"), "Creme");
+        assert_eq!(sanitize("Straße"), "Strasse");
+        assert_eq!(sanitize("雨の音"), "雨の音");
+    }
+
+    #[test]
+    fn truncates_by_characters() {
+        let mut name = "x".repeat(130);
+        truncate(&mut name, MAX_NAME_CHARS);
+        assert_eq!(name.len(), MAX_NAME_CHARS);
+    }
+
+    #[test]
+    fn truncates_multibyte_names_on_a_boundary() {
+        // 1 + 70 * 3 bytes: the byte limit is hit first, mid-character.
+        let mut name = format!("a{}", "雨".repeat(70));
+        truncate(&mut name, MAX_NAME_CHARS);
+        assert_eq!(name.len(), 1 + 66 * 3);
+        assert!(name.len() <= MAX_NAME_BYTES);
+
+        let mut emoji = "🔊".repeat(60);
+        truncate(&mut emoji, MAX_NAME_CHARS);
+        assert_eq!(emoji.chars().count(), MAX_NAME_BYTES / 4);
+        assert_eq!(sanitize(&"雨".repeat(100)).len(), 66 * 3);
+    }
+
+    #[test]
+    fn prompts_are_cut_at_a_word() {
+        assert_eq!(
+            from_prompt("Footsteps on gravel, slow, close up"),
+            "Footsteps on gravel slow close up"
+        );
+        let long = "rain on a tin roof ".repeat(10);
+        let name = from_prompt(&long);
+        assert!(name.chars().count() <= PROMPT_NAME_CHARS);
+        assert!(long.starts_with(&format!("{} ", name)));
+    }
+
+    #[test]
+    fn case_changes_are_told_from_collisions() {
+        let dir = std::env::temp_dir().join(format!("noises-case-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let from = dir.join("kick.wav");
+        std::fs::write(&from, b"").unwrap();
+        let to = dir.join("Kick.wav");
+        let alone = is_case_change(&dir, &from, &to);
+        let other_name = is_case_change(&dir, &from, &dir.join("snare.wav"));
+        // Only possible on a case-sensitive file system: a second file.
+        let both = std::fs::write(&to, b"").is_ok()
+            && std::fs::read_dir(&dir).unwrap().count() == 2
+            && is_case_change(&dir, &from, &to);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(alone);
+        assert!(!other_name);
+        assert!(!both);
+    }
+}
@@ -0,0 +1,393 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use crate::editor;
+use crate::history::{self, HistoryEntry};
+use crate::protocol;
+use crate::proxy;
+use crate::secrets::{self, Secret};
+use crate::telemetry;
+use crate::workspaces;
+
+// Set at build time by release builds, from the app's registration on
+// freesound.org/apiv2/apply (whose redirect URI is noises://freesound).
+// Without them the integration is off.
+const CLIENT_ID: Option<&str> = option_env!("NOISES_FREESOUND_CLIENT_ID");
+const CLIENT_SECRET: Option<&str> = option_env!("NOISES_FREESOUND_CLIENT_SECRET");
+
+const API_URL: &str = "https://freesound.org/apiv2";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Freesound won't describe a sound with fewer.
+const MIN_TAGS: usize = 3;
+
+// The `state` of the sign-in in progress, checked when the browser comes
+// back through the deep link.
+static PENDING_LOGIN: Mutex<Option<String>> = Mutex::new(None);
+
+// What goes in the keychain, as JSON. Access tokens last a day; the refresh
+// token gets a new one.
+#[derive(Serialize, Deserialize)]
+struct Tokens {
+    access_token: String,
+    refresh_token: String,
+    // Unix time in milliseconds.
+    expires: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: String,
+    expires_in: u64,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum License {
+    Cc0,
+    By,
+    ByNc,
+}
+
+impl License {
+    // As the upload endpoint spells them.
+    fn name(self) -> &'static str {
+        match self {
+            License::Cc0 => "Creative Commons 0",
+            License::By => "Attribution",
+            License::ByNc => "Attribution NonCommercial",
+        }
+    }
+}
+
+// From the publish form. Tags are added to the sound's own; the name and
+// description default to the file name and the prompt.
+#[derive(Deserialize)]
+pub struct Metadata {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    description: Option<String>,
+    license: License,
+    #[serde(default)]
+    pack: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct FreesoundStatus {
+    // Whether this build can talk to Freesound at all.
+    available: bool,
+    connected: bool,
+}
+
+#[derive(Clone, Serialize)]
+struct FreesoundConnected {
+    error: Option<String>,
+}
+
+fn credentials() -> Result<(&'static str, &'static str), String> {
+    CLIENT_ID
+        .zip(CLIENT_SECRET)
+        .ok_or_else(|| "This build of Noises has no Freesound API key".to_string())
+}
+
+fn client(app: &AppHandle) -> Result<reqwest::Client, String> {
+    proxy::client_builder(app)?
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+fn load_tokens(app: &AppHandle) -> Result<Option<Tokens>, String> {
+    Ok(
+        secrets::get(app, Secret::FreesoundToken)?
+            .and_then(|json| serde_json::from_str(&json).ok()),
+    )
+}
+
+async fn request_tokens(app: &AppHandle, grant: &[(&str, &str)]) -> Result<Tokens, String> {
+    let (id, secret) = credentials()?;
+    let mut form = vec![("client_id", id), ("client_secret", secret)];
+    form.extend_from_slice(grant);
+    let res = client(app)?
+        .post(format!("{}/oauth2/access_token/", API_URL))
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach Freesound: {}", e))?;
+    if !res.status().is_success() {
+        return Err(error_from_response(res).await);
+    }
+    let tokens: TokenResponse = res
+        .json()
+        .await
+        .map_err(|e| format!("Failed to read Freesound's answer: {}", e))?;
+    let tokens = Tokens {
+        access_token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+        expires: history::now_millis() + tokens.expires_in * 1000,
+    };
+    let json = serde_json::to_string(&tokens).map_err(|e| e.to_string())?;
+    secrets::set(app, Secret::FreesoundToken, Some(json))?;
+    Ok(tokens)
+}
+
+// A valid access token, refreshed first if it has run out (or is about to).
+async fn access_token(app: &AppHandle) -> Result<String, String> {
+    let tokens = load_tokens(app)?.ok_or("Connect a Freesound account in Settings first")?;
+    if tokens.expires > history::now_millis() + 60_000 {
+        return Ok(tokens.access_token);
+    }
+    let grant = [
+        ("grant_type", "refresh_token"),
+        ("refresh_token", tokens.refresh_token.as_str()),
+    ];
+    match request_tokens(app, &grant).await {
+        Ok(tokens) => Ok(tokens.access_token),
+        Err(e) => {
+            eprintln!("[Freesound] Refreshing the token failed: {}", e);
+            Err(
+                "The Freesound sign-in has expired; connect the account again in Settings"
+                    .to_string(),
+            )
+        }
+    }
+}
+
+// Freesound answers errors as {"detail": ...}, or per field for uploads.
+async fn error_from_response(res: reqwest::Response) -> String {
+    let status = res.status();
+    let body = res.text().await.unwrap_or_default();
+    let detail = serde_json::from_str::<Value>(&body)
+        .ok()
+        .map(|json| match json.get("detail") {
+            Some(Value::String(detail)) => detail.clone(),
+            _ => json.to_string(),
+        });
+    format!(
+        "Freesound refused the request ({}): {}",
+        status,
+        detail.unwrap_or(body)
+    )
+}
+
+// Single words, as Freesound splits tags at spaces: "Dusty Vinyl" ->
+// "dusty-vinyl".
+fn clean_tags(tags: &[String]) -> Vec<String> {
+    let mut clean: Vec<String> = tags
+        .iter()
+        .map(|tag| {
+            tag.to_lowercase()
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    clean.sort();
+    clean.dedup();
+    clean
+}
+
+fn default_description(entry: &HistoryEntry) -> String {
+    let seed = entry
+        .params
+        .get("seed")
+        .map(|seed| format!(", seed {}", seed))
+        .unwrap_or_default();
+    format!(
+        "{}\n\nGenerated with Noises ({}{}).",
+        entry.prompt, entry.model, seed
+    )
+}
+
+async fn upload(
+    app: &AppHandle,
+    token: &str,
+    path: &Path,
+    name: String,
+    tags: &[String],
+    description: &str,
+    metadata: &Metadata,
+) -> Result<u64, String> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let len = file
+        .metadata()
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    let body = reqwest::Body::wrap_stream(tokio_util::io::ReaderStream::new(file));
+    let part = reqwest::multipart::Part::stream_with_length(body, len)
+        .file_name(
+            path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        )
+        .mime_str(protocol::content_type(path))
+        .map_err(|e| format!("Failed to build upload: {}", e))?;
+    let mut form = reqwest::multipart::Form::new()
+        .part("audiofile", part)
+        .text("name", name)
+        .text("tags", tags.join(" "))
+        .text("description", description.to_string())
+        .text("license", metadata.license.name());
+    if let Some(pack) = metadata.pack.as_ref().filter(|p| !p.trim().is_empty()) {
+        form = form.text("pack", pack.trim().to_string());
+    }
+
+    let res = client(app)?
+        .post(format!("{}/sounds/upload/", API_URL))
+        .bearer_auth(token)
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to upload {}: {}", path.display(), e))?;
+    if !res.status().is_success() {
+        return Err(error_from_response(res).await);
+    }
+    let answer: Value = res
+        .json()
+        .await
+        .map_err(|e| format!("Failed to read Freesound's answer: {}", e))?;
+    answer
+        .get("id")
+        .and_then(|id| id.as_u64())
+        .ok_or_else(|| "Freesound didn't say which upload it was".to_string())
+}
+
+// Called for noises://freesound?code=...&state=... once the user has allowed
+// access in the browser. Reported as `freesound-connected`.
+pub fn finish_login(app: &AppHandle, code: Option<String>, state: Option<String>) {
+    let expected = PENDING_LOGIN.lock().unwrap().take();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = match (code, expected) {
+            (Some(code), Some(expected)) if state.as_ref() == Some(&expected) => {
+                let grant = [
+                    ("grant_type", "authorization_code"),
+                    ("code", code.as_str()),
+                ];
+                request_tokens(&app, &grant).await.map(|_| ())
+            }
+            (None, _) => Err("Freesound access wasn't granted".to_string()),
+            _ => Err("That Freesound sign-in wasn't started here; try again".to_string()),
+        };
+        match &result {
+            Ok(()) => println!("[Freesound] Connected"),
+            Err(e) => eprintln!("[Freesound] {}", e),
+        }
+        let _ = app.emit(
+            "freesound-connected",
+            FreesoundConnected {
+                error: result.err(),
+            },
+        );
+    });
+}
+
+#[tauri::command]
+pub async fn freesound_status(app: AppHandle) -> Result<FreesoundStatus, String> {
+    Ok(FreesoundStatus {
+        available: credentials().is_ok(),
+        connected: load_tokens(&app)?.is_some(),
+    })
+}
+
+// Opens Freesound's sign-in page in the browser; it comes back through the
+// deep link (finish_login).
+#[tauri::command]
+pub fn connect_freesound() -> Result<(), String> {
+    let (id, _) = credentials()?;
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|e| format!("Failed to start sign-in: {}", e))?;
+    let state: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    let url = format!(
+        "{}/oauth2/authorize/?client_id={}&response_type=code&state={}",
+        API_URL, id, state
+    );
+    *PENDING_LOGIN.lock().unwrap() = Some(state);
+    editor::open_url(&url)
+}
+
+#[tauri::command]
+pub async fn disconnect_freesound(app: AppHandle) -> Result<(), String> {
+    secrets::set(&app, Secret::FreesoundToken, None)
+}
+
+// Publishes a generation's files on Freesound, each as its own sound, with
+// the sound's library tags plus the form's. Returns Freesound's upload ids;
+// the sounds go public once its moderators have had a look.
+#[tauri::command]
+pub async fn upload_to_freesound(
+    app: AppHandle,
+    id: String,
+    metadata: Metadata,
+) -> Result<Vec<u64>, String> {
+    let entry = history::load(&app)
+        .into_iter()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| format!("Unknown generation: {}", id))?;
+    let files: Vec<PathBuf> = entry
+        .outputs
+        .iter()
+        .map(PathBuf::from)
+        .filter(|path| path.is_file())
+        .collect();
+    if files.is_empty() {
+        return Err("The files of that generation aren't there any more".to_string());
+    }
+    let library_tags = workspaces::tags(&app);
+    let description = metadata
+        .description
+        .clone()
+        .filter(|d| !d.trim().is_empty())
+        .unwrap_or_else(|| default_description(&entry));
+
+    // All checked before the first upload, so nothing goes up half-described.
+    let mut uploads = Vec::with_capacity(files.len());
+    for (i, path) in files.iter().enumerate() {
+        let mut tags = metadata.tags.clone();
+        tags.extend(library_tags.get(path).cloned().unwrap_or_default());
+        let tags = clean_tags(&tags);
+        if tags.len() < MIN_TAGS {
+            return Err(format!(
+                "Freesound needs at least {} tags for {}",
+                MIN_TAGS,
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ));
+        }
+        let name = match metadata.name.as_deref().map(str::trim) {
+            Some(name) if !name.is_empty() && files.len() > 1 => format!("{} {}", name, i + 1),
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        };
+        uploads.push((path, name, tags));
+    }
+
+    telemetry::record_feature(&app, "freesound");
+    let token = access_token(&app).await?;
+    let mut ids = Vec::with_capacity(uploads.len());
+    for (path, name, tags) in uploads {
+        let upload_id = upload(&app, &token, path, name, &tags, &description, &metadata).await?;
+        println!("[Freesound] Uploaded {} as {}", path.display(), upload_id);
+        ids.push(upload_id);
+    }
+    Ok(ids)
+}
@@ -0,0 +1,217 @@
+use std::path::{Path, PathBuf};
+
+use rodio::{Decoder, Source};
+use serde_json::{Value, json};
+use tauri::AppHandle;
+
+use crate::history;
+use crate::loop_metadata;
+use crate::paths;
+use crate::settings;
+use crate::workspaces;
+
+// Anything longer is treated as music: streamed rather than held in memory.
+const MUSIC_SECONDS: f64 = 30.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Engine {
+    Godot,
+    Unity,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Sfx,
+    Loop,
+    Music,
+}
+
+impl Role {
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Role::Sfx => "sfx",
+            Role::Loop => "loop",
+            Role::Music => "music",
+        }
+    }
+}
+
+// What the companion files are written from.
+pub struct SoundInfo {
+    pub role: Role,
+    pub bpm: Option<f64>,
+    pub key: Option<String>,
+    pub tags: Vec<String>,
+    pub entry: Option<history::HistoryEntry>,
+}
+
+// The project a folder belongs to: a project.godot in it or above it, or a
+// Unity project's Assets folder (the one next to ProjectSettings).
+fn detect(dir: &Path) -> Option<Engine> {
+    for ancestor in dir.ancestors() {
+        if ancestor.join("project.godot").is_file() {
+            return Some(Engine::Godot);
+        }
+        if ancestor.file_name().is_some_and(|n| n == "Assets")
+            && ancestor
+                .parent()
+                .is_some_and(|project| project.join("ProjectSettings").is_dir())
+        {
+            return Some(Engine::Unity);
+        }
+    }
+    None
+}
+
+fn duration(path: &Path) -> Option<f64> {
+    let file = std::fs::File::open(path).ok()?;
+    let decoder = Decoder::new(std::io::BufReader::new(file)).ok()?;
+    decoder.total_duration().map(|d| d.as_secs_f64())
+}
+
+pub fn sound_info(app: &AppHandle, source: &Path) -> SoundInfo {
+    let looping = loop_metadata::lookup(app, source);
+    let role = match &looping {
+        Some(_) => Role::Loop,
+        None if duration(source).is_some_and(|d| d > MUSIC_SECONDS) => Role::Music,
+        None => Role::Sfx,
+    };
+    SoundInfo {
+        role,
+        bpm: looping.as_ref().map(|l| l.bpm),
+        key: looping.and_then(|l| l.key),
+        tags: workspaces::tags(app).remove(source).unwrap_or_default(),
+        entry: history::find_output(app, &source.to_string_lossy()),
+    }
+}
+
+// Godot keeps the [params] of an existing .import file when it imports the
+// sound, and fills in the rest (uid, cache paths) itself.
+fn godot_import(target: &Path, info: &SoundInfo) -> Option<String> {
+    let extension = target.extension()?.to_string_lossy().to_lowercase();
+    let looped = info.role == Role::Loop;
+    let params = match extension.as_str() {
+        "wav" => {
+            // PCM keeps loop points sample-exact; music can take the smaller ADPCM.
+            let compress = if info.role == Role::Music { 1 } else { 0 };
+            format!(
+                "force/8_bit=false\nforce/mono=false\nforce/max_rate=false\n\
+                 force/max_rate_hz=44100\nedit/trim=false\nedit/normalize=false\n\
+                 edit/loop_mode={}\nedit/loop_begin=0\nedit/loop_end=-1\ncompress/mode={}\n",
+                if looped { 2 } else { 1 },
+                compress
+            )
+        }
+        "ogg" | "mp3" => format!(
+            "loop={}\nloop_offset=0\nbpm={}\nbeat_count=0\nbar_beats=4\n",
+            looped,
+            info.bpm.unwrap_or(0.0)
+        ),
+        _ => return None,
+    };
+    let (importer, kind) = match extension.as_str() {
+        "wav" => ("wav", "AudioStreamWAV"),
+        "ogg" => ("oggvorbisstr", "AudioStreamOggVorbis"),
+        _ => ("mp3", "AudioStreamMP3"),
+    };
+    Some(format!(
+        "[remap]\n\nimporter=\"{}\"\ntype=\"{}\"\n\n[params]\n\n{}",
+        importer, kind, params
+    ))
+}
+
+// "Rain on a tin roof" -> rain_on_a_tin_roof.
+pub fn snake_case(name: &str) -> String {
+    let words: Vec<String> = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect();
+    words.join("_")
+}
+
+// There's no importer file Unity would read without an editor script, so
+// this is the data for one: the asset name and labels to give the clip and
+// the AudioImporter settings that suit it, named like Unity's own fields.
+fn unity_json(target: &Path, info: &SoundInfo) -> Value {
+    let stem = target.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match snake_case(&stem) {
+        stem if stem.is_empty() => info.role.prefix().to_string(),
+        stem => format!("{}_{}", info.role.prefix(), stem),
+    };
+    let mut labels = vec!["Noises".to_string(), info.role.prefix().to_string()];
+    labels.extend(info.tags.iter().cloned());
+    let (load_type, compression) = match info.role {
+        Role::Sfx => ("DecompressOnLoad", "ADPCM"),
+        Role::Loop => ("CompressedInMemory", "Vorbis"),
+        Role::Music => ("Streaming", "Vorbis"),
+    };
+    json!({
+        "file": target.file_name().map(|n| n.to_string_lossy().into_owned()),
+        "name": name,
+        "labels": labels,
+        "loop": info.role == Role::Loop,
+        "bpm": info.bpm,
+        "key": info.key,
+        "prompt": info.entry.as_ref().map(|e| &e.prompt),
+        "seed": info.entry.as_ref().and_then(|e| e.params.get("seed")),
+        "importer": {
+            "forceToMono": false,
+            "loadInBackground": info.role == Role::Music,
+            "defaultSampleSettings": {
+                "loadType": load_type,
+                "compressionFormat": compression,
+                "preloadAudioData": info.role != Role::Music,
+            },
+        },
+    })
+}
+
+fn companion(target: &Path, suffix: &str) -> PathBuf {
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    target.with_file_name(name)
+}
+
+fn write(app: &AppHandle, source: &Path, target: &Path) -> Result<Option<PathBuf>, String> {
+    let Some(engine) = target.parent().and_then(detect) else {
+        return Ok(None);
+    };
+    let info = sound_info(app, source);
+    let (path, contents) = match engine {
+        Engine::Godot => {
+            let path = companion(target, ".import");
+            // Godot's own file has the uid scenes refer to; it stays.
+            if path.exists() {
+                return Ok(None);
+            }
+            let Some(contents) = godot_import(target, &info) else {
+                return Ok(None);
+            };
+            (path, contents)
+        }
+        Engine::Unity => {
+            let contents = serde_json::to_string_pretty(&unity_json(target, &info))
+                .map_err(|e| format!("Failed to write Unity metadata: {}", e))?;
+            (companion(target, ".noises.json"), contents)
+        }
+    };
+    paths::write_atomic(&path, contents)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(Some(path))
+}
+
+// With the `engine_metadata` setting, gives a sound exported into a Godot or
+// Unity project the file that engine imports it with: a .import for Godot,
+// <file>.noises.json for Unity. Failing here is only logged; the export
+// itself is done.
+pub fn write_companions(app: &AppHandle, source: &Path, target: &Path) {
+    if !settings::get(app).engine_metadata {
+        return;
+    }
+    match write(app, source, target) {
+        Ok(Some(path)) => println!("[GameEngines] Wrote {}", path.display()),
+        Ok(None) => {}
+        Err(e) => eprintln!("[GameEngines] {}", e),
+    }
+}
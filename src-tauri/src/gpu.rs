@@ -0,0 +1,104 @@
+// Whether this machine has a GPU the backend's torch can use, going by the
+// driver files alone (loading CUDA here would cost seconds at startup). Only
+// consulted when the device setting is "auto": without a GPU the backend is
+// started on the CPU from the start, instead of finding out halfway through
+// loading a model.
+#[cfg(target_os = "macos")]
+pub fn has_supported_gpu() -> bool {
+    // Metal (mps) on every Mac the app is built for.
+    true
+}
+
+// The NVIDIA driver installs nvcuda.dll; the torch we ship is CUDA only.
+#[cfg(windows)]
+pub fn has_supported_gpu() -> bool {
+    let root = std::env::var_os("SystemRoot").unwrap_or_else(|| "C:\\Windows".into());
+    std::path::Path::new(&root)
+        .join("System32")
+        .join("nvcuda.dll")
+        .is_file()
+}
+
+// The NVIDIA kernel driver, or ROCm's device node (torch's ROCm build goes
+// through the same "cuda" device).
+#[cfg(target_os = "linux")]
+pub fn has_supported_gpu() -> bool {
+    std::path::Path::new("/proc/driver/nvidia/version").exists()
+        || std::path::Path::new("/dev/kfd").exists()
+}
+
+// Memory on one GPU, in bytes.
+pub struct Vram {
+    pub free: u64,
+    pub total: u64,
+}
+
+// Asks the NVIDIA driver's management library (NVML), loaded when first
+// needed, about the card at `index` (ComputeDevice::gpu_index). None without
+// an NVIDIA driver (AMD, Macs) or such a card, so callers carry on as if
+// there were room.
+#[cfg(any(windows, target_os = "linux"))]
+pub fn vram(index: u32) -> Option<Vram> {
+    nvml::vram(index)
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn vram(_index: u32) -> Option<Vram> {
+    None
+}
+
+// The same card's core temperature in °C, also from NVML.
+#[cfg(any(windows, target_os = "linux"))]
+pub fn temperature(index: u32) -> Option<u32> {
+    nvml::temperature(index)
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn temperature(_index: u32) -> Option<u32> {
+    None
+}
+
+#[cfg(any(windows, target_os = "linux"))]
+mod nvml {
+    use std::sync::OnceLock;
+
+    use nvml_wrapper::Nvml;
+    use nvml_wrapper::enum_wrappers::device::TemperatureSensor;
+
+    use super::Vram;
+
+    // Installed with the driver: into System32 on Windows, next to libcuda
+    // on Linux, where some distributions only ship the unversioned name.
+    fn load() -> Option<Nvml> {
+        let nvml = Nvml::init();
+        #[cfg(target_os = "linux")]
+        let nvml = nvml.or_else(|_| {
+            Nvml::builder()
+                .lib_path(std::ffi::OsStr::new("libnvidia-ml.so"))
+                .init()
+        });
+        nvml.ok()
+    }
+
+    // Loaded and initialized once, the first time it's asked.
+    fn nvml() -> Option<&'static Nvml> {
+        static NVML: OnceLock<Option<Nvml>> = OnceLock::new();
+        NVML.get_or_init(load).as_ref()
+    }
+
+    pub fn vram(index: u32) -> Option<Vram> {
+        let memory = nvml()?.device_by_index(index).ok()?.memory_info().ok()?;
+        Some(Vram {
+            free: memory.free,
+            total: memory.total,
+        })
+    }
+
+    pub fn temperature(index: u32) -> Option<u32> {
+        nvml()?
+            .device_by_index(index)
+            .ok()?
+            .temperature(TemperatureSensor::Gpu)
+            .ok()
+    }
+}
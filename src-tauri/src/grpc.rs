@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fs::File;
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+use tauri::http::uri::PathAndQuery;
+use tauri::{AppHandle, Emitter, Url};
+use tonic::transport::Endpoint;
+use tonic_prost::ProstCodec;
+
+use crate::backend;
+use crate::import;
+use crate::paths;
+use crate::providers::{self, Phase};
+use crate::proxy;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// The backend's optional gRPC service (backend/main.py, --grpc-port), for a
+// long render whose audio would otherwise be written on the other machine
+// and fetched again. Generate streams the steps as the model makes them and
+// then each output as chunks of raw bytes, so nothing goes through JSON or
+// base64. The messages, as a .proto:
+//
+//   service Generator { rpc Generate(GenerateRequest) returns (stream GenerateReply); }
+//   message GenerateRequest { string config_json = 1; }
+//   message GenerateReply { oneof event { Progress progress = 1; AudioFrame frame = 2; } }
+//   message Progress { uint32 step = 1; uint32 total = 2; }
+//   message AudioFrame { uint32 index = 1; string file_name = 2; bytes data = 3; bool last = 4; }
+const GENERATE: &str = "/noises.Generator/Generate";
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GenerateRequest {
+    #[prost(string, tag = "1")]
+    pub config_json: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GenerateReply {
+    #[prost(oneof = "Event", tags = "1, 2")]
+    pub event: Option<Event>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum Event {
+    #[prost(message, tag = "1")]
+    Progress(Progress),
+    #[prost(message, tag = "2")]
+    Frame(AudioFrame),
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Progress {
+    #[prost(uint32, tag = "1")]
+    pub step: u32,
+    #[prost(uint32, tag = "2")]
+    pub total: u32,
+}
+
+// One chunk of output `index`; its file_name only matters for the extension.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AudioFrame {
+    #[prost(uint32, tag = "1")]
+    pub index: u32,
+    #[prost(string, tag = "2")]
+    pub file_name: String,
+    #[prost(bytes = "vec", tag = "3")]
+    pub data: Vec<u8>,
+    #[prost(bool, tag = "4")]
+    pub last: bool,
+}
+
+#[derive(Clone, Serialize)]
+struct ProviderProgress<'a> {
+    provider: &'a str,
+    step: u32,
+    total: u32,
+}
+
+// Plain http:// only: the backend serves it without TLS, so one on another
+// machine belongs behind a VPN or an SSH tunnel. The channel doesn't go
+// through the proxy, and offline mode still allows a backend on this machine.
+pub fn endpoint(app: &AppHandle, url: &str) -> Result<Endpoint, String> {
+    let parsed = Url::parse(url).map_err(|e| format!("Invalid gRPC URL {}: {}", url, e))?;
+    if parsed.scheme() != "http" {
+        return Err(format!("The gRPC URL has to start with http:// ({})", url));
+    }
+    let local = match parsed.host_str() {
+        Some("localhost") => true,
+        Some(host) => host
+            .trim_matches(['[', ']'])
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback()),
+        None => false,
+    };
+    if !local {
+        proxy::ensure_online(app)?;
+    }
+    Ok(Endpoint::from_shared(url.to_string())
+        .map_err(|e| format!("Invalid gRPC URL {}: {}", url, e))?
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(backend::generate_timeout()))
+}
+
+// An output being received, in a hidden file next to where it ends up.
+struct Incoming {
+    temp: PathBuf,
+    file: File,
+    extension: String,
+}
+
+// Deletes whatever didn't arrive whole.
+struct Partial(HashMap<u32, Incoming>);
+
+impl Drop for Partial {
+    fn drop(&mut self) {
+        for (_, Incoming { temp, file, .. }) in self.0.drain() {
+            drop(file);
+            let _ = std::fs::remove_file(&temp);
+        }
+    }
+}
+
+fn extension(file_name: &str) -> String {
+    let path = PathBuf::from(file_name);
+    match path.extension() {
+        Some(extension) if import::is_audio_file(&path) => {
+            extension.to_string_lossy().to_ascii_lowercase()
+        }
+        _ => "wav".to_string(),
+    }
+}
+
+// Runs the request on the backend at `url` and brings its audio into the
+// library, answering like providers::generate. Its steps are announced as
+// `provider-progress`.
+pub async fn generate(
+    app: &AppHandle,
+    name: &str,
+    url: &str,
+    input: &Value,
+    on_phase: impl Fn(Phase),
+) -> Result<Value, String> {
+    let channel = endpoint(app, url)?
+        .connect()
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", name, e))?;
+    let mut client = tonic::client::Grpc::new(channel);
+    client
+        .ready()
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", name, e))?;
+    let request = GenerateRequest {
+        config_json: input.to_string(),
+    };
+    let mut stream = client
+        .server_streaming(
+            tonic::Request::new(request),
+            PathAndQuery::from_static(GENERATE),
+            ProstCodec::<GenerateRequest, GenerateReply>::default(),
+        )
+        .await
+        .map_err(|status| error_from_status(name, &status))?
+        .into_inner();
+    on_phase(Phase::Running);
+
+    let (dir, prefix) = providers::output_folder(app, input)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create output folder: {}", e))?;
+    let mut partial = Partial(HashMap::new());
+    let mut files = Vec::new();
+    while let Some(reply) = stream
+        .message()
+        .await
+        .map_err(|status| error_from_status(name, &status))?
+    {
+        let frame = match reply.event {
+            Some(Event::Progress(progress)) => {
+                let _ = app.emit(
+                    "provider-progress",
+                    ProviderProgress {
+                        provider: name,
+                        step: progress.step,
+                        total: progress.total,
+                    },
+                );
+                continue;
+            }
+            Some(Event::Frame(frame)) => frame,
+            None => continue,
+        };
+        let incoming = match partial.0.entry(frame.index) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let extension = extension(&frame.file_name);
+                let temp = paths::temp_path(&dir.join(format!("{}.{}", prefix, extension)));
+                let file = File::create(&temp)
+                    .map_err(|e| format!("Failed to save {}'s output: {}", name, e))?;
+                entry.insert(Incoming {
+                    temp,
+                    file,
+                    extension,
+                })
+            }
+        };
+        incoming
+            .file
+            .write_all(&frame.data)
+            .map_err(|e| format!("Failed to save {}'s output: {}", name, e))?;
+        if !frame.last {
+            continue;
+        }
+        // Numbered once it's whole, so two outputs don't take the same name.
+        let Incoming {
+            temp,
+            file,
+            extension,
+        } = partial.0.remove(&frame.index).unwrap();
+        let path = dir.join(paths::next_filename(
+            &dir,
+            prefix,
+            &format!(".{}", extension),
+        ));
+        // Closed before the rename, which Windows won't do to an open file.
+        let synced = file.sync_all();
+        drop(file);
+        if let Err(e) = synced.and_then(|_| std::fs::rename(&temp, &path)) {
+            let _ = std::fs::remove_file(&temp);
+            return Err(format!("Failed to save {}: {}", path.display(), e));
+        }
+        files.push(json!({ "path": path.to_string_lossy() }));
+    }
+    if !partial.0.is_empty() {
+        return Err(format!("{} stopped in the middle of the audio", name));
+    }
+    if files.is_empty() {
+        return Err(format!("{} finished without any audio", name));
+    }
+    Ok(json!({ "files": files, "provider": name }))
+}
+
+// The same shape as the HTTP providers' errors; a backend that's busy with
+// another generation says so as RESOURCE_EXHAUSTED and is retried.
+fn error_from_status(name: &str, status: &tonic::Status) -> String {
+    match status.code() {
+        tonic::Code::ResourceExhausted => format!(
+            "{}: retry in 10 s: {} is busy with another generation",
+            backend::BUSY_ERROR,
+            name
+        ),
+        tonic::Code::Unavailable => format!("Failed to reach {}: {}", name, status.message()),
+        _ => format!("{}: {}", name, status.message()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn frames_round_trip() {
+        let reply = GenerateReply {
+            event: Some(Event::Frame(AudioFrame {
+                index: 1,
+                file_name: "loop_001.wav".to_string(),
+                data: vec![0, 1, 2, 255],
+                last: true,
+            })),
+        };
+        let decoded = GenerateReply::decode(reply.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(decoded, reply);
+    }
+
+    // What backend/main.py encodes by hand for Progress { step: 3, total: 50 }.
+    #[test]
+    fn decodes_the_backends_progress() {
+        let bytes = [0x0a, 0x04, 0x08, 0x03, 0x10, 0x32];
+        let reply = GenerateReply::decode(&bytes[..]).unwrap();
+        assert_eq!(
+            reply.event,
+            Some(Event::Progress(Progress { step: 3, total: 50 }))
+        );
+    }
+
+    #[test]
+    fn decodes_the_backends_frames() {
+        let bytes = [
+            0x12, 0x0f, 0x08, 0x00, 0x12, 0x05, b'a', b'.', b'w', b'a', b'v', 0x1a, 0x02, b'x',
+            b'y', 0x20, 0x01,
+        ];
+        let reply = GenerateReply::decode(&bytes[..]).unwrap();
+        assert_eq!(
+            reply.event,
+            Some(Event::Frame(AudioFrame {
+                index: 0,
+                file_name: "a.wav".to_string(),
+                data: b"xy".to_vec(),
+                last: true,
+            }))
+        );
+    }
+
+    #[test]
+    fn requests_carry_the_config_as_json() {
+        let request = GenerateRequest {
+            config_json: "{}".to_string(),
+        };
+        assert_eq!(request.encode_to_vec(), [0x0a, 0x02, b'{', b'}']);
+    }
+
+    #[test]
+    fn extensions_come_from_audio_names() {
+        assert_eq!(extension("song_002.FLAC"), "flac");
+        assert_eq!(extension("output.bin"), "wav");
+        assert_eq!(extension(""), "wav");
+    }
+
+    #[test]
+    fn busy_backends_are_retried() {
+        let status = tonic::Status::resource_exhausted("busy");
+        assert!(error_from_status("studio", &status).starts_with(backend::BUSY_ERROR));
+        let status = tonic::Status::invalid_argument("Unknown model");
+        assert_eq!(
+            error_from_status("studio", &status),
+            "studio: Unknown model"
+        );
+    }
+}
@@ -0,0 +1,365 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::paths;
+use crate::workspaces::WorkspaceStore;
+
+// One finished generation (or failed attempt). The log is per workspace, in
+// workspaces/<id>.history.jsonl in the app data dir, one entry per line and
+// oldest first.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub prompt: String,
+    // The request as the app sent it (type, length, steps, seed, ...).
+    pub params: Value,
+    pub model: String,
+    // Unix time in milliseconds, like LibraryItem::modified.
+    pub started: u64,
+    pub duration_seconds: f64,
+    pub outputs: Vec<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    // 1-5, set by the user afterwards.
+    #[serde(default)]
+    pub rating: Option<u8>,
+    // The generation this one was made from, by id, and how.
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub derivation: Option<Derivation>,
+    // Shared by the generations of one seed sweep (jobs::enqueue_variations).
+    #[serde(default)]
+    pub batch: Option<String>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Derivation {
+    // Generated again from the same settings (Regenerate).
+    Regenerate,
+    // A plugin processor's copy (plugins.rs).
+    Process,
+}
+
+// Where a generation came from and what was made from it since.
+#[derive(Serialize)]
+pub struct Lineage {
+    // Root first, ending with the generation's parent.
+    pub ancestors: Vec<HistoryEntry>,
+    pub entry: HistoryEntry,
+    // Everything made from it, directly or not, oldest first. Each one's
+    // `parent` says where it hangs in the tree.
+    pub descendants: Vec<HistoryEntry>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct HistoryFilter {
+    // Case-insensitive match anywhere in the prompt.
+    pub text: Option<String>,
+    pub model: Option<String>,
+    // Unix milliseconds, inclusive.
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub min_rating: Option<u8>,
+    // Only the generations of this seed sweep.
+    pub batch: Option<String>,
+    // Failed attempts are left out unless asked for.
+    pub include_failed: bool,
+}
+
+impl HistoryFilter {
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        self.text
+            .as_ref()
+            .is_none_or(|text| entry.prompt.to_lowercase().contains(&text.to_lowercase()))
+            && self.model.as_ref().is_none_or(|m| *m == entry.model)
+            && self.from.is_none_or(|from| entry.started >= from)
+            && self.to.is_none_or(|to| entry.started <= to)
+            && self
+                .min_rating
+                .is_none_or(|min| entry.rating.is_some_and(|r| r >= min))
+            && self
+                .batch
+                .as_ref()
+                .is_none_or(|batch| entry.batch.as_ref() == Some(batch))
+            && (self.include_failed || entry.error.is_none())
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+fn history_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let id = app
+        .try_state::<WorkspaceStore>()
+        .and_then(|store| store.active())
+        .map(|w| w.id)
+        .ok_or("No active workspace")?;
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve data directory: {}", e))?;
+    Ok(dir.join("workspaces").join(format!("{}.history.jsonl", id)))
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Appends to the active workspace's log. Called by jobs.rs when a job ends.
+pub fn record(app: &AppHandle, entry: &HistoryEntry) -> Result<(), String> {
+    let path = history_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create data folder: {}", e))?;
+    }
+    let line = serde_json::to_string(entry)
+        .map_err(|e| format!("Failed to serialize history entry: {}", e))?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open history: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to save history: {}", e))
+}
+
+// Oldest first. Lines that don't parse (a crash mid-write) are skipped.
+pub fn load(app: &AppHandle) -> Vec<HistoryEntry> {
+    let Ok(contents) = history_path(app).and_then(|path| {
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read history: {}", e))
+    }) else {
+        return Vec::new();
+    };
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+// The newest generation that produced this file.
+pub fn find_output(app: &AppHandle, path: &str) -> Option<HistoryEntry> {
+    load(app)
+        .into_iter()
+        .rev()
+        .find(|entry| entry.outputs.iter().any(|output| output == path))
+}
+
+pub fn lineage(entries: &[HistoryEntry], id: &str) -> Option<Lineage> {
+    let by_id: HashMap<&str, &HistoryEntry> = entries.iter().map(|e| (e.id.as_str(), e)).collect();
+    let entry = *by_id.get(id)?;
+
+    // A hand-edited log could loop; each generation is visited once.
+    let mut seen = HashSet::from([id]);
+    let mut ancestors = Vec::new();
+    let mut next = entry.parent.as_deref();
+    while let Some(parent) = next.and_then(|id| by_id.get(id))
+        && seen.insert(parent.id.as_str())
+    {
+        ancestors.push((*parent).clone());
+        next = parent.parent.as_deref();
+    }
+    ancestors.reverse();
+
+    // The log is append-only, so children always come after their parents
+    // and one pass finds the whole tree.
+    let mut family = HashSet::from([id]);
+    let mut descendants = Vec::new();
+    for candidate in entries {
+        if let Some(parent) = candidate.parent.as_deref()
+            && family.contains(parent)
+            && family.insert(candidate.id.as_str())
+        {
+            descendants.push(candidate.clone());
+        }
+    }
+    Some(Lineage {
+        ancestors,
+        entry: entry.clone(),
+        descendants,
+    })
+}
+
+// Points the entries that made `from` at its new name.
+pub fn move_output(app: &AppHandle, from: &str, to: &str) -> Result<(), String> {
+    let mut entries = load(app);
+    let mut moved = false;
+    for output in entries
+        .iter_mut()
+        .flat_map(|entry| entry.outputs.iter_mut())
+    {
+        if output == from {
+            *output = to.to_string();
+            moved = true;
+        }
+    }
+    if !moved {
+        return Ok(());
+    }
+    save_all(app, &entries)
+}
+
+fn save_all(app: &AppHandle, entries: &[HistoryEntry]) -> Result<(), String> {
+    let mut contents = String::new();
+    for entry in entries {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| format!("Failed to serialize history entry: {}", e))?;
+        contents.push_str(&line);
+        contents.push('\n');
+    }
+    paths::write_atomic(history_path(app)?, contents)
+        .map_err(|e| format!("Failed to save history: {}", e))
+}
+
+fn rfc3339(millis: u64) -> String {
+    time::OffsetDateTime::from_unix_timestamp_nanos(millis as i128 * 1_000_000)
+        .ok()
+        .and_then(|t| {
+            t.format(&time::format_description::well_known::Rfc3339)
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
+// Quotes a field when it has to, doubling any quotes inside (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Common parameters get a column each for spreadsheets; the full request is
+// in `params` as JSON.
+fn to_csv(entries: &[HistoryEntry]) -> String {
+    const PARAMS: [&str; 6] = ["type", "length", "steps", "seed", "bpm", "key"];
+    let mut csv = String::from("id,started,prompt,model,");
+    csv.push_str(&PARAMS.join(","));
+    csv.push_str(",duration_seconds,outputs,error,rating,parent,params\r\n");
+    for entry in entries {
+        let param = |key: &str| match entry.params.get(key) {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => String::new(),
+            Some(other) => other.to_string(),
+        };
+        let mut fields = vec![
+            entry.id.clone(),
+            rfc3339(entry.started),
+            entry.prompt.clone(),
+            entry.model.clone(),
+        ];
+        fields.extend(PARAMS.iter().map(|key| param(key)));
+        fields.extend([
+            format!("{:.2}", entry.duration_seconds),
+            entry.outputs.join(";"),
+            entry.error.clone().unwrap_or_default(),
+            entry.rating.map(|r| r.to_string()).unwrap_or_default(),
+            entry.parent.clone().unwrap_or_default(),
+            entry.params.to_string(),
+        ]);
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+pub fn export(
+    app: &AppHandle,
+    format: ExportFormat,
+    path: &Path,
+    filter: &HistoryFilter,
+) -> Result<usize, String> {
+    let entries: Vec<HistoryEntry> = load(app)
+        .into_iter()
+        .filter(|entry| filter.matches(entry))
+        .collect();
+    let contents = match format {
+        ExportFormat::Csv => to_csv(&entries),
+        ExportFormat::Json => serde_json::to_string_pretty(&entries)
+            .map_err(|e| format!("Failed to serialize history: {}", e))?,
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+    }
+    paths::write_atomic(path, contents).map_err(|e| format!("Failed to write export: {}", e))?;
+    println!(
+        "[History] Exported {} entries to {}",
+        entries.len(),
+        path.display()
+    );
+    Ok(entries.len())
+}
+
+// Newest first, for the UI.
+#[tauri::command]
+pub fn list_history(app: AppHandle, filter: Option<HistoryFilter>) -> Vec<HistoryEntry> {
+    let filter = filter.unwrap_or_default();
+    let mut entries: Vec<HistoryEntry> = load(&app)
+        .into_iter()
+        .filter(|entry| filter.matches(entry))
+        .collect();
+    entries.reverse();
+    entries
+}
+
+// Without a path it goes to the Downloads folder as noises-history_NNN.csv
+// (or .json). Returns where it was written.
+#[tauri::command]
+pub fn export_history(
+    app: AppHandle,
+    format: ExportFormat,
+    path: Option<String>,
+    filter: Option<HistoryFilter>,
+) -> Result<String, String> {
+    let path = match path {
+        Some(path) => paths::normalize(&path),
+        None => {
+            let dir = app
+                .path()
+                .download_dir()
+                .map_err(|e| format!("Failed to resolve Downloads folder: {}", e))?;
+            let extension = match format {
+                ExportFormat::Csv => ".csv",
+                ExportFormat::Json => ".json",
+            };
+            dir.join(paths::next_filename(&dir, "noises-history", extension))
+        }
+    };
+    export(&app, format, &path, &filter.unwrap_or_default())?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+pub fn get_lineage(app: AppHandle, id: String) -> Result<Lineage, String> {
+    lineage(&load(&app), &id).ok_or_else(|| format!("Unknown generation: {}", id))
+}
+
+// 1-5, or null to clear.
+#[tauri::command]
+pub fn rate_generation(app: AppHandle, id: String, rating: Option<u8>) -> Result<(), String> {
+    if rating.is_some_and(|r| !(1..=5).contains(&r)) {
+        return Err("Ratings go from 1 to 5".to_string());
+    }
+    let mut entries = load(&app);
+    let entry = entries
+        .iter_mut()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| format!("Unknown generation: {}", id))?;
+    entry.rating = rating;
+    save_all(&app, &entries)
+}
@@ -0,0 +1,239 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tauri::AppHandle;
+
+use crate::history::HistoryEntry;
+use crate::paths;
+use crate::sandbox::{self, Confined, Finished};
+use crate::settings;
+
+// Enough of the hook's output to see why it failed, without flooding the log.
+const OUTPUT_LIMIT: usize = 2000;
+
+// Inherited so the hook can find programs and a temp folder; everything else
+// from our environment (tokens included) stays out.
+const PASSED_ENV: [&str; 13] = [
+    "PATH",
+    "PATHEXT",
+    "COMSPEC",
+    "HOME",
+    "USER",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "SYSTEMROOT",
+    "TEMP",
+    "TMP",
+    "TMPDIR",
+    "LANG",
+];
+
+fn passed_env() -> impl Iterator<Item = (&'static str, std::ffi::OsString)> {
+    PASSED_ENV
+        .iter()
+        .filter_map(|key| Some((*key, std::env::var_os(key)?)))
+}
+
+// A plugin's program (plugins.rs), with a clean environment, that dies with
+// its handle. Plugins talk over pipes from their own folder and are trusted
+// like any app the user installs; the post-generation hook, a command typed
+// into settings, runs confined instead (sandbox.rs).
+pub fn user_command(program: &str) -> tokio::process::Command {
+    let mut cmd = tokio::process::Command::new(program);
+    cmd.env_clear().envs(passed_env()).kill_on_drop(true);
+    #[cfg(windows)]
+    {
+        // CREATE_NO_WINDOW, so a console program doesn't flash a window.
+        cmd.creation_flags(0x0800_0000);
+    }
+    cmd
+}
+
+// Splits the post_generation_hook setting (or a plugin's command) into a
+// program and its arguments.
+// Double or single quotes group words ("C:\Program Files\x.exe" --flag).
+// Nothing goes through a shell, so a prompt can never become a command.
+pub fn split_command(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    for c in command.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => word.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            None => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+pub fn tail(bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim();
+    match text.char_indices().rev().nth(OUTPUT_LIMIT) {
+        Some((at, _)) => format!("...{}", &text[at..]),
+        None => text.to_string(),
+    }
+}
+
+// Runs the user's post_generation_hook after a successful generation, with
+// the output paths as the last arguments and the rest in the environment:
+//
+//   NOISES_OUTPUT     the first output
+//   NOISES_OUTPUTS    every output, one per line
+//   NOISES_PROMPT     the prompt
+//   NOISES_MODEL      the model id
+//   NOISES_PARAMS     the whole request as JSON (type, length, seed, bpm, ...)
+//   NOISES_DURATION   how long the generation took, in seconds
+//
+// It runs confined (sandbox.rs): it may write to the active workspace and
+// the outputs' folders but nowhere else, starts in a scratch folder with
+// stdin closed and only a handful of our environment variables, and is
+// killed, along with anything it started, after hook_timeout_seconds. A
+// failing hook is logged and doesn't touch the generation.
+pub fn run_post_generation(app: &AppHandle, entry: &HistoryEntry) {
+    let settings = settings::get(app);
+    let Some(command) = settings
+        .post_generation_hook
+        .filter(|c| !c.trim().is_empty())
+    else {
+        return;
+    };
+    let Some((program, args)) = split_command(&command)
+        .split_first()
+        .map(|(p, a)| (p.clone(), a.to_vec()))
+    else {
+        return;
+    };
+    let Some(first) = entry.outputs.first() else {
+        return;
+    };
+    let timeout = Duration::from_secs(settings.hook_timeout_seconds.max(1));
+
+    let mut env: Vec<(String, String)> = passed_env()
+        .map(|(key, value)| (key.to_string(), value.to_string_lossy().into_owned()))
+        .collect();
+    env.extend([
+        ("NOISES_OUTPUT".to_string(), first.clone()),
+        ("NOISES_OUTPUTS".to_string(), entry.outputs.join("\n")),
+        ("NOISES_PROMPT".to_string(), entry.prompt.clone()),
+        ("NOISES_MODEL".to_string(), entry.model.clone()),
+        ("NOISES_PARAMS".to_string(), entry.params.to_string()),
+        (
+            "NOISES_DURATION".to_string(),
+            format!("{:.2}", entry.duration_seconds),
+        ),
+    ]);
+    // The workspace, for hooks that commit or sort what's in it.
+    let mut writable: Vec<PathBuf> = paths::output_dir(app).into_iter().collect();
+    writable.extend(
+        entry
+            .outputs
+            .iter()
+            .filter_map(|output| Path::new(output).parent().map(Path::to_path_buf)),
+    );
+    let confined = Confined {
+        program: program.clone(),
+        args: args
+            .into_iter()
+            .chain(entry.outputs.iter().cloned())
+            .collect(),
+        env,
+        writable,
+        timeout,
+    };
+
+    tauri::async_runtime::spawn(async move {
+        match sandbox::run(confined).await {
+            Err(e) => eprintln!("[Hooks] {}", e),
+            Ok(Finished::TimedOut) => eprintln!(
+                "[Hooks] {} didn't finish within {} s and was stopped",
+                program,
+                timeout.as_secs()
+            ),
+            Ok(Finished::Exited { success: true, .. }) => {
+                println!("[Hooks] {} finished", program);
+            }
+            Ok(Finished::Exited { status, stderr, .. }) => eprintln!(
+                "[Hooks] {} exited with {}: {}",
+                program,
+                status,
+                tail(&stderr)
+            ),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(
+            split_command("  ffmpeg -i   x.wav\t-y "),
+            ["ffmpeg", "-i", "x.wav", "-y"]
+        );
+        assert!(split_command("   ").is_empty());
+    }
+
+    #[test]
+    fn quotes_group_words() {
+        assert_eq!(
+            split_command(r#""C:\Program Files\x.exe" --name 'two words'"#),
+            [r"C:\Program Files\x.exe", "--name", "two words"]
+        );
+        // Each quote keeps the other kind as is.
+        assert_eq!(
+            split_command(r#"echo "it's" '"hi"'"#),
+            ["echo", "it's", "\"hi\""]
+        );
+    }
+
+    #[test]
+    fn quotes_join_onto_the_word_around_them() {
+        assert_eq!(split_command(r#"--out="a b".wav"#), ["--out=a b.wav"]);
+        // An empty pair is still an argument.
+        assert_eq!(split_command(r#"run "" x"#), ["run", "", "x"]);
+    }
+
+    #[test]
+    fn an_unclosed_quote_runs_to_the_end() {
+        assert_eq!(split_command(r#"say "hello there"#), ["say", "hello there"]);
+    }
+
+    #[test]
+    fn shell_syntax_is_just_text() {
+        assert_eq!(
+            split_command("notify $PROMPT; rm -rf ~"),
+            ["notify", "$PROMPT;", "rm", "-rf", "~"]
+        );
+    }
+
+    #[test]
+    fn tail_keeps_the_end() {
+        assert_eq!(tail(b"  short  "), "short");
+        let long = "é".repeat(OUTPUT_LIMIT + 10);
+        let cut = tail(long.as_bytes());
+        assert!(cut.starts_with("..."));
+        assert_eq!(cut.chars().count(), OUTPUT_LIMIT + 1 + 3);
+    }
+}
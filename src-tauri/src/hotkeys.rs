@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+use crate::clipboard;
+use crate::jobs::JobManager;
+use crate::paths;
+use crate::playback::PlaybackEngine;
+use crate::tray;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum HotkeyAction {
+    ReplayLast,
+    StopPlayback,
+    GenerateFromClipboard,
+    ImportClipboardFiles,
+}
+
+// Action -> accelerator ("CmdOrCtrl+Alt+R"). An action missing from the map has no hotkey.
+pub type Keymap = BTreeMap<HotkeyAction, String>;
+
+fn default_keymap() -> Keymap {
+    BTreeMap::from([
+        (HotkeyAction::ReplayLast, "CmdOrCtrl+Alt+R".to_string()),
+        (HotkeyAction::StopPlayback, "CmdOrCtrl+Alt+S".to_string()),
+        (
+            HotkeyAction::GenerateFromClipboard,
+            "CmdOrCtrl+Alt+G".to_string(),
+        ),
+        (
+            HotkeyAction::ImportClipboardFiles,
+            "CmdOrCtrl+Alt+I".to_string(),
+        ),
+    ])
+}
+
+// System-wide shortcuts so the app can be driven while a game or DAW has focus.
+// The keymap lives in hotkeys.json in the app config dir.
+pub struct HotkeyStore {
+    keymap: Mutex<Keymap>,
+}
+
+fn keymap_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve config directory: {}", e))?;
+    Ok(dir.join("hotkeys.json"))
+}
+
+impl HotkeyStore {
+    pub fn load(app: &AppHandle) -> Self {
+        let keymap = keymap_path(app)
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| match serde_json::from_str(&json) {
+                Ok(keymap) => Some(keymap),
+                Err(e) => {
+                    eprintln!("[Hotkeys] Ignoring unreadable hotkeys.json: {}", e);
+                    None
+                }
+            })
+            .unwrap_or_else(default_keymap);
+        HotkeyStore {
+            keymap: Mutex::new(keymap),
+        }
+    }
+
+    fn save(&self, app: &AppHandle) -> Result<(), String> {
+        let path = keymap_path(app)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create config folder: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(&*self.keymap.lock().unwrap())
+            .map_err(|e| format!("Failed to serialize hotkeys: {}", e))?;
+        paths::write_atomic(path, json).map_err(|e| format!("Failed to save hotkeys: {}", e))
+    }
+}
+
+// Called once from setup. A shortcut another app already owns is skipped, not fatal.
+pub fn register_all(app: &AppHandle) {
+    let keymap = app.state::<HotkeyStore>().keymap.lock().unwrap().clone();
+    for (action, accelerator) in keymap {
+        if let Err(e) = app.global_shortcut().register(accelerator.as_str()) {
+            eprintln!(
+                "[Hotkeys] Failed to register {} for {:?}: {}",
+                accelerator, action, e
+            );
+        }
+    }
+}
+
+// Plugin-wide handler, installed in main.rs.
+pub fn on_shortcut(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let action = app
+        .state::<HotkeyStore>()
+        .keymap
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(_, accelerator)| {
+            Shortcut::from_str(accelerator).is_ok_and(|s| s.id() == shortcut.id())
+        })
+        .map(|(action, _)| *action);
+
+    match action {
+        Some(HotkeyAction::ReplayLast) => {
+            if let Some(path) = app.state::<JobManager>().last_output() {
+                tray::play(app, path);
+            }
+        }
+        Some(HotkeyAction::StopPlayback) => {
+            if let Err(e) = app.state::<PlaybackEngine>().stop() {
+                eprintln!("[Hotkeys] Failed to stop playback: {}", e);
+            }
+        }
+        Some(HotkeyAction::GenerateFromClipboard) => clipboard::generate_from_clipboard(app),
+        Some(HotkeyAction::ImportClipboardFiles) => clipboard::import_from_clipboard(app),
+        None => {}
+    }
+}
+
+pub fn keymap(app: &AppHandle) -> Keymap {
+    app.state::<HotkeyStore>().keymap.lock().unwrap().clone()
+}
+
+// `accelerator: None` clears the hotkey. The new shortcut is registered before
+// anything is saved, so a combination that's taken leaves the old one in place.
+pub fn set(
+    app: &AppHandle,
+    action: HotkeyAction,
+    accelerator: Option<String>,
+) -> Result<Keymap, String> {
+    let store = app.state::<HotkeyStore>();
+    let shortcuts = app.global_shortcut();
+    let previous = store.keymap.lock().unwrap().get(&action).cloned();
+
+    if let Some(accelerator) = &accelerator {
+        let shortcut = Shortcut::from_str(accelerator)
+            .map_err(|e| format!("Invalid shortcut {}: {}", accelerator, e))?;
+        let unchanged = previous
+            .as_deref()
+            .and_then(|p| Shortcut::from_str(p).ok())
+            .is_some_and(|p| p.id() == shortcut.id());
+        if unchanged {
+            return Ok(store.keymap.lock().unwrap().clone());
+        }
+        shortcuts
+            .register(shortcut)
+            .map_err(|e| format!("Failed to register {}: {}", accelerator, e))?;
+    }
+    if let Some(previous) = &previous {
+        let _ = shortcuts.unregister(previous.as_str());
+    }
+
+    {
+        let mut keymap = store.keymap.lock().unwrap();
+        match accelerator {
+            Some(accelerator) => keymap.insert(action, accelerator),
+            None => keymap.remove(&action),
+        };
+    }
+    store.save(app)?;
+    Ok(store.keymap.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub fn get_hotkeys(store: State<'_, HotkeyStore>) -> Keymap {
+    store.keymap.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_hotkey(
+    app: AppHandle,
+    action: HotkeyAction,
+    accelerator: Option<String>,
+) -> Result<Keymap, String> {
+    set(&app, action, accelerator)
+}
@@ -0,0 +1,492 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_json::{Value, json};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::jobs::{self, JobManager};
+use crate::library;
+use crate::mcp;
+use crate::secrets::{self, Secret};
+use crate::settings;
+
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+// Only for reading the request; a generation can take as long as it takes.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+// A comment line on idle event streams, so a client that went away is noticed.
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+
+// An optional HTTP server on 127.0.0.1 for scripts and editor plugins:
+//
+//   POST /v1/generate   same body as the generate_audio command, answers
+//                       with its result once the sound is done
+//   GET  /v1/queue      the queue status (as in `jobs-changed`)
+//   GET  /v1/library    the sounds in the active workspace
+//   GET  /mcp/sse       MCP over server-sent events (see mcp.rs); replies
+//                       come on this stream
+//   POST /mcp/messages  a JSON-RPC message for the stream it names
+//
+// Every request needs `Authorization: Bearer <token>`, with the token from
+// the keychain (see reset_api_token). One request per connection.
+#[derive(Default)]
+pub struct HttpApi {
+    running: Mutex<Option<CancellationToken>>,
+    // The last listener's task, so the next start can wait for it to let go
+    // of the port.
+    listener: Mutex<Option<JoinHandle<()>>>,
+    // Cached so requests don't each wait on the keychain.
+    token: Mutex<Option<String>>,
+    // Open MCP event streams by session id.
+    sessions: Mutex<HashMap<String, mpsc::UnboundedSender<Value>>>,
+}
+
+struct Response {
+    status: u16,
+    body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Response { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Response {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+}
+
+fn random_hex(len: usize) -> Result<String, String> {
+    let mut bytes = vec![0u8; len];
+    getrandom::fill(&mut bytes).map_err(|e| format!("Failed to generate a token: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn new_token() -> Result<String, String> {
+    random_hex(32)
+}
+
+// Starts the server if the setting is on. Called from setup and whenever
+// api_enabled or api_port change.
+pub fn restart(app: &AppHandle) {
+    let api = app.state::<HttpApi>();
+    if let Some(running) = api.running.lock().unwrap().take() {
+        running.cancel();
+    }
+    let settings = settings::get(app);
+    if !settings.api_enabled {
+        return;
+    }
+
+    let cancel = CancellationToken::new();
+    *api.running.lock().unwrap() = Some(cancel.clone());
+    let mut listener = api.listener.lock().unwrap();
+    let previous = listener.take();
+    let app = app.clone();
+    *listener = Some(tauri::async_runtime::spawn(async move {
+        // The old listener holds the port until its task ends, and binding
+        // the same port before then fails.
+        if let Some(previous) = previous {
+            let _ = previous.await;
+        }
+        listen(app, settings.api_port, cancel).await
+    }));
+}
+
+async fn listen(app: AppHandle, port: u16, cancel: CancellationToken) {
+    let token = match token(&app) {
+        Ok(token) => token,
+        Err(e) => {
+            eprintln!("[HttpApi] {}", e);
+            return;
+        }
+    };
+    *app.state::<HttpApi>().token.lock().unwrap() = Some(token);
+
+    let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("[HttpApi] Failed to listen on port {}: {}", port, e);
+            return;
+        }
+    };
+    println!("[HttpApi] Listening on 127.0.0.1:{}", port);
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            accepted = listener.accept() => {
+                let Ok((stream, _)) = accepted else { continue };
+                let app = app.clone();
+                let cancel = cancel.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = serve(&app, stream, cancel).await {
+                        eprintln!("[HttpApi] {}", e);
+                    }
+                });
+            }
+        }
+    }
+    println!("[HttpApi] Stopped");
+}
+
+// The stored token, or a new one the first time the API is turned on.
+fn token(app: &AppHandle) -> Result<String, String> {
+    if let Some(token) = secrets::get(app, Secret::ApiToken)? {
+        return Ok(token);
+    }
+    let token = new_token()?;
+    secrets::set(app, Secret::ApiToken, Some(token.clone()))?;
+    Ok(token)
+}
+
+async fn serve(
+    app: &AppHandle,
+    mut stream: TcpStream,
+    cancel: CancellationToken,
+) -> Result<(), String> {
+    let response = match tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream)).await {
+        Ok(Ok(request))
+            if request.method == "GET"
+                && request.route() == "/mcp/sse"
+                && authorized(app, request.authorization.as_deref()) =>
+        {
+            return mcp_events(app, stream, cancel).await;
+        }
+        Ok(Ok(request)) => route(app, request).await,
+        Ok(Err(response)) => response,
+        Err(_) => Response::error(408, "Timed out reading the request"),
+    };
+
+    let body = serde_json::to_vec(&response.body).unwrap_or_default();
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        body.len()
+    );
+    let sent = async {
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(&body).await
+    };
+    sent.await
+        .map_err(|e| format!("Failed to send response: {}", e))
+}
+
+struct Request {
+    method: String,
+    path: String,
+    authorization: Option<String>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    // The path without its query string.
+    fn route(&self) -> &str {
+        self.path.split('?').next().unwrap_or_default()
+    }
+
+    fn query(&self, key: &str) -> Option<&str> {
+        let (_, query) = self.path.split_once('?')?;
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v)
+    }
+}
+
+async fn read_request(stream: &mut (impl AsyncRead + Unpin)) -> Result<Request, Response> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|_| Response::error(400, "Failed to read the request"))?;
+        if read == 0 {
+            return Err(Response::error(400, "Incomplete request"));
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+
+        let mut headers = [httparse::EMPTY_HEADER; 32];
+        let mut parsed = httparse::Request::new(&mut headers);
+        let header_len = match parsed.parse(&buffer) {
+            Ok(httparse::Status::Complete(len)) => len,
+            Ok(httparse::Status::Partial) if buffer.len() < MAX_HEADER_BYTES => continue,
+            Ok(httparse::Status::Partial) => {
+                return Err(Response::error(431, "Request headers too large"));
+            }
+            Err(_) => return Err(Response::error(400, "Malformed request")),
+        };
+
+        let header = |name: &str| {
+            parsed
+                .headers
+                .iter()
+                .find(|h| h.name.eq_ignore_ascii_case(name))
+                .map(|h| String::from_utf8_lossy(h.value).into_owned())
+        };
+        let content_length: usize = header("content-length")
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(0);
+        if content_length > MAX_BODY_BYTES {
+            return Err(Response::error(413, "Request body too large"));
+        }
+        let mut request = Request {
+            method: parsed.method.unwrap_or_default().to_string(),
+            path: parsed.path.unwrap_or_default().to_string(),
+            authorization: header("authorization"),
+            body: buffer[header_len..].to_vec(),
+        };
+        while request.body.len() < content_length {
+            let read = stream
+                .read(&mut chunk)
+                .await
+                .map_err(|_| Response::error(400, "Failed to read the request body"))?;
+            if read == 0 {
+                return Err(Response::error(400, "Incomplete request body"));
+            }
+            request.body.extend_from_slice(&chunk[..read]);
+        }
+        request.body.truncate(content_length);
+        return Ok(request);
+    }
+}
+
+fn authorized(app: &AppHandle, authorization: Option<&str>) -> bool {
+    let expected = app.state::<HttpApi>().token.lock().unwrap().clone();
+    token_matches(expected.as_deref(), authorization)
+}
+
+// Compares in constant time, so the token can't be guessed byte by byte.
+fn token_matches(expected: Option<&str>, authorization: Option<&str>) -> bool {
+    let (Some(expected), Some(given)) = (
+        expected,
+        authorization.and_then(|a| a.strip_prefix("Bearer ")),
+    ) else {
+        return false;
+    };
+    let (expected, given) = (expected.as_bytes(), given.trim().as_bytes());
+    expected.len() == given.len()
+        && expected
+            .iter()
+            .zip(given)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn route(app: &AppHandle, request: Request) -> Response {
+    if !authorized(app, request.authorization.as_deref()) {
+        return Response::error(401, "Missing or wrong API token");
+    }
+    match (request.method.as_str(), request.route()) {
+        ("POST", "/v1/generate") => {
+            let config: Value = match serde_json::from_slice(&request.body) {
+                Ok(config @ Value::Object(_)) => config,
+                _ => return Response::error(400, "Expected a JSON object"),
+            };
+            match jobs::generate(app, config).await {
+                Ok(result) => Response::ok(result),
+                Err(e) => Response::error(500, e),
+            }
+        }
+        ("GET", "/v1/queue") => Response::ok(json!(app.state::<JobManager>().status())),
+        ("GET", "/v1/library") => {
+            let app = app.clone();
+            match tauri::async_runtime::spawn_blocking(move || library::scan(&app)).await {
+                Ok(items) => Response::ok(json!(items)),
+                Err(e) => Response::error(500, format!("Library scan failed: {}", e)),
+            }
+        }
+        ("POST", "/mcp/messages") => {
+            let Some(sender) = request.query("session_id").and_then(|id| {
+                app.state::<HttpApi>()
+                    .sessions
+                    .lock()
+                    .unwrap()
+                    .get(id)
+                    .cloned()
+            }) else {
+                return Response::error(404, "Unknown MCP session");
+            };
+            let Ok(message) = String::from_utf8(request.body) else {
+                return Response::error(400, "Expected a JSON-RPC message");
+            };
+            // Accepted straight away; the reply goes out on the event stream
+            // whenever the tool is done.
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Some(reply) = mcp::handle(&app, &message).await {
+                    let _ = sender.send(reply);
+                }
+            });
+            Response {
+                status: 202,
+                body: json!({}),
+            }
+        }
+        (_, "/v1/generate" | "/v1/queue" | "/v1/library" | "/mcp/sse" | "/mcp/messages") => {
+            Response::error(405, "Method not allowed")
+        }
+        _ => Response::error(404, "Not found"),
+    }
+}
+
+// Holds the connection open as an MCP event stream: first the endpoint to
+// post messages to, then every reply for this session.
+async fn mcp_events(
+    app: &AppHandle,
+    mut stream: TcpStream,
+    cancel: CancellationToken,
+) -> Result<(), String> {
+    let session = random_hex(16)?;
+    let (sender, mut replies) = mpsc::unbounded_channel();
+    let api = app.state::<HttpApi>();
+    api.sessions.lock().unwrap().insert(session.clone(), sender);
+
+    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n";
+    let endpoint = format!(
+        "event: endpoint\ndata: /mcp/messages?session_id={}\n\n",
+        session
+    );
+    let mut result = async {
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(endpoint.as_bytes()).await
+    }
+    .await;
+    while result.is_ok() {
+        let event = tokio::select! {
+            _ = cancel.cancelled() => break,
+            reply = replies.recv() => match reply {
+                Some(reply) => format!("event: message\ndata: {}\n\n", reply),
+                None => break,
+            },
+            _ = tokio::time::sleep(KEEP_ALIVE) => ": keep-alive\n\n".to_string(),
+        };
+        result = stream.write_all(event.as_bytes()).await;
+    }
+
+    api.sessions.lock().unwrap().remove(&session);
+    // A client closing the stream is how a session normally ends.
+    Ok(())
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+// Makes a new token, which stops the old one working straight away.
+#[tauri::command]
+pub async fn reset_api_token(app: AppHandle) -> Result<String, String> {
+    let token = new_token()?;
+    secrets::set(&app, Secret::ApiToken, Some(token.clone()))?;
+    *app.state::<HttpApi>().token.lock().unwrap() = Some(token.clone());
+    println!("[HttpApi] Token reset");
+    Ok(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read(raw: &[u8]) -> Result<Request, u16> {
+        let mut raw = raw;
+        read_request(&mut raw)
+            .await
+            .map_err(|response| response.status)
+    }
+
+    #[tokio::test]
+    async fn reads_the_head_and_body() {
+        let request = read(
+            b"POST /v1/generate?wait=1 HTTP/1.1\r\nAuthorization: Bearer abc\r\nContent-Length: 4\r\n\r\n{}{}extra",
+        )
+        .await
+        .ok()
+        .unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.route(), "/v1/generate");
+        assert_eq!(request.query("wait"), Some("1"));
+        assert_eq!(request.authorization.as_deref(), Some("Bearer abc"));
+        assert_eq!(request.body, b"{}{}");
+    }
+
+    #[tokio::test]
+    async fn reads_bodies_longer_than_one_chunk() {
+        let body = vec![b'x'; 10_000];
+        let mut raw =
+            format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", body.len()).into_bytes();
+        raw.extend_from_slice(&body);
+        assert_eq!(read(&raw).await.ok().unwrap().body, body);
+    }
+
+    #[tokio::test]
+    async fn refuses_oversize_bodies_before_reading_them() {
+        let raw = format!(
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_BYTES + 1
+        );
+        assert_eq!(read(raw.as_bytes()).await.err(), Some(413));
+    }
+
+    #[tokio::test]
+    async fn refuses_oversize_headers() {
+        let raw = format!(
+            "GET / HTTP/1.1\r\nX-Padding: {}\r\n",
+            "a".repeat(MAX_HEADER_BYTES)
+        );
+        assert_eq!(read(raw.as_bytes()).await.err(), Some(431));
+    }
+
+    #[tokio::test]
+    async fn refuses_incomplete_and_malformed_requests() {
+        assert_eq!(read(b"GET / HTTP/1.1\r\n").await.err(), Some(400));
+        assert_eq!(
+            read(b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort")
+                .await
+                .err(),
+            Some(400)
+        );
+        assert_eq!(read(b"\x00\x01 nonsense\r\n\r\n").await.err(), Some(400));
+    }
+
+    #[test]
+    fn needs_the_exact_bearer_token() {
+        let token = Some("s3cret-token");
+        assert!(token_matches(token, Some("Bearer s3cret-token")));
+        assert!(token_matches(token, Some("Bearer s3cret-token ")));
+        assert!(!token_matches(token, None));
+        assert!(!token_matches(token, Some("s3cret-token")));
+        assert!(!token_matches(token, Some("Basic s3cret-token")));
+        assert!(!token_matches(token, Some("Bearer s3cret-tokem")));
+        assert!(!token_matches(token, Some("Bearer s3cret")));
+        assert!(!token_matches(token, Some("Bearer s3cret-token-and-more")));
+        assert!(!token_matches(token, Some("Bearer ")));
+    }
+
+    #[test]
+    fn refuses_everything_without_a_token_set() {
+        assert!(!token_matches(None, Some("Bearer anything")));
+        assert!(!token_matches(None, None));
+    }
+}
@@ -0,0 +1,301 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use percent_encoding::percent_decode_str;
+use reqwest::Url;
+use serde::Serialize;
+use tauri::AppHandle;
+use tauri::http::header;
+use tokio::io::AsyncWriteExt;
+
+use crate::deep_link::{self, DeepLink};
+use crate::disk;
+use crate::filenames;
+use crate::paths;
+use crate::proxy;
+use crate::sound_pack;
+use crate::tasks::{self, TaskKind};
+use crate::telemetry;
+use crate::workspaces;
+
+// Matches the fileAssociations in tauri.conf.json.
+const AUDIO_EXTENSIONS: [&str; 3] = ["wav", "mp3", "flac"];
+
+// A reference clip is seconds to minutes long; anything bigger is more likely
+// a wrong link than a sound.
+const MAX_URL_IMPORT_BYTES: u64 = 200 * 1024 * 1024;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+// What sounds downloaded with import_from_url are tagged with, so they can be
+// told apart from generated ones when picking conditioning audio.
+const REFERENCE_TAG: &str = "reference";
+
+#[derive(Clone, Serialize)]
+pub struct ImportedFile {
+    pub file: String,
+    pub path: String,
+}
+
+pub fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| AUDIO_EXTENSIONS.iter().any(|a| a.eq_ignore_ascii_case(e)))
+}
+
+// Copies files into the library (~/Music/Noises/imports) so they behave like
+// generated sounds. Files that are already in the library are left where they are.
+pub fn import_files(app: &AppHandle, files: &[PathBuf]) -> Result<Vec<ImportedFile>, String> {
+    telemetry::record_feature(app, "import");
+    let library = paths::canonical(&paths::output_dir(app)?).ok();
+    let dir = paths::imports_dir(app)?;
+
+    let mut imported = Vec::new();
+    for source in files {
+        if !is_audio_file(source) {
+            return Err(format!("Not a supported audio file: {}", source.display()));
+        }
+        let source = paths::canonical(source)
+            .map_err(|e| format!("Failed to open {}: {}", source.display(), e))?;
+
+        let target = if library
+            .as_ref()
+            .is_some_and(|root| source.starts_with(root))
+        {
+            source
+        } else {
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create imports folder: {}", e))?;
+            let target = unique_path(&dir, &source);
+            paths::copy_atomic(&source, &target)
+                .map_err(|e| format!("Failed to import {}: {}", source.display(), e))?;
+            println!("[Import] {} -> {}", source.display(), target.display());
+            target
+        };
+
+        imported.push(ImportedFile {
+            file: target
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path: target.to_string_lossy().into_owned(),
+        });
+    }
+    Ok(imported)
+}
+
+// "kick.wav" -> "kick-1.wav" if the name is taken. The name is sanitized
+// first, since a sound pack can carry names made on another system.
+pub fn unique_path(dir: &Path, source: &Path) -> PathBuf {
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = source.extension().unwrap_or_default().to_string_lossy();
+    filenames::unique(dir, &filenames::sanitize(&stem), &extension)
+}
+
+// "Open with Noises" on a launch or via a second instance. The last file is
+// shown in the player, same as a freshly generated one. Sound packs are
+// imported whole.
+pub fn open_files(app: &AppHandle, files: Vec<PathBuf>) {
+    let (packs, files): (Vec<PathBuf>, Vec<PathBuf>) = files
+        .into_iter()
+        .partition(|f| sound_pack::is_sound_pack(f));
+    for pack in packs {
+        sound_pack::open(app, pack);
+    }
+    let files: Vec<PathBuf> = files.into_iter().filter(|f| is_audio_file(f)).collect();
+    if files.is_empty() {
+        return;
+    }
+    match import_files(app, &files) {
+        Ok(imported) => {
+            if let Some(last) = imported.into_iter().last() {
+                deep_link::deliver(
+                    app,
+                    DeepLink::OpenSound {
+                        file: last.file,
+                        path: last.path,
+                    },
+                );
+            }
+        }
+        Err(e) => eprintln!("[Import] {}", e),
+    }
+}
+
+// The extension for a Content-Type the app can play. application/octet-stream
+// (or none at all) is what many file hosts send, so it's left to the URL.
+pub fn extension_for_content_type(content_type: &str) -> Result<Option<&'static str>, String> {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    Ok(Some(match mime.as_str() {
+        "audio/wav" | "audio/x-wav" | "audio/wave" | "audio/vnd.wave" => "wav",
+        "audio/mpeg" | "audio/mp3" | "audio/x-mp3" => "mp3",
+        "audio/flac" | "audio/x-flac" => "flac",
+        "" | "application/octet-stream" | "binary/octet-stream" => return Ok(None),
+        "text/html" => return Err("That link leads to a web page, not an audio file".to_string()),
+        _ => return Err(format!("Not a supported audio file ({})", mime)),
+    }))
+}
+
+// The file name at the end of the URL's path, percent-decoded.
+fn name_from_url(url: &Url) -> Option<String> {
+    let segment = url.path_segments()?.rev().find(|s| !s.is_empty())?;
+    Some(percent_decode_str(segment).decode_utf8_lossy().into_owned())
+}
+
+// Checks the first bytes, since servers and links both get the type wrong.
+fn looks_like(extension: &str, start: &[u8]) -> bool {
+    match extension {
+        "wav" => start.len() >= 12 && &start[..4] == b"RIFF" && &start[8..12] == b"WAVE",
+        "flac" => start.starts_with(b"fLaC"),
+        "mp3" => {
+            start.starts_with(b"ID3")
+                || (start.len() >= 2 && start[0] == 0xff && start[1] & 0xe0 == 0xe0)
+        }
+        _ => false,
+    }
+}
+
+// Downloads an audio file into the library's imports folder and tags it as a
+// reference. Shows up as a download task, which cancels it. Only http(s)
+// links to WAV, MP3 or FLAC files up to MAX_URL_IMPORT_BYTES are taken.
+#[tauri::command]
+pub async fn import_from_url(app: AppHandle, url: String) -> Result<ImportedFile, String> {
+    let url = Url::parse(url.trim()).map_err(|e| format!("Invalid URL: {}", e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err("Only http and https links can be imported".to_string());
+    }
+    telemetry::record_feature(&app, "import_url");
+    let client = proxy::client_builder(&app)?
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let name = name_from_url(&url).unwrap_or_else(|| "reference".to_string());
+    let task = tasks::begin(&app, TaskKind::Download, &name);
+
+    let dir = paths::imports_dir(&app)?;
+    let temp = paths::temp_path(&dir.join(filenames::sanitize(&name)));
+    let result = task
+        .run(async {
+            let mut res = client
+                .get(url.clone())
+                .send()
+                .await
+                .map_err(|e| format!("Failed to download {}: {}", url, e))?
+                .error_for_status()
+                .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+
+            let content_type = res
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default();
+            let url_extension = Path::new(&name)
+                .extension()
+                .map(|e| e.to_string_lossy().to_ascii_lowercase())
+                .filter(|e| AUDIO_EXTENSIONS.contains(&e.as_str()));
+            let extension = match extension_for_content_type(content_type)? {
+                Some(extension) => extension.to_string(),
+                None => url_extension.ok_or("The link doesn't say it's a WAV, MP3 or FLAC file")?,
+            };
+            let total = res.content_length();
+            if total.is_some_and(|len| len > MAX_URL_IMPORT_BYTES) {
+                return Err(format!(
+                    "The file is too big to import ({} MB, the limit is {} MB)",
+                    total.unwrap_or_default() / (1024 * 1024),
+                    MAX_URL_IMPORT_BYTES / (1024 * 1024)
+                ));
+            }
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create imports folder: {}", e))?;
+            disk::ensure_space(&dir, total.unwrap_or(0))?;
+
+            let mut file = tokio::fs::File::create(&temp)
+                .await
+                .map_err(|e| format!("Failed to create {}: {}", temp.display(), e))?;
+            let mut start = Vec::new();
+            let mut downloaded = 0u64;
+            let mut last_progress = Instant::now();
+            task.progress(0, total);
+            while let Some(chunk) = res
+                .chunk()
+                .await
+                .map_err(|e| format!("Download interrupted: {}", e))?
+            {
+                downloaded += chunk.len() as u64;
+                if downloaded > MAX_URL_IMPORT_BYTES {
+                    return Err(format!(
+                        "The file is too big to import (the limit is {} MB)",
+                        MAX_URL_IMPORT_BYTES / (1024 * 1024)
+                    ));
+                }
+                if start.len() < 12 {
+                    start.extend(chunk.iter().take(12 - start.len()));
+                }
+                file.write_all(&chunk)
+                    .await
+                    .map_err(|e| format!("Failed to write download: {}", e))?;
+                if last_progress.elapsed() >= PROGRESS_INTERVAL {
+                    task.progress(downloaded, total);
+                    last_progress = Instant::now();
+                }
+            }
+            file.flush()
+                .await
+                .map_err(|e| format!("Failed to write download: {}", e))?;
+            drop(file);
+            task.progress(downloaded, total);
+            if !looks_like(&extension, &start) {
+                return Err(format!(
+                    "The downloaded file isn't a valid {} file",
+                    extension.to_uppercase()
+                ));
+            }
+            Ok(extension)
+        })
+        .await;
+    let extension = match result {
+        Ok(extension) => extension,
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp);
+            return Err(e);
+        }
+    };
+
+    let stem = Path::new(&name)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let target = filenames::unique(&dir, &filenames::sanitize(&stem), &extension);
+    if let Err(e) = std::fs::rename(&temp, &target) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(format!("Failed to import {}: {}", url, e));
+    }
+    println!("[Import] {} -> {}", url, target.display());
+    if let Err(e) = workspaces::add_tags(&app, &target, &[REFERENCE_TAG.to_string()]) {
+        eprintln!("[Import] {}", e);
+    }
+    Ok(ImportedFile {
+        file: target
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        path: target.to_string_lossy().into_owned(),
+    })
+}
+
+#[tauri::command]
+pub async fn import_audio_files(
+    app: AppHandle,
+    paths: Vec<PathBuf>,
+) -> Result<Vec<ImportedFile>, String> {
+    tauri::async_runtime::spawn_blocking(move || import_files(&app, &paths))
+        .await
+        .map_err(|e| format!("Import task failed: {}", e))?
+}
@@ -0,0 +1,59 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::import;
+use crate::sound_pack;
+use crate::tray;
+
+#[derive(Clone, Serialize)]
+struct OpenArgs {
+    // Relative file paths are resolved against the launching shell's directory,
+    // URLs are passed through untouched.
+    args: Vec<String>,
+}
+
+// A second launch would spawn another backend and fight over port 8000, so the
+// single-instance plugin exits it early and hands its arguments to us instead.
+pub fn on_second_instance(app: &AppHandle, argv: Vec<String>, cwd: String) {
+    println!("[Instance] Second launch forwarded: {:?}", argv);
+    tray::show_main_window(app);
+
+    let (files, args): (Vec<String>, Vec<String>) = forwarded_args(argv, Path::new(&cwd))
+        .into_iter()
+        .partition(|arg| {
+            let path = Path::new(arg);
+            import::is_audio_file(path) || sound_pack::is_sound_pack(path)
+        });
+    import::open_files(app, files.into_iter().map(PathBuf::from).collect());
+    if !args.is_empty() {
+        let _ = app.emit("open-args", OpenArgs { args });
+    }
+}
+
+// Files the app was launched with ("Open with Noises" when it wasn't running).
+pub fn open_launch_files(app: &AppHandle) {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let files = forwarded_args(std::env::args().collect(), &cwd)
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+    import::open_files(app, files);
+}
+
+// Drops the executable path, our own flags, and noises:// links (the deep-link
+// plugin picks those up on its own).
+fn forwarded_args(argv: Vec<String>, cwd: &Path) -> Vec<String> {
+    argv.into_iter()
+        .skip(1)
+        .filter(|arg| !arg.starts_with('-') && !arg.starts_with("noises:"))
+        .map(|arg| {
+            if arg.contains("://") || Path::new(&arg).is_absolute() {
+                arg
+            } else {
+                cwd.join(&arg).to_string_lossy().into_owned()
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,611 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::{Value, json};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::backend;
+use crate::disk;
+use crate::gpu;
+use crate::history::{self, Derivation, HistoryEntry};
+use crate::hooks;
+use crate::library::{self, SoundKind};
+use crate::low_vram;
+use crate::models;
+use crate::notifications;
+use crate::power::PowerGuard;
+use crate::providers;
+use crate::render_target;
+use crate::segments;
+use crate::settings;
+use crate::sidecar;
+use crate::sync;
+use crate::tasks::{self, Task, TaskKind};
+use crate::telemetry;
+use crate::thermal;
+use crate::workspaces;
+
+// How many finished outputs the tray keeps under "Recent".
+const RECENT_LIMIT: usize = 5;
+
+// How many times a generation is sent again after the backend died under it.
+const MAX_REQUEUES: u32 = 2;
+// How long a failed request waits to hear whether the backend died, since
+// the connection drops a moment before the process is reaped.
+const EXIT_GRACE: Duration = Duration::from_secs(3);
+
+// Leading code of the error for a generation the backend died under too
+// many times; history keeps it like any other failure.
+pub const INTERRUPTED_ERROR: &str = "Interrupted";
+
+// More than this in one sweep is better done as a few.
+const MAX_VARIATIONS: u32 = 32;
+
+// A job that wouldn't fit in the VRAM left over (a game running, say) waits,
+// looking again this often. After the limit it's sent anyway and left to
+// the backend, since the estimate could be wrong.
+const GPU_POLL: Duration = Duration::from_secs(10);
+const GPU_WAIT_LIMIT: Duration = Duration::from_secs(30 * 60);
+
+// Jobs that are past the VRAM check and talking to the backend. While one
+// is, the memory in use is (mostly) the backend's own, and the others wait
+// their turn through BackendBusy instead.
+static ON_BACKEND: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JobState {
+    Running,
+    WaitingForGpu,
+    // Held while the queue is paused for heat (thermal.rs).
+    CoolingDown,
+    // Sent to a remote provider that hasn't started on it yet (providers.rs).
+    WaitingForProvider,
+}
+
+#[derive(Clone, Serialize)]
+pub struct JobInfo {
+    pub id: u64,
+    pub prompt: String,
+    pub state: JobState,
+}
+
+#[derive(Clone, Serialize)]
+pub struct QueueStatus {
+    pub active: Vec<JobInfo>,
+    pub recent: Vec<String>,
+    // Progress through the current batch: jobs started since the queue was
+    // last empty, and how many of those have ended.
+    pub batch_total: usize,
+    pub batch_done: usize,
+}
+
+#[derive(Clone, Serialize)]
+struct JobFinished {
+    id: u64,
+    prompt: String,
+    result: Option<Value>,
+    error: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct BatchProgress {
+    batch: String,
+    total: u32,
+    // Ended either way; `failed` of them didn't produce a sound.
+    done: u32,
+    failed: u32,
+    // In seed order.
+    outputs: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct WaitingForGpu {
+    id: u64,
+    prompt: String,
+    // Bytes.
+    free: u64,
+    needed: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct JobInterrupted {
+    id: u64,
+    prompt: String,
+    // False once it's run out of retries and fails.
+    requeued: bool,
+}
+
+#[derive(Default)]
+struct JobsState {
+    next_id: u64,
+    active: Vec<JobInfo>,
+    // Newest first.
+    recent: VecDeque<String>,
+    batch_total: usize,
+    batch_done: usize,
+}
+
+// Tracks the generations in flight and the files they produced, so the parts
+// of the app outside the webview (tray, etc.) can show what's going on.
+// Every change is broadcast as `jobs-changed`.
+pub struct JobManager {
+    state: Mutex<JobsState>,
+}
+
+// Returned by JobManager::begin. Dropping it without finish() counts the job as
+// abandoned and just removes it from the active list.
+pub struct Job {
+    app: AppHandle,
+    info: JobInfo,
+    params: Value,
+    model: &'static str,
+    started: Instant,
+    // Unix milliseconds, for the history.
+    started_at: u64,
+    // The generation it was made from (history id), for the lineage.
+    parent: Option<String>,
+    // The seed sweep it's part of.
+    batch: Option<String>,
+}
+
+impl JobManager {
+    // Seeds "recent" from the newest files already in the library.
+    pub fn new(app: &AppHandle) -> Self {
+        JobManager {
+            state: Mutex::new(JobsState {
+                recent: scan_recent(app),
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn begin(&self, app: &AppHandle, config: &Value) -> Job {
+        let info = {
+            let mut state = self.state.lock().unwrap();
+            if state.active.is_empty() {
+                state.batch_total = 0;
+                state.batch_done = 0;
+            }
+            state.batch_total += 1;
+            state.next_id += 1;
+            let info = JobInfo {
+                id: state.next_id,
+                prompt: config
+                    .get("prompt")
+                    .and_then(|p| p.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                state: JobState::Running,
+            };
+            state.active.push(info.clone());
+            info
+        };
+        changed(app);
+        // "parent" is the path of the sound it was made from (Regenerate).
+        // Only the link goes in the history, not the path. "batch" is set by
+        // enqueue_variations.
+        let mut params = config.clone();
+        let mut take = |key: &str| params.as_object_mut().and_then(|config| config.remove(key));
+        let parent = take("parent")
+            .and_then(|path| history::find_output(app, path.as_str()?))
+            .map(|entry| entry.id);
+        let batch = take("batch").and_then(|batch| Some(batch.as_str()?.to_string()));
+        Job {
+            app: app.clone(),
+            info,
+            params,
+            model: if providers::is_remote(config) {
+                providers::REMOTE_MODEL
+            } else {
+                models::model_for_type(
+                    config
+                        .get("type")
+                        .and_then(|t| t.as_str())
+                        .unwrap_or_default(),
+                )
+            },
+            started: Instant::now(),
+            started_at: history::now_millis(),
+            parent,
+            batch,
+        }
+    }
+
+    pub fn status(&self) -> QueueStatus {
+        let state = self.state.lock().unwrap();
+        QueueStatus {
+            active: state.active.clone(),
+            recent: state.recent.iter().cloned().collect(),
+            batch_total: state.batch_total,
+            batch_done: state.batch_done,
+        }
+    }
+
+    pub fn last_output(&self) -> Option<String> {
+        self.state.lock().unwrap().recent.front().cloned()
+    }
+}
+
+impl Job {
+    pub fn prompt(&self) -> &str {
+        &self.info.prompt
+    }
+
+    // Sends the job's request, starting the backend first if need be. When
+    // the backend dies under it (a crash, or the OS killing it for memory)
+    // the job is announced as `job-interrupted` and sent again to a fresh
+    // backend, instead of ending as "connection closed" or waiting out the
+    // request timeout.
+    pub async fn run_on_backend<F: Future<Output = Result<Value, String>>>(
+        &self,
+        mut request: impl FnMut() -> F,
+    ) -> Result<Value, String> {
+        self.wait_until_cool().await;
+        self.wait_for_gpu().await;
+        ON_BACKEND.fetch_add(1, Ordering::SeqCst);
+        let _on_backend = OnBackend;
+        let mut requeues = 0;
+        loop {
+            sidecar::ensure_running(&self.app).await?;
+            let seen = sidecar::exits();
+            let result = tokio::select! {
+                result = backend::retry_while_busy(self.prompt(), &mut request) => result,
+                _ = sidecar::exited_since(seen) => Err(String::new()),
+            };
+            let interrupted = match &result {
+                Ok(_) => false,
+                Err(_) if sidecar::exits() != seen => true,
+                // A failure from a backend that's still there is the
+                // request's own; only wait for news when it's gone.
+                Err(_) if backend::is_healthy().await => false,
+                Err(_) => tokio::time::timeout(EXIT_GRACE, sidecar::exited_since(seen))
+                    .await
+                    .is_ok(),
+            };
+            if !interrupted {
+                return result;
+            }
+
+            let requeued = requeues < MAX_REQUEUES;
+            eprintln!(
+                "[Jobs] The backend stopped during \"{}\"{}",
+                self.info.prompt,
+                if requeued { ", sending it again" } else { "" }
+            );
+            let _ = self.app.emit(
+                "job-interrupted",
+                JobInterrupted {
+                    id: self.info.id,
+                    prompt: self.info.prompt.clone(),
+                    requeued,
+                },
+            );
+            if !requeued {
+                return Err(format!(
+                    "{}: The AI engine stopped during this generation",
+                    INTERRUPTED_ERROR
+                ));
+            }
+            requeues += 1;
+        }
+    }
+
+    // Sends the job to a remote provider instead. None of the local
+    // sidecar's concerns (heat, VRAM, restarts) apply; the job shows as
+    // waiting until the provider starts on it.
+    async fn run_remote(
+        &self,
+        provider: &providers::RemoteProvider,
+        config: &Value,
+    ) -> Result<Value, String> {
+        let result = providers::generate(&self.app, provider, config, |phase| {
+            self.set_state(match phase {
+                providers::Phase::Queued => JobState::WaitingForProvider,
+                providers::Phase::Running => JobState::Running,
+            })
+        })
+        .await;
+        self.set_state(JobState::Running);
+        result
+    }
+
+    // Holds the job for as long as the queue is paused for heat. Segments
+    // of a long request each come through here, so it pauses between them.
+    async fn wait_until_cool(&self) {
+        let mut waited = false;
+        while thermal::too_hot(&self.app) {
+            if !waited {
+                self.set_state(JobState::CoolingDown);
+                waited = true;
+            }
+            tokio::time::sleep(thermal::POLL).await;
+        }
+        if waited {
+            self.set_state(JobState::Running);
+        }
+    }
+
+    // Holds the job while the GPU doesn't have the VRAM it needs free,
+    // announcing it as `job-waiting-for-gpu` and in its state. Only NVIDIA
+    // cards can be asked; elsewhere, and on the CPU, it goes straight on.
+    async fn wait_for_gpu(&self) {
+        if backend::is_cpu_fallback() {
+            return;
+        }
+        let settings = settings::get(&self.app);
+        let needed = models::vram_needed(&self.params, settings.low_vram);
+        let index = settings.compute_device.gpu_index();
+        let started = Instant::now();
+        let mut announced = false;
+        loop {
+            let short = index
+                .and_then(gpu::vram)
+                .filter(|vram| vram.total >= needed && vram.free < needed)
+                .filter(|_| ON_BACKEND.load(Ordering::SeqCst) == 0);
+            let Some(vram) = short else { break };
+            if started.elapsed() >= GPU_WAIT_LIMIT {
+                eprintln!(
+                    "[Jobs] Still not enough free VRAM for \"{}\", sending it anyway",
+                    self.info.prompt
+                );
+                break;
+            }
+            if !announced {
+                println!(
+                    "[Jobs] Waiting for VRAM for \"{}\": {} MB free, about {} MB needed",
+                    self.info.prompt,
+                    vram.free / (1024 * 1024),
+                    needed / (1024 * 1024)
+                );
+                self.set_state(JobState::WaitingForGpu);
+                announced = true;
+            }
+            let _ = self.app.emit(
+                "job-waiting-for-gpu",
+                WaitingForGpu {
+                    id: self.info.id,
+                    prompt: self.info.prompt.clone(),
+                    free: vram.free,
+                    needed,
+                },
+            );
+            tokio::time::sleep(GPU_POLL).await;
+        }
+        if announced {
+            self.set_state(JobState::Running);
+        }
+    }
+
+    fn set_state(&self, job_state: JobState) {
+        {
+            let jobs = self.app.state::<JobManager>();
+            let mut state = jobs.state.lock().unwrap();
+            if let Some(info) = state.active.iter_mut().find(|j| j.id == self.info.id) {
+                info.state = job_state;
+            }
+        }
+        changed(&self.app);
+    }
+
+    // finish() unless the task was cancelled; a cancelled generation is just
+    // dropped from the queue, without a history entry or a notification.
+    pub fn end(self, task: &Task, result: &Result<Value, String>) {
+        if !task.is_cancelled() {
+            self.finish(result);
+        }
+    }
+
+    // Records the outputs of a successful generation and tells listeners how it went.
+    pub fn finish(self, result: &Result<Value, String>) {
+        let outputs = result.as_ref().map(output_paths).unwrap_or_default();
+
+        let entry = HistoryEntry {
+            id: format!("{}-{}", self.started_at, self.info.id),
+            prompt: self.info.prompt.clone(),
+            params: self.params.clone(),
+            model: self.model.to_string(),
+            started: self.started_at,
+            duration_seconds: self.started.elapsed().as_secs_f64(),
+            outputs: outputs.clone(),
+            error: result.as_ref().err().cloned(),
+            rating: None,
+            derivation: self.parent.is_some().then_some(Derivation::Regenerate),
+            parent: self.parent.clone(),
+            batch: self.batch.clone(),
+        };
+        if let Err(e) = history::record(&self.app, &entry) {
+            eprintln!("[Jobs] {}", e);
+        }
+
+        if result.is_ok() {
+            hooks::run_post_generation(&self.app, &entry);
+            render_target::update(&self.app, &entry);
+            sync::upload_new(&self.app, outputs.clone());
+            let jobs = self.app.state::<JobManager>();
+            let mut state = jobs.state.lock().unwrap();
+            for path in outputs {
+                state.recent.retain(|p| *p != path);
+                state.recent.push_front(path);
+            }
+            state.recent.truncate(RECENT_LIMIT);
+            let recent: Vec<String> = state.recent.iter().cloned().collect();
+            drop(state);
+            crate::jumplist::update(&recent);
+        }
+
+        models::record_use(&self.app, self.model);
+        telemetry::record_generation(
+            &self.app,
+            self.model,
+            self.started.elapsed(),
+            result.as_ref().err(),
+        );
+        notifications::job_finished(&self.app, &self.info.prompt, result);
+        let _ = self.app.emit(
+            "job-finished",
+            JobFinished {
+                id: self.info.id,
+                prompt: self.info.prompt.clone(),
+                result: result.as_ref().ok().cloned(),
+                error: result.as_ref().err().cloned(),
+            },
+        );
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        {
+            let jobs = self.app.state::<JobManager>();
+            let mut state = jobs.state.lock().unwrap();
+            state.active.retain(|j| j.id != self.info.id);
+            state.batch_done += 1;
+        }
+        changed(&self.app);
+    }
+}
+
+// Counts a job off the backend however run_on_backend ends, cancels included.
+struct OnBackend;
+
+impl Drop for OnBackend {
+    fn drop(&mut self) {
+        ON_BACKEND.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn output_paths(result: &Value) -> Vec<String> {
+    result
+        .get("files")
+        .and_then(|f| f.as_array())
+        .map(|files| {
+            files
+                .iter()
+                .filter_map(|f| f.get("path").and_then(|p| p.as_str()))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn scan_recent(app: &AppHandle) -> VecDeque<String> {
+    library::scan(app)
+        .into_iter()
+        .filter(|item| matches!(item.kind, SoundKind::Loop | SoundKind::Oneshot))
+        .take(RECENT_LIMIT)
+        .map(|item| item.path)
+        .collect()
+}
+
+// Swaps "recent" for the newest files in the library, after switching workspace.
+pub fn reload_recent(app: &AppHandle) {
+    let recent = scan_recent(app);
+    app.state::<JobManager>().state.lock().unwrap().recent = recent;
+    crate::jumplist::update(&app.state::<JobManager>().status().recent);
+    changed(app);
+}
+
+fn changed(app: &AppHandle) {
+    let status = app.state::<JobManager>().status();
+    crate::tray::refresh(app, &status);
+    crate::taskbar::update(app, &status);
+    crate::close_guard::on_jobs_changed(app, &status);
+    crate::sidecar::on_jobs_changed(app, &status);
+    let _ = app.emit("jobs-changed", status);
+}
+
+// The non-streaming generation path shared by the generate_audio command and
+// the tray and hotkey actions. Requests longer than the model makes in one go
+// are made in segments. A request whose "provider" names a remote one goes
+// there whole (providers.rs).
+pub async fn generate(app: &AppHandle, config: Value) -> Result<Value, String> {
+    backend::validate_config(&config)?;
+    let remote = providers::remote(app, &config)?;
+    // Long batches shouldn't be interrupted by the laptop going to sleep.
+    let _awake = app.state::<PowerGuard>().acquire();
+    let job = app.state::<JobManager>().begin(app, &config);
+    let task = tasks::begin(app, TaskKind::Generation, job.prompt());
+    let config = match remote {
+        Some(_) => config,
+        None => workspaces::with_output_dir(app, low_vram::clamp(app, config)),
+    };
+
+    let result = task
+        .run(async {
+            disk::ensure_space_for_generation(app, &config)?;
+            let result = if let Some(provider) = &remote {
+                job.run_remote(provider, &config).await?
+            } else if segments::is_long(&config) {
+                segments::generate(&job, &task, &config).await?
+            } else {
+                job.run_on_backend(|| backend::generate(&config)).await?
+            };
+            backend::settle_outputs(app, &config, result)
+        })
+        .await;
+    job.end(&task, &result);
+    result
+}
+
+// For a run of consecutive seeds (sweeps, segments) when none was given.
+pub fn random_seed() -> Result<u64, String> {
+    let mut bytes = [0u8; 4];
+    getrandom::fill(&mut bytes).map_err(|e| format!("Failed to pick a seed: {}", e))?;
+    Ok(u32::from_le_bytes(bytes) as u64)
+}
+
+// A seed sweep: the same config `count` times with consecutive seeds, from
+// its own seed or a random one, so any of them can be made again. They run
+// one after another in the background, share a batch id in the history, and
+// report as `batch-progress` after each one.
+#[tauri::command]
+pub fn enqueue_variations(app: AppHandle, config: Value, count: u32) -> Result<String, String> {
+    backend::validate_config(&config)?;
+    if !(1..=MAX_VARIATIONS).contains(&count) {
+        return Err(format!("A sweep is 1 to {} variations", MAX_VARIATIONS));
+    }
+    let base = match config.get("seed").and_then(|s| s.as_u64()) {
+        Some(seed) => seed,
+        None => random_seed()?,
+    };
+    let batch = format!("batch-{}", history::now_millis());
+    println!(
+        "[Jobs] Seed sweep {}: {} variations from seed {}",
+        batch, count, base
+    );
+
+    let mut progress = BatchProgress {
+        batch: batch.clone(),
+        total: count,
+        done: 0,
+        failed: 0,
+        outputs: Vec::new(),
+    };
+    tauri::async_runtime::spawn(async move {
+        let _ = app.emit("batch-progress", progress.clone());
+        for i in 0..count {
+            let mut config = config.clone();
+            config["seed"] = json!(base.wrapping_add(i as u64));
+            config["batch"] = json!(progress.batch);
+            match generate(&app, config).await {
+                Ok(result) => progress.outputs.extend(output_paths(&result)),
+                Err(e) => {
+                    eprintln!("[Jobs] Sweep {} variation {}: {}", progress.batch, i + 1, e);
+                    progress.failed += 1;
+                }
+            }
+            progress.done += 1;
+            let _ = app.emit("batch-progress", progress.clone());
+        }
+    });
+    Ok(batch)
+}
+
+#[tauri::command]
+pub fn queue_status(jobs: State<'_, JobManager>) -> QueueStatus {
+    jobs.status()
+}
@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+
+// "Play" / "Reveal" entries for the most recent outputs on the taskbar jump
+// list. Each entry relaunches us with a noises:// link, which the
+// single-instance plugin hands to the running app (see deep_link.rs).
+//
+// macOS has no equivalent here: the dock menu needs an app delegate method
+// that tao doesn't let us provide.
+pub fn update(recent: &[String]) {
+    let entries: Vec<(String, String)> = recent
+        .iter()
+        .flat_map(|path| {
+            let name = Path::new(path)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.clone());
+            let sound = utf8_percent_encode(path, NON_ALPHANUMERIC).to_string();
+            [
+                (
+                    format!("Play {}", name),
+                    format!("noises://play?sound={}", sound),
+                ),
+                (
+                    format!("Reveal {}", name),
+                    format!("noises://reveal?sound={}", sound),
+                ),
+            ]
+        })
+        .collect();
+
+    #[cfg(target_os = "windows")]
+    // COM wants a thread it can initialise on its own terms.
+    std::thread::spawn(move || {
+        if let Err(e) = windows_jump_list::write(&entries) {
+            eprintln!("[JumpList] Failed to update jump list: {}", e);
+        }
+    });
+    #[cfg(not(target_os = "windows"))]
+    let _ = entries;
+}
+
+#[cfg(target_os = "windows")]
+mod windows_jump_list {
+    use windows::Win32::Storage::EnhancedStorage::PKEY_Title;
+    use windows::Win32::System::Com::StructuredStorage::PROPVARIANT;
+    use windows::Win32::System::Com::{
+        CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED, CoCreateInstance, CoInitializeEx,
+        CoUninitialize,
+    };
+    use windows::Win32::UI::Shell::Common::{IObjectArray, IObjectCollection};
+    use windows::Win32::UI::Shell::PropertiesSystem::IPropertyStore;
+    use windows::Win32::UI::Shell::{
+        DestinationList, EnumerableObjectCollection, ICustomDestinationList, IShellLinkW, ShellLink,
+    };
+    use windows::core::{HSTRING, Interface};
+
+    const CATEGORY: &str = "Recent sounds";
+
+    // (title, arguments) pairs, shown in order.
+    pub fn write(entries: &[(String, String)]) -> Result<(), String> {
+        let exe =
+            std::env::current_exe().map_err(|e| format!("Failed to resolve executable: {}", e))?;
+        let exe = HSTRING::from(exe.as_os_str());
+
+        unsafe {
+            CoInitializeEx(None, COINIT_APARTMENTTHREADED)
+                .ok()
+                .map_err(|e| e.to_string())?;
+            let result = build(&exe, entries).map_err(|e| e.to_string());
+            CoUninitialize();
+            result
+        }
+    }
+
+    unsafe fn build(exe: &HSTRING, entries: &[(String, String)]) -> windows::core::Result<()> {
+        unsafe {
+            let list: ICustomDestinationList =
+                CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)?;
+            let mut max_slots = 0u32;
+            let _removed: IObjectArray = list.BeginList(&mut max_slots)?;
+
+            let items: IObjectCollection =
+                CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)?;
+            for (title, args) in entries.iter().take(max_slots as usize) {
+                let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+                link.SetPath(exe)?;
+                link.SetArguments(&HSTRING::from(args.as_str()))?;
+                link.SetIconLocation(exe, 0)?;
+                // Custom categories take their label from the link's title property.
+                let store: IPropertyStore = link.cast()?;
+                store.SetValue(&PKEY_Title, &PROPVARIANT::from(title.as_str()))?;
+                store.Commit()?;
+                items.AddObject(&link)?;
+            }
+
+            if entries.is_empty() {
+                return list.DeleteList(None);
+            }
+            let array: IObjectArray = items.cast()?;
+            list.AppendCategory(&HSTRING::from(CATEGORY), &array)?;
+            list.CommitList()
+        }
+    }
+}
@@ -0,0 +1,457 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::import;
+use crate::paths;
+use crate::workspaces;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SoundKind {
+    Loop,
+    Oneshot,
+    Import,
+    Recording,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LibraryItem {
+    pub file: String,
+    pub path: String,
+    pub kind: SoundKind,
+    pub size: u64,
+    // Unix time in milliseconds, so the frontend can hand it straight to Date.
+    pub modified: u64,
+    // See fingerprint(). Empty when the file couldn't be read.
+    pub hash: String,
+    pub tags: Vec<String>,
+    // For a sound that came as stems (stems.rs): its stems' paths. A stem
+    // has the path of the sound it belongs to instead.
+    pub stems: Vec<String>,
+    pub stem_of: Option<String>,
+}
+
+pub fn folders(app: &AppHandle) -> Vec<(SoundKind, PathBuf)> {
+    [
+        (SoundKind::Loop, paths::loops_dir(app)),
+        (SoundKind::Oneshot, paths::oneshots_dir(app)),
+        (SoundKind::Import, paths::imports_dir(app)),
+        (SoundKind::Recording, paths::recordings_dir(app)),
+    ]
+    .into_iter()
+    .filter_map(|(kind, dir)| dir.ok().map(|dir| (kind, dir)))
+    .collect()
+}
+
+#[derive(Clone, Serialize)]
+struct ScanProgress {
+    done: usize,
+    total: usize,
+}
+
+// What the last scan found, per folder and within it per file. A folder
+// whose mtime hasn't moved (adding, removing or renaming a file bumps it,
+// which covers everything the app itself does) and that the watcher hasn't
+// flagged is reused whole. Otherwise each file in it is statted and only
+// those whose size or mtime changed are probed and fingerprinted again.
+//
+// Kept in library_index.json in the app's data folder too, so the first scan
+// after a start doesn't fingerprint everything again; a scan that changed
+// anything writes it once at the end.
+#[derive(Default)]
+pub struct LibraryIndex {
+    // None until the first scan loads it.
+    folders: Mutex<Option<HashMap<PathBuf, IndexedFolder>>>,
+    // Folders the watcher saw something change in since the last scan.
+    dirty: Mutex<HashSet<PathBuf>>,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct IndexedFolder {
+    // None while the folder hasn't settled, so its files are statted again.
+    modified: Option<SystemTime>,
+    files: HashMap<PathBuf, LibraryItem>,
+}
+
+// A folder changed this recently may change again within the same mtime tick
+// (2 s on FAT drives), so it's scanned again next time instead of trusted.
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+// A burst of events (a sync, an import of hundreds) is one rescan: it waits
+// for a quiet moment, but not forever.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+const MAX_DEBOUNCE: Duration = Duration::from_secs(2);
+
+// Smaller libraries are done before a progress bar would even show.
+const PROGRESS_THRESHOLD: usize = 500;
+const PROGRESS_EVERY: usize = 250;
+
+// How much of each end of a file goes into its fingerprint.
+const FINGERPRINT_BYTES: u64 = 64 * 1024;
+
+// SHA-256 of the size and the first and last 64 KiB: enough to tell sounds
+// apart and notice an edit (headers and tails both change), without reading
+// an hour-long recording whole on every first scan.
+fn fingerprint(path: &Path, size: u64) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    hasher.update(size.to_le_bytes());
+    let mut buffer = Vec::with_capacity(2 * FINGERPRINT_BYTES as usize);
+    (&mut file)
+        .take(FINGERPRINT_BYTES)
+        .read_to_end(&mut buffer)
+        .ok()?;
+    if size > FINGERPRINT_BYTES {
+        let tail = size
+            .saturating_sub(FINGERPRINT_BYTES)
+            .max(FINGERPRINT_BYTES);
+        file.seek(SeekFrom::Start(tail)).ok()?;
+        file.take(FINGERPRINT_BYTES).read_to_end(&mut buffer).ok()?;
+    }
+    hasher.update(&buffer);
+    Some(
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+    )
+}
+
+fn index_path(app: &AppHandle) -> Option<PathBuf> {
+    let dir = app.path().app_data_dir().ok()?;
+    Some(dir.join("library_index.json"))
+}
+
+// Files may have been edited in place while the app was closed, with no
+// watcher to see it, so every loaded folder gets its files statted once.
+fn load_index(app: &AppHandle) -> HashMap<PathBuf, IndexedFolder> {
+    let mut folders: HashMap<PathBuf, IndexedFolder> = index_path(app)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    for folder in folders.values_mut() {
+        folder.modified = None;
+    }
+    folders
+}
+
+fn save_index(app: &AppHandle, folders: &HashMap<PathBuf, IndexedFolder>) {
+    let Some(path) = index_path(app) else {
+        return;
+    };
+    let result = serde_json::to_string(folders)
+        .map_err(|e| e.to_string())
+        .and_then(|json| paths::write_atomic(&path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        eprintln!("[Library] Failed to save the index: {}", e);
+    }
+}
+
+// Reuses `known` when the file's size and mtime still match it; otherwise
+// reads the file again. Tags and stems are filled in by scan.
+fn probe(path: PathBuf, kind: SoundKind, known: Option<LibraryItem>) -> Option<LibraryItem> {
+    let meta = std::fs::metadata(&path).ok()?;
+    if !meta.is_file() {
+        return None;
+    }
+    let size = meta.len();
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    if let Some(item) = known
+        && item.size == size
+        && item.modified == modified
+        && item.kind == kind
+    {
+        return Some(item);
+    }
+    Some(LibraryItem {
+        file: path.file_name()?.to_string_lossy().into_owned(),
+        path: path.to_string_lossy().into_owned(),
+        kind,
+        size,
+        modified,
+        hash: fingerprint(&path, size).unwrap_or_default(),
+        tags: Vec::new(),
+        stems: Vec::new(),
+        stem_of: None,
+    })
+}
+
+// Everything in the active workspace the app put there, newest first. Folders
+// that don't exist yet are just empty.
+//
+// Listing a folder is cheap; the per-file metadata calls and fingerprints
+// are what make a library of thousands slow (network drives especially), so
+// those go to rayon's pool. Large scans report `library-scan-progress`.
+pub fn scan(app: &AppHandle) -> Vec<LibraryItem> {
+    let index = app.try_state::<LibraryIndex>();
+    let (mut known, dirty) = match &index {
+        Some(index) => (
+            index
+                .folders
+                .lock()
+                .unwrap()
+                .get_or_insert_with(|| load_index(app))
+                .clone(),
+            std::mem::take(&mut *index.dirty.lock().unwrap()),
+        ),
+        None => Default::default(),
+    };
+    let now = SystemTime::now();
+    let mut items = Vec::new();
+    let mut stale = Vec::new();
+    let mut candidates = Vec::new();
+    for (kind, dir) in folders(app) {
+        let modified = std::fs::metadata(&dir).and_then(|m| m.modified()).ok();
+        let mut folder = known.remove(&dir).unwrap_or_default();
+        if modified.is_some() && folder.modified == modified && !dirty.contains(&dir) {
+            items.extend(folder.files.into_values());
+            continue;
+        }
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if import::is_audio_file(&path) {
+                let item = folder.files.remove(&path);
+                candidates.push((path, kind, item));
+            }
+        }
+        let settled =
+            modified.filter(|m| now.duration_since(*m).unwrap_or_default() >= SETTLE_TIME);
+        stale.push((dir, settled));
+    }
+
+    let total = candidates.len();
+    let done = AtomicUsize::new(0);
+    let report = |finished: usize| {
+        if total >= PROGRESS_THRESHOLD {
+            let _ = app.emit(
+                "library-scan-progress",
+                ScanProgress {
+                    done: finished,
+                    total,
+                },
+            );
+        }
+    };
+    let probed: Vec<LibraryItem> = candidates
+        .into_par_iter()
+        .filter_map(|(path, kind, item)| {
+            let item = probe(path, kind, item);
+            let finished = done.fetch_add(1, Ordering::Relaxed) + 1;
+            if finished.is_multiple_of(PROGRESS_EVERY) {
+                report(finished);
+            }
+            item
+        })
+        .collect();
+    report(total);
+
+    if let Some(index) = &index
+        && !stale.is_empty()
+    {
+        let mut folders = index.folders.lock().unwrap();
+        let folders = folders.get_or_insert_with(HashMap::new);
+        for (dir, modified) in stale {
+            let files = probed
+                .iter()
+                .filter(|item| Path::new(&item.path).parent() == Some(dir.as_path()))
+                .map(|item| (PathBuf::from(&item.path), item.clone()))
+                .collect();
+            folders.insert(dir, IndexedFolder { modified, files });
+        }
+        save_index(app, folders);
+    }
+    items.extend(probed);
+
+    // Tags and links live in the workspace, not the folder, so they're
+    // always filled in afresh.
+    let tags = workspaces::tags(app);
+    let stems = workspaces::stems(app);
+    let mut stem_of = HashMap::new();
+    for (sound, sound_stems) in &stems {
+        for stem in sound_stems {
+            stem_of.insert(stem.clone(), sound.to_string_lossy().into_owned());
+        }
+    }
+    for item in &mut items {
+        let path = PathBuf::from(&item.path);
+        item.tags = tags.get(&path).cloned().unwrap_or_default();
+        item.stems = stems
+            .get(&path)
+            .map(|s| s.iter().map(|s| s.to_string_lossy().into_owned()).collect())
+            .unwrap_or_default();
+        item.stem_of = stem_of.get(&path).cloned();
+    }
+
+    items.sort_by_key(|item| std::cmp::Reverse(item.modified));
+    items
+}
+
+// Watches the active workspace's folders, so a file another program adds or
+// edits in place marks its folder for the next scan, and tells the frontend
+// to list again with `library-changed`. Called from setup and after a
+// workspace switch, since the folders move with the workspace.
+pub fn watch(app: &AppHandle) {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(sender) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("[Library] Failed to start the watcher: {}", e);
+            return;
+        }
+    };
+    for (_, dir) in folders(app) {
+        let result = std::fs::create_dir_all(&dir)
+            .map_err(|e| e.to_string())
+            .and_then(|_| {
+                watcher
+                    .watch(&dir, RecursiveMode::NonRecursive)
+                    .map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            eprintln!("[Library] Failed to watch {}: {}", dir.display(), e);
+        }
+    }
+    // Dropping the previous watcher closes its channel, which ends its thread.
+    *app.state::<LibraryIndex>().watcher.lock().unwrap() = Some(watcher);
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        while let Ok(first) = receiver.recv() {
+            let deadline = Instant::now() + MAX_DEBOUNCE;
+            let mut events = vec![first];
+            while Instant::now() < deadline
+                && let Ok(event) = receiver.recv_timeout(DEBOUNCE)
+            {
+                events.push(event);
+            }
+            // Reads (playback, fingerprinting) don't change anything.
+            let dirs: HashSet<PathBuf> = events
+                .into_iter()
+                .flatten()
+                .filter(|event| !matches!(event.kind, EventKind::Access(_)))
+                .flat_map(|event| event.paths)
+                .filter_map(|path| path.parent().map(Path::to_path_buf))
+                .collect();
+            if dirs.is_empty() {
+                continue;
+            }
+            app.state::<LibraryIndex>()
+                .dirty
+                .lock()
+                .unwrap()
+                .extend(dirs);
+            let _ = app.emit("library-changed", ());
+        }
+    });
+}
+
+// Full rescan, in case the watcher missed something (network drives don't
+// always report changes): every file is statted again, but only the ones
+// that changed are read.
+#[tauri::command]
+pub async fn refresh_library(
+    app: AppHandle,
+    index: tauri::State<'_, LibraryIndex>,
+) -> Result<Vec<LibraryItem>, String> {
+    index
+        .dirty
+        .lock()
+        .unwrap()
+        .extend(folders(&app).into_iter().map(|(_, dir)| dir));
+    list_library(app).await
+}
+
+#[tauri::command]
+pub async fn list_library(app: AppHandle) -> Result<Vec<LibraryItem>, String> {
+    tauri::async_runtime::spawn_blocking(move || scan(&app))
+        .await
+        .map_err(|e| format!("Library scan failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint_of(name: &str, contents: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("noises-fingerprint-{}", name));
+        std::fs::write(&path, contents).unwrap();
+        let hash = fingerprint(&path, contents.len() as u64).unwrap();
+        let _ = std::fs::remove_file(&path);
+        hash
+    }
+
+    #[test]
+    fn same_contents_same_fingerprint() {
+        let long = vec![7u8; 300 * 1024];
+        assert_eq!(fingerprint_of("a", &long), fingerprint_of("b", &long));
+        assert_eq!(fingerprint_of("c", b"short"), fingerprint_of("d", b"short"));
+    }
+
+    #[test]
+    fn edits_at_either_end_change_it() {
+        let long = vec![7u8; 300 * 1024];
+        let mut head = long.clone();
+        head[10] = 8;
+        let mut tail = long.clone();
+        *tail.last_mut().unwrap() = 8;
+        let original = fingerprint_of("e", &long);
+        assert_ne!(original, fingerprint_of("f", &head));
+        assert_ne!(original, fingerprint_of("g", &tail));
+    }
+
+    #[test]
+    fn files_between_one_and_two_samples_are_read_whole() {
+        let mut middle = vec![7u8; 100 * 1024];
+        let original = fingerprint_of("h", &middle);
+        middle[70 * 1024] = 8;
+        assert_ne!(original, fingerprint_of("i", &middle));
+    }
+
+    fn item(path: &Path, kind: SoundKind) -> LibraryItem {
+        let mut item = probe(path.to_path_buf(), kind, None).unwrap();
+        item.hash = "kept".to_string();
+        item
+    }
+
+    #[test]
+    fn unchanged_files_keep_their_entry() {
+        let path = std::env::temp_dir().join("noises-probe-unchanged.wav");
+        std::fs::write(&path, b"sound").unwrap();
+        let known = item(&path, SoundKind::Loop);
+        let probed = probe(path.clone(), SoundKind::Loop, Some(known)).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(probed.hash, "kept");
+    }
+
+    #[test]
+    fn a_changed_size_fingerprints_again() {
+        let path = std::env::temp_dir().join("noises-probe-changed.wav");
+        std::fs::write(&path, b"sound").unwrap();
+        let known = item(&path, SoundKind::Loop);
+        std::fs::write(&path, b"a longer sound").unwrap();
+        let probed = probe(path.clone(), SoundKind::Loop, Some(known)).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_ne!(probed.hash, "kept");
+        assert_eq!(probed.size, 14);
+    }
+}
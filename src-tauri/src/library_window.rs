@@ -0,0 +1,47 @@
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, Window, WindowEvent};
+
+pub const LABEL: &str = "library";
+
+// The library in its own full-size window, e.g. on a second monitor next to
+// the generation form. Managed state and app-wide events are shared by every
+// window, so it sees the same jobs, playback and results as the main one.
+// main.jsx renders the library view for `?view=library`.
+pub fn open(app: &AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(LABEL) {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+        return Ok(());
+    }
+
+    WebviewWindowBuilder::new(
+        app,
+        LABEL,
+        WebviewUrl::App("index.html?view=library".into()),
+    )
+    .title("Noises Library")
+    .inner_size(900.0, 640.0)
+    .min_inner_size(480.0, 360.0)
+    .decorations(false)
+    .build()
+    .map_err(|e| format!("Failed to open library window: {}", e))?;
+    println!("[Library] Window opened");
+    Ok(())
+}
+
+// Async for the same reason as open_mini_player.
+#[tauri::command]
+pub async fn open_library_window(app: AppHandle) -> Result<(), String> {
+    open(&app)
+}
+
+// Hooked up in main.rs via on_window_event. Closing the main window quits
+// rather than leaving a lone library window behind.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    if let WindowEvent::Destroyed = event
+        && window.label() == "main"
+        && let Some(library) = window.app_handle().get_webview_window(LABEL)
+    {
+        let _ = library.close();
+    }
+}
@@ -0,0 +1,266 @@
+use std::path::Path;
+
+use tauri::AppHandle;
+
+use crate::history;
+use crate::paths;
+
+// Tempo and key for a loop, written into the WAV as an ACID chunk (what
+// Ableton, FL Studio, Reaper and Acid itself read to warp a loop to the
+// project tempo instead of treating it as raw audio) and a smpl chunk (loop
+// points, read by samplers and Logic). DAWs only look at the file itself, so
+// there's no special drag or clipboard format to offer on top.
+pub struct LoopInfo {
+    pub bpm: f64,
+    // e.g. "C minor", "F# major"; only the root note ends up in the file.
+    pub key: Option<String>,
+}
+
+// From the generation that made the file, or failing that its name, which
+// the backend builds as loop_120bpm_C_minor_001.wav. Not a loop (no tempo)
+// is None.
+pub fn lookup(app: &AppHandle, path: &Path) -> Option<LoopInfo> {
+    let path_str = path.to_string_lossy();
+    if let Some(entry) = history::find_output(app, &path_str)
+        && let Some(bpm) = entry.params.get("bpm").and_then(|b| b.as_f64())
+        && bpm > 0.0
+    {
+        let key = entry
+            .params
+            .get("key")
+            .and_then(|k| k.as_str())
+            .map(str::to_string);
+        return Some(LoopInfo { bpm, key });
+    }
+
+    from_file_name(&path.file_stem()?.to_string_lossy())
+}
+
+fn from_file_name(stem: &str) -> Option<LoopInfo> {
+    let parts: Vec<&str> = stem.split('_').collect();
+    let at = parts.iter().position(|p| p.ends_with("bpm"))?;
+    let bpm: f64 = parts[at].trim_end_matches("bpm").parse().ok()?;
+    let rest = &parts[at + 1..];
+    // Everything after the tempo up to the counter, if there is one; "Key"
+    // means none given.
+    let rest = match rest.split_last() {
+        Some((last, key)) if last.bytes().all(|b| b.is_ascii_digit()) => key,
+        _ => rest,
+    };
+    let key = rest.join(" ");
+    Some(LoopInfo {
+        bpm,
+        key: (!key.is_empty() && key != "Key").then_some(key),
+    })
+}
+
+// MIDI note of the key's root in the octave ACID uses (C = 60).
+fn root_note(key: &str) -> Option<u16> {
+    let mut chars = key.trim().chars();
+    let base = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let shift = match chars.next() {
+        Some('#' | '♯') => 1,
+        Some('b' | '♭') => -1,
+        _ => 0,
+    };
+    Some((60 + (base + shift + 12) % 12) as u16)
+}
+
+struct Chunk<'a> {
+    id: [u8; 4],
+    data: &'a [u8],
+}
+
+fn chunks(riff: &[u8]) -> Result<Vec<Chunk<'_>>, String> {
+    if riff.len() < 12 || &riff[..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+        return Err("Not a WAV file".to_string());
+    }
+    let mut found = Vec::new();
+    let mut pos = 12;
+    while pos + 8 <= riff.len() {
+        let id: [u8; 4] = riff[pos..pos + 4].try_into().unwrap_or_default();
+        let size = u32::from_le_bytes(riff[pos + 4..pos + 8].try_into().unwrap_or_default());
+        let start = pos + 8;
+        // Writers that crashed mid-way leave a data size past the end.
+        let end = start.saturating_add(size as usize).min(riff.len());
+        found.push(Chunk {
+            id,
+            data: &riff[start..end],
+        });
+        // Chunks are padded to an even length.
+        pos = end + (size as usize & 1);
+    }
+    Ok(found)
+}
+
+// Adds (or refreshes) the ACID and smpl chunks in place. Returns whether the
+// file changed, so an already tagged loop isn't rewritten on every drag.
+pub fn tag(path: &Path, info: &LoopInfo) -> Result<bool, String> {
+    let bytes =
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let chunks = chunks(&bytes)?;
+    let fmt = chunks
+        .iter()
+        .find(|c| &c.id == b"fmt ")
+        .filter(|c| c.data.len() >= 16)
+        .ok_or("WAV file has no format chunk")?;
+    let sample_rate = u32::from_le_bytes(fmt.data[4..8].try_into().unwrap_or_default());
+    let block_align = u16::from_le_bytes(fmt.data[12..14].try_into().unwrap_or_default());
+    let data = chunks
+        .iter()
+        .find(|c| &c.id == b"data")
+        .ok_or("WAV file has no audio data")?;
+    if sample_rate == 0 || block_align == 0 {
+        return Err("WAV file has an invalid format chunk".to_string());
+    }
+    let frames = (data.data.len() / block_align as usize) as u32;
+    let seconds = frames as f64 / sample_rate as f64;
+    let beats = ((seconds * info.bpm / 60.0).round() as u32).max(1);
+    let root = info.key.as_deref().and_then(root_note);
+
+    let mut acid = Vec::with_capacity(24);
+    // Stretch on, plus "root note set" when there is one.
+    let flags: u32 = 0x04 | if root.is_some() { 0x02 } else { 0 };
+    acid.extend(flags.to_le_bytes());
+    acid.extend(root.unwrap_or(60).to_le_bytes());
+    acid.extend(0x8000u16.to_le_bytes());
+    acid.extend(0f32.to_le_bytes());
+    acid.extend(beats.to_le_bytes());
+    // 4/4: denominator, then numerator.
+    acid.extend(4u16.to_le_bytes());
+    acid.extend(4u16.to_le_bytes());
+    acid.extend((info.bpm as f32).to_le_bytes());
+
+    let mut smpl = Vec::with_capacity(60);
+    for value in [
+        0,                           // manufacturer
+        0,                           // product
+        1_000_000_000 / sample_rate, // sample period in ns
+        root.unwrap_or(60) as u32,   // unity note
+        0,                           // pitch fraction
+        0,                           // SMPTE format
+        0,                           // SMPTE offset
+        1,                           // loops
+        0,                           // sampler data
+        0,                           // loop id
+        0,                           // forward loop
+        0,                           // start frame
+        frames.saturating_sub(1),    // end frame (inclusive)
+        0,                           // fraction
+        0,                           // play forever
+    ] {
+        smpl.extend(value.to_le_bytes());
+    }
+
+    let existing = |id: &[u8; 4]| chunks.iter().find(|c| &c.id == id).map(|c| c.data);
+    if existing(b"acid") == Some(&acid[..]) && existing(b"smpl") == Some(&smpl[..]) {
+        return Ok(false);
+    }
+
+    let mut out = Vec::with_capacity(bytes.len() + 100);
+    out.extend(b"RIFF\0\0\0\0WAVE");
+    let new_chunks = [(*b"acid", &acid[..]), (*b"smpl", &smpl[..])];
+    let kept = chunks
+        .iter()
+        .filter(|c| &c.id != b"acid" && &c.id != b"smpl")
+        .map(|c| (c.id, c.data));
+    for (id, data) in kept.chain(new_chunks) {
+        out.extend(id);
+        out.extend((data.len() as u32).to_le_bytes());
+        out.extend(data);
+        if data.len() % 2 == 1 {
+            out.push(0);
+        }
+    }
+    let riff_size = (out.len() - 8) as u32;
+    out[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    // Atomic, so a failed write can't cost the loop.
+    paths::write_atomic(path, &out)
+        .map_err(|e| format!("Failed to update {}: {}", path.display(), e))?;
+    Ok(true)
+}
+
+// Called before a drag out. Anything that isn't a WAV loop is left alone.
+pub fn tag_for_daw(app: &AppHandle, path: &Path) {
+    let is_wav = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("wav"));
+    if !is_wav {
+        return;
+    }
+    let Some(info) = lookup(app, path) else {
+        return;
+    };
+    match tag(path, &info) {
+        Ok(true) => println!(
+            "[LoopMetadata] Tagged {} at {} bpm",
+            path.display(),
+            info.bpm
+        ),
+        Ok(false) => {}
+        Err(e) => eprintln!("[LoopMetadata] {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(stem: &str) -> Option<(f64, Option<String>)> {
+        from_file_name(stem).map(|info| (info.bpm, info.key))
+    }
+
+    #[test]
+    fn reads_the_backends_names() {
+        assert_eq!(
+            parsed("loop_120bpm_C_minor_001"),
+            Some((120.0, Some("C minor".to_string())))
+        );
+        assert_eq!(
+            parsed("loop_95.5bpm_F#_major_012"),
+            Some((95.5, Some("F# major".to_string())))
+        );
+        assert_eq!(parsed("loop_140bpm_Key_003"), Some((140.0, None)));
+    }
+
+    #[test]
+    fn tempo_last_has_no_key() {
+        assert_eq!(parsed("loop_120bpm"), Some((120.0, None)));
+        assert_eq!(parsed("120bpm"), Some((120.0, None)));
+    }
+
+    #[test]
+    fn works_without_a_counter() {
+        assert_eq!(parsed("loop_120bpm_001"), Some((120.0, None)));
+        assert_eq!(
+            parsed("loop_120bpm_A_minor"),
+            Some((120.0, Some("A minor".to_string())))
+        );
+    }
+
+    #[test]
+    fn no_tempo_is_not_a_loop() {
+        assert_eq!(parsed("song_001"), None);
+        assert_eq!(parsed("loop_fastbpm_001"), None);
+        assert_eq!(parsed(""), None);
+    }
+
+    #[test]
+    fn root_notes() {
+        assert_eq!(root_note("C minor"), Some(60));
+        assert_eq!(root_note("F# major"), Some(66));
+        assert_eq!(root_note("Bb"), Some(70));
+        assert_eq!(root_note("Cb"), Some(71));
+        assert_eq!(root_note("H"), None);
+    }
+}
@@ -0,0 +1,164 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter};
+
+use crate::settings;
+
+// What fits a 6 GB card with the backend's offloading on. Loops go to Stable
+// Audio, everything else to ACE-Step (same split as backend/main.py).
+struct Limits {
+    length: f64,
+    steps: u64,
+    variations: u64,
+}
+
+const LOOP_LIMITS: Limits = Limits {
+    length: 10.0,
+    steps: 100,
+    variations: 1,
+};
+
+const SONG_LIMITS: Limits = Limits {
+    length: 60.0,
+    steps: 60,
+    variations: 1,
+};
+
+#[derive(Clone, Serialize)]
+struct Adjustment {
+    field: &'static str,
+    requested: Value,
+    used: Value,
+}
+
+#[derive(Clone, Serialize)]
+struct RequestAdjusted {
+    prompt: String,
+    adjustments: Vec<Adjustment>,
+}
+
+// The step count the backend uses when a request doesn't say (main.py),
+// more than either model fits in low-VRAM mode.
+const BACKEND_DEFAULT_STEPS: u64 = 200;
+
+// Caps the request's fields in place and returns what the user asked for
+// that changed. A missing step count gets the cap put in, since the
+// backend's default is over it; missing length and variations default to
+// within the limits already.
+fn cap(fields: &mut Map<String, Value>) -> Vec<Adjustment> {
+    let is_loop = fields
+        .get("type")
+        .and_then(|t| t.as_str())
+        .is_some_and(|t| t.contains("loop"));
+    let limits = if is_loop { &LOOP_LIMITS } else { &SONG_LIMITS };
+
+    let mut adjustments = Vec::new();
+    if let Some(length) = fields.get("length").and_then(|v| v.as_f64())
+        && length > limits.length
+    {
+        adjustments.push(Adjustment {
+            field: "length",
+            requested: length.into(),
+            used: limits.length.into(),
+        });
+    }
+    for (field, limit) in [("steps", limits.steps), ("variations", limits.variations)] {
+        if let Some(value) = fields.get(field).and_then(|v| v.as_u64())
+            && value > limit
+        {
+            adjustments.push(Adjustment {
+                field,
+                requested: value.into(),
+                used: limit.into(),
+            });
+        }
+    }
+    for adjustment in &adjustments {
+        fields.insert(adjustment.field.to_string(), adjustment.used.clone());
+    }
+    if !fields.get("steps").is_some_and(|v| v.is_u64()) && BACKEND_DEFAULT_STEPS > limits.steps {
+        fields.insert("steps".to_string(), limits.steps.into());
+    }
+    adjustments
+}
+
+// With low-VRAM mode on, caps length, steps and batch size before the request
+// goes to the backend and says what changed with `request-adjusted`, so the
+// UI can tell the user why they got a shorter sound.
+pub fn clamp(app: &AppHandle, mut config: Value) -> Value {
+    if !settings::get(app).low_vram {
+        return config;
+    }
+    let Some(fields) = config.as_object_mut() else {
+        return config;
+    };
+    let adjustments = cap(fields);
+    if adjustments.is_empty() {
+        return config;
+    }
+
+    let prompt = fields
+        .get("prompt")
+        .and_then(|p| p.as_str())
+        .unwrap_or_default()
+        .to_string();
+    println!(
+        "[LowVram] Adjusted {}",
+        adjustments
+            .iter()
+            .map(|a| format!("{} {} -> {}", a.field, a.requested, a.used))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let _ = app.emit(
+        "request-adjusted",
+        RequestAdjusted {
+            prompt,
+            adjustments,
+        },
+    );
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn capped(request: Value) -> (Value, Vec<&'static str>) {
+        let mut request = request;
+        let adjustments = cap(request.as_object_mut().unwrap());
+        (request, adjustments.iter().map(|a| a.field).collect())
+    }
+
+    #[test]
+    fn over_the_limits_is_capped_and_reported() {
+        let (request, changed) = capped(json!({
+            "type": "loop", "length": 30.0, "steps": 250, "variations": 4
+        }));
+        assert_eq!(request["length"], json!(10.0));
+        assert_eq!(request["steps"], json!(100));
+        assert_eq!(request["variations"], json!(1));
+        assert_eq!(changed, ["length", "steps", "variations"]);
+    }
+
+    #[test]
+    fn within_the_limits_is_left_alone() {
+        let (request, changed) = capped(json!({
+            "type": "song", "length": 45.0, "steps": 40, "variations": 1
+        }));
+        assert_eq!(request["length"], json!(45.0));
+        assert_eq!(request["steps"], json!(40));
+        assert!(changed.is_empty());
+    }
+
+    #[test]
+    fn missing_steps_get_the_cap_instead_of_the_backend_default() {
+        let (request, changed) = capped(json!({ "type": "loop" }));
+        assert_eq!(request["steps"], json!(100));
+        assert!(changed.is_empty());
+
+        let (request, _) = capped(json!({ "type": "song" }));
+        assert_eq!(request["steps"], json!(60));
+    }
+}
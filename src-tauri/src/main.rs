@@ -3,179 +3,367 @@
     windows_subsystem = "windows"
 )]
 
-use std::process::{Child, Stdio};
-use std::sync::{Arc, Mutex};
-use tauri::RunEvent;
+mod autostart;
+mod backend;
+mod child_guard;
+mod cli;
+mod clipboard;
+mod close_guard;
+mod compare;
+mod crash;
+mod deep_link;
+mod discord;
+mod disk;
+mod downloads;
+mod drag_out;
+mod editor;
+mod error;
+mod file_lock;
+mod filenames;
+mod freesound;
+mod game_engines;
+mod gpu;
+mod grpc;
+mod history;
+mod hooks;
+mod hotkeys;
+mod http_api;
+mod import;
+mod instance;
+mod jobs;
+mod jumplist;
+mod library;
+mod library_window;
+mod loop_metadata;
+mod low_vram;
+mod mcp;
+mod meter;
+mod middleware;
+mod midi;
+mod mini_player;
+mod models;
+mod notifications;
+mod osc;
+mod paths;
+mod playback;
+mod plugins;
+mod power;
+mod profile;
+mod protocol;
+mod providers;
+mod proxy;
+mod recording;
+mod render_target;
+mod samplers;
+mod sandbox;
+mod secrets;
+mod segments;
+mod settings;
+mod share;
+mod sidecar;
+mod sound_pack;
+mod stems;
+mod streaming;
+mod suggest;
+mod sync;
+mod taskbar;
+mod tasks;
+mod telemetry;
+mod templates;
+mod theme;
+mod thermal;
+mod trash;
+mod tray;
+mod updater;
+mod watch_folder;
+mod waveform;
+mod window_state;
+mod workspaces;
+mod ws_bridge;
 
-#[cfg(not(debug_assertions))]
-use tauri_plugin_shell::ShellExt;
+use tauri::{Manager, RunEvent};
 
-use tauri_plugin_shell::process::CommandChild;
-
-const BACKEND_URL: &str = "http://127.0.0.1:8000";
-
-// Structure to hold the child process handle.
-// We need to support both std::process::Child (for dev) and CommandChild (for prod/sidecar)
-// this should give us tools to kill the process on exit.
-struct BackendState {
-    dev_process: Option<Child>,
-    prod_process: Option<CommandChild>,
-}
+use error::AppError;
 
 #[tauri::command]
-async fn check_backend_health() -> Result<bool, String> {
-    match reqwest::get(format!("{}/health", BACKEND_URL)).await {
-        Ok(res) => Ok(res.status().is_success()),
-        Err(_) => Ok(false),
-    }
+async fn check_backend_health(
+    app: tauri::AppHandle,
+    health: tauri::State<'_, backend::HealthCache>,
+) -> Result<bool, String> {
+    Ok(health.check(&app).await)
 }
 
 #[tauri::command]
-async fn generate_audio(config: serde_json::Value) -> Result<serde_json::Value, String> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(600)) // 10 min for GPU inference
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-
-    let res = client
-        .post(format!("{}/generate", BACKEND_URL))
-        .json(&config)
-        .send()
-        .await
-        .map_err(|e| format!("Backend request failed: {}", e))?;
-
-    if !res.status().is_success() {
-        let status = res.status();
-        let body = res.text().await.unwrap_or_default();
-        // Try to extract FastAPI's "detail" field
-        if let Ok(err_json) = serde_json::from_str::<serde_json::Value>(&body) {
-            if let Some(detail) = err_json.get("detail").and_then(|d| d.as_str()) {
-                return Err(detail.to_string());
-            }
-        }
-        return Err(format!("Backend error ({}): {}", status, body));
-    }
-
-    res.json::<serde_json::Value>()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))
+async fn generate_audio(
+    app: tauri::AppHandle,
+    config: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    jobs::generate(&app, config).await
 }
 
+// Errors go back to the frontend instead of panicking when the path is gone
+// or there's no file manager to launch.
 #[tauri::command]
-fn show_in_folder(path: String) {
-  #[cfg(target_os = "windows")]
-  {
-    std::process::Command::new("explorer")
-      .args(["/select,", &path])
-      .spawn()
-      .unwrap();
+fn show_in_folder(path: String) -> Result<(), AppError> {
+  let path = &paths::normalize(&path);
+  if !path.exists() {
+    return Err(AppError::NotFound(path.clone()));
   }
+
+  #[cfg(target_os = "windows")]
+  let mut cmd = {
+    use std::os::windows::process::CommandExt;
+    // Explorer parses its own command line and wants the path glued to the
+    // switch, quoted as a whole; std's quoting splits it at spaces.
+    let mut cmd = std::process::Command::new("explorer");
+    cmd.raw_arg(format!("/select,\"{}\"", path.display()));
+    cmd
+  };
   #[cfg(target_os = "macos")]
-  {
-    std::process::Command::new("open")
-      .args(["-R", &path])
-      .spawn()
-      .unwrap();
-  }
+  let mut cmd = {
+    let mut cmd = std::process::Command::new("open");
+    cmd.arg("-R").arg(path);
+    cmd
+  };
+  // xdg-open can't select a file, so open the folder it's in.
   #[cfg(target_os = "linux")]
-  {
-    std::process::Command::new("xdg-open")
-      .arg(&path)
-      .spawn()
-      .unwrap();
-  }
+  let mut cmd = {
+    let folder = if path.is_dir() { path } else { path.parent().unwrap_or(path) };
+    let mut cmd = std::process::Command::new("xdg-open");
+    cmd.arg(folder);
+    cmd
+  };
+
+  let mut child = cmd
+    .spawn()
+    .map_err(|error| AppError::Launch { program: "file manager", error })?;
+  // Reaped in the background; the file manager may well outlive the call.
+  std::thread::spawn(move || {
+    let _ = child.wait();
+  });
+  Ok(())
 }
 
+// Into the trash's holding folder, so it can be undone. Async so the retries
+// while another program holds the file don't block the main thread.
 #[tauri::command]
-fn delete_file(path: String) -> Result<(), String> {
-  std::fs::remove_file(&path)
-    .map_err(|e| format!("Failed to delete file: {}", e))?;
-  Ok(())
+async fn delete_file(app: tauri::AppHandle, path: String) -> Result<(), String> {
+  let path = paths::normalize(&path);
+  trash::delete(&app, &path).await
 }
 
+// The exit handler is the baseline's single-arm match.
+#[allow(clippy::single_match)]
 fn main() {
-    let backend_state = Arc::new(Mutex::new(BackendState {
-        dev_process: None,
-        prod_process: None,
-    }));
-
-    let state_clone = backend_state.clone();
+    let context = tauri::generate_context!();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(command) = cli::parse(&args) {
+        std::process::exit(cli::run(command, context));
+    }
 
     tauri::Builder::default()
+        // Must be registered first so a second launch exits before doing anything else.
+        .plugin(tauri_plugin_single_instance::init(instance::on_second_instance))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_drag::init())
-        .invoke_handler(tauri::generate_handler![show_in_folder, delete_file, check_backend_health, generate_audio])
-        .setup(move |app| {
-            #[cfg(debug_assertions)]
-            let _ = app; // Suppress unused warning in debug mode
-            
-            let pid = std::process::id();
-
-            #[cfg(debug_assertions)]
-            {
-                // Use the venv Python for dev mode
-                let venv_python = std::path::Path::new("../.venv312/Scripts/python.exe");
-                let python_cmd = if venv_python.exists() {
-                    venv_python.to_str().unwrap().to_string()
-                } else {
-                    "python".to_string()
-                };
-                
-                let mut cmd = std::process::Command::new(&python_cmd);
-                cmd.arg("../backend/main.py");
-                cmd.arg("--parent-pid");
-                cmd.arg(pid.to_string());
-                cmd.stdin(Stdio::piped());
-                
-                match cmd.spawn() {
-                    Ok(child) => {
-                        println!("[Tauri] Backend (DEV) started with PID: {}", child.id());
-                        let mut state = state_clone.lock().unwrap();
-                        state.dev_process = Some(child);
-                    }
-                    Err(e) => eprintln!("[Tauri] Failed to spawn dev backend: {}", e),
-                }
-            }
-
-            #[cfg(not(debug_assertions))]
-            {
-                let sidecar_command = app.shell().sidecar("backend").unwrap()
-                    .args(["--parent-pid", &pid.to_string()]);
-                
-                match sidecar_command.spawn() {
-                    Ok((_rx, child)) => {
-                        println!("[Tauri] Backend (PROD) started with PID: {}", child.pid());
-                        let mut state = state_clone.lock().unwrap();
-                        state.prod_process = Some(child);
-                        
-                        // Optional: listen to backend logs via _rx here if needed
-                    }
-                    Err(e) => eprintln!("[Tauri] Failed to spawn sidecar: {}", e),
-                };
-            }
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(autostart::plugin())
+        .plugin(updater::plugin())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(hotkeys::on_shortcut)
+                .build(),
+        )
+        .register_asynchronous_uri_scheme_protocol("noises", |ctx, request, responder| {
+            // Nothing is read on the webview's thread.
+            let app = ctx.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                responder.respond(protocol::respond(app, request).await);
+            });
+        })
+        .on_window_event(|window, event| {
+            window_state::on_window_event(window, event);
+            mini_player::on_window_event(window, event);
+            library_window::on_window_event(window, event);
+            theme::on_window_event(window, event);
+            close_guard::on_window_event(window, event);
+            tray::on_window_event(window, event);
+        })
+        .invoke_handler(tauri::generate_handler![
+            show_in_folder,
+            delete_file,
+            trash::restore_deleted,
+            check_backend_health,
+            clipboard::read_clipboard,
+            close_guard::resolve_close_request,
+            compare::compare_generations,
+            crash::take_crash_report,
+            generate_audio,
+            autostart::get_autostart,
+            autostart::set_autostart,
+            streaming::generate_audio_streaming,
+            theme::get_system_theme,
+            backend::upload_reference_audio,
+            deep_link::take_pending_deep_links,
+            downloads::start_download,
+            downloads::pause_download,
+            downloads::resume_download,
+            downloads::cancel_download,
+            downloads::list_downloads,
+            drag_out::start_drag_out,
+            editor::open_in_editor,
+            filenames::rename_sound,
+            history::list_history,
+            history::export_history,
+            history::rate_generation,
+            history::get_lineage,
+            hotkeys::get_hotkeys,
+            hotkeys::set_hotkey,
+            http_api::reset_api_token,
+            import::import_audio_files,
+            import::import_from_url,
+            jobs::queue_status,
+            jobs::enqueue_variations,
+            library::list_library,
+            library::refresh_library,
+            library_window::open_library_window,
+            midi::list_midi_inputs,
+            midi::set_midi_mapping,
+            mini_player::open_mini_player,
+            mini_player::close_mini_player,
+            mini_player::set_mini_player_on_top,
+            models::list_models,
+            models::download_model,
+            models::delete_model,
+            models::set_default_model,
+            models::get_model_disk_usage,
+            models::reveal_model_cache,
+            models::list_cached_checkpoints,
+            models::purge_unused_models,
+            plugins::list_plugins,
+            plugins::run_processor,
+            plugins::run_plugin_command,
+            playback::play_audio,
+            playback::preload_audio,
+            playback::pause_audio,
+            playback::resume_audio,
+            playback::toggle_audio,
+            playback::stop_audio,
+            playback::set_playback_volume,
+            playback::set_cue_device,
+            recording::list_input_devices,
+            recording::list_output_devices,
+            recording::start_recording,
+            recording::start_loopback_capture,
+            recording::stop_recording,
+            profile::export_profile,
+            profile::import_profile,
+            secrets::get_secret,
+            secrets::set_secret,
+            settings::get_setting,
+            settings::set_setting,
+            settings::get_all_settings,
+            share::share_file,
+            discord::share_to_discord,
+            freesound::freesound_status,
+            freesound::connect_freesound,
+            freesound::disconnect_freesound,
+            freesound::upload_to_freesound,
+            sidecar::backend_stage,
+            sidecar::cpu_fallback_active,
+            middleware::export_to_middleware,
+            samplers::export_for_sampler,
+            sound_pack::export_sound_pack,
+            sound_pack::import_sound_pack,
+            stems::export_stems,
+            suggest::list_prompt_categories,
+            suggest::suggest_prompt,
+            sync::sync_now,
+            tasks::cancel_task,
+            tasks::list_tasks,
+            templates::list_templates,
+            templates::save_template,
+            templates::delete_template,
+            templates::expand_template,
+            templates::expand_template_matrix,
+            tray::set_close_to_tray,
+            updater::check_for_updates,
+            updater::install_update,
+            waveform::set_visible_sounds,
+            workspaces::list_workspaces,
+            workspaces::create_workspace,
+            workspaces::switch_workspace,
+            workspaces::get_workspace_presets,
+            workspaces::save_workspace_presets,
+            workspaces::set_sound_tags,
+            ws_bridge::ws_connect,
+            ws_bridge::ws_send,
+            ws_bridge::ws_close
+        ])
+        .setup(|app| {
+            crash::install(app.handle());
+            app.manage(crash::load_previous(app.handle()));
+            app.manage(settings::SettingsStore::load(app.handle()));
+            app.manage(workspaces::WorkspaceStore::load(app.handle()));
+            let power = power::PowerGuard::new();
+            app.manage(power.clone());
+            app.manage(playback::PlaybackEngine::new(app.handle().clone(), power));
+            app.manage(recording::Recorder::default());
+            app.manage(tasks::TaskRegistry::default());
+            app.manage(downloads::DownloadManager::default());
+            app.manage(models::ModelManager::default());
+            app.manage(ws_bridge::WsBridge::default());
+            app.manage(jobs::JobManager::new(app.handle()));
+            app.manage(library::LibraryIndex::default());
+            library::watch(app.handle());
+            app.manage(waveform::WaveformWorkers::default());
+            app.manage(theme::ThemeState::default());
+            app.manage(close_guard::CloseGuard::default());
+            tray::create(app.handle())?;
+            window_state::restore(app.handle());
+            autostart::apply_launch_flags(app.handle());
+            jumplist::update(&app.state::<jobs::JobManager>().status().recent);
+            notifications::init(app.handle());
+            app.manage(hotkeys::HotkeyStore::load(app.handle()));
+            hotkeys::register_all(app.handle());
+            app.manage(deep_link::DeepLinkState::default());
+            deep_link::init(app.handle());
+            instance::open_launch_files(app.handle());
+            streaming::clear_partials(app.handle());
+            app.manage(trash::Trash::default());
+            trash::clear(app.handle());
+            app.manage(sidecar::Sidecar::default());
+            app.manage(backend::HealthCache::default());
+            sidecar::init(app.handle());
+            app.manage(updater::UpdaterState::default());
+            app.manage(telemetry::Telemetry::default());
+            telemetry::init(app.handle());
+            app.manage(http_api::HttpApi::default());
+            http_api::restart(app.handle());
+            app.manage(osc::OscServer::default());
+            osc::restart(app.handle());
+            app.manage(midi::MidiInput::default());
+            midi::restart(app.handle());
+            app.manage(watch_folder::WatchFolder::default());
+            watch_folder::restart(app.handle());
 
             Ok(())
         })
-        .build(tauri::generate_context!())
+        .build(context)
         .expect("error while building tauri application")
-        .run(move |_app_handle, event| match event {
+        .run(|app_handle, event| match event {
             // This event fires when the app is completely shutting down
             RunEvent::Exit => {
-                println!("[Tauri] App exiting, killing backend...");
-                let mut state = backend_state.lock().unwrap();
-
-                // Kill DEV process (std::process::Child)
-                if let Some(mut child) = state.dev_process.take() {
-                    let _ = child.kill(); // Sends SIGKILL / TerminateProcess
-                    println!("[Tauri] Killed dev backend");
+                if let Err(e) = window_state::save(app_handle) {
+                    eprintln!("[WindowState] {}", e);
                 }
-
-                // Kill PROD process (CommandChild)
-                if let Some(child) = state.prod_process.take() {
-                    let _ = child.kill(); 
-                    println!("[Tauri] Killed prod backend");
+                if let Err(e) = telemetry::save_pending(app_handle) {
+                    eprintln!("[Telemetry] {}", e);
                 }
+                println!("[Tauri] App exiting, killing backend...");
+                app_handle.state::<sidecar::Sidecar>().kill();
             }
             _ => {}
         });
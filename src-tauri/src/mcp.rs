@@ -0,0 +1,254 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde_json::{Value, json};
+use tauri::AppHandle;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::disk;
+use crate::filenames;
+use crate::game_engines;
+use crate::jobs;
+use crate::library::{self, LibraryItem};
+use crate::models;
+use crate::paths;
+
+// The newest revision we speak; clients asking for another one get this and
+// decide for themselves.
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+const SEARCH_LIMIT: usize = 20;
+
+// Model Context Protocol server, so LLM agents and editors can use Noises as
+// a tool: JSON-RPC over stdio (`noises mcp`, see cli.rs) or over SSE on the
+// local HTTP API (/mcp/sse, see http_api.rs). Tools:
+//
+//   generate_sound   prompt, type, length, seed -> the new files
+//   search_library   text and/or tag -> matching sounds, newest first
+//   export_sound     a library path and a folder -> the copy's path
+fn tools() -> Value {
+    json!([
+        {
+            "name": "generate_sound",
+            "description": "Generate a sound effect, loop or song from a text prompt and save it to the Noises library. Returns the paths of the new files.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "prompt": { "type": "string", "description": "What the sound should be, e.g. \"rain on a tin roof\"" },
+                    "type": { "type": "string", "enum": ["loop", "one-shot"], "description": "loop for short sounds and loops, one-shot for longer music" },
+                    "length": { "type": "number", "description": "Length in seconds" },
+                    "seed": { "type": "integer", "description": "Fixed seed for a reproducible result" }
+                },
+                "required": ["prompt"]
+            }
+        },
+        {
+            "name": "search_library",
+            "description": "Search the sounds in the Noises library by file name and tags.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Text to look for in file names and tags; leave out to list everything" },
+                    "tag": { "type": "string", "description": "Only sounds with this tag" },
+                    "limit": { "type": "integer", "description": "Maximum number of results (default 20)" }
+                }
+            }
+        },
+        {
+            "name": "export_sound",
+            "description": "Copy a sound from the Noises library into a folder, e.g. a game project's assets.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "The sound's path, as returned by search_library or generate_sound" },
+                    "destination": { "type": "string", "description": "Folder to copy it into; created if missing" }
+                },
+                "required": ["path", "destination"]
+            }
+        }
+    ])
+}
+
+// Handles one JSON-RPC message and returns the reply, or None for
+// notifications.
+pub async fn handle(app: &AppHandle, message: &str) -> Option<Value> {
+    let message: Value = match serde_json::from_str(message) {
+        Ok(message) => message,
+        Err(e) => {
+            return Some(error_reply(
+                Value::Null,
+                -32700,
+                format!("Parse error: {}", e),
+            ));
+        }
+    };
+    let id = message.get("id").cloned()?;
+    let method = message
+        .get("method")
+        .and_then(|m| m.as_str())
+        .unwrap_or_default();
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = match method {
+        "initialize" => json!({
+            "protocolVersion": params
+                .get("protocolVersion")
+                .and_then(|v| v.as_str())
+                .unwrap_or(PROTOCOL_VERSION),
+            "capabilities": { "tools": {} },
+            "serverInfo": {
+                "name": "noises",
+                "version": app.package_info().version.to_string(),
+            },
+        }),
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": tools() }),
+        "tools/call" => {
+            let name = params
+                .get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or_default();
+            let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+            // Tool failures are results the model gets to read, not protocol
+            // errors.
+            match call_tool(app, name, &arguments).await {
+                Ok(result) => json!({
+                    "content": [{ "type": "text", "text": result.to_string() }],
+                    "isError": false,
+                }),
+                Err(e) => json!({
+                    "content": [{ "type": "text", "text": e }],
+                    "isError": true,
+                }),
+            }
+        }
+        _ => {
+            return Some(error_reply(
+                id,
+                -32601,
+                format!("Method not found: {}", method),
+            ));
+        }
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn error_reply(id: Value, code: i32, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+async fn call_tool(app: &AppHandle, name: &str, arguments: &Value) -> Result<Value, String> {
+    let text = |key: &str| arguments.get(key).and_then(|v| v.as_str());
+    match name {
+        "generate_sound" => {
+            let prompt = text("prompt").ok_or("generate_sound needs a prompt")?;
+            let kind = text("type").unwrap_or_else(|| models::default_generation_type(app));
+            let mut config = json!({ "type": kind, "prompt": prompt, "variations": 1 });
+            for key in ["length", "seed"] {
+                if let Some(value) = arguments.get(key).filter(|v| v.is_number()) {
+                    config[key] = value.clone();
+                }
+            }
+            // Through the queue, so it shows up there like any other
+            // generation; `noises mcp` sets the queue up without the GUI.
+            let result = jobs::generate(app, config).await?;
+            let files: Vec<&Value> = result
+                .get("files")
+                .and_then(|f| f.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|file| file.get("path"))
+                .collect();
+            Ok(json!({ "files": files }))
+        }
+        "search_library" => {
+            let query = text("query").map(str::to_lowercase);
+            let tag = text("tag").map(str::to_lowercase);
+            let limit = arguments
+                .get("limit")
+                .and_then(|v| v.as_u64())
+                .map_or(SEARCH_LIMIT, |limit| limit as usize);
+            let items = scan(app).await?;
+            let matches: Vec<LibraryItem> = items
+                .into_iter()
+                .filter(|item| {
+                    let tags: Vec<String> = item.tags.iter().map(|t| t.to_lowercase()).collect();
+                    tag.as_ref().is_none_or(|tag| tags.contains(tag))
+                        && query.as_ref().is_none_or(|query| {
+                            item.file.to_lowercase().contains(query)
+                                || tags.iter().any(|t| t.contains(query))
+                        })
+                })
+                .take(limit)
+                .collect();
+            Ok(json!(matches))
+        }
+        "export_sound" => {
+            let path = text("path").ok_or("export_sound needs a path")?;
+            let destination = text("destination").ok_or("export_sound needs a destination")?;
+            // Only what's in the library, so this can't be used to copy any
+            // file on the machine.
+            let items = scan(app).await?;
+            if !items
+                .iter()
+                .any(|item| Path::new(&item.path) == Path::new(path))
+            {
+                return Err(format!("Not a sound in the library: {}", path));
+            }
+            let target = export(app, Path::new(path), Path::new(destination))?;
+            Ok(json!({ "path": target }))
+        }
+        _ => Err(format!("Unknown tool: {}", name)),
+    }
+}
+
+async fn scan(app: &AppHandle) -> Result<Vec<LibraryItem>, String> {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || library::scan(&app))
+        .await
+        .map_err(|e| format!("Library scan failed: {}", e))
+}
+
+fn export(app: &AppHandle, path: &Path, destination: &Path) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(destination)
+        .map_err(|e| format!("Failed to create {}: {}", destination.display(), e))?;
+    let name = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    let target = filenames::unique(destination, &filenames::sanitize(&name), &extension);
+    disk::ensure_space_for_files(destination, &[path.to_path_buf()])?;
+    paths::copy_atomic(path, &target)
+        .map_err(|e| format!("Failed to copy to {}: {}", target.display(), e))?;
+    println!("[Mcp] Exported {}", target.display());
+    game_engines::write_companions(app, path, &target);
+    Ok(target)
+}
+
+// `noises mcp`: one message per line on stdin, replies on `out` (the real
+// stdout; logs have been moved to stderr by then). Messages are handled
+// concurrently so a ping still answers during a long generation. Returns
+// when the client closes stdin.
+pub async fn serve_stdio(app: &AppHandle, out: Box<dyn Write + Send>) -> Result<(), String> {
+    let out = Arc::new(Mutex::new(out));
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    eprintln!("[Mcp] Serving on stdio");
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| format!("Failed to read stdin: {}", e))?
+    {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let app = app.clone();
+        let out = out.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Some(reply) = handle(&app, &line).await {
+                let mut out = out.lock().unwrap();
+                let _ = writeln!(out, "{}", reply);
+                let _ = out.flush();
+            }
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use serde::Serialize;
+
+// Roughly 30 meter frames per second is plenty for a smooth UI meter
+// without flooding the IPC bridge.
+const METER_RATE_HZ: u32 = 30;
+
+#[derive(Clone, Serialize)]
+pub struct MeterFrame {
+    // Linear 0.0..=1.0 values, one entry per channel.
+    pub peak: Vec<f32>,
+    pub rms: Vec<f32>,
+}
+
+impl MeterFrame {
+    pub fn silent(channels: usize) -> Self {
+        MeterFrame {
+            peak: vec![0.0; channels],
+            rms: vec![0.0; channels],
+        }
+    }
+}
+
+// Measures peak/RMS per channel of an interleaved sample stream over ~1/30 s
+// windows. Used from audio callbacks: a sample only updates the running
+// sums, and the one allocation is the finished frame (two small Vecs, ~30
+// times a second), which leaves for another thread.
+pub struct Meter {
+    channels: usize,
+    window: usize,
+    position: usize,
+    peak: Vec<f32>,
+    sum_sq: Vec<f32>,
+}
+
+impl Meter {
+    pub fn new(channels: u16, sample_rate: u32) -> Self {
+        let mut meter = Meter {
+            channels: 0,
+            window: 0,
+            position: 0,
+            peak: Vec::new(),
+            sum_sq: Vec::new(),
+        };
+        meter.reset(channels, sample_rate);
+        meter
+    }
+
+    // Starts a new window, picking up a format change if there was one.
+    pub fn reset(&mut self, channels: u16, sample_rate: u32) {
+        self.channels = channels.max(1) as usize;
+        self.window = (sample_rate / METER_RATE_HZ).max(1) as usize * self.channels;
+        self.position = 0;
+        // clear + resize keeps the allocation.
+        self.peak.clear();
+        self.peak.resize(self.channels, 0.0);
+        self.sum_sq.clear();
+        self.sum_sq.resize(self.channels, 0.0);
+    }
+
+    // Returns a finished frame once per window. The caller resets the meter afterwards.
+    pub fn push(&mut self, sample: f32) -> Option<MeterFrame> {
+        let channel = self.position % self.channels;
+        let abs = sample.abs();
+        if abs > self.peak[channel] {
+            self.peak[channel] = abs;
+        }
+        self.sum_sq[channel] += sample * sample;
+        self.position += 1;
+
+        if self.position < self.window {
+            return None;
+        }
+
+        let frames_per_channel = (self.position / self.channels).max(1) as f32;
+        Some(MeterFrame {
+            peak: self.peak.clone(),
+            rms: self
+                .sum_sq
+                .iter()
+                .map(|s| (s / frames_per_channel).sqrt())
+                .collect(),
+        })
+    }
+}
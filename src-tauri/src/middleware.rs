@@ -0,0 +1,312 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::json;
+use tauri::AppHandle;
+
+use crate::disk;
+use crate::game_engines::{self, Role, SoundInfo};
+use crate::paths;
+use crate::render_target;
+use crate::telemetry;
+
+// What FMOD Studio imports as is; anything else is converted to WAV. Wwise
+// only takes WAV.
+const FMOD_FORMATS: &[&str] = &["wav", "mp3", "ogg", "flac", "aif", "aiff"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Middleware {
+    Wwise,
+    Fmod,
+}
+
+#[derive(Serialize)]
+pub struct MiddlewareExport {
+    pub middleware: Middleware,
+    pub folder: String,
+    pub manifest: String,
+    pub files: Vec<String>,
+}
+
+struct Exported {
+    source: PathBuf,
+    path: PathBuf,
+    name: String,
+    info: SoundInfo,
+}
+
+// The folder with the .wproj or .fspro in it; the project file itself works
+// too.
+fn detect(project: &Path) -> Result<(PathBuf, Middleware), String> {
+    let dir = if project.is_file() {
+        project.parent().unwrap_or(project)
+    } else {
+        project
+    };
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("Failed to open {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("wproj") => return Ok((dir.to_path_buf(), Middleware::Wwise)),
+            Some("fspro") => return Ok((dir.to_path_buf(), Middleware::Fmod)),
+            _ => {}
+        }
+    }
+    Err(format!(
+        "No Wwise (.wproj) or FMOD Studio (.fspro) project in {}",
+        dir.display()
+    ))
+}
+
+// Where sounds go in the project: Wwise's Originals for sound effects, the
+// Assets folder FMOD Studio's audio bin reads.
+fn folder(project: &Path, middleware: Middleware) -> PathBuf {
+    match middleware {
+        Middleware::Wwise => project.join("Originals").join("SFX").join("Noises"),
+        Middleware::Fmod => project.join("Assets").join("Noises"),
+    }
+}
+
+// "Rain on a tin roof.wav" as a loop -> loop_rain_on_a_tin_roof_01, the
+// number counting up past files already there, so variations of a prompt
+// line up for a random container.
+fn unique_name(dir: &Path, base: &str, extension: &str, taken: &HashSet<String>) -> String {
+    (1..)
+        .map(|n| format!("{}_{:02}", base, n))
+        .find(|name| !taken.contains(name) && !dir.join(file_name(name, extension)).exists())
+        .unwrap()
+}
+
+fn file_name(name: &str, extension: &str) -> String {
+    match extension {
+        "" => name.to_string(),
+        extension => format!("{}.{}", name, extension),
+    }
+}
+
+fn needs_conversion(source: &Path, middleware: Middleware) -> bool {
+    let extension = source
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match middleware {
+        Middleware::Wwise => extension != "wav",
+        Middleware::Fmod => !FMOD_FORMATS.contains(&extension.as_str()),
+    }
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// For Wwise's File > Import > Tab Delimited: one Sound SFX per file, under
+// a Noises folder in the Default Work Unit, looping where the sound is a loop.
+fn wwise_manifest(exported: &[Exported]) -> String {
+    let mut lines =
+        vec!["Audio File\tObject Path\tObject Type\t@IsLoopingEnabled\tNotes".to_string()];
+    for sound in exported {
+        lines.push(format!(
+            "{}\t\\Actor-Mixer Hierarchy\\Default Work Unit\\Noises\\{}\tSound SFX\t{}\t{}",
+            sound.path.display(),
+            sound.name,
+            if sound.info.role == Role::Loop {
+                "True"
+            } else {
+                "False"
+            },
+            one_line(sound.info.entry.as_ref().map_or("", |e| &e.prompt)),
+        ));
+    }
+    lines.join("\r\n") + "\r\n"
+}
+
+// FMOD Studio has no import file of its own; this has what a Studio script
+// (or a person) needs to make the events.
+fn fmod_manifest(project: &Path, exported: &[Exported]) -> String {
+    let sounds: Vec<_> = exported
+        .iter()
+        .map(|sound| {
+            let asset = sound
+                .path
+                .strip_prefix(project.join("Assets"))
+                .unwrap_or(&sound.path);
+            json!({
+                "asset": asset.to_string_lossy().replace('\\', "/"),
+                "event": format!("event:/Noises/{}", sound.name),
+                "loop": sound.info.role == Role::Loop,
+                "stream": sound.info.role == Role::Music,
+                "bpm": sound.info.bpm,
+                "key": sound.info.key,
+                "tags": sound.info.tags,
+                "prompt": sound.info.entry.as_ref().map(|e| &e.prompt),
+                "source": sound.source,
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&json!({ "sounds": sounds })).unwrap_or_default()
+}
+
+pub fn export(
+    app: &AppHandle,
+    sounds: &[PathBuf],
+    project: &Path,
+) -> Result<MiddlewareExport, String> {
+    if sounds.is_empty() {
+        return Err("Pick at least one sound to export".to_string());
+    }
+    if let Some(missing) = sounds.iter().find(|s| !s.is_file()) {
+        return Err(format!("File not found: {}", missing.display()));
+    }
+    let (project, middleware) = detect(project)?;
+    let dir = folder(&project, middleware);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+    disk::ensure_space_for_files(&dir, sounds)?;
+
+    let mut taken = HashSet::new();
+    let mut exported = Vec::new();
+    for source in sounds {
+        let info = game_engines::sound_info(app, source);
+        let stem =
+            game_engines::snake_case(&source.file_stem().unwrap_or_default().to_string_lossy());
+        let base = match stem.as_str() {
+            "" => info.role.prefix().to_string(),
+            stem => format!("{}_{}", info.role.prefix(), stem),
+        };
+        let convert = needs_conversion(source, middleware);
+        let extension = if convert {
+            "wav".to_string()
+        } else {
+            source
+                .extension()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_lowercase()
+        };
+        let name = unique_name(&dir, &base, &extension, &taken);
+        let path = dir.join(file_name(&name, &extension));
+        if convert {
+            let bytes = render_target::to_wav(source, None)?;
+            paths::write_atomic(&path, bytes)
+        } else {
+            paths::copy_atomic(source, &path).map(|_| ())
+        }
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        taken.insert(name.clone());
+        exported.push(Exported {
+            source: source.clone(),
+            path,
+            name,
+            info,
+        });
+    }
+
+    let (manifest, contents) = match middleware {
+        Middleware::Wwise => (project.join("noises_import.txt"), wwise_manifest(&exported)),
+        Middleware::Fmod => (
+            project.join("noises_import.json"),
+            fmod_manifest(&project, &exported),
+        ),
+    };
+    paths::write_atomic(&manifest, contents)
+        .map_err(|e| format!("Failed to write {}: {}", manifest.display(), e))?;
+    println!(
+        "[Middleware] Exported {} sounds to {}",
+        exported.len(),
+        dir.display()
+    );
+    Ok(MiddlewareExport {
+        middleware,
+        folder: dir.to_string_lossy().into_owned(),
+        manifest: manifest.to_string_lossy().into_owned(),
+        files: exported
+            .iter()
+            .map(|sound| sound.path.to_string_lossy().into_owned())
+            .collect(),
+    })
+}
+
+// Copies the sounds into a Wwise or FMOD Studio project (whichever `project`
+// is) under the usual naming, with a manifest next to the project file for
+// importing them in one go: noises_import.txt for Wwise, noises_import.json
+// for FMOD.
+#[tauri::command]
+pub async fn export_to_middleware(
+    app: AppHandle,
+    paths: Vec<String>,
+    project: String,
+) -> Result<MiddlewareExport, String> {
+    telemetry::record_feature(&app, "middleware");
+    let sounds: Vec<PathBuf> = paths.iter().map(|p| paths::normalize(p)).collect();
+    let project = paths::normalize(&project);
+    tauri::async_runtime::spawn_blocking(move || export(&app, &sounds, &project))
+        .await
+        .map_err(|e| format!("Export task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str, files: &[&str]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("noises-middleware-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in files {
+            std::fs::write(dir.join(file), b"sound").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn numbers_start_at_one() {
+        let dir = scratch("first", &[]);
+        assert_eq!(
+            unique_name(&dir, "sfx_kick", "wav", &HashSet::new()),
+            "sfx_kick_01"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn counts_past_files_already_there() {
+        let dir = scratch("existing", &["sfx_kick_01.wav", "sfx_kick_02.wav"]);
+        assert_eq!(
+            unique_name(&dir, "sfx_kick", "wav", &HashSet::new()),
+            "sfx_kick_03"
+        );
+        // The same name in another format doesn't count.
+        assert_eq!(
+            unique_name(&dir, "sfx_kick", "ogg", &HashSet::new()),
+            "sfx_kick_01"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    // A gap is filled, and names this export already handed out are
+    // skipped even though they aren't on disk yet.
+    #[test]
+    fn skips_suffixed_names_taken_either_way() {
+        let dir = scratch("suffixed", &["sfx_kick_02.wav"]);
+        let taken = HashSet::from(["sfx_kick_01".to_string()]);
+        assert_eq!(unique_name(&dir, "sfx_kick", "wav", &taken), "sfx_kick_03");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn missing_extensions() {
+        let dir = scratch("bare", &["sfx_kick_01"]);
+        assert_eq!(
+            unique_name(&dir, "sfx_kick", "", &HashSet::new()),
+            "sfx_kick_02"
+        );
+        assert_eq!(file_name("sfx_kick_02", ""), "sfx_kick_02");
+        let _ = std::fs::remove_dir_all(&dir);
+        // Such a sound is converted, so it's exported as a .wav after all.
+        assert!(needs_conversion(Path::new("kick"), Middleware::Wwise));
+        assert!(needs_conversion(Path::new("kick"), Middleware::Fmod));
+    }
+}
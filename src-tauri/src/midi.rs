@@ -0,0 +1,318 @@
+use std::sync::Mutex;
+use std::sync::mpsc;
+
+use midir::MidiInputConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::jobs;
+use crate::library;
+use crate::models;
+use crate::playback::PlaybackEngine;
+use crate::settings;
+use crate::workspaces;
+
+// MIDI input, so a pad controller or keyboard can play sounds and fire off
+// generations. Every note-on and every controller pushed past halfway is
+// announced with `midi-input` (for "press a key to map it" in the UI) and
+// then looked up in the `midi_mappings` setting.
+#[derive(Default)]
+pub struct MidiInput {
+    // Dropping them closes the ports.
+    connections: Mutex<Vec<MidiInputConnection<()>>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TriggerKind {
+    Note,
+    Cc,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MidiTrigger {
+    pub kind: TriggerKind,
+    // 1-16; None matches any channel.
+    #[serde(default)]
+    pub channel: Option<u8>,
+    // Note or controller number.
+    pub number: u8,
+}
+
+impl MidiTrigger {
+    fn matches(&self, kind: TriggerKind, channel: u8, number: u8) -> bool {
+        self.kind == kind && self.number == number && self.channel.is_none_or(|c| c == channel)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MidiAction {
+    // The nth newest sound in the library, 1 being the newest.
+    PlaySound { index: usize },
+    // A saved preset of the active workspace, by its id.
+    GeneratePreset { preset: u64 },
+    StopPlayback,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MidiMapping {
+    pub trigger: MidiTrigger,
+    pub action: MidiAction,
+}
+
+#[derive(Clone, Serialize)]
+struct MidiEvent {
+    kind: TriggerKind,
+    channel: u8,
+    number: u8,
+    value: u8,
+}
+
+// Opens the configured input if MIDI is on. Called from setup and whenever
+// midi_enabled or midi_input change; mappings are read per message.
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<MidiInput>();
+    state.connections.lock().unwrap().clear();
+    let settings = settings::get(app);
+    if !settings.midi_enabled {
+        return;
+    }
+
+    let (tx, rx) = mpsc::channel();
+    match connect(settings.midi_input.as_deref(), tx) {
+        Ok(connections) => *state.connections.lock().unwrap() = connections,
+        Err(e) => {
+            eprintln!("[Midi] {}", e);
+            return;
+        }
+    }
+    println!(
+        "[Midi] Listening on {}",
+        settings.midi_input.as_deref().unwrap_or("every input")
+    );
+
+    // Ends when the connections are dropped, which drops the senders.
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut controllers = [[0u8; 128]; 16];
+        while let Ok(message) = rx.recv() {
+            handle(&app, message, &mut controllers);
+        }
+    });
+}
+
+fn handle(app: &AppHandle, [status, number, value]: [u8; 3], controllers: &mut [[u8; 128]; 16]) {
+    let channel = status & 0x0f;
+    let (number, value) = (number & 0x7f, value & 0x7f);
+    let kind = match status & 0xf0 {
+        // Velocity 0 is a note-off.
+        0x90 if value > 0 => TriggerKind::Note,
+        // Only on the way up, so turning a knob fires once, not per step.
+        0xb0 => {
+            let last =
+                std::mem::replace(&mut controllers[channel as usize][number as usize], value);
+            if value < 64 || last >= 64 {
+                return;
+            }
+            TriggerKind::Cc
+        }
+        _ => return,
+    };
+    let channel = channel + 1;
+    let _ = app.emit(
+        "midi-input",
+        MidiEvent {
+            kind,
+            channel,
+            number,
+            value,
+        },
+    );
+
+    for mapping in settings::get(app).midi_mappings {
+        if !mapping.trigger.matches(kind, channel, number) {
+            continue;
+        }
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = run(&app, mapping.action).await {
+                eprintln!("[Midi] {}", e);
+            }
+        });
+    }
+}
+
+async fn run(app: &AppHandle, action: MidiAction) -> Result<(), String> {
+    match action {
+        MidiAction::PlaySound { index } => {
+            let scan_app = app.clone();
+            let items = tauri::async_runtime::spawn_blocking(move || library::scan(&scan_app))
+                .await
+                .map_err(|e| format!("Library scan failed: {}", e))?;
+            let item = index
+                .checked_sub(1)
+                .and_then(|i| items.get(i))
+                .ok_or_else(|| format!("The library has no sound #{}", index))?;
+            app.state::<PlaybackEngine>().play(item.path.clone()).await
+        }
+        MidiAction::GeneratePreset { preset } => {
+            let presets = workspaces::presets(app);
+            let preset = presets
+                .iter()
+                .find(|p| p.get("id").and_then(|id| id.as_u64()) == Some(preset))
+                .ok_or("That preset no longer exists in this workspace")?;
+            let mut config = json!({
+                "type": preset
+                    .get("type")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or_else(|| models::default_generation_type(app)),
+                "variations": 1,
+            });
+            for key in ["prompt", "bpm", "key"] {
+                if let Some(value) = preset.get(key).filter(|v| !v.is_null()) {
+                    config[key] = value.clone();
+                }
+            }
+            println!("[Midi] Generating preset {}", preset["id"]);
+            jobs::generate(app, config).await.map(|_| ())
+        }
+        MidiAction::StopPlayback => app.state::<PlaybackEngine>().stop(),
+    }
+}
+
+#[tauri::command]
+pub fn list_midi_inputs() -> Result<Vec<String>, String> {
+    inputs()
+}
+
+// Maps a trigger to an action, replacing what it did before, or unmaps it
+// when `action` is null. Returns the new mappings.
+#[tauri::command]
+pub fn set_midi_mapping(
+    app: AppHandle,
+    trigger: MidiTrigger,
+    action: Option<MidiAction>,
+) -> Result<Vec<MidiMapping>, String> {
+    let mut mappings = settings::get(&app).midi_mappings;
+    mappings.retain(|mapping| mapping.trigger != trigger);
+    if let Some(action) = action {
+        mappings.push(MidiMapping { trigger, action });
+    }
+    let value: Value = serde_json::to_value(&mappings)
+        .map_err(|e| format!("Failed to serialize MIDI mappings: {}", e))?;
+    settings::set(&app, "midi_mappings", value)?;
+    Ok(mappings)
+}
+
+// Splits raw MIDI bytes into channel messages. A backend can hand over
+// more than one message at a time; system messages are skipped.
+fn channel_messages(bytes: &[u8], sender: &mpsc::Sender<[u8; 3]>) {
+    let mut i = 0;
+    while i < bytes.len() {
+        let status = bytes[i];
+        let data_len = match status & 0xf0 {
+            0x80 | 0x90 | 0xa0 | 0xb0 | 0xe0 => 2,
+            0xc0 | 0xd0 => 1,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        if i + data_len >= bytes.len() {
+            break;
+        }
+        let data = &bytes[i + 1..i + 1 + data_len];
+        let _ = sender.send([status, data[0], data.get(1).copied().unwrap_or(0)]);
+        i += 1 + data_len;
+    }
+}
+
+fn open() -> Result<midir::MidiInput, String> {
+    let mut input =
+        midir::MidiInput::new("Noises").map_err(|e| format!("Failed to open MIDI input: {}", e))?;
+    // SysEx, clock and active sensing.
+    input.ignore(midir::Ignore::All);
+    Ok(input)
+}
+
+fn inputs() -> Result<Vec<String>, String> {
+    let input = open()?;
+    Ok(input
+        .ports()
+        .iter()
+        .filter_map(|port| input.port_name(port).ok())
+        .collect())
+}
+
+// Connects to the input called `name`, or to every input. Each midir
+// connection takes a MidiInput of its own.
+fn connect(
+    name: Option<&str>,
+    sender: mpsc::Sender<[u8; 3]>,
+) -> Result<Vec<MidiInputConnection<()>>, String> {
+    let mut connections = Vec::new();
+    for port in open()?.ports() {
+        let input = open()?;
+        let Ok(port_name) = input.port_name(&port) else {
+            continue;
+        };
+        if name.is_some_and(|name| name != port_name) {
+            continue;
+        }
+        let sender = sender.clone();
+        match input.connect(
+            &port,
+            "Noises input",
+            move |_, bytes, _| channel_messages(bytes, &sender),
+            (),
+        ) {
+            Ok(connection) => connections.push(connection),
+            Err(e) => eprintln!("[Midi] Failed to open {}: {}", port_name, e),
+        }
+    }
+    if let Some(name) = name
+        && connections.is_empty()
+    {
+        return Err(format!("MIDI input not found: {}", name));
+    }
+    Ok(connections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(bytes: &[u8]) -> Vec<[u8; 3]> {
+        let (tx, rx) = mpsc::channel();
+        channel_messages(bytes, &tx);
+        drop(tx);
+        rx.iter().collect()
+    }
+
+    #[test]
+    fn splits_several_messages() {
+        assert_eq!(
+            split(&[0x90, 60, 100, 0xb1, 7, 127]),
+            [[0x90, 60, 100], [0xb1, 7, 127]]
+        );
+    }
+
+    #[test]
+    fn one_data_byte_messages_are_padded() {
+        assert_eq!(split(&[0xc2, 5, 0x90, 1, 2]), [[0xc2, 5, 0], [0x90, 1, 2]]);
+    }
+
+    #[test]
+    fn system_messages_are_skipped() {
+        // Clock, then a stray data byte, then a note.
+        assert_eq!(split(&[0xf8, 0x10, 0x90, 60, 1]), [[0x90, 60, 1]]);
+    }
+
+    #[test]
+    fn a_cut_off_message_is_dropped() {
+        assert_eq!(split(&[0x90, 60, 100, 0xb0, 7]), [[0x90, 60, 100]]);
+        assert!(split(&[0x90]).is_empty());
+    }
+}
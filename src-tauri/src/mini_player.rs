@@ -0,0 +1,65 @@
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, Window, WindowEvent};
+
+pub const LABEL: &str = "mini-player";
+
+// A small always-on-top strip with the playback state, the queue and transport
+// buttons, for keeping an eye on long batches from another app. It's the same
+// frontend bundle; main.jsx renders the mini view for `?view=mini`.
+pub fn open(app: &AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(LABEL) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return Ok(());
+    }
+
+    WebviewWindowBuilder::new(app, LABEL, WebviewUrl::App("index.html?view=mini".into()))
+        .title("Noises Mini Player")
+        .inner_size(340.0, 112.0)
+        .resizable(false)
+        .maximizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .build()
+        .map_err(|e| format!("Failed to open mini player: {}", e))?;
+    println!("[MiniPlayer] Opened");
+    Ok(())
+}
+
+// Async so the window isn't built on the main thread from inside a command,
+// which deadlocks on Windows.
+#[tauri::command]
+pub async fn open_mini_player(app: AppHandle) -> Result<(), String> {
+    open(&app)
+}
+
+#[tauri::command]
+pub fn close_mini_player(app: AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(LABEL) {
+        window
+            .close()
+            .map_err(|e| format!("Failed to close mini player: {}", e))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_mini_player_on_top(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let window = app
+        .get_webview_window(LABEL)
+        .ok_or("Mini player is not open")?;
+    window
+        .set_always_on_top(enabled)
+        .map_err(|e| format!("Failed to change always-on-top: {}", e))
+}
+
+// Hooked up in main.rs via on_window_event. The mini player alone would keep
+// the app alive after the main window is closed, so it goes with it.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    if let WindowEvent::Destroyed = event
+        && window.label() == "main"
+        && let Some(mini) = window.app_handle().get_webview_window(LABEL)
+    {
+        let _ = mini.close();
+    }
+}
@@ -0,0 +1,727 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::downloads::{self, DownloadStatus};
+use crate::history;
+use crate::jobs::JobManager;
+use crate::paths;
+use crate::proxy;
+use crate::secrets::{self, Secret};
+use crate::settings;
+
+const HF_URL: &str = "https://huggingface.co";
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+// Where a model's checkpoint lives. Both are Hugging Face hub caches
+// (models--org--name/{refs,snapshots}); ACE-Step just keeps its own.
+#[derive(Clone, Copy)]
+enum CacheRoot {
+    HuggingFace,
+    AceStep,
+}
+
+struct ModelSpec {
+    id: &'static str,
+    name: &'static str,
+    repo: &'static str,
+    // The request type the backend routes to this model.
+    generation_type: &'static str,
+    // The longest clip it makes in one go, in seconds. Longer requests are
+    // made in segments (segments.rs).
+    max_length: f64,
+    // Rough VRAM a generation takes, in MB: the weights (less with low-VRAM
+    // mode's offloading) plus the working memory per second of audio per
+    // variation. Only used to decide whether to wait for the GPU (jobs.rs),
+    // so it errs low.
+    vram_mb: u64,
+    low_vram_mb: u64,
+    vram_mb_per_second: u64,
+    // What the backend writes it out as: 16-bit stereo WAV at this rate.
+    sample_rate: u64,
+    // Length the backend picks when a request doesn't say (backend/main.py).
+    default_length: f64,
+    cache: CacheRoot,
+}
+
+// The checkpoints the backend knows how to load (backend/models/).
+const MODELS: &[ModelSpec] = &[
+    ModelSpec {
+        id: "stable-audio-open",
+        name: "Stable Audio Open 1.0",
+        repo: "stabilityai/stable-audio-open-1.0",
+        generation_type: "loop",
+        max_length: 47.0,
+        vram_mb: 4500,
+        low_vram_mb: 3000,
+        vram_mb_per_second: 20,
+        sample_rate: 44100,
+        default_length: 2.5,
+        cache: CacheRoot::HuggingFace,
+    },
+    ModelSpec {
+        id: "ace-step",
+        name: "ACE-Step v1 3.5B",
+        repo: "ACE-Step/ACE-Step-v1-3.5B",
+        generation_type: "one-shot",
+        max_length: 240.0,
+        vram_mb: 8000,
+        low_vram_mb: 4500,
+        vram_mb_per_second: 10,
+        sample_rate: 48000,
+        default_length: 30.0,
+        cache: CacheRoot::AceStep,
+    },
+];
+
+pub const DEFAULT_MODEL: &str = "stable-audio-open";
+
+#[derive(Clone, Serialize)]
+pub struct ModelInfo {
+    id: &'static str,
+    name: &'static str,
+    repo: &'static str,
+    generation_type: &'static str,
+    installed: bool,
+    // Bytes on disk, including a partial download.
+    size: u64,
+    downloading: bool,
+    is_default: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ModelDiskUsage {
+    total: u64,
+    models: Vec<ModelSize>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ModelSize {
+    id: &'static str,
+    size: u64,
+}
+
+// A checkpoint in one of the caches, whether the backend loads it or some
+// other program put it in the shared Hugging Face cache.
+#[derive(Clone, Serialize)]
+pub struct CachedCheckpoint {
+    repo: String,
+    path: String,
+    size: u64,
+    // The id of the model it is, if it's one of ours.
+    model: Option<&'static str>,
+    // Unix time in milliseconds: when a generation last ran on it, as far
+    // as this app knows, and when it was downloaded.
+    last_used: Option<u64>,
+    downloaded: Option<u64>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct PurgeReport {
+    removed: Vec<&'static str>,
+    freed: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct ModelDownloadProgress {
+    model: &'static str,
+    file: String,
+    downloaded: u64,
+    total: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct ModelDownloadFinished {
+    model: &'static str,
+    error: Option<String>,
+}
+
+// Models with a download running, so a second click doesn't start another.
+#[derive(Default)]
+pub struct ModelManager {
+    downloading: Mutex<HashSet<&'static str>>,
+    // Held while model-usage.json is read and written back.
+    usage: Mutex<()>,
+}
+
+fn spec(id: &str) -> Result<&'static ModelSpec, String> {
+    MODELS
+        .iter()
+        .find(|model| model.id == id)
+        .ok_or_else(|| format!("Unknown model: {}", id))
+}
+
+// Mirrors huggingface_hub's lookup: HF_HUB_CACHE, then HF_HOME/hub, then
+// ~/.cache/huggingface/hub. ACE-Step passes its own cache_dir.
+fn cache_root(app: &AppHandle, cache: CacheRoot) -> Result<PathBuf, String> {
+    if let CacheRoot::HuggingFace = cache {
+        if let Some(dir) = std::env::var_os("HF_HUB_CACHE") {
+            return Ok(PathBuf::from(dir));
+        }
+        if let Some(dir) = std::env::var_os("HF_HOME") {
+            return Ok(PathBuf::from(dir).join("hub"));
+        }
+    }
+    let home = app
+        .path()
+        .home_dir()
+        .map_err(|e| format!("Failed to resolve home directory: {}", e))?;
+    Ok(match cache {
+        CacheRoot::HuggingFace => home.join(".cache").join("huggingface").join("hub"),
+        CacheRoot::AceStep => home.join(".cache").join("ace-step").join("checkpoints"),
+    })
+}
+
+fn repo_dir(app: &AppHandle, model: &ModelSpec) -> Result<PathBuf, String> {
+    let name = format!("models--{}", model.repo.replace('/', "--"));
+    Ok(cache_root(app, model.cache)?.join(name))
+}
+
+// Installed once refs/main points at a snapshot we have; that's written last,
+// so an interrupted download doesn't count.
+fn is_installed(dir: &Path) -> bool {
+    std::fs::read_to_string(dir.join("refs").join("main"))
+        .is_ok_and(|sha| dir.join("snapshots").join(sha.trim()).is_dir())
+}
+
+// Snapshots are usually symlinks into blobs/, so links aren't followed and
+// nothing is counted twice.
+fn dir_size(path: &Path) -> u64 {
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| dir_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+fn modified_millis(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
+}
+
+// When each model last generated, by id, in the app data dir. The caches
+// themselves don't say: reading a checkpoint leaves no trace in it.
+fn usage_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve data directory: {}", e))?;
+    Ok(dir.join("model-usage.json"))
+}
+
+fn load_usage(app: &AppHandle) -> BTreeMap<String, u64> {
+    usage_path(app)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+// Called when a generation finishes, failed or not: the checkpoint was loaded
+// either way.
+pub fn record_use(app: &AppHandle, id: &str) {
+    if spec(id).is_err() {
+        return;
+    }
+    let manager = app.state::<ModelManager>();
+    let _lock = manager.usage.lock().unwrap();
+    let mut usage = load_usage(app);
+    usage.insert(id.to_string(), history::now_millis());
+    let saved = usage_path(app).and_then(|path| {
+        let json = serde_json::to_string_pretty(&usage).map_err(|e| e.to_string())?;
+        paths::write_atomic(path, json).map_err(|e| e.to_string())
+    });
+    if let Err(e) = saved {
+        eprintln!("[Models] Failed to save model usage: {}", e);
+    }
+}
+
+// Every models--org--name folder in the caches the backend reads, ours
+// matched to their ids. A cache root shared by both is only listed once.
+fn cached_checkpoints(app: &AppHandle) -> Result<Vec<CachedCheckpoint>, String> {
+    let usage = load_usage(app);
+    let mut roots = Vec::new();
+    for cache in [CacheRoot::HuggingFace, CacheRoot::AceStep] {
+        let root = cache_root(app, cache)?;
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    let mut checkpoints = Vec::new();
+    for root in roots {
+        let Ok(entries) = std::fs::read_dir(&root) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Some(repo) = name.strip_prefix("models--") else {
+                continue;
+            };
+            if !path.is_dir() {
+                continue;
+            }
+            let repo = repo.replacen("--", "/", 1);
+            let model = MODELS
+                .iter()
+                .find(|model| model.repo == repo && repo_dir(app, model).is_ok_and(|d| d == path))
+                .map(|model| model.id);
+            checkpoints.push(CachedCheckpoint {
+                size: dir_size(&path),
+                model,
+                last_used: model.and_then(|id| usage.get(id).copied()),
+                downloaded: modified_millis(&path.join("refs").join("main"))
+                    .or_else(|| modified_millis(&path)),
+                path: path.to_string_lossy().into_owned(),
+                repo,
+            });
+        }
+    }
+    checkpoints.sort_by_key(|checkpoint| std::cmp::Reverse(checkpoint.size));
+    Ok(checkpoints)
+}
+
+// Checkpoints can only go while nothing is loading or fetching them.
+fn ensure_idle(app: &AppHandle, manager: &ModelManager, model: &ModelSpec) -> Result<(), String> {
+    if manager.downloading.lock().unwrap().contains(model.id) {
+        return Err(format!("{} is still downloading", model.name));
+    }
+    // The backend loads checkpoints per generation and lets go afterwards.
+    if !app.state::<JobManager>().status().active.is_empty() {
+        return Err("Wait for the running generations to finish first".to_string());
+    }
+    Ok(())
+}
+
+fn remove_repo_dir(dir: &Path) -> std::io::Result<()> {
+    match std::fs::remove_dir_all(dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+// The model the backend routes a request type to (see backend/main.py).
+pub fn model_for_type(generation_type: &str) -> &'static str {
+    let is_loop = generation_type.contains("loop");
+    MODELS
+        .iter()
+        .find(|model| model.generation_type.contains("loop") == is_loop)
+        .map(|model| model.id)
+        .unwrap_or(DEFAULT_MODEL)
+}
+
+pub fn max_length(generation_type: &str) -> f64 {
+    spec(model_for_type(generation_type))
+        .map(|model| model.max_length)
+        .unwrap_or(f64::INFINITY)
+}
+
+// For a request the way it's sent (after low_vram::clamp), in bytes. A long
+// request counts one segment, since that's all that's made at once.
+pub fn vram_needed(config: &Value, low_vram: bool) -> u64 {
+    let generation_type = config
+        .get("type")
+        .and_then(|t| t.as_str())
+        .unwrap_or_default();
+    let Ok(model) = spec(model_for_type(generation_type)) else {
+        return 0;
+    };
+    let length = config
+        .get("length")
+        .and_then(|l| l.as_f64())
+        .unwrap_or(model.default_length)
+        .clamp(0.0, model.max_length);
+    let variations = config
+        .get("variations")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1)
+        .max(1);
+    let base = if low_vram {
+        model.low_vram_mb
+    } else {
+        model.vram_mb
+    };
+    let working = (length * model.vram_mb_per_second as f64) as u64 * variations;
+    (base + working) * 1024 * 1024
+}
+
+// Roughly how big the files for a request come out, in bytes. A long
+// request counts twice: its segments are on disk while they're stitched.
+pub fn output_bytes(config: &Value) -> u64 {
+    let generation_type = config
+        .get("type")
+        .and_then(|t| t.as_str())
+        .unwrap_or_default();
+    let Ok(model) = spec(model_for_type(generation_type)) else {
+        return 0;
+    };
+    let length = config
+        .get("length")
+        .and_then(|l| l.as_f64())
+        .unwrap_or(model.default_length)
+        .max(0.0);
+    let variations = config
+        .get("variations")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1)
+        .max(1);
+    let copies = if length > model.max_length { 2 } else { 1 };
+    // 2 channels x 2 bytes.
+    (length * model.sample_rate as f64) as u64 * 4 * variations * copies
+}
+
+pub fn default_model(app: &AppHandle) -> &'static str {
+    spec(&settings::get(app).default_model)
+        .map(|model| model.id)
+        .unwrap_or(DEFAULT_MODEL)
+}
+
+// For generations started without the form (tray, hotkeys): the request type
+// that picks the default model.
+pub fn default_generation_type(app: &AppHandle) -> &'static str {
+    spec(default_model(app))
+        .map(|model| model.generation_type)
+        .unwrap_or("loop")
+}
+
+#[tauri::command]
+pub async fn list_models(
+    app: AppHandle,
+    manager: State<'_, ModelManager>,
+) -> Result<Vec<ModelInfo>, String> {
+    let default = default_model(&app);
+    let downloading = manager.downloading.lock().unwrap().clone();
+    let mut dirs = Vec::new();
+    for model in MODELS {
+        dirs.push(repo_dir(&app, model)?);
+    }
+    let sizes = tauri::async_runtime::spawn_blocking(move || {
+        dirs.iter()
+            .map(|dir| (is_installed(dir), dir_size(dir)))
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| format!("Failed to read model cache: {}", e))?;
+
+    Ok(MODELS
+        .iter()
+        .zip(sizes)
+        .map(|(model, (installed, size))| ModelInfo {
+            id: model.id,
+            name: model.name,
+            repo: model.repo,
+            generation_type: model.generation_type,
+            installed,
+            size,
+            downloading: downloading.contains(model.id),
+            is_default: model.id == default,
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_model_disk_usage(app: AppHandle) -> Result<ModelDiskUsage, String> {
+    let mut dirs = Vec::new();
+    for model in MODELS {
+        dirs.push((model.id, repo_dir(&app, model)?));
+    }
+    let models = tauri::async_runtime::spawn_blocking(move || {
+        dirs.into_iter()
+            .map(|(id, dir)| ModelSize {
+                id,
+                size: dir_size(&dir),
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| format!("Failed to read model cache: {}", e))?;
+    Ok(ModelDiskUsage {
+        total: models.iter().map(|model| model.size).sum(),
+        models,
+    })
+}
+
+#[tauri::command]
+pub fn set_default_model(app: AppHandle, id: String) -> Result<(), String> {
+    let model = spec(&id)?;
+    settings::set(&app, "default_model", json!(model.id))
+}
+
+#[tauri::command]
+pub async fn delete_model(
+    app: AppHandle,
+    manager: State<'_, ModelManager>,
+    id: String,
+) -> Result<(), String> {
+    let model = spec(&id)?;
+    ensure_idle(&app, &manager, model)?;
+    let dir = repo_dir(&app, model)?;
+    tauri::async_runtime::spawn_blocking(move || remove_repo_dir(&dir))
+        .await
+        .map_err(|e| format!("Failed to delete model: {}", e))?
+        .map_err(|e| format!("Failed to delete {}: {}", model.name, e))?;
+    println!("[Models] Deleted {}", model.name);
+    Ok(())
+}
+
+// Shows a model's checkpoint in the file manager, or without an id the
+// Hugging Face cache the backend downloads into.
+#[tauri::command]
+pub fn reveal_model_cache(app: AppHandle, id: Option<String>) -> Result<(), String> {
+    let dir = match id {
+        Some(id) => repo_dir(&app, spec(&id)?)?,
+        None => cache_root(&app, CacheRoot::HuggingFace)?,
+    };
+    crate::show_in_folder(dir.to_string_lossy().into_owned()).map_err(|e| e.to_string())
+}
+
+// Everything in the model caches, biggest first, including checkpoints of
+// other programs sharing the Hugging Face cache.
+#[tauri::command]
+pub async fn list_cached_checkpoints(app: AppHandle) -> Result<Vec<CachedCheckpoint>, String> {
+    tauri::async_runtime::spawn_blocking(move || cached_checkpoints(&app))
+        .await
+        .map_err(|e| format!("Failed to read model cache: {}", e))?
+}
+
+// Deletes the checkpoints of our models that haven't generated anything in
+// `days` days (or, never used, were downloaded longer ago than that). The
+// default model stays, as do other programs' checkpoints: the app can't
+// tell whether those are in use.
+#[tauri::command]
+pub async fn purge_unused_models(
+    app: AppHandle,
+    manager: State<'_, ModelManager>,
+    days: u64,
+) -> Result<PurgeReport, String> {
+    let cutoff = history::now_millis().saturating_sub(days * DAY_MILLIS);
+    let default = default_model(&app);
+    let checkpoints = {
+        let app = app.clone();
+        tauri::async_runtime::spawn_blocking(move || cached_checkpoints(&app))
+            .await
+            .map_err(|e| format!("Failed to read model cache: {}", e))??
+    };
+    let mut stale = Vec::new();
+    for checkpoint in checkpoints {
+        let Some(model) = checkpoint.model.and_then(|id| spec(id).ok()) else {
+            continue;
+        };
+        let last = checkpoint.last_used.or(checkpoint.downloaded).unwrap_or(0);
+        if model.id == default || last >= cutoff {
+            continue;
+        }
+        ensure_idle(&app, &manager, model)?;
+        stale.push((model, PathBuf::from(checkpoint.path), checkpoint.size));
+    }
+
+    let mut report = PurgeReport {
+        removed: Vec::new(),
+        freed: 0,
+    };
+    for (model, dir, size) in stale {
+        tauri::async_runtime::spawn_blocking(move || remove_repo_dir(&dir))
+            .await
+            .map_err(|e| format!("Failed to delete model: {}", e))?
+            .map_err(|e| format!("Failed to delete {}: {}", model.name, e))?;
+        println!("[Models] Purged {} (unused for {} days)", model.name, days);
+        report.removed.push(model.id);
+        report.freed += size;
+    }
+    Ok(report)
+}
+
+// Fetches the checkpoint into the same cache the backend loads from, so the
+// first generation doesn't stall on a multi-gigabyte download. Runs in the
+// background: `model-download-progress` while it goes, then
+// `model-download-finished`.
+#[tauri::command]
+pub fn download_model(
+    app: AppHandle,
+    manager: State<'_, ModelManager>,
+    id: String,
+) -> Result<(), String> {
+    let model = spec(&id)?;
+    if !manager.downloading.lock().unwrap().insert(model.id) {
+        return Err(format!("{} is already downloading", model.name));
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let result = download(&app, model).await;
+        app.state::<ModelManager>()
+            .downloading
+            .lock()
+            .unwrap()
+            .remove(model.id);
+        match &result {
+            Ok(()) => println!("[Models] {} downloaded", model.name),
+            Err(e) => eprintln!("[Models] {} download failed: {}", model.name, e),
+        }
+        let _ = app.emit(
+            "model-download-finished",
+            ModelDownloadFinished {
+                model: model.id,
+                error: result.err(),
+            },
+        );
+    });
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct RepoManifest {
+    sha: String,
+    siblings: Vec<RepoFile>,
+}
+
+#[derive(Deserialize)]
+struct RepoFile {
+    rfilename: String,
+    size: Option<u64>,
+    lfs: Option<LfsInfo>,
+}
+
+#[derive(Deserialize)]
+struct LfsInfo {
+    sha256: String,
+}
+
+async fn download(app: &AppHandle, model: &'static ModelSpec) -> Result<(), String> {
+    let hf_token = secrets::get_or_none(app, Secret::HuggingfaceToken);
+    let client = proxy::client_builder(app)?
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let mut request = client.get(format!(
+        "{}/api/models/{}/revision/main?blobs=true",
+        HF_URL, model.repo
+    ));
+    if let Some(token) = &hf_token {
+        request = request.bearer_auth(token);
+    }
+    let manifest: RepoManifest = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach Hugging Face: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to look up {}: {}", model.repo, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to read the file list for {}: {}", model.repo, e))?;
+
+    let dir = repo_dir(app, model)?;
+    // Plain files in the snapshot rather than blobs + symlinks, the same as
+    // huggingface_hub does where it can't make symlinks; it reads both.
+    let snapshot = dir.join("snapshots").join(&manifest.sha);
+    let total = manifest.siblings.iter().filter_map(|file| file.size).sum();
+    let mut done = 0u64;
+
+    for file in &manifest.siblings {
+        let dest = snapshot.join(&file.rfilename);
+        let size = file.size.unwrap_or(0);
+        if file.size.is_some() && std::fs::metadata(&dest).is_ok_and(|meta| meta.len() == size) {
+            done += size;
+            continue;
+        }
+
+        let download_id = format!("model:{}:{}", model.id, file.rfilename);
+        downloads::start(
+            app,
+            download_id.clone(),
+            format!(
+                "{}/{}/resolve/{}/{}",
+                HF_URL, model.repo, manifest.sha, file.rfilename
+            ),
+            dest.to_string_lossy().into_owned(),
+            file.lfs.as_ref().map(|lfs| lfs.sha256.clone()),
+            hf_token.clone(),
+        )?;
+
+        loop {
+            tokio::time::sleep(PROGRESS_INTERVAL).await;
+            let info = downloads::info(app, &download_id)
+                .ok_or_else(|| format!("{} was cancelled", file.rfilename))?;
+            let _ = app.emit(
+                "model-download-progress",
+                ModelDownloadProgress {
+                    model: model.id,
+                    file: file.rfilename.clone(),
+                    downloaded: done + info.downloaded,
+                    total,
+                },
+            );
+            match info.status {
+                DownloadStatus::Completed => break,
+                DownloadStatus::Failed => {
+                    return Err(info.error.unwrap_or_else(|| "Download failed".to_string()));
+                }
+                // Resuming goes through download_model again; finished files are skipped.
+                DownloadStatus::Paused => return Err("Download paused".to_string()),
+                DownloadStatus::Downloading | DownloadStatus::Verifying => {}
+            }
+        }
+        done += size;
+    }
+
+    let refs = dir.join("refs");
+    std::fs::create_dir_all(&refs).map_err(|e| format!("Failed to create model cache: {}", e))?;
+    paths::write_atomic(refs.join("main"), &manifest.sha)
+        .map_err(|e| format!("Failed to finish model download: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // 16-bit stereo: 4 bytes a frame.
+    #[test]
+    fn output_is_length_times_variations_times_frame_size() {
+        let config = json!({ "type": "loop", "length": 10.0, "variations": 3 });
+        assert_eq!(output_bytes(&config), 10 * 44100 * 4 * 3);
+        let config = json!({ "type": "one-shot", "length": 10.0, "variations": 2 });
+        assert_eq!(output_bytes(&config), 10 * 48000 * 4 * 2);
+    }
+
+    #[test]
+    fn zero_length_is_nothing() {
+        assert_eq!(output_bytes(&json!({ "type": "loop", "length": 0.0 })), 0);
+        assert_eq!(output_bytes(&json!({ "type": "loop", "length": -5.0 })), 0);
+    }
+
+    #[test]
+    fn missing_fields_take_the_backends_defaults() {
+        // Stable Audio's 2.5 s, one variation.
+        assert_eq!(output_bytes(&json!({ "type": "loop" })), 110250 * 4);
+        assert_eq!(
+            output_bytes(&json!({ "type": "one-shot", "variations": 0 })),
+            30 * 48000 * 4
+        );
+        // No type counts as a one-shot (model_for_type).
+        assert_eq!(output_bytes(&json!({})), 30 * 48000 * 4);
+        assert_eq!(
+            output_bytes(&json!({ "type": "one-shot", "length": "long" })),
+            30 * 48000 * 4
+        );
+    }
+
+    // Its segments are still on disk while they're stitched.
+    #[test]
+    fn segmented_requests_count_twice() {
+        let at_max = json!({ "type": "loop", "length": 47.0 });
+        assert_eq!(output_bytes(&at_max), 47 * 44100 * 4);
+        let over = json!({ "type": "loop", "length": 60.0 });
+        assert_eq!(output_bytes(&over), 60 * 44100 * 4 * 2);
+    }
+}
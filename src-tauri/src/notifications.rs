@@ -0,0 +1,102 @@
+use std::path::Path;
+
+use notify_rust::{Notification, NotificationResponse};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::tray;
+
+#[derive(Clone, Serialize)]
+struct SelectSound {
+    file: String,
+    path: String,
+}
+
+// Only worth interrupting for when the user isn't looking at the window.
+fn window_in_view(app: &AppHandle) -> bool {
+    app.get_webview_window("main").is_some_and(|w| {
+        w.is_visible().unwrap_or(false)
+            && !w.is_minimized().unwrap_or(false)
+            && w.is_focused().unwrap_or(false)
+    })
+}
+
+// Called by the job manager when a generation ends. Clicking a success
+// notification focuses the app and emits `select-sound` for the new file.
+pub fn job_finished(app: &AppHandle, prompt: &str, result: &Result<Value, String>) {
+    // The CLI (cli.rs) has no window and reports on the terminal instead.
+    if app.get_webview_window("main").is_none() || window_in_view(app) {
+        return;
+    }
+
+    let mut notification = Notification::new();
+    // "default" is what XDG servers report for a click on the body.
+    notification.appname("Noises").action("default", "Show");
+    // Unpackaged dev builds have no registered AUMID, so let notify-rust use its fallback.
+    #[cfg(all(target_os = "windows", not(debug_assertions)))]
+    notification.app_id(&app.config().identifier);
+
+    let selected = match result {
+        Ok(value) => {
+            let first = value
+                .get("files")
+                .and_then(|f| f.as_array())
+                .and_then(|files| files.first());
+            let path = first
+                .and_then(|f| f.get("path"))
+                .and_then(|p| p.as_str())
+                .unwrap_or_default()
+                .to_string();
+            let file = first
+                .and_then(|f| f.get("file"))
+                .and_then(|f| f.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| {
+                    Path::new(&path)
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default()
+                });
+            notification
+                .summary("Sound ready")
+                .body(&format!("{}\n{}", file, prompt));
+            Some(SelectSound { file, path })
+        }
+        Err(e) => {
+            notification.summary("Generation failed").body(e);
+            None
+        }
+    };
+
+    // Waiting for the click blocks, so each notification gets its own thread.
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let handle = match notification.show() {
+            Ok(handle) => handle,
+            Err(e) => {
+                eprintln!("[Notifications] Failed to show notification: {}", e);
+                return;
+            }
+        };
+        let _ = handle.wait_for_response(move |response: &NotificationResponse| {
+            if !matches!(response, NotificationResponse::Default) {
+                return;
+            }
+            tray::show_main_window(&app);
+            if let Some(selected) = selected {
+                let _ = app.emit("select-sound", selected);
+            }
+        });
+    });
+}
+
+// macOS attributes notifications to a bundle id; without this they show up as Terminal's.
+pub fn init(app: &AppHandle) {
+    #[cfg(target_os = "macos")]
+    if let Err(e) = notify_rust::set_application(&app.config().identifier) {
+        eprintln!("[Notifications] Failed to set application: {}", e);
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = app;
+}
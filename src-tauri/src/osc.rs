@@ -0,0 +1,346 @@
+use std::sync::Mutex;
+
+use serde_json::json;
+use tauri::{AppHandle, Manager};
+use tokio::net::UdpSocket;
+use tokio_util::sync::CancellationToken;
+
+use crate::jobs;
+use crate::library;
+use crate::models;
+use crate::playback::PlaybackEngine;
+use crate::settings;
+
+// Larger than any message we act on; bigger datagrams are cut off and then
+// fail to decode.
+const MAX_PACKET: usize = 8 * 1024;
+
+// An optional OSC listener on 127.0.0.1, for live sets and DAW environments
+// (Max, Pure Data, TouchOSC through a bridge, ...):
+//
+//   /noises/generate "prompt" ["loop"|"one-shot"] [length]
+//                            queues a generation like the form does
+//   /noises/play <n>         plays the nth newest library sound (1 = newest)
+//   /noises/play "file"      plays a library sound by file name or path
+//   /noises/stop             stops playback
+//
+// Bundles are unpacked and run straight away; their timetags are ignored.
+#[derive(Default)]
+pub struct OscServer {
+    running: Mutex<Option<CancellationToken>>,
+}
+
+enum Arg {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    // Types with nothing we'd act on (booleans, blobs, ...).
+    Other,
+}
+
+impl Arg {
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Arg::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Arg::Int(i) => Some(*i as f64),
+            Arg::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+}
+
+struct Message {
+    address: String,
+    args: Vec<Arg>,
+}
+
+// Starts the listener if the setting is on. Called from setup and whenever
+// osc_enabled or osc_port change.
+pub fn restart(app: &AppHandle) {
+    let osc = app.state::<OscServer>();
+    if let Some(running) = osc.running.lock().unwrap().take() {
+        running.cancel();
+    }
+    let settings = settings::get(app);
+    if !settings.osc_enabled {
+        return;
+    }
+
+    let cancel = CancellationToken::new();
+    *osc.running.lock().unwrap() = Some(cancel.clone());
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let socket = match UdpSocket::bind(("127.0.0.1", settings.osc_port)).await {
+            Ok(socket) => socket,
+            Err(e) => {
+                eprintln!(
+                    "[Osc] Failed to listen on port {}: {}",
+                    settings.osc_port, e
+                );
+                return;
+            }
+        };
+        println!("[Osc] Listening on 127.0.0.1:{}", settings.osc_port);
+        let mut buffer = vec![0u8; MAX_PACKET];
+        loop {
+            let received = tokio::select! {
+                _ = cancel.cancelled() => break,
+                received = socket.recv_from(&mut buffer) => received,
+            };
+            let Ok((len, _)) = received else { continue };
+            let mut messages = Vec::new();
+            if let Err(e) = decode(&buffer[..len], &mut messages) {
+                eprintln!("[Osc] Ignoring packet: {}", e);
+                continue;
+            }
+            for message in messages {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = dispatch(&app, message).await {
+                        eprintln!("[Osc] {}", e);
+                    }
+                });
+            }
+        }
+        println!("[Osc] Stopped");
+    });
+}
+
+async fn dispatch(app: &AppHandle, message: Message) -> Result<(), String> {
+    let first = message.args.first();
+    match message.address.as_str() {
+        "/noises/generate" => {
+            let prompt = first
+                .and_then(Arg::as_str)
+                .ok_or("/noises/generate needs a prompt")?;
+            let kind = message
+                .args
+                .get(1)
+                .and_then(Arg::as_str)
+                .unwrap_or_else(|| models::default_generation_type(app));
+            let mut config = json!({ "type": kind, "prompt": prompt, "variations": 1 });
+            if let Some(length) = message.args.get(2).and_then(Arg::as_f64) {
+                config["length"] = json!(length);
+            }
+            println!("[Osc] Generating \"{}\"", prompt);
+            jobs::generate(app, config).await.map(|_| ())
+        }
+        "/noises/play" => {
+            let scan_app = app.clone();
+            let items = tauri::async_runtime::spawn_blocking(move || library::scan(&scan_app))
+                .await
+                .map_err(|e| format!("Library scan failed: {}", e))?;
+            let item = match first {
+                Some(Arg::Int(n)) if *n >= 1 => items
+                    .get(*n as usize - 1)
+                    .ok_or_else(|| format!("The library has no sound #{}", n))?,
+                Some(Arg::Str(name)) => items
+                    .iter()
+                    .find(|item| item.file == *name || item.path == *name)
+                    .ok_or_else(|| format!("No library sound named {}", name))?,
+                _ => return Err("/noises/play needs a number or a file name".to_string()),
+            };
+            app.state::<PlaybackEngine>().play(item.path.clone()).await
+        }
+        "/noises/stop" => app.state::<PlaybackEngine>().stop(),
+        address => Err(format!("Unknown address {}", address)),
+    }
+}
+
+// OSC 1.0: strings are NUL-terminated and padded to four bytes, numbers are
+// big-endian.
+fn decode(packet: &[u8], out: &mut Vec<Message>) -> Result<(), String> {
+    let mut reader = Reader {
+        data: packet,
+        pos: 0,
+    };
+    if packet.starts_with(b"#bundle\0") {
+        reader.string()?;
+        reader.take(8)?; // timetag
+        while reader.pos < packet.len() {
+            let size = reader.i32()?;
+            let size = usize::try_from(size).map_err(|_| "Negative bundle element size")?;
+            decode(reader.take(size)?, out)?;
+        }
+        return Ok(());
+    }
+
+    let address = reader.string()?;
+    if !address.starts_with('/') {
+        return Err(format!("Not an OSC address: {}", address));
+    }
+    // Tags are optional in old senders; no tags means no arguments.
+    let tags = if reader.pos < packet.len() {
+        reader.string()?
+    } else {
+        String::new()
+    };
+    let mut args = Vec::new();
+    for tag in tags.chars().skip_while(|&c| c == ',') {
+        args.push(match tag {
+            'i' => Arg::Int(reader.i32()? as i64),
+            'h' => Arg::Int(i64::from_be_bytes(reader.array()?)),
+            'f' => Arg::Float(f32::from_be_bytes(reader.array()?) as f64),
+            'd' => Arg::Float(f64::from_be_bytes(reader.array()?)),
+            's' | 'S' => Arg::Str(reader.string()?),
+            'T' | 'F' | 'N' | 'I' => Arg::Other,
+            'b' => {
+                let size = usize::try_from(reader.i32()?).map_err(|_| "Negative blob size")?;
+                reader.take(size.div_ceil(4) * 4)?;
+                Arg::Other
+            }
+            'c' | 'r' | 'm' | 't' => {
+                reader.take(if tag == 't' { 8 } else { 4 })?;
+                Arg::Other
+            }
+            other => return Err(format!("Unsupported argument type '{}'", other)),
+        });
+    }
+    out.push(Message { address, args });
+    Ok(())
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or("Packet is truncated")?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let bytes = self.take(N)?;
+        Ok(bytes.try_into().unwrap_or([0; N]))
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_be_bytes(self.array()?))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let rest = &self.data[self.pos..];
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or("Unterminated string")?;
+        let text = String::from_utf8_lossy(&rest[..len]).into_owned();
+        self.take((len + 1).div_ceil(4) * 4)?;
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(s: &str) -> Vec<u8> {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.push(0);
+        bytes.resize(bytes.len().div_ceil(4) * 4, 0);
+        bytes
+    }
+
+    fn message(address: &str, tags: &str, args: &[&[u8]]) -> Vec<u8> {
+        let mut packet = string(address);
+        packet.extend(string(tags));
+        for arg in args {
+            packet.extend_from_slice(arg);
+        }
+        packet
+    }
+
+    fn decoded(packet: &[u8]) -> Result<Vec<Message>, String> {
+        let mut out = Vec::new();
+        decode(packet, &mut out).map(|_| out)
+    }
+
+    #[test]
+    fn decodes_the_address_and_arguments() {
+        let packet = message(
+            "/noises/generate",
+            ",sfi",
+            &[
+                &string("dusty drums"),
+                &4.5f32.to_be_bytes(),
+                &(-2i32).to_be_bytes(),
+            ],
+        );
+        let messages = decoded(&packet).unwrap();
+        assert_eq!(messages.len(), 1);
+        let message = &messages[0];
+        assert_eq!(message.address, "/noises/generate");
+        assert_eq!(message.args[0].as_str(), Some("dusty drums"));
+        assert_eq!(message.args[1].as_f64(), Some(4.5));
+        assert_eq!(message.args[2].as_f64(), Some(-2.0));
+    }
+
+    #[test]
+    fn wide_numbers_and_ignored_types_keep_their_place() {
+        let packet = message(
+            "/noises/play",
+            ",hbTd",
+            &[
+                &7i64.to_be_bytes(),
+                &[0, 0, 0, 5, 1, 2, 3, 4, 5, 0, 0, 0],
+                &0.25f64.to_be_bytes(),
+            ],
+        );
+        let args = &decoded(&packet).unwrap()[0].args;
+        assert!(matches!(args[0], Arg::Int(7)));
+        assert!(matches!(args[1], Arg::Other));
+        assert!(matches!(args[2], Arg::Other));
+        assert_eq!(args[3].as_f64(), Some(0.25));
+    }
+
+    #[test]
+    fn messages_without_tags_have_no_arguments() {
+        let messages = decoded(&string("/noises/stop")).unwrap();
+        assert_eq!(messages[0].address, "/noises/stop");
+        assert!(messages[0].args.is_empty());
+    }
+
+    #[test]
+    fn bundles_are_unpacked_in_order() {
+        let first = message("/noises/play", ",i", &[&1i32.to_be_bytes()]);
+        let second = string("/noises/stop");
+        let mut packet = string("#bundle");
+        packet.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        for element in [&first, &second] {
+            packet.extend_from_slice(&(element.len() as i32).to_be_bytes());
+            packet.extend_from_slice(element);
+        }
+        let addresses: Vec<String> = decoded(&packet)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.address)
+            .collect();
+        assert_eq!(addresses, ["/noises/play", "/noises/stop"]);
+    }
+
+    #[test]
+    fn refuses_broken_packets() {
+        assert!(decoded(&string("noises/stop")).is_err());
+        assert!(decoded(b"/noises/stop").is_err());
+        assert!(decoded(&message("/noises/play", ",i", &[&[0, 1]])).is_err());
+        assert!(decoded(&message("/noises/play", ",x", &[])).is_err());
+        let mut bundle = string("#bundle");
+        bundle.extend_from_slice(&[0; 8]);
+        bundle.extend_from_slice(&(-4i32).to_be_bytes());
+        assert!(decoded(&bundle).is_err());
+    }
+}
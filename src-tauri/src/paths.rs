@@ -0,0 +1,246 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tauri::{AppHandle, Manager};
+
+use crate::workspaces;
+
+// Same layout as backend/config.py: everything lives under ~/Music/Noises so we
+// never write inside the app bundle. That's the default workspace's root.
+pub fn default_output_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let home = app
+        .path()
+        .home_dir()
+        .map_err(|e| format!("Failed to resolve home directory: {}", e))?;
+    Ok(home.join("Music").join("Noises"))
+}
+
+// Paths from the frontend, tidied up before they touch the file system. On
+// Windows a path may arrive with forward slashes (joined in JS) or in the
+// extended-length \\?\ form (from an earlier canonicalize); the two don't mix,
+// since \\?\ turns off slash handling, so both are brought back to the plain
+// form. The standard library adds the prefix itself for long paths, and
+// Explorer and the UI don't understand it.
+pub fn normalize(path: &str) -> PathBuf {
+    #[cfg(windows)]
+    let path = path.replace('/', "\\");
+    dunce::simplified(Path::new(&path)).to_path_buf()
+}
+
+// canonicalize() without the \\?\ prefix wherever Windows allows it, so the
+// result can be shown, compared with plain paths and handed to other
+// programs.
+pub fn canonical(path: &Path) -> std::io::Result<PathBuf> {
+    dunce::canonicalize(path)
+}
+
+// The active workspace's root (see workspaces.rs).
+pub fn output_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    match workspaces::active_root(app) {
+        Some(root) => Ok(root),
+        None => default_output_dir(app),
+    }
+}
+
+pub fn recordings_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(output_dir(app)?.join("recordings"))
+}
+
+pub fn imports_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(output_dir(app)?.join("imports"))
+}
+
+// Mirrors get_next_filename() in backend/audio/utils.py: prefix_001.wav, prefix_002.wav, ...
+pub fn next_filename(dir: &Path, prefix: &str, extension: &str) -> String {
+    let mut max_count = 0;
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let Some(stem) = name.strip_suffix(extension) else {
+                continue;
+            };
+            let Some(count) = stem.strip_prefix(prefix).and_then(|s| s.strip_prefix('_')) else {
+                continue;
+            };
+            if let Ok(count) = count.parse::<u32>() {
+                max_count = max_count.max(count);
+            }
+        }
+    }
+    format!("{}_{:03}{}", prefix, max_count + 1, extension)
+}
+
+pub fn loops_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(output_dir(app)?.join("samples").join("loops"))
+}
+
+pub fn oneshots_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(output_dir(app)?.join("samples").join("oneshots"))
+}
+
+// Scratch space for audio that is still being generated. Wiped on startup.
+pub fn partial_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let cache = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to resolve cache directory: {}", e))?;
+    Ok(cache.join("partial"))
+}
+
+// rename() can't cross volumes (cache dir vs. ~/Music), so fall back to copy + delete.
+pub fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if let Err(e) = copy_atomic(from, to) {
+        return Err(format!("Failed to move file: {}", e));
+    }
+    let _ = std::fs::remove_file(from);
+    Ok(())
+}
+
+// A hidden name next to the target, so the rename never crosses volumes.
+// Not audio, so the library never lists one left behind by a crash.
+pub fn temp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        name,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+// Everything we write goes through here: the data lands in a temporary file,
+// is flushed to disk, and only then renamed over the target, so a crash or a
+// full disk leaves either the old file or the new one, never half of one.
+pub fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let path = path.as_ref();
+    let temp = temp_path(path);
+    let written = (|| {
+        let mut file = std::fs::File::create(&temp)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        std::fs::rename(&temp, path)
+    })();
+    if written.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    written
+}
+
+// std::fs::copy the way write_atomic writes: into a temporary file next to
+// the target, flushed, then renamed over it.
+pub fn copy_atomic(from: impl AsRef<Path>, to: impl AsRef<Path>) -> std::io::Result<u64> {
+    let to = to.as_ref();
+    let temp = temp_path(to);
+    let copied = (|| {
+        let bytes = std::fs::copy(from, &temp)?;
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&temp)?
+            .sync_all()?;
+        std::fs::rename(&temp, to)?;
+        Ok(bytes)
+    })();
+    if copied.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    copied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(path: &Path) -> Vec<String> {
+        path.components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn keeps_emoji_and_cjk_names() {
+        let path = normalize("samples/🥁 ドラム/鼓の音 🎶.wav");
+        assert_eq!(names(&path), ["samples", "🥁 ドラム", "鼓の音 🎶.wav"]);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn mixed_separators_become_backslashes() {
+        assert_eq!(
+            normalize(r"C:\Users/me\Music/Noises/samples\loops/loop_001.wav"),
+            PathBuf::from(r"C:\Users\me\Music\Noises\samples\loops\loop_001.wav")
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn strips_the_extended_prefix() {
+        assert_eq!(
+            normalize(r"\\?\C:\Music\Noises\鼓 🥁.wav"),
+            PathBuf::from(r"C:\Music\Noises\鼓 🥁.wav")
+        );
+        // Slashes would mean nothing after the prefix; they're turned first.
+        assert_eq!(
+            normalize(r"\\?\C:/Music/Noises/kick.wav"),
+            PathBuf::from(r"C:\Music\Noises\kick.wav")
+        );
+    }
+
+    // Left alone where the plain form would mean something else.
+    #[cfg(windows)]
+    #[test]
+    fn keeps_the_prefix_it_needs() {
+        for path in [
+            r"\\?\UNC\server\share\kick.wav",
+            r"\\?\C:\Music\con.wav",
+            r"\\?\C:\Music\..\kick.wav",
+        ] {
+            assert_eq!(normalize(path), PathBuf::from(path));
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn backslashes_are_part_of_names_elsewhere() {
+        let path = normalize(r"samples\loops/loop_001.wav");
+        assert_eq!(names(&path), [r"samples\loops", "loop_001.wav"]);
+    }
+
+    #[test]
+    fn canonical_paths_are_plain() {
+        let dir = std::env::temp_dir().join(format!("noises-canonical-{}", std::process::id()));
+        let folder = dir.join("🥁 ドラム");
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("鼓.wav"), b"sound").unwrap();
+
+        let path = canonical(&folder.join("..").join("🥁 ドラム").join("鼓.wav"));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = path.unwrap();
+        assert!(!path.to_string_lossy().starts_with(r"\\?\"));
+        assert!(path.ends_with(Path::new("🥁 ドラム").join("鼓.wav")));
+        assert!(path.is_absolute());
+    }
+
+    #[test]
+    fn copies_leave_no_temporary_file() {
+        let dir = std::env::temp_dir().join(format!("noises-copy-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let from = dir.join("kick.wav");
+        let to = dir.join("kick copy.wav");
+        std::fs::write(&from, b"sound").unwrap();
+        std::fs::write(&to, b"old").unwrap();
+
+        assert_eq!(copy_atomic(&from, &to).unwrap(), 5);
+        assert_eq!(std::fs::read(&to).unwrap(), b"sound");
+        assert!(copy_atomic(dir.join("missing.wav"), &to).is_err());
+        assert_eq!(std::fs::read(&to).unwrap(), b"sound");
+        let left: Vec<_> = std::fs::read_dir(&dir).unwrap().flatten().collect();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(left.len(), 2);
+    }
+}
@@ -0,0 +1,695 @@
+use std::collections::VecDeque;
+use std::ffi::c_void;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError};
+use std::time::Duration;
+
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source, cpal};
+use serde::Serialize;
+use serde_json::json;
+use souvlaki::{MediaControlEvent, MediaControls, MediaMetadata, MediaPlayback, PlatformConfig};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::oneshot;
+
+use crate::meter::{Meter, MeterFrame};
+use crate::paths;
+use crate::power::{PowerGuard, PowerLease};
+use crate::settings;
+
+// How often the engine thread wakes up to check whether the current sound ended.
+const ENGINE_TICK: Duration = Duration::from_millis(50);
+
+// Upper bound for decoded audio kept in memory by preload_audio (~64 MB of f32
+// samples). That's several minutes of stereo audio, far more than a page of SFX.
+const PRELOAD_CACHE_BYTES: usize = 64 * 1024 * 1024;
+
+enum PlaybackCommand {
+    Play {
+        path: String,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    Preload {
+        path: String,
+        audio: DecodedAudio,
+    },
+    Pause,
+    Resume,
+    Toggle,
+    Stop,
+    SetVolume(f32),
+    // None stops mirroring.
+    SetCueDevice(Option<String>),
+}
+
+// Fully decoded file. Samples are shared so starting playback never copies them.
+#[derive(Clone)]
+struct DecodedAudio {
+    channels: u16,
+    sample_rate: u32,
+    samples: Arc<[f32]>,
+}
+
+impl DecodedAudio {
+    fn decode(path: &str) -> Result<Self, String> {
+        let source = open_source(path)?;
+        let channels = source.channels();
+        let sample_rate = source.sample_rate();
+        // Stop decoding as soon as we know it won't fit instead of decoding a whole hour.
+        let max_samples = PRELOAD_CACHE_BYTES / std::mem::size_of::<f32>();
+        let samples: Vec<f32> = source.take(max_samples + 1).collect();
+        if samples.len() > max_samples {
+            return Err("File is too large to preload".to_string());
+        }
+        Ok(DecodedAudio {
+            channels,
+            sample_rate,
+            samples: samples.into(),
+        })
+    }
+
+    fn byte_len(&self) -> usize {
+        self.samples.len() * std::mem::size_of::<f32>()
+    }
+}
+
+// Small LRU of preloaded files, owned by the engine thread.
+#[derive(Default)]
+struct PreloadCache {
+    entries: VecDeque<(String, DecodedAudio)>,
+    bytes: usize,
+}
+
+impl PreloadCache {
+    fn get(&mut self, path: &str) -> Option<DecodedAudio> {
+        let index = self.entries.iter().position(|(p, _)| p == path)?;
+        // Move to the back so the most recently used entries survive eviction.
+        let entry = self.entries.remove(index)?;
+        let audio = entry.1.clone();
+        self.entries.push_back(entry);
+        Some(audio)
+    }
+
+    fn insert(&mut self, path: String, audio: DecodedAudio) {
+        if let Some(index) = self.entries.iter().position(|(p, _)| *p == path)
+            && let Some((_, old)) = self.entries.remove(index)
+        {
+            self.bytes -= old.byte_len();
+        }
+        self.bytes += audio.byte_len();
+        self.entries.push_back((path, audio));
+        while self.bytes > PRELOAD_CACHE_BYTES {
+            match self.entries.pop_front() {
+                Some((_, old)) => self.bytes -= old.byte_len(),
+                None => break,
+            }
+        }
+    }
+}
+
+// Plays a preloaded buffer from memory.
+struct MemorySource {
+    audio: DecodedAudio,
+    position: usize,
+}
+
+impl Iterator for MemorySource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.audio.samples.get(self.position).copied()?;
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for MemorySource {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.audio.samples.len() - self.position)
+    }
+
+    fn channels(&self) -> u16 {
+        self.audio.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.audio.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        let frames = self.audio.samples.len() / self.audio.channels.max(1) as usize;
+        Some(Duration::from_secs_f64(
+            frames as f64 / self.audio.sample_rate as f64,
+        ))
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), rodio::source::SeekError> {
+        let channels = self.audio.channels.max(1) as usize;
+        let frame = (pos.as_secs_f64() * self.audio.sample_rate as f64) as usize;
+        self.position = (frame * channels).min(self.audio.samples.len());
+        Ok(())
+    }
+}
+
+// Handle to the playback thread. rodio's OutputStream is !Send, so the
+// device and the sink live on a dedicated thread and we talk to it via a channel.
+pub struct PlaybackEngine {
+    tx: Sender<PlaybackCommand>,
+}
+
+impl PlaybackEngine {
+    pub fn new(app: AppHandle, power: PowerGuard) -> Self {
+        let (tx, rx) = mpsc::channel();
+        // Bounded so a stalled webview can't make the audio thread pile up frames.
+        let (meter_tx, meter_rx) = mpsc::sync_channel::<MeterFrame>(8);
+
+        let meter_app = app.clone();
+        std::thread::spawn(move || {
+            while let Ok(frame) = meter_rx.recv() {
+                let _ = meter_app.emit("playback-meter", frame);
+            }
+        });
+
+        let cue_device = settings::get(&app).cue_device;
+        let window_handle = main_window_handle(&app);
+        let engine_tx = tx.clone();
+        std::thread::spawn(move || {
+            let controls = create_media_controls(&app, window_handle, engine_tx);
+            Engine {
+                app,
+                meter_tx,
+                output: None,
+                sink: None,
+                cue_device,
+                cue_output: None,
+                cue_sink: None,
+                cache: PreloadCache::default(),
+                controls,
+                current: None,
+                power,
+                awake: None,
+                channels: 2,
+                volume: 1.0,
+            }
+            .run(rx)
+        });
+
+        PlaybackEngine { tx }
+    }
+
+    pub async fn play(&self, path: String) -> Result<(), String> {
+        let (reply, result) = oneshot::channel();
+        self.send(PlaybackCommand::Play { path, reply })?;
+        result
+            .await
+            .map_err(|_| "Playback engine stopped unexpectedly".to_string())?
+    }
+
+    pub fn stop(&self) -> Result<(), String> {
+        self.send(PlaybackCommand::Stop)
+    }
+
+    fn send(&self, cmd: PlaybackCommand) -> Result<(), String> {
+        self.tx
+            .send(cmd)
+            .map_err(|_| "Playback engine is not running".to_string())
+    }
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum PlaybackState {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+#[derive(Clone, Serialize)]
+struct PlaybackStatus {
+    state: PlaybackState,
+    path: Option<String>,
+}
+
+// Everything owned by the playback thread.
+struct Engine {
+    app: AppHandle,
+    meter_tx: SyncSender<MeterFrame>,
+    // The output device is opened lazily on the first play (or preload) request.
+    output: Option<(OutputStream, OutputStreamHandle)>,
+    sink: Option<Sink>,
+    // A second device that gets the same preview (the `cue_device` setting),
+    // e.g. headphones next to the stream mix. Opened on the first play.
+    cue_device: Option<String>,
+    cue_output: Option<(OutputStream, OutputStreamHandle)>,
+    cue_sink: Option<Sink>,
+    cache: PreloadCache,
+    controls: Option<MediaControls>,
+    current: Option<String>,
+    power: PowerGuard,
+    // Held while audio is actually playing so long previews don't get cut by sleep.
+    awake: Option<PowerLease>,
+    channels: usize,
+    volume: f32,
+}
+
+impl Engine {
+    fn run(mut self, rx: Receiver<PlaybackCommand>) {
+        loop {
+            match rx.recv_timeout(ENGINE_TICK) {
+                Ok(PlaybackCommand::Play { path, reply }) => match self.play(&path) {
+                    Ok(()) => {
+                        println!("[Playback] Playing {}", path);
+                        self.current = Some(path);
+                        self.publish(PlaybackState::Playing);
+                        let _ = reply.send(Ok(()));
+                    }
+                    Err(e) => {
+                        self.current = None;
+                        self.publish(PlaybackState::Stopped);
+                        let _ = reply.send(Err(e));
+                    }
+                },
+                Ok(PlaybackCommand::Preload { path, audio }) => {
+                    // Warm up the device too, so the first play after a preload is instant.
+                    if let Err(e) = ensure_output(&mut self.output) {
+                        eprintln!("[Playback] {}", e);
+                    }
+                    self.cache.insert(path, audio);
+                }
+                Ok(PlaybackCommand::Pause) => {
+                    if let Some(s) = &self.sink {
+                        s.pause();
+                        self.for_cue(Sink::pause);
+                        self.silence();
+                        self.publish(PlaybackState::Paused);
+                    }
+                }
+                Ok(PlaybackCommand::Resume) => {
+                    if let Some(s) = &self.sink {
+                        s.play();
+                        self.for_cue(Sink::play);
+                        self.publish(PlaybackState::Playing);
+                    }
+                }
+                Ok(PlaybackCommand::Toggle) => {
+                    if let Some(s) = &self.sink {
+                        if s.is_paused() {
+                            s.play();
+                            self.for_cue(Sink::play);
+                            self.publish(PlaybackState::Playing);
+                        } else {
+                            s.pause();
+                            self.for_cue(Sink::pause);
+                            self.silence();
+                            self.publish(PlaybackState::Paused);
+                        }
+                    }
+                }
+                Ok(PlaybackCommand::Stop) => {
+                    if let Some(s) = self.sink.take() {
+                        s.stop();
+                        self.stop_cue();
+                        self.silence();
+                        self.publish(PlaybackState::Stopped);
+                    }
+                }
+                Ok(PlaybackCommand::SetVolume(v)) => {
+                    self.volume = v;
+                    if let Some(s) = &self.sink {
+                        s.set_volume(v);
+                    }
+                    self.for_cue(|s| s.set_volume(v));
+                }
+                Ok(PlaybackCommand::SetCueDevice(device)) => {
+                    self.stop_cue();
+                    self.cue_output = None;
+                    self.cue_device = device;
+                    // Picks up a preview that's already running where it is.
+                    if let (Some(path), Some(sink)) = (self.current.clone(), &self.sink) {
+                        let (position, paused) = (sink.get_pos(), sink.is_paused());
+                        self.mirror(&path, position);
+                        if paused {
+                            self.for_cue(Sink::pause);
+                        }
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if self.sink.as_ref().is_some_and(|s| s.empty()) {
+                        self.sink = None;
+                        self.stop_cue();
+                        self.silence();
+                        self.publish(PlaybackState::Stopped);
+                        let _ = self.app.emit("playback-finished", ());
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    }
+
+    fn play(&mut self, path: &str) -> Result<(), String> {
+        if let Some(old) = self.sink.take() {
+            old.stop();
+        }
+        self.stop_cue();
+
+        ensure_output(&mut self.output)?;
+        let (_, handle) = self.output.as_ref().unwrap();
+
+        let source: Box<dyn Source<Item = f32> + Send> = match self.cache.get(path) {
+            Some(audio) => Box::new(MemorySource { audio, position: 0 }),
+            None => Box::new(open_source(path)?),
+        };
+
+        let sink =
+            Sink::try_new(handle).map_err(|e| format!("Failed to create audio sink: {}", e))?;
+        self.channels = source.channels() as usize;
+        sink.set_volume(self.volume);
+        sink.append(LevelMeter::new(source, self.meter_tx.clone()));
+        self.sink = Some(sink);
+        self.mirror(path, Duration::ZERO);
+        Ok(())
+    }
+
+    // Starts the same sound on the cue device, from `position`. The cue is
+    // only a monitor: if it can't play, the main output still does.
+    fn mirror(&mut self, path: &str, position: Duration) {
+        let Some(device) = self.cue_device.clone() else {
+            return;
+        };
+        if let Err(e) = self.start_cue(&device, path, position) {
+            eprintln!("[Playback] Cue output {}: {}", device, e);
+        }
+    }
+
+    fn start_cue(&mut self, device: &str, path: &str, position: Duration) -> Result<(), String> {
+        if self.cue_output.is_none() {
+            self.cue_output = Some(open_device(device)?);
+        }
+        let (_, handle) = self.cue_output.as_ref().unwrap();
+
+        let mut source: Box<dyn Source<Item = f32> + Send> = match self.cache.get(path) {
+            Some(audio) => Box::new(MemorySource { audio, position: 0 }),
+            None => Box::new(open_source(path)?),
+        };
+        if !position.is_zero() {
+            source
+                .try_seek(position)
+                .map_err(|e| format!("Failed to seek: {}", e))?;
+        }
+
+        let sink =
+            Sink::try_new(handle).map_err(|e| format!("Failed to create audio sink: {}", e))?;
+        sink.set_volume(self.volume);
+        sink.append(source);
+        self.cue_sink = Some(sink);
+        Ok(())
+    }
+
+    fn for_cue(&self, f: impl FnOnce(&Sink)) {
+        if let Some(sink) = &self.cue_sink {
+            f(sink);
+        }
+    }
+
+    fn stop_cue(&mut self) {
+        if let Some(sink) = self.cue_sink.take() {
+            sink.stop();
+        }
+    }
+
+    // Drops the UI meters to zero; the meter source stops producing frames while idle.
+    fn silence(&self) {
+        let _ = self.meter_tx.try_send(MeterFrame::silent(self.channels));
+    }
+
+    // Keeps the frontend and the OS media overlay in sync, including changes
+    // that came from media keys rather than the UI.
+    fn publish(&mut self, state: PlaybackState) {
+        match state {
+            PlaybackState::Playing => {
+                if self.awake.is_none() {
+                    self.awake = Some(self.power.acquire());
+                }
+            }
+            PlaybackState::Paused | PlaybackState::Stopped => self.awake = None,
+        }
+
+        if let Some(controls) = self.controls.as_mut() {
+            let playback = match state {
+                PlaybackState::Playing => MediaPlayback::Playing { progress: None },
+                PlaybackState::Paused => MediaPlayback::Paused { progress: None },
+                PlaybackState::Stopped => MediaPlayback::Stopped,
+            };
+            if let PlaybackState::Playing = state {
+                let title = self.current.as_deref().map(sound_name);
+                let _ = controls.set_metadata(MediaMetadata {
+                    title: title.as_deref(),
+                    artist: Some("Noises"),
+                    ..Default::default()
+                });
+            }
+            let _ = controls.set_playback(playback);
+        }
+
+        let _ = self.app.emit(
+            "playback-state",
+            PlaybackStatus {
+                state,
+                path: self.current.clone(),
+            },
+        );
+    }
+}
+
+fn sound_name(path: &str) -> String {
+    std::path::Path::new(path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
+
+// SMTC needs the HWND of our window. Raw pointers aren't Send, so it crosses
+// into the playback thread as an integer.
+#[cfg(target_os = "windows")]
+fn main_window_handle(app: &AppHandle) -> Option<usize> {
+    let window = app.get_webview_window("main")?;
+    window.hwnd().ok().map(|hwnd| hwnd.0 as usize)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn main_window_handle(_app: &AppHandle) -> Option<usize> {
+    None
+}
+
+// Registers with SMTC (Windows) / MPRIS (Linux) so media keys and the OS media
+// overlay control the preview. Failing here only costs us the integration.
+fn create_media_controls(
+    app: &AppHandle,
+    window_handle: Option<usize>,
+    tx: Sender<PlaybackCommand>,
+) -> Option<MediaControls> {
+    if cfg!(target_os = "windows") && window_handle.is_none() {
+        eprintln!("[Playback] No window handle, media controls disabled");
+        return None;
+    }
+
+    let config = PlatformConfig {
+        display_name: "Noises",
+        dbus_name: "noises",
+        hwnd: window_handle.map(|h| h as *mut c_void),
+    };
+
+    let mut controls = match MediaControls::new(config) {
+        Ok(controls) => controls,
+        Err(e) => {
+            eprintln!("[Playback] Failed to create media controls: {:?}", e);
+            return None;
+        }
+    };
+
+    let app = app.clone();
+    let attached = controls.attach(move |event| {
+        let cmd = match event {
+            MediaControlEvent::Play => PlaybackCommand::Resume,
+            MediaControlEvent::Pause => PlaybackCommand::Pause,
+            MediaControlEvent::Toggle => PlaybackCommand::Toggle,
+            MediaControlEvent::Stop => PlaybackCommand::Stop,
+            MediaControlEvent::SetVolume(v) => PlaybackCommand::SetVolume(v.clamp(0.0, 1.0) as f32),
+            MediaControlEvent::Raise => {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+                return;
+            }
+            _ => return,
+        };
+        let _ = tx.send(cmd);
+    });
+
+    if let Err(e) = attached {
+        eprintln!("[Playback] Failed to attach media controls: {:?}", e);
+        return None;
+    }
+    Some(controls)
+}
+
+fn ensure_output(output: &mut Option<(OutputStream, OutputStreamHandle)>) -> Result<(), String> {
+    if output.is_none() {
+        let stream = OutputStream::try_default()
+            .map_err(|e| format!("Failed to open audio output: {}", e))?;
+        *output = Some(stream);
+    }
+    Ok(())
+}
+
+fn find_output_device(name: &str) -> Result<cpal::Device, String> {
+    cpal::default_host()
+        .output_devices()
+        .map_err(|e| format!("Failed to list output devices: {}", e))?
+        .find(|d| d.name().is_ok_and(|n| n == name))
+        .ok_or_else(|| format!("Output device not found: {}", name))
+}
+
+fn open_device(name: &str) -> Result<(OutputStream, OutputStreamHandle), String> {
+    let device = find_output_device(name)?;
+    OutputStream::try_from_device(&device)
+        .map_err(|e| format!("Failed to open audio output: {}", e))
+}
+
+fn open_source(path: &str) -> Result<impl Source<Item = f32> + Send + 'static, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+    let decoder = Decoder::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to decode audio file: {}", e))?;
+    Ok(decoder.convert_samples::<f32>())
+}
+
+// Pass-through source that feeds every sample through a Meter and hands the
+// frames to the emitter thread. Levels are pre-volume.
+struct LevelMeter<S> {
+    inner: S,
+    tx: SyncSender<MeterFrame>,
+    meter: Meter,
+}
+
+impl<S: Source<Item = f32>> LevelMeter<S> {
+    fn new(inner: S, tx: SyncSender<MeterFrame>) -> Self {
+        let meter = Meter::new(inner.channels(), inner.sample_rate());
+        LevelMeter { inner, tx, meter }
+    }
+
+    // Re-reads the format from the inner source, which may change between frames.
+    fn reset(&mut self) {
+        self.meter
+            .reset(self.inner.channels(), self.inner.sample_rate());
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for LevelMeter<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.inner.next()?;
+        if let Some(frame) = self.meter.push(sample) {
+            // Dropping a frame is fine; blocking the audio thread is not.
+            if let Err(TrySendError::Disconnected(_)) = self.tx.try_send(frame) {
+                eprintln!("[Playback] Meter channel closed");
+            }
+            self.reset();
+        }
+        Some(sample)
+    }
+}
+
+impl<S: Source<Item = f32>> Source for LevelMeter<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), rodio::source::SeekError> {
+        self.inner.try_seek(pos)?;
+        self.reset();
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub async fn play_audio(path: String, engine: State<'_, PlaybackEngine>) -> Result<(), String> {
+    engine
+        .play(paths::normalize(&path).to_string_lossy().into_owned())
+        .await
+}
+
+// Decodes a file into memory ahead of time (e.g. on hover) so a following
+// play_audio for the same path starts within a few milliseconds.
+#[tauri::command]
+pub async fn preload_audio(path: String, engine: State<'_, PlaybackEngine>) -> Result<(), String> {
+    let path = paths::normalize(&path).to_string_lossy().into_owned();
+    let decode_path = path.clone();
+    let audio = tauri::async_runtime::spawn_blocking(move || DecodedAudio::decode(&decode_path))
+        .await
+        .map_err(|e| format!("Preload task failed: {}", e))??;
+    engine.send(PlaybackCommand::Preload { path, audio })
+}
+
+#[tauri::command]
+pub fn pause_audio(engine: State<'_, PlaybackEngine>) -> Result<(), String> {
+    engine.send(PlaybackCommand::Pause)
+}
+
+#[tauri::command]
+pub fn resume_audio(engine: State<'_, PlaybackEngine>) -> Result<(), String> {
+    engine.send(PlaybackCommand::Resume)
+}
+
+#[tauri::command]
+pub fn toggle_audio(engine: State<'_, PlaybackEngine>) -> Result<(), String> {
+    engine.send(PlaybackCommand::Toggle)
+}
+
+#[tauri::command]
+pub fn stop_audio(engine: State<'_, PlaybackEngine>) -> Result<(), String> {
+    engine.stop()
+}
+
+// Passes a changed `cue_device` setting on to the engine thread.
+pub fn cue_device_changed(app: &AppHandle) {
+    let device = settings::get(app).cue_device;
+    if let Err(e) = app
+        .state::<PlaybackEngine>()
+        .send(PlaybackCommand::SetCueDevice(device))
+    {
+        eprintln!("[Playback] {}", e);
+    }
+}
+
+// Mirrors previews to a second output device (a name from
+// list_output_devices), on top of the default one. None turns it off.
+#[tauri::command]
+pub fn set_cue_device(app: AppHandle, device: Option<String>) -> Result<(), String> {
+    let device = device.filter(|name| !name.is_empty());
+    if let Some(name) = &device {
+        find_output_device(name)?;
+    }
+    settings::set(&app, "cue_device", json!(device))
+}
+
+#[tauri::command]
+pub fn set_playback_volume(volume: f32, engine: State<'_, PlaybackEngine>) -> Result<(), String> {
+    engine.send(PlaybackCommand::SetVolume(volume.clamp(0.0, 1.0)))
+}
@@ -0,0 +1,323 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tauri::{AppHandle, Manager};
+use tokio::io::AsyncWriteExt;
+
+use crate::history::{self, Derivation, HistoryEntry};
+use crate::hooks;
+use crate::paths;
+use crate::tasks::{self, TaskKind};
+
+// Community extensions without a fork: every folder in the app data dir's
+// plugins/ with a plugin.json is a plugin.
+//
+//   {
+//     "name": "Tape saturation",
+//     "command": "python saturate.py",
+//     "processors": [{ "id": "saturate", "label": "Saturate", "params": { "drive": 0.5 } }],
+//     "commands": [{ "id": "upload", "label": "Upload to my server" }]
+//   }
+//
+// The command runs in the plugin's folder once per request, started like a
+// post-generation hook (hooks.rs) and just as unconfined: installing a plugin
+// means trusting it with the user's account. It gets one JSON line on stdin:
+//
+//   {"action": "process", "processor": "saturate", "input": "/.../loop_001.wav",
+//    "output": "/.../loop_001_saturate_001.wav", "params": {...}}
+//   {"action": "command", "command": "upload", "args": {...}}
+//
+// and answers with a JSON object as the last line of stdout: {"result": ...}
+// or {"error": "..."}. Anything else it prints (stderr especially) is just
+// logged. Executables only for now; there's no WASM runtime in the build.
+const MANIFEST: &str = "plugin.json";
+
+fn default_timeout() -> u64 {
+    300
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProcessorInfo {
+    pub id: String,
+    pub label: String,
+    // Defaults the UI fills in, passed back as `params`.
+    #[serde(default)]
+    pub params: Value,
+    // For converters: the output's extension (e.g. "mp3"). Same as the input
+    // when missing.
+    #[serde(default)]
+    pub extension: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CommandInfo {
+    pub id: String,
+    pub label: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub processors: Vec<ProcessorInfo>,
+    #[serde(default)]
+    pub commands: Vec<CommandInfo>,
+    #[serde(default = "default_timeout")]
+    pub timeout_seconds: u64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Plugin {
+    // The folder name.
+    pub id: String,
+    #[serde(flatten)]
+    pub manifest: Manifest,
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+fn plugins_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve data directory: {}", e))?;
+    Ok(dir.join("plugins"))
+}
+
+// Read fresh each time, so dropping a plugin in needs no restart. A broken
+// manifest is logged and skipped.
+pub fn discover(app: &AppHandle) -> Vec<Plugin> {
+    let Ok(dir) = plugins_dir(app) else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let mut plugins: Vec<Plugin> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let json = std::fs::read_to_string(dir.join(MANIFEST)).ok()?;
+            match serde_json::from_str(&json) {
+                Ok(manifest) => Some(Plugin {
+                    id: entry.file_name().to_string_lossy().into_owned(),
+                    manifest,
+                    dir,
+                }),
+                Err(e) => {
+                    eprintln!("[Plugins] Ignoring {}: {}", dir.display(), e);
+                    None
+                }
+            }
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.manifest.name.cmp(&b.manifest.name));
+    plugins
+}
+
+fn find(app: &AppHandle, id: &str) -> Result<Plugin, String> {
+    discover(app)
+        .into_iter()
+        .find(|p| p.id == id)
+        .ok_or_else(|| format!("No plugin named {}", id))
+}
+
+impl Plugin {
+    async fn call(&self, request: Value) -> Result<Value, String> {
+        let words = hooks::split_command(&self.manifest.command);
+        let (program, args) = words
+            .split_first()
+            .ok_or_else(|| format!("{} has no command", self.manifest.name))?;
+        // A program shipped with the plugin, or else one on the PATH.
+        let bundled = self.dir.join(program);
+        let program = if bundled.is_file() {
+            bundled.to_string_lossy().into_owned()
+        } else {
+            program.clone()
+        };
+
+        let mut child = hooks::user_command(&program)
+            .args(args)
+            .current_dir(&self.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", self.manifest.name, e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            let line = format!("{}\n", request);
+            stdin
+                .write_all(line.as_bytes())
+                .await
+                .map_err(|e| format!("Failed to talk to {}: {}", self.manifest.name, e))?;
+        }
+
+        let timeout = Duration::from_secs(self.manifest.timeout_seconds.max(1));
+        let output = tokio::time::timeout(timeout, child.wait_with_output())
+            .await
+            .map_err(|_| {
+                format!(
+                    "{} didn't answer within {} s",
+                    self.manifest.name,
+                    timeout.as_secs()
+                )
+            })?
+            .map_err(|e| format!("{} failed: {}", self.manifest.name, e))?;
+        let stderr = hooks::tail(&output.stderr);
+        if !stderr.is_empty() {
+            println!("[Plugins] {}: {}", self.manifest.name, stderr);
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let reply: Option<Value> = stdout
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .and_then(|line| serde_json::from_str(line).ok());
+        match reply {
+            Some(reply) => match reply.get("error") {
+                Some(error) => Err(format!(
+                    "{}: {}",
+                    self.manifest.name,
+                    error
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or(error.to_string())
+                )),
+                None => Ok(reply.get("result").cloned().unwrap_or(Value::Null)),
+            },
+            None if !output.status.success() => Err(format!(
+                "{} exited with {}",
+                self.manifest.name, output.status
+            )),
+            None => Err(format!("{} didn't answer with JSON", self.manifest.name)),
+        }
+    }
+}
+
+#[tauri::command]
+pub fn list_plugins(app: AppHandle) -> Vec<Plugin> {
+    discover(&app)
+}
+
+// Runs a processor on a sound and returns the new file, saved next to the
+// input as <name>_<processor>_001.wav (or the processor's extension).
+#[tauri::command]
+pub async fn run_processor(
+    app: AppHandle,
+    plugin: String,
+    processor: String,
+    path: String,
+    params: Option<Value>,
+) -> Result<String, String> {
+    let plugin = find(&app, &plugin)?;
+    let info = plugin
+        .manifest
+        .processors
+        .iter()
+        .find(|p| p.id == processor)
+        .ok_or_else(|| format!("{} has no processor {}", plugin.manifest.name, processor))?;
+    let input = paths::normalize(&path);
+    if !input.is_file() {
+        return Err(format!("File not found: {}", path));
+    }
+    let dir = input.parent().unwrap_or(Path::new("."));
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = info
+        .extension
+        .clone()
+        .or_else(|| Some(input.extension()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "wav".to_string());
+    let output = dir.join(paths::next_filename(
+        dir,
+        &format!("{}_{}", stem, info.id),
+        &format!(".{}", extension.trim_start_matches('.')),
+    ));
+
+    println!(
+        "[Plugins] {} / {} on {}",
+        plugin.manifest.name,
+        info.label,
+        input.display()
+    );
+    let params = params.unwrap_or_else(|| info.params.clone());
+    let started = history::now_millis();
+    let task = tasks::begin(&app, TaskKind::Processing, &info.label);
+    let result = task
+        .run(plugin.call(json!({
+            "action": "process",
+            "processor": info.id,
+            "input": input,
+            "output": output,
+            "params": params,
+        })))
+        .await;
+    if let Err(e) = result {
+        // Whatever a failed or stopped plugin left behind is unfinished. The
+        // name was free before it ran, so the file can only be its own.
+        let _ = std::fs::remove_file(&output);
+        return Err(e);
+    }
+    if !output.is_file() {
+        return Err(format!(
+            "{} finished without writing {}",
+            plugin.manifest.name,
+            output.display()
+        ));
+    }
+
+    // The copy goes in the history as made from the input, so get_lineage
+    // leads from it back to the generation.
+    let output = output.to_string_lossy().into_owned();
+    // History has outputs as the app wrote them, which `path` from the
+    // frontend may not match character for character.
+    let source = history::find_output(&app, &input.to_string_lossy());
+    let entry = HistoryEntry {
+        id: format!("{}-{}", started, info.id),
+        prompt: source
+            .as_ref()
+            .map(|s| s.prompt.clone())
+            .unwrap_or_else(|| stem.into_owned()),
+        params,
+        model: format!("{}/{}", plugin.id, info.id),
+        started,
+        duration_seconds: (history::now_millis() - started) as f64 / 1000.0,
+        outputs: vec![output.clone()],
+        error: None,
+        rating: None,
+        parent: source.map(|s| s.id),
+        derivation: Some(Derivation::Process),
+        batch: None,
+    };
+    if let Err(e) = history::record(&app, &entry) {
+        eprintln!("[Plugins] {}", e);
+    }
+    Ok(output)
+}
+
+#[tauri::command]
+pub async fn run_plugin_command(
+    app: AppHandle,
+    plugin: String,
+    command: String,
+    args: Option<Value>,
+) -> Result<Value, String> {
+    let plugin = find(&app, &plugin)?;
+    let Some(info) = plugin.manifest.commands.iter().find(|c| c.id == command) else {
+        return Err(format!(
+            "{} has no command {}",
+            plugin.manifest.name, command
+        ));
+    };
+    let task = tasks::begin(&app, TaskKind::Processing, &info.label);
+    task.run(plugin.call(json!({
+        "action": "command",
+        "command": command,
+        "args": args.unwrap_or(Value::Null),
+    })))
+    .await
+}
@@ -0,0 +1,79 @@
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+
+// Keeps the machine awake while anything holds a PowerLease. Generations take a
+// lease for their whole request and the playback engine holds one while audio plays.
+#[derive(Clone)]
+pub struct PowerGuard {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    holders: Mutex<usize>,
+    tx: Sender<bool>,
+}
+
+// Dropping the lease releases it.
+pub struct PowerLease {
+    inner: Arc<Inner>,
+}
+
+impl PowerGuard {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel::<bool>();
+
+        // On Windows the inhibitor (SetThreadExecutionState) belongs to the thread
+        // that requested it, so one thread owns it for its whole lifetime.
+        std::thread::spawn(move || {
+            let mut inhibitor: Option<keepawake::KeepAwake> = None;
+            while let Ok(hold) = rx.recv() {
+                if hold && inhibitor.is_none() {
+                    match keepawake::Builder::default()
+                        .idle(true)
+                        .display(true)
+                        .reason("Generating or playing audio")
+                        .app_name("Noises")
+                        .app_reverse_domain("com.noises.dev")
+                        .create()
+                    {
+                        Ok(awake) => {
+                            println!("[Power] Sleep inhibitor acquired");
+                            inhibitor = Some(awake);
+                        }
+                        Err(e) => eprintln!("[Power] Failed to inhibit sleep: {}", e),
+                    }
+                } else if !hold && inhibitor.take().is_some() {
+                    println!("[Power] Sleep inhibitor released");
+                }
+            }
+        });
+
+        PowerGuard {
+            inner: Arc::new(Inner {
+                holders: Mutex::new(0),
+                tx,
+            }),
+        }
+    }
+
+    pub fn acquire(&self) -> PowerLease {
+        let mut holders = self.inner.holders.lock().unwrap();
+        if *holders == 0 {
+            let _ = self.inner.tx.send(true);
+        }
+        *holders += 1;
+        PowerLease {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl Drop for PowerLease {
+    fn drop(&mut self) {
+        let mut holders = self.inner.holders.lock().unwrap();
+        *holders -= 1;
+        if *holders == 0 {
+            let _ = self.inner.tx.send(false);
+        }
+    }
+}
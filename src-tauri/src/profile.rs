@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::hotkeys::{self, HotkeyAction};
+use crate::paths;
+use crate::settings;
+use crate::workspaces;
+
+// Marks the file as ours, so importing some other JSON fails clearly.
+const FORMAT: &str = "noises-profile";
+// Bump when a field changes meaning. Older versions are read as-is (missing
+// fields are just skipped); newer ones are refused.
+const VERSION: u32 = 1;
+
+// Settings, the active workspace's presets and the hotkeys in one file, for
+// setting up another machine the same way. Secrets (the HF token) stay in the
+// keychain and are never exported.
+#[derive(Serialize, Deserialize)]
+struct Profile {
+    format: String,
+    version: u32,
+    // Informational; compatibility goes by `version`.
+    #[serde(default)]
+    app_version: String,
+    #[serde(default)]
+    exported_at: String,
+    #[serde(default)]
+    settings: Map<String, Value>,
+    #[serde(default)]
+    presets: Option<Vec<Value>>,
+    // Strings rather than HotkeyAction so an action this version doesn't have
+    // is skipped instead of failing the whole file.
+    #[serde(default)]
+    hotkeys: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Default, Serialize)]
+pub struct ImportReport {
+    pub settings: usize,
+    pub presets: usize,
+    pub hotkeys: usize,
+    // Entries that couldn't be applied, with the reason.
+    pub skipped: Vec<String>,
+}
+
+// Writes noises-profile_NNN.json to `dir` and returns its path.
+pub fn export(app: &AppHandle, dir: &Path) -> Result<PathBuf, String> {
+    let settings = match serde_json::to_value(settings::get(app)) {
+        Ok(Value::Object(fields)) => fields,
+        _ => return Err("Failed to read settings".to_string()),
+    };
+    let hotkeys = hotkeys::keymap(app)
+        .into_iter()
+        .filter_map(|(action, accelerator)| match serde_json::to_value(action) {
+            Ok(Value::String(action)) => Some((action, accelerator)),
+            _ => None,
+        })
+        .collect();
+    let profile = Profile {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        app_version: app.package_info().version.to_string(),
+        exported_at: time::OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap_or_default(),
+        settings,
+        presets: Some(workspaces::presets(app)),
+        hotkeys: Some(hotkeys),
+    };
+
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+    let path = dir.join(paths::next_filename(dir, "noises-profile", ".json"));
+    let json = serde_json::to_string_pretty(&profile)
+        .map_err(|e| format!("Failed to serialize profile: {}", e))?;
+    paths::write_atomic(&path, json).map_err(|e| format!("Failed to save profile: {}", e))?;
+
+    println!("[Profile] Exported to {}", path.display());
+    Ok(path)
+}
+
+// Saves to the Downloads folder.
+#[tauri::command]
+pub fn export_profile(app: AppHandle) -> Result<String, String> {
+    let dir = app
+        .path()
+        .download_dir()
+        .map_err(|e| format!("Failed to resolve Downloads folder: {}", e))?;
+    export(&app, &dir).map(|path| path.to_string_lossy().into_owned())
+}
+
+// Takes the file's contents (the webview reads it from a file picker).
+// Everything valid is applied, the rest is reported back; presets replace the
+// active workspace's. Listeners get `profile-imported` to reload.
+#[tauri::command]
+pub fn import_profile(app: AppHandle, contents: String) -> Result<ImportReport, String> {
+    let value: Value =
+        serde_json::from_str(&contents).map_err(|e| format!("Not a valid profile file: {}", e))?;
+    if value.get("format").and_then(|f| f.as_str()) != Some(FORMAT) {
+        return Err("Not a Noises profile".to_string());
+    }
+    let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+    if version > VERSION as u64 {
+        return Err(format!(
+            "This profile is from a newer version of Noises (profile v{}, this app reads up to v{}). Update the app and try again.",
+            version, VERSION
+        ));
+    }
+    let profile: Profile =
+        serde_json::from_value(value).map_err(|e| format!("Not a valid profile file: {}", e))?;
+
+    let mut report = ImportReport::default();
+    for (key, value) in profile.settings {
+        match settings::set(&app, &key, value) {
+            Ok(()) => report.settings += 1,
+            Err(e) => report.skipped.push(e),
+        }
+    }
+    if let Some(presets) = profile.presets {
+        let count = presets.len();
+        match workspaces::set_presets(&app, presets) {
+            Ok(()) => report.presets = count,
+            Err(e) => report.skipped.push(e),
+        }
+    }
+    for (action, accelerator) in profile.hotkeys.unwrap_or_default() {
+        let Ok(parsed) = serde_json::from_value::<HotkeyAction>(Value::String(action.clone()))
+        else {
+            report
+                .skipped
+                .push(format!("Unknown hotkey action: {}", action));
+            continue;
+        };
+        match hotkeys::set(&app, parsed, Some(accelerator)) {
+            Ok(_) => report.hotkeys += 1,
+            Err(e) => report.skipped.push(e),
+        }
+    }
+
+    println!(
+        "[Profile] Imported {} settings, {} presets, {} hotkeys ({} skipped)",
+        report.settings,
+        report.presets,
+        report.hotkeys,
+        report.skipped.len()
+    );
+    let _ = app.emit("profile-imported", ());
+    Ok(report)
+}
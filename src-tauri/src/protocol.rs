@@ -0,0 +1,376 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use tauri::http::{Request, Response, StatusCode, header};
+use tauri::{AppHandle, Manager};
+
+use crate::paths;
+use crate::waveform::{self, Render, WaveformWorkers};
+
+// Largest body we hand back for a single ranged request. Media elements ask for
+// the next range on their own, so seeking never has to load the whole file.
+const MAX_RANGE_BYTES: u64 = 4 * 1024 * 1024;
+
+// Handles `noises://localhost/<route>/<path>` (on Windows the webview sees it as
+// `http://noises.localhost/...`). The frontend builds these with
+// `convertFileSrc("audio/" + path, "noises")`, which percent-encodes the whole
+// thing, so we decode before splitting off the route. Routes:
+//   audio/<path>     library audio with HTTP Range support
+//   partial/<name>   audio that is still being streamed from the backend
+//   peaks/<id>       cached waveform peaks JSON (?buckets=N)
+//   thumb/<id>       cached waveform thumbnail PNG (?w=W&h=H)
+// A sound's id is its path inside the output directory (absolute paths work too).
+// Peaks and thumbnails are rendered on the waveform workers (waveform.rs), so
+// a screenful of new sounds queues up instead of taking a blocking thread each.
+pub async fn respond(app: AppHandle, request: Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let Some((route, rest)) = split_route(&request) else {
+        return status(StatusCode::BAD_REQUEST);
+    };
+    let render = match route.as_str() {
+        "peaks" => Render::Peaks {
+            buckets: query_param(&request, "buckets")
+                .unwrap_or(waveform::DEFAULT_BUCKETS)
+                .clamp(1, waveform::MAX_BUCKETS),
+        },
+        "thumb" => Render::Thumbnail {
+            width: query_param(&request, "w").unwrap_or(400).clamp(16, 2048) as u32,
+            height: query_param(&request, "h").unwrap_or(64).clamp(8, 512) as u32,
+        },
+        // File reads stay off the runtime.
+        _ => {
+            let task = tauri::async_runtime::spawn_blocking(move || handle(&app, &request));
+            return task
+                .await
+                .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+
+    let path = match resolve_library_path(&app, &rest) {
+        Ok(path) => path,
+        Err(code) => return status(code),
+    };
+    let content_type = match render {
+        Render::Peaks { .. } => "application/json",
+        Render::Thumbnail { .. } => "image/png",
+    };
+    let workers = app.state::<WaveformWorkers>();
+    let result = workers.render(&app, path, render).await;
+    cached_response(&request, content_type, result)
+}
+
+fn split_route(request: &Request<Vec<u8>>) -> Option<(String, String)> {
+    let decoded = percent_encoding::percent_decode_str(request.uri().path())
+        .decode_utf8()
+        .ok()?;
+    let uri_path = decoded.trim_start_matches('/');
+    let (route, rest) = uri_path.split_once('/').unwrap_or((uri_path, ""));
+    Some((route.to_string(), rest.to_string()))
+}
+
+pub fn handle(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let Some((route, rest)) = split_route(request) else {
+        return status(StatusCode::BAD_REQUEST);
+    };
+
+    match route.as_str() {
+        "audio" => match resolve_library_path(app, &rest) {
+            Ok(path) => serve_file(&path, request),
+            Err(code) => status(code),
+        },
+        "partial" => match resolve_partial_path(app, &rest) {
+            Ok(path) => serve_file(&path, request),
+            Err(code) => status(code),
+        },
+        _ => status(StatusCode::NOT_FOUND),
+    }
+}
+
+fn query_param(request: &Request<Vec<u8>>, name: &str) -> Option<usize> {
+    request.uri().query()?.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        if key == name {
+            value.parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+// Derived data is keyed by file size + mtime, so the key works as an ETag and
+// the webview can cache these like any other resource.
+fn cached_response(
+    request: &Request<Vec<u8>>,
+    content_type: &str,
+    result: Result<(String, Vec<u8>), String>,
+) -> Response<Vec<u8>> {
+    let (key, body) = match result {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("[Protocol] {}", e);
+            return status(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let etag = format!("\"{}\"", key);
+
+    let builder = Response::builder()
+        .header(header::ETAG, &etag)
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+
+    let not_modified = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v == etag);
+    let response = if not_modified {
+        builder.status(StatusCode::NOT_MODIFIED).body(Vec::new())
+    } else {
+        builder
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, content_type)
+            .body(body)
+    };
+    response.unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+// Partial files are addressed by bare file name inside the temp folder.
+fn resolve_partial_path(app: &AppHandle, name: &str) -> Result<PathBuf, StatusCode> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let dir = paths::partial_dir(app).map_err(|_| StatusCode::NOT_FOUND)?;
+    Ok(dir.join(name))
+}
+
+// Only files inside the output directory are served, whatever the webview asks for.
+pub fn resolve_library_path(app: &AppHandle, raw: &str) -> Result<PathBuf, StatusCode> {
+    let root = paths::output_dir(app)
+        .ok()
+        .and_then(|root| paths::canonical(&root).ok())
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let requested = Path::new(raw);
+    let requested = if requested.is_absolute() {
+        requested.to_path_buf()
+    } else {
+        root.join(requested)
+    };
+    let path = paths::canonical(&requested).map_err(|_| StatusCode::NOT_FOUND)?;
+
+    if !path.starts_with(&root) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(path)
+}
+
+fn serve_file(path: &Path, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return status(StatusCode::NOT_FOUND),
+    };
+    let len = match file.metadata() {
+        Ok(meta) => meta.len(),
+        Err(_) => return status(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok());
+
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, content_type(path))
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+
+    // Without a Range header the file is served as if "bytes=0-" had been
+    // asked, so nothing past MAX_RANGE_BYTES is ever held in memory: a file
+    // that fits comes back whole with a plain 200, a larger one as its first
+    // range, which clients continue with ranged requests.
+    let (start, end) = match range {
+        Some(range) => match parse_range(range, len) {
+            Some(range) => range,
+            None => {
+                return Response::builder()
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+                    .body(Vec::new())
+                    .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR));
+            }
+        },
+        None if len == 0 => {
+            return builder
+                .status(StatusCode::OK)
+                .header(header::CONTENT_LENGTH, 0)
+                .body(Vec::new())
+                .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR));
+        }
+        None => (0, len - 1),
+    };
+    // Open-ended ranges ("bytes=0-") would otherwise mean the whole file.
+    let end = end.min(start + MAX_RANGE_BYTES - 1);
+
+    let mut body = vec![0; (end - start + 1) as usize];
+    if file.seek(SeekFrom::Start(start)).is_err() || file.read_exact(&mut body).is_err() {
+        return status(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    if range.is_none() && end == len - 1 {
+        return builder
+            .status(StatusCode::OK)
+            .header(header::CONTENT_LENGTH, len)
+            .body(body)
+            .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR));
+    }
+    builder
+        .status(StatusCode::PARTIAL_CONTENT)
+        .header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, end, len),
+        )
+        .header(header::CONTENT_LENGTH, body.len())
+        .body(body)
+        .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+// Parses a single `bytes=` range into inclusive offsets. Multi-range requests
+// only get their first range, which is what media elements send anyway.
+fn parse_range(header: &str, len: u64) -> Option<(u64, u64)> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    let spec = spec.split(',').next()?.trim();
+    let (start, end) = spec.split_once('-')?;
+
+    if len == 0 {
+        return None;
+    }
+
+    let (start, end) = if start.is_empty() {
+        // Suffix range: the last N bytes.
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 {
+            return None;
+        }
+        (len.saturating_sub(suffix), len - 1)
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let end = if end.is_empty() {
+            len - 1
+        } else {
+            end.parse::<u64>().ok()?.min(len - 1)
+        };
+        (start, end)
+    };
+
+    if start > end || start >= len {
+        return None;
+    }
+    Some((start, end))
+}
+
+pub fn content_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "wav" => "audio/wav",
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "ogg" => "audio/ogg",
+        _ => "application/octet-stream",
+    }
+}
+
+fn status(code: StatusCode) -> Response<Vec<u8>> {
+    let mut response = Response::new(Vec::new());
+    *response.status_mut() = code;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_ended_range_runs_to_the_last_byte() {
+        assert_eq!(parse_range("bytes=5-", 10), Some((5, 9)));
+        assert_eq!(parse_range("bytes=0-", 1), Some((0, 0)));
+    }
+
+    #[test]
+    fn suffix_range_is_the_last_n_bytes() {
+        assert_eq!(parse_range("bytes=-3", 10), Some((7, 9)));
+        // Longer than the file: all of it.
+        assert_eq!(parse_range("bytes=-100", 10), Some((0, 9)));
+    }
+
+    #[test]
+    fn empty_suffix_is_unsatisfiable() {
+        assert_eq!(parse_range("bytes=-0", 10), None);
+    }
+
+    #[test]
+    fn end_past_the_file_is_clamped() {
+        assert_eq!(parse_range("bytes=2-100", 10), Some((2, 9)));
+    }
+
+    #[test]
+    fn start_past_the_file_is_unsatisfiable() {
+        assert_eq!(parse_range("bytes=10-", 10), None);
+        assert_eq!(parse_range("bytes=10-20", 10), None);
+        assert_eq!(parse_range("bytes=0-0", 0), None);
+    }
+
+    #[test]
+    fn backwards_or_malformed_ranges_are_rejected() {
+        assert_eq!(parse_range("bytes=5-2", 10), None);
+        assert_eq!(parse_range("bytes=a-b", 10), None);
+        assert_eq!(parse_range("bytes=5", 10), None);
+        assert_eq!(parse_range("items=0-5", 10), None);
+    }
+
+    #[test]
+    fn only_the_first_of_several_ranges_is_used() {
+        assert_eq!(parse_range("bytes=0-1, 4-5", 10), Some((0, 1)));
+    }
+
+    fn serve(name: &str, len: usize, range: Option<&str>) -> Response<Vec<u8>> {
+        let path = std::env::temp_dir().join(format!("noises-serve-{}.wav", name));
+        std::fs::write(&path, vec![7u8; len]).unwrap();
+        let mut request = Request::builder();
+        if let Some(range) = range {
+            request = request.header(header::RANGE, range);
+        }
+        let response = serve_file(&path, &request.body(Vec::new()).unwrap());
+        let _ = std::fs::remove_file(&path);
+        response
+    }
+
+    #[test]
+    fn small_files_without_a_range_come_back_whole() {
+        let response = serve("small", 1000, None);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body().len(), 1000);
+        assert_eq!(serve("empty", 0, None).status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn large_files_without_a_range_start_as_the_first_range() {
+        let len = MAX_RANGE_BYTES as usize + 10;
+        let response = serve("large", len, None);
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.body().len(), MAX_RANGE_BYTES as usize);
+        assert_eq!(
+            response.headers()[header::CONTENT_RANGE],
+            format!("bytes 0-{}/{}", MAX_RANGE_BYTES - 1, len).as_str()
+        );
+    }
+
+    #[test]
+    fn ranges_past_the_end_are_refused() {
+        let response = serve("past", 10, Some("bytes=20-"));
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes */10");
+    }
+}
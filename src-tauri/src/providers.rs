@@ -0,0 +1,451 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tauri::AppHandle;
+use tauri::http::{StatusCode, header};
+
+use crate::backend;
+use crate::grpc;
+use crate::import;
+use crate::paths;
+use crate::proxy;
+use crate::render_target;
+use crate::secrets::{self, Secret};
+use crate::settings;
+
+// What a request's "provider" says to run it on the sidecar; the same as
+// leaving it out.
+pub const LOCAL: &str = "local";
+
+// The model a remote job is recorded under (history, telemetry): which
+// checkpoint the provider runs isn't ours to know.
+pub const REMOTE_MODEL: &str = "remote";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+const POLL: Duration = Duration::from_secs(2);
+
+// Cold starts on a serverless GPU can take minutes before the generation
+// itself does.
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+// A generation host other than the local sidecar, picked per job by name
+// through the request's "provider". Its API key is in the credential store,
+// one per kind (replicate-token, runpod-api-key). The request goes as the
+// model's input the way the backend would get it, so the model on the other
+// end has to take the backend's parameters (prompt, type, length, seed, ...).
+// A "grpc" provider is another Noises backend and needs no key (grpc.rs).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RemoteProvider {
+    pub name: String,
+    #[serde(flatten)]
+    pub api: RemoteApi,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum RemoteApi {
+    // Replicate's predictions API; `version` is the model version's id.
+    Replicate { version: String },
+    // A RunPod serverless endpoint.
+    Runpod { endpoint_id: String },
+    // A Noises backend started with --grpc-port, e.g. http://studio-pc:50051.
+    Grpc { url: String },
+}
+
+impl RemoteApi {
+    fn label(&self) -> &'static str {
+        match self {
+            RemoteApi::Replicate { .. } => "Replicate",
+            RemoteApi::Runpod { .. } => "RunPod",
+            RemoteApi::Grpc { .. } => "gRPC",
+        }
+    }
+
+    fn secret(&self) -> Option<Secret> {
+        match self {
+            RemoteApi::Replicate { .. } => Some(Secret::ReplicateToken),
+            RemoteApi::Runpod { .. } => Some(Secret::RunpodApiKey),
+            RemoteApi::Grpc { .. } => None,
+        }
+    }
+}
+
+// Where a remote job is, for the job's state.
+pub enum Phase {
+    // Waiting for the provider to start it (its queue, a cold start).
+    Queued,
+    Running,
+}
+
+enum Status {
+    Phase(Phase),
+    Done(Value),
+    Failed(String),
+}
+
+// The provider named in the request, or None to run it locally.
+pub fn remote(app: &AppHandle, config: &Value) -> Result<Option<RemoteProvider>, String> {
+    let Some(name) = requested(config) else {
+        return Ok(None);
+    };
+    settings::get(app)
+        .remote_providers
+        .into_iter()
+        .find(|provider| provider.name == name)
+        .map(Some)
+        .ok_or_else(|| format!("Unknown generation provider: {}", name))
+}
+
+pub fn is_remote(config: &Value) -> bool {
+    requested(config).is_some()
+}
+
+fn requested(config: &Value) -> Option<&str> {
+    config
+        .get("provider")
+        .and_then(|p| p.as_str())
+        .filter(|p| !p.is_empty() && *p != LOCAL)
+}
+
+// The same shape as the backend's errors, so the job handles both alike: a
+// rate limit comes back as BackendBusy and is retried (backend::
+// retry_while_busy), anything else ends the job with the provider's reason.
+async fn error_from_response(api: &RemoteApi, res: reqwest::Response) -> String {
+    let status = res.status();
+    let wait = res
+        .headers()
+        .get(header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(10);
+    let body = res.text().await.unwrap_or_default();
+    let detail = serde_json::from_str::<Value>(&body)
+        .ok()
+        .and_then(|json| {
+            ["detail", "error", "message"]
+                .iter()
+                .find_map(|key| Some(json.get(*key)?.as_str()?.to_string()))
+        })
+        .unwrap_or(body);
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => format!(
+            "{} didn't accept the API key; check it in Settings",
+            api.label()
+        ),
+        StatusCode::TOO_MANY_REQUESTS => format!(
+            "{}: retry in {} s: {} is rate limiting requests",
+            backend::BUSY_ERROR,
+            wait.min(60),
+            api.label()
+        ),
+        _ => format!("{} error ({}): {}", api.label(), status, detail),
+    }
+}
+
+// A provider reached over its HTTP API; gRPC ones never get one of these.
+struct Remote {
+    client: reqwest::Client,
+    api: RemoteApi,
+    key: String,
+}
+
+impl Remote {
+    fn open(app: &AppHandle, provider: &RemoteProvider) -> Result<Self, String> {
+        let secret = provider
+            .api
+            .secret()
+            .ok_or("gRPC providers don't use the HTTP API")?;
+        let key = secrets::get(app, secret)?
+            .ok_or_else(|| format!("Add a {} API key in Settings first", provider.api.label()))?;
+        let client = proxy::client_builder(app)?
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        Ok(Remote {
+            client,
+            api: provider.api.clone(),
+            key,
+        })
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Value, String> {
+        let res = request
+            .bearer_auth(&self.key)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach {}: {}", self.api.label(), e))?;
+        if !res.status().is_success() {
+            return Err(error_from_response(&self.api, res).await);
+        }
+        res.json()
+            .await
+            .map_err(|e| format!("Failed to read {}'s answer: {}", self.api.label(), e))
+    }
+
+    // Starts the job and returns its id.
+    async fn submit(&self, input: &Value) -> Result<String, String> {
+        let (url, body) = match &self.api {
+            RemoteApi::Replicate { version } => (
+                "https://api.replicate.com/v1/predictions".to_string(),
+                json!({ "version": version, "input": input }),
+            ),
+            RemoteApi::Runpod { endpoint_id } => (
+                format!("https://api.runpod.ai/v2/{}/run", endpoint_id),
+                json!({ "input": input }),
+            ),
+            RemoteApi::Grpc { .. } => unreachable!("gRPC providers have no Remote"),
+        };
+        let answer = self.send(self.client.post(url).json(&body)).await?;
+        answer
+            .get("id")
+            .and_then(|id| id.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| format!("{} didn't say which job it started", self.api.label()))
+    }
+
+    async fn status(&self, id: &str) -> Result<Status, String> {
+        let url = match &self.api {
+            RemoteApi::Replicate { .. } => {
+                format!("https://api.replicate.com/v1/predictions/{}", id)
+            }
+            RemoteApi::Runpod { endpoint_id } => {
+                format!("https://api.runpod.ai/v2/{}/status/{}", endpoint_id, id)
+            }
+            RemoteApi::Grpc { .. } => unreachable!("gRPC providers have no Remote"),
+        };
+        let answer = self.send(self.client.get(url)).await?;
+        let status = answer
+            .get("status")
+            .and_then(|s| s.as_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let error = || {
+            let reason = answer
+                .get("error")
+                .map(|e| {
+                    e.as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| e.to_string())
+                })
+                .unwrap_or_else(|| status.clone());
+            format!("{}: {}", self.api.label(), reason)
+        };
+        Ok(match status.as_str() {
+            "starting" | "in_queue" => Status::Phase(Phase::Queued),
+            "processing" | "in_progress" => Status::Phase(Phase::Running),
+            "succeeded" | "completed" => {
+                Status::Done(answer.get("output").cloned().unwrap_or_default())
+            }
+            "canceled" | "cancelled" => {
+                Status::Failed(format!("{}: The job was cancelled", self.api.label()))
+            }
+            "timed_out" => Status::Failed(format!("{}: The job timed out", self.api.label())),
+            _ => Status::Failed(error()),
+        })
+    }
+
+    // Best effort, from a drop: the job's future is gone by then.
+    fn cancel(&self, id: &str) {
+        let url = match &self.api {
+            RemoteApi::Replicate { .. } => {
+                format!("https://api.replicate.com/v1/predictions/{}/cancel", id)
+            }
+            RemoteApi::Runpod { endpoint_id } => {
+                format!("https://api.runpod.ai/v2/{}/cancel/{}", endpoint_id, id)
+            }
+            RemoteApi::Grpc { .. } => unreachable!("gRPC providers have no Remote"),
+        };
+        let request = self.client.post(url).bearer_auth(&self.key);
+        let label = self.api.label();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = request.send().await {
+                eprintln!("[Providers] Failed to cancel the {} job: {}", label, e);
+            }
+        });
+    }
+}
+
+// Cancels the remote job unless disarmed, so a cancelled task (whose future
+// is just dropped) doesn't leave a GPU running on the user's bill.
+struct CancelOnDrop<'a> {
+    remote: &'a Remote,
+    id: &'a str,
+    armed: bool,
+}
+
+impl Drop for CancelOnDrop<'_> {
+    fn drop(&mut self) {
+        if self.armed {
+            self.remote.cancel(self.id);
+        }
+    }
+}
+
+// Audio in a job's output, wherever it is: a URL, a list of them, or an
+// object holding them (e.g. {"files": [{"url": ...}]}), or data: URIs.
+fn collect_audio(output: &Value, found: &mut Vec<String>) {
+    match output {
+        Value::String(s) if s.starts_with("https://") || s.starts_with("http://") => {
+            found.push(s.clone())
+        }
+        Value::String(s) if s.starts_with("data:audio/") => found.push(s.clone()),
+        Value::Array(items) => items.iter().for_each(|item| collect_audio(item, found)),
+        Value::Object(fields) => fields.values().for_each(|item| collect_audio(item, found)),
+        _ => {}
+    }
+}
+
+// The bytes and the extension they go under.
+async fn fetch(remote: &Remote, source: &str) -> Result<(Vec<u8>, String), String> {
+    if let Some(data) = source.strip_prefix("data:") {
+        let (kind, encoded) = data
+            .split_once(";base64,")
+            .ok_or("The provider sent audio in a data URI that isn't base64")?;
+        let extension = import::extension_for_content_type(kind)?.unwrap_or("wav");
+        let bytes = BASE64
+            .decode(encoded)
+            .map_err(|e| format!("The provider sent audio that doesn't decode: {}", e))?;
+        return Ok((bytes, extension.to_string()));
+    }
+    let res = remote
+        .client
+        .get(source)
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|e| {
+            format!(
+                "Failed to download the result from {}: {}",
+                remote.api.label(),
+                e
+            )
+        })?;
+    let from_type = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| import::extension_for_content_type(v).ok().flatten());
+    let from_url = reqwest::Url::parse(source).ok().and_then(|url| {
+        let name = url.path_segments()?.next_back()?.to_string();
+        let path = PathBuf::from(name);
+        path.extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .filter(|_| import::is_audio_file(&path))
+    });
+    let extension = from_type
+        .map(str::to_string)
+        .or(from_url)
+        .unwrap_or_else(|| "wav".to_string());
+    let bytes = res.bytes().await.map_err(|e| {
+        format!(
+            "Failed to download the result from {}: {}",
+            remote.api.label(),
+            e
+        )
+    })?;
+    Ok((bytes.to_vec(), extension))
+}
+
+// Where a remote job's audio goes, and the prefix it's numbered under.
+pub fn output_folder(app: &AppHandle, config: &Value) -> Result<(PathBuf, &'static str), String> {
+    let is_loop = config
+        .get("type")
+        .and_then(|t| t.as_str())
+        .is_some_and(|t| t.contains("loop"));
+    Ok(if is_loop {
+        (paths::loops_dir(app)?, "loop")
+    } else {
+        (paths::oneshots_dir(app)?, "song")
+    })
+}
+
+// Runs the request on the provider and brings its audio into the library
+// (loops or one-shots, numbered like the backend's), answering the way the
+// backend does ({"files": [{"path": ...}]}) so backend::settle_outputs takes
+// it from there. `on_phase` hears when the provider starts on it.
+pub async fn generate(
+    app: &AppHandle,
+    provider: &RemoteProvider,
+    config: &Value,
+    on_phase: impl Fn(Phase),
+) -> Result<Value, String> {
+    let mut input = config.clone();
+    if let Some(fields) = input.as_object_mut() {
+        // Local paths and routing mean nothing over there.
+        fields.remove("output_dir");
+        fields.remove("provider");
+        fields.remove(render_target::FLAG);
+    }
+    if let RemoteApi::Grpc { url } = &provider.api {
+        let result = backend::retry_while_busy(&provider.name, || {
+            grpc::generate(app, &provider.name, url, &input, &on_phase)
+        })
+        .await?;
+        println!("[Providers] {} done over gRPC", provider.name);
+        return Ok(result);
+    }
+    let remote = Remote::open(app, provider)?;
+
+    let id = backend::retry_while_busy(&provider.name, || remote.submit(&input)).await?;
+    println!("[Providers] {} job {} started", provider.name, id);
+    let mut guard = CancelOnDrop {
+        remote: &remote,
+        id: &id,
+        armed: true,
+    };
+    on_phase(Phase::Queued);
+    let started = Instant::now();
+    let output = loop {
+        tokio::time::sleep(POLL).await;
+        if started.elapsed() > REMOTE_TIMEOUT {
+            return Err(format!(
+                "{} took too long; the job was cancelled",
+                provider.name
+            ));
+        }
+        // A rate-limited status check just waits for the next round.
+        match remote.status(&id).await {
+            Ok(Status::Phase(phase)) => on_phase(phase),
+            Ok(Status::Done(output)) => break output,
+            Ok(Status::Failed(e)) => {
+                guard.armed = false;
+                return Err(e);
+            }
+            Err(e) if e.starts_with(backend::BUSY_ERROR) => {}
+            Err(e) => return Err(e),
+        }
+    };
+    guard.armed = false;
+
+    let mut sources = Vec::new();
+    collect_audio(&output, &mut sources);
+    if sources.is_empty() {
+        return Err(format!("{} finished without any audio", provider.name));
+    }
+    let (dir, prefix) = output_folder(app, config)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create output folder: {}", e))?;
+    let mut files = Vec::with_capacity(sources.len());
+    for source in &sources {
+        let (bytes, extension) = fetch(&remote, source).await?;
+        let path = dir.join(paths::next_filename(
+            &dir,
+            prefix,
+            &format!(".{}", extension),
+        ));
+        paths::write_atomic(&path, bytes)
+            .map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+        files.push(json!({ "path": path.to_string_lossy() }));
+    }
+    println!(
+        "[Providers] {} job {} done, {} files",
+        provider.name,
+        id,
+        files.len()
+    );
+    Ok(json!({ "files": files, "provider": provider.name }))
+}
@@ -0,0 +1,355 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Instant;
+
+use rodio::cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rodio::cpal::{self, FromSample, SampleFormat, SizedSample, StreamConfig};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::meter::{Meter, MeterFrame};
+use crate::paths;
+
+type SharedWriter = Arc<Mutex<Option<hound::WavWriter<BufWriter<File>>>>>;
+
+// Only one capture runs at a time.
+#[derive(Default)]
+pub struct Recorder {
+    active: Mutex<Option<ActiveRecording>>,
+}
+
+struct ActiveRecording {
+    path: PathBuf,
+    started: Instant,
+    stop_tx: Sender<()>,
+    thread: JoinHandle<Result<(), String>>,
+}
+
+impl Recorder {
+    // Two starts at once both get past the first check; the later one to
+    // get its stream running is stopped and thrown away here.
+    fn activate(&self, recording: ActiveRecording) -> Result<(), String> {
+        let mut active = self.active.lock().unwrap();
+        if active.is_some() {
+            drop(active);
+            let _ = recording.stop_tx.send(());
+            std::thread::spawn(move || {
+                let _ = recording.thread.join();
+                let _ = std::fs::remove_file(&recording.path);
+            });
+            return Err("A recording is already in progress".to_string());
+        }
+        *active = Some(recording);
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+pub struct RecordingInfo {
+    pub path: String,
+    pub duration_secs: f64,
+}
+
+#[tauri::command]
+pub fn list_output_devices() -> Result<Vec<String>, String> {
+    let host = cpal::default_host();
+    let devices = host
+        .output_devices()
+        .map_err(|e| format!("Failed to list output devices: {}", e))?;
+    Ok(devices.filter_map(|d| d.name().ok()).collect())
+}
+
+#[tauri::command]
+pub fn list_input_devices() -> Result<Vec<String>, String> {
+    let host = cpal::default_host();
+    let devices = host
+        .input_devices()
+        .map_err(|e| format!("Failed to list input devices: {}", e))?;
+    Ok(devices.filter_map(|d| d.name().ok()).collect())
+}
+
+// Starts capturing the default (or named) microphone to a WAV file and returns
+// its path. Levels are emitted as `recording-meter` events while it runs.
+#[tauri::command]
+pub async fn start_recording(
+    app: AppHandle,
+    recorder: State<'_, Recorder>,
+    device: Option<String>,
+    path: Option<String>,
+) -> Result<String, String> {
+    if recorder.active.lock().unwrap().is_some() {
+        return Err("A recording is already in progress".to_string());
+    }
+    let path = target_path(&app, path, "recording")?;
+    // Opening the device and waiting for its stream to run both block.
+    let recording = tauri::async_runtime::spawn_blocking(move || {
+        let host = cpal::default_host();
+        let input = match device {
+            Some(name) => host
+                .input_devices()
+                .map_err(|e| format!("Failed to list input devices: {}", e))?
+                .find(|d| d.name().is_ok_and(|n| n == name))
+                .ok_or_else(|| format!("Input device not found: {}", name))?,
+            None => host
+                .default_input_device()
+                .ok_or_else(|| "No microphone found".to_string())?,
+        };
+        let config = input
+            .default_input_config()
+            .map_err(|e| format!("Failed to query input device: {}", e))?;
+        start_capture(app, input, config.sample_format(), config.into(), path)
+    })
+    .await
+    .map_err(|e| format!("Recording task failed: {}", e))??;
+    let path = recording.path.to_string_lossy().into_owned();
+    recorder.activate(recording)?;
+    println!("[Recording] Started: {}", path);
+    Ok(path)
+}
+
+// Captures what the system is playing (WASAPI loopback) so sounds from other apps
+// can be grabbed as references. cpal switches to loopback mode when an input
+// stream is opened on an output device. Stopped with stop_recording like a mic take.
+#[cfg(target_os = "windows")]
+#[tauri::command]
+pub async fn start_loopback_capture(
+    app: AppHandle,
+    recorder: State<'_, Recorder>,
+    device: Option<String>,
+    path: Option<String>,
+) -> Result<String, String> {
+    if recorder.active.lock().unwrap().is_some() {
+        return Err("A recording is already in progress".to_string());
+    }
+    let path = target_path(&app, path, "loopback")?;
+    let recording = tauri::async_runtime::spawn_blocking(move || {
+        let host = cpal::default_host();
+        let output = match device {
+            Some(name) => host
+                .output_devices()
+                .map_err(|e| format!("Failed to list output devices: {}", e))?
+                .find(|d| d.name().is_ok_and(|n| n == name))
+                .ok_or_else(|| format!("Output device not found: {}", name))?,
+            None => host
+                .default_output_device()
+                .ok_or_else(|| "No audio output device found".to_string())?,
+        };
+        let config = output
+            .default_output_config()
+            .map_err(|e| format!("Failed to query output device: {}", e))?;
+        start_capture(app, output, config.sample_format(), config.into(), path)
+    })
+    .await
+    .map_err(|e| format!("Recording task failed: {}", e))??;
+    let path = recording.path.to_string_lossy().into_owned();
+    recorder.activate(recording)?;
+    println!("[Recording] Loopback capture started: {}", path);
+    Ok(path)
+}
+
+#[cfg(not(target_os = "windows"))]
+#[tauri::command]
+pub async fn start_loopback_capture(
+    device: Option<String>,
+    path: Option<String>,
+) -> Result<String, String> {
+    let _ = (device, path);
+    Err("System audio capture is only supported on Windows".to_string())
+}
+
+#[tauri::command]
+pub async fn stop_recording(recorder: State<'_, Recorder>) -> Result<RecordingInfo, String> {
+    let recording = recorder
+        .active
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| "No recording in progress".to_string())?;
+
+    let duration_secs = recording.started.elapsed().as_secs_f64();
+    let _ = recording.stop_tx.send(());
+    tauri::async_runtime::spawn_blocking(move || recording.thread.join())
+        .await
+        .map_err(|e| format!("Failed to stop recording: {}", e))?
+        .map_err(|_| "Recording thread panicked".to_string())??;
+
+    let path = recording.path.to_string_lossy().into_owned();
+    println!("[Recording] Saved: {}", path);
+    Ok(RecordingInfo {
+        path,
+        duration_secs,
+    })
+}
+
+// Explicit path if the caller gave one, otherwise the next free
+// recordings/<prefix>_NNN.wav in the output directory.
+fn target_path(app: &AppHandle, path: Option<String>, prefix: &str) -> Result<PathBuf, String> {
+    if let Some(path) = path {
+        return Ok(PathBuf::from(path));
+    }
+    let dir = paths::recordings_dir(app)?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create recordings folder: {}", e))?;
+    let name = paths::next_filename(&dir, prefix, ".wav");
+    Ok(dir.join(name))
+}
+
+// The cpal stream isn't Send on every platform, so it lives on its own thread
+// until stop is requested. Returns once the stream is actually running.
+fn start_capture(
+    app: AppHandle,
+    device: cpal::Device,
+    format: SampleFormat,
+    config: StreamConfig,
+    path: PathBuf,
+) -> Result<ActiveRecording, String> {
+    let (stop_tx, stop_rx) = mpsc::channel::<()>();
+    let (ready_tx, ready_rx) = mpsc::channel::<Result<(), String>>();
+
+    let thread_path = path.clone();
+    let thread = std::thread::spawn(move || {
+        capture_thread(app, device, format, config, thread_path, stop_rx, ready_tx)
+    });
+
+    match ready_rx.recv() {
+        Ok(Ok(())) => Ok(ActiveRecording {
+            path,
+            started: Instant::now(),
+            stop_tx,
+            thread,
+        }),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err("Recording thread exited unexpectedly".to_string()),
+    }
+}
+
+fn capture_thread(
+    app: AppHandle,
+    device: cpal::Device,
+    format: SampleFormat,
+    config: StreamConfig,
+    path: PathBuf,
+    stop_rx: Receiver<()>,
+    ready_tx: Sender<Result<(), String>>,
+) -> Result<(), String> {
+    let spec = hound::WavSpec {
+        channels: config.channels,
+        sample_rate: config.sample_rate.0,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    // Written under a hidden name and renamed once it's finalized, so the
+    // library never lists a recording that's still going or was cut off.
+    let temp = paths::temp_path(&path);
+    let writer = match hound::WavWriter::create(&temp, spec) {
+        Ok(writer) => Arc::new(Mutex::new(Some(writer))),
+        Err(e) => {
+            let e = format!("Failed to create recording file: {}", e);
+            let _ = ready_tx.send(Err(e.clone()));
+            return Err(e);
+        }
+    };
+
+    // Bounded so a stalled webview can't make the audio callback pile up frames.
+    let (meter_tx, meter_rx) = mpsc::sync_channel::<MeterFrame>(8);
+    let meter_app = app.clone();
+    std::thread::spawn(move || {
+        while let Ok(frame) = meter_rx.recv() {
+            let _ = meter_app.emit("recording-meter", frame);
+        }
+    });
+
+    let stream = match format {
+        SampleFormat::F32 => build_stream::<f32>(&app, &device, &config, &writer, meter_tx),
+        SampleFormat::I16 => build_stream::<i16>(&app, &device, &config, &writer, meter_tx),
+        SampleFormat::U16 => build_stream::<u16>(&app, &device, &config, &writer, meter_tx),
+        SampleFormat::I32 => build_stream::<i32>(&app, &device, &config, &writer, meter_tx),
+        other => Err(format!("Unsupported input sample format: {}", other)),
+    }
+    .and_then(|stream| {
+        stream
+            .play()
+            .map_err(|e| format!("Failed to start input stream: {}", e))?;
+        Ok(stream)
+    });
+
+    let stream = match stream {
+        Ok(stream) => stream,
+        Err(e) => {
+            let _ = ready_tx.send(Err(e.clone()));
+            drop(writer.lock().unwrap().take());
+            let _ = std::fs::remove_file(&temp);
+            return Err(e);
+        }
+    };
+    let _ = ready_tx.send(Ok(()));
+
+    // Blocks until stop_recording (or the Recorder being dropped).
+    let _ = stop_rx.recv();
+    drop(stream);
+
+    let writer = writer.lock().unwrap().take();
+    let saved = match writer {
+        Some(writer) => writer.finalize().map_err(|e| e.to_string()).and_then(|_| {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(&temp)
+                .and_then(|file| file.sync_all())
+                .and_then(|_| std::fs::rename(&temp, &path))
+                .map_err(|e| e.to_string())
+        }),
+        None => Ok(()),
+    };
+    if let Err(e) = saved {
+        let _ = std::fs::remove_file(&temp);
+        return Err(format!("Failed to finalize recording: {}", e));
+    }
+    Ok(())
+}
+
+fn build_stream<T>(
+    app: &AppHandle,
+    device: &cpal::Device,
+    config: &StreamConfig,
+    writer: &SharedWriter,
+    meter_tx: SyncSender<MeterFrame>,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels;
+    let sample_rate = config.sample_rate.0;
+    let mut meter = Meter::new(channels, sample_rate);
+    let writer = writer.clone();
+    let error_app = app.clone();
+
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let mut guard = writer.lock().unwrap();
+                for &sample in data {
+                    let sample = sample.to_sample::<f32>();
+                    if let Some(writer) = guard.as_mut() {
+                        let _ = writer.write_sample(sample);
+                    }
+                    if let Some(frame) = meter.push(sample) {
+                        // Dropping a frame is fine; blocking the audio callback is not.
+                        let _ = meter_tx.try_send(frame);
+                        meter.reset(channels, sample_rate);
+                    }
+                }
+            },
+            move |err| {
+                eprintln!("[Recording] Stream error: {}", err);
+                let _ = error_app.emit("recording-error", err.to_string());
+            },
+            None,
+        )
+        .map_err(|e| format!("Failed to open input stream: {}", e))
+}
@@ -0,0 +1,150 @@
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use rodio::source::UniformSourceIterator;
+use rodio::{Decoder, Source};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::file_lock;
+use crate::game_engines;
+use crate::history::HistoryEntry;
+use crate::paths;
+use crate::settings;
+
+// The request field that marks a generation for the render target.
+pub const FLAG: &str = "render_target";
+
+#[derive(Clone, Serialize)]
+struct RenderTargetUpdated {
+    source: String,
+    path: String,
+    error: Option<String>,
+}
+
+fn is_wav(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("wav"))
+}
+
+fn same_extension(a: &Path, b: &Path) -> bool {
+    match (a.extension(), b.extension()) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        _ => false,
+    }
+}
+
+// 16-bit PCM, which every engine imports, at the file's own sample rate or
+// resampled to `sample_rate`.
+pub fn to_wav(source: &Path, sample_rate: Option<u32>) -> Result<Vec<u8>, String> {
+    let file = std::fs::File::open(source)
+        .map_err(|e| format!("Failed to open {}: {}", source.display(), e))?;
+    let decoder = Decoder::new(std::io::BufReader::new(file))
+        .map_err(|e| format!("Failed to decode {}: {}", source.display(), e))?;
+    let channels = decoder.channels();
+    let sample_rate = sample_rate.unwrap_or(decoder.sample_rate());
+    let spec = hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let samples = UniformSourceIterator::<_, f32>::new(
+        decoder.convert_samples::<f32>(),
+        channels,
+        sample_rate,
+    );
+    let mut bytes = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut bytes, spec)
+        .map_err(|e| format!("Failed to convert to WAV: {}", e))?;
+    for sample in samples {
+        writer
+            .write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16)
+            .map_err(|e| format!("Failed to convert to WAV: {}", e))?;
+    }
+    writer
+        .finalize()
+        .map_err(|e| format!("Failed to convert to WAV: {}", e))?;
+    Ok(bytes.into_inner())
+}
+
+// The file's bytes as they should land at `target`: a plain copy when the
+// formats match, else converted to WAV. Other conversions aren't offered.
+fn contents_for(source: &Path, target: &Path) -> Result<Vec<u8>, String> {
+    if same_extension(source, target) {
+        return std::fs::read(source)
+            .map_err(|e| format!("Failed to read {}: {}", source.display(), e));
+    }
+    if !is_wav(target) {
+        return Err(format!(
+            "Can't convert {} to {}; use a .wav render target",
+            source.display(),
+            target.display()
+        ));
+    }
+    to_wav(source, None)
+}
+
+async fn replace(source: &Path, target: &Path) -> Result<(), String> {
+    let dir = target.parent().unwrap_or(Path::new("."));
+    if !dir.is_dir() {
+        return Err(format!(
+            "The render target's folder doesn't exist: {}",
+            dir.display()
+        ));
+    }
+    let bytes = {
+        let (source, target) = (source.to_path_buf(), target.to_path_buf());
+        tauri::async_runtime::spawn_blocking(move || contents_for(&source, &target))
+            .await
+            .map_err(|e| format!("Render target task failed: {}", e))??
+    };
+    // A game engine reading the old file can hold it open for a moment.
+    file_lock::retry_while_in_use(target, "replace", || {
+        std::future::ready(paths::write_atomic(target, &bytes))
+    })
+    .await
+}
+
+// After a generation marked with "render_target": true, puts its first
+// output at the `render_target` path (e.g. Assets/Audio/latest.wav), swapped
+// in whole so an engine's hot reload never sees half a file. The outcome is
+// announced as `render-target-updated`.
+pub fn update(app: &AppHandle, entry: &HistoryEntry) {
+    if entry.params.get(FLAG).and_then(|v| v.as_bool()) != Some(true) {
+        return;
+    }
+    let Some(target) = settings::get(app).render_target else {
+        return;
+    };
+    let Some(source) = entry.outputs.first().map(PathBuf::from) else {
+        return;
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = replace(&source, &target).await;
+        match &result {
+            Ok(()) => {
+                println!(
+                    "[RenderTarget] {} -> {}",
+                    source.display(),
+                    target.display()
+                );
+                let (app, source, target) = (app.clone(), source.clone(), target.clone());
+                let _ = tauri::async_runtime::spawn_blocking(move || {
+                    game_engines::write_companions(&app, &source, &target)
+                })
+                .await;
+            }
+            Err(e) => eprintln!("[RenderTarget] {}", e),
+        }
+        let _ = app.emit(
+            "render-target-updated",
+            RenderTargetUpdated {
+                source: source.to_string_lossy().into_owned(),
+                path: target.to_string_lossy().into_owned(),
+                error: result.err(),
+            },
+        );
+    });
+}
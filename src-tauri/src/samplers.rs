@@ -0,0 +1,252 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::disk;
+use crate::paths;
+use crate::render_target;
+use crate::telemetry;
+
+// What both take without complaint, from any card.
+const SAMPLE_RATE: u32 = 44_100;
+
+// MPCs from the 1000 on show this many characters of a name.
+const MPC_NAME_CHARS: usize = 16;
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SamplerLayout {
+    // SP-404SX / SP-404A: ROLAND/SP-404SX/SMPL/A0000001.WAV, banks A-J with
+    // 12 pads each, loaded from the card on start.
+    Sp404,
+    // MPC: a Bank A..H folder per 16 pads, names led by the pad (A01 ...).
+    Mpc,
+}
+
+impl SamplerLayout {
+    fn name(self) -> &'static str {
+        match self {
+            SamplerLayout::Sp404 => "SP-404",
+            SamplerLayout::Mpc => "MPC",
+        }
+    }
+
+    fn banks(self) -> &'static [char] {
+        match self {
+            SamplerLayout::Sp404 => &['A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J'],
+            SamplerLayout::Mpc => &['A', 'B', 'C', 'D', 'E', 'F', 'G', 'H'],
+        }
+    }
+
+    fn pads(self) -> usize {
+        match self {
+            SamplerLayout::Sp404 => 12,
+            SamplerLayout::Mpc => 16,
+        }
+    }
+
+    fn root(self, destination: &Path) -> PathBuf {
+        match self {
+            SamplerLayout::Sp404 => destination.join("ROLAND").join("SP-404SX").join("SMPL"),
+            SamplerLayout::Mpc => destination.join("Noises"),
+        }
+    }
+
+    fn bank_dir(self, root: &Path, bank: char) -> PathBuf {
+        match self {
+            SamplerLayout::Sp404 => root.to_path_buf(),
+            SamplerLayout::Mpc => root.join(format!("Bank {}", bank)),
+        }
+    }
+
+    // What a file on `pad` (1-based) starts with, so a taken pad is seen
+    // whatever the sound on it is called.
+    fn slot(self, bank: char, pad: usize) -> String {
+        match self {
+            SamplerLayout::Sp404 => format!("{}{:07}", bank, pad),
+            SamplerLayout::Mpc => format!("{}{:02}", bank, pad),
+        }
+    }
+
+    fn file_name(self, slot: &str, source: &Path) -> String {
+        match self {
+            SamplerLayout::Sp404 => format!("{}.WAV", slot),
+            SamplerLayout::Mpc => {
+                let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+                // Plain ASCII; older MPCs show anything else as boxes.
+                let words: Vec<&str> = stem
+                    .split(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+                    .filter(|w| !w.is_empty())
+                    .collect();
+                let mut name = format!("{} {}", slot, words.join(" "));
+                name.truncate(MPC_NAME_CHARS);
+                format!("{}.wav", name.trim_end())
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SamplerExport {
+    pub folder: String,
+    pub files: Vec<String>,
+}
+
+fn taken(dir: &Path, slot: &str) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        entry
+            .file_name()
+            .to_string_lossy()
+            .to_ascii_uppercase()
+            .starts_with(slot)
+    })
+}
+
+// Pads nothing is on yet, in bank order, so what's on the card stays.
+fn free_slots(layout: SamplerLayout, root: &Path) -> Vec<(char, String)> {
+    layout
+        .banks()
+        .iter()
+        .flat_map(|&bank| (1..=layout.pads()).map(move |pad| (bank, layout.slot(bank, pad))))
+        .filter(|(bank, slot)| !taken(&layout.bank_dir(root, *bank), slot))
+        .collect()
+}
+
+// Writes the sounds as 16-bit 44.1 kHz WAVs onto free pads of a sampler's
+// layout under `destination` (a card's root, or a folder to copy over) and
+// returns where they went.
+pub fn export(
+    sounds: &[PathBuf],
+    layout: SamplerLayout,
+    destination: &Path,
+) -> Result<SamplerExport, String> {
+    if sounds.is_empty() {
+        return Err("Pick at least one sound to export".to_string());
+    }
+    if let Some(missing) = sounds.iter().find(|s| !s.is_file()) {
+        return Err(format!("File not found: {}", missing.display()));
+    }
+    let root = layout.root(destination);
+    let slots = free_slots(layout, &root);
+    if sounds.len() > slots.len() {
+        return Err(format!(
+            "The {} has {} free pads there, not enough for {} sounds",
+            layout.name(),
+            slots.len(),
+            sounds.len()
+        ));
+    }
+    std::fs::create_dir_all(&root).map_err(|e| format!("Failed to create folder: {}", e))?;
+    disk::ensure_space_for_files(&root, sounds)?;
+
+    let mut files = Vec::new();
+    for (source, (bank, slot)) in sounds.iter().zip(slots) {
+        let dir = layout.bank_dir(&root, bank);
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+        let path = dir.join(layout.file_name(&slot, source));
+        let bytes = render_target::to_wav(source, Some(SAMPLE_RATE))?;
+        paths::write_atomic(&path, bytes)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        files.push(path.to_string_lossy().into_owned());
+    }
+    println!(
+        "[Samplers] Exported {} sounds for the {} to {}",
+        files.len(),
+        layout.name(),
+        root.display()
+    );
+    Ok(SamplerExport {
+        folder: root.to_string_lossy().into_owned(),
+        files,
+    })
+}
+
+#[tauri::command]
+pub async fn export_for_sampler(
+    app: AppHandle,
+    paths: Vec<String>,
+    layout: SamplerLayout,
+    destination: String,
+) -> Result<SamplerExport, String> {
+    telemetry::record_feature(&app, "sampler-export");
+    let sounds: Vec<PathBuf> = paths.iter().map(|p| paths::normalize(p)).collect();
+    let destination = paths::normalize(&destination);
+    tauri::async_runtime::spawn_blocking(move || export(&sounds, layout, &destination))
+        .await
+        .map_err(|e| format!("Export task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("noises-samplers-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn slots_follow_each_layout() {
+        assert_eq!(SamplerLayout::Sp404.slot('A', 1), "A0000001");
+        assert_eq!(SamplerLayout::Sp404.slot('J', 12), "J0000012");
+        assert_eq!(SamplerLayout::Mpc.slot('A', 1), "A01");
+        assert_eq!(SamplerLayout::Mpc.slot('H', 16), "H16");
+    }
+
+    #[test]
+    fn sp404_names_are_only_the_slot() {
+        let name = SamplerLayout::Sp404.file_name("B0000003", Path::new("/sounds/kick_001.flac"));
+        assert_eq!(name, "B0000003.WAV");
+    }
+
+    #[test]
+    fn mpc_names_are_short_and_ascii() {
+        let layout = SamplerLayout::Mpc;
+        assert_eq!(
+            layout.file_name("A01", Path::new("/sounds/kick_001.flac")),
+            "A01 kick 001.wav"
+        );
+        assert_eq!(
+            layout.file_name("A02", Path::new("/sounds/café-snare.wav")),
+            "A02 caf -snare.wav"
+        );
+        // Cut at 16 characters, with no space left before the extension.
+        assert_eq!(
+            layout.file_name("A03", Path::new("/sounds/kick drumss hit.wav")),
+            "A03 kick drumss.wav"
+        );
+    }
+
+    #[test]
+    fn free_slots_skip_taken_pads() {
+        let root = scratch("sp404");
+        std::fs::write(root.join("A0000001.WAV"), b"").unwrap();
+        // Whatever case the card wrote it in.
+        std::fs::write(root.join("a0000002.wav"), b"").unwrap();
+        let free = free_slots(SamplerLayout::Sp404, &root);
+        assert_eq!(free.len(), 10 * 12 - 2);
+        assert_eq!(free[0], ('A', "A0000003".to_string()));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn mpc_pads_are_per_bank_folder() {
+        let root = scratch("mpc");
+        std::fs::create_dir_all(root.join("Bank A")).unwrap();
+        std::fs::write(root.join("Bank A").join("A01 kick.wav"), b"").unwrap();
+        // A name led by another bank's pad doesn't take it.
+        std::fs::write(root.join("Bank A").join("B01 snare.wav"), b"").unwrap();
+        let free = free_slots(SamplerLayout::Mpc, &root);
+        assert_eq!(free.len(), 8 * 16 - 1);
+        assert_eq!(free[0], ('A', "A02".to_string()));
+        assert!(free.contains(&('B', "B01".to_string())));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,536 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+// How much of the end of the program's stderr is kept for the log.
+const STDERR_TAIL: u64 = 64 * 1024;
+
+static NEXT_SCRATCH: AtomicU64 = AtomicU64::new(0);
+
+// A user-supplied program (the post-generation hook) run confined:
+//   Windows  a restricted token (Administrators deny-only, no privileges)
+//            in a job object of its own: no clipboard, desktop or system
+//            settings, and everything in it is killed when it's closed
+//   Linux    Landlock: the filesystem is read-only but for `writable`;
+//            kernels without Landlock (before 5.13) don't run it at all
+//   macOS    sandbox-exec with the same rule
+// Everywhere it starts in a scratch folder of its own (also its TEMP),
+// with stdin closed and only `env`, and it and whatever it started are
+// killed when it exits or after `timeout`. The network stays open, since
+// uploading outputs is one of the things hooks are for.
+pub struct Confined {
+    pub program: String,
+    pub args: Vec<String>,
+    // The whole environment; nothing of ours is inherited.
+    pub env: Vec<(String, String)>,
+    pub writable: Vec<PathBuf>,
+    pub timeout: Duration,
+}
+
+pub enum Finished {
+    Exited {
+        success: bool,
+        // As the OS reports it ("exit status: 2", "signal: 9").
+        status: String,
+        stderr: Vec<u8>,
+    },
+    TimedOut,
+}
+
+pub async fn run(confined: Confined) -> Result<Finished, String> {
+    let scratch = std::env::temp_dir().join(format!(
+        "noises-hook-{}-{}",
+        std::process::id(),
+        NEXT_SCRATCH.fetch_add(1, Ordering::Relaxed)
+    ));
+    let work = scratch.join("work");
+    std::fs::create_dir_all(&work)
+        .map_err(|e| format!("Failed to create {}: {}", work.display(), e))?;
+    let result = run_in(&confined, &scratch, &work).await;
+    if let Err(e) = std::fs::remove_dir_all(&scratch) {
+        eprintln!("[Sandbox] Failed to remove {}: {}", scratch.display(), e);
+    }
+    result
+}
+
+async fn run_in(confined: &Confined, scratch: &Path, work: &Path) -> Result<Finished, String> {
+    let log = |name: &str| {
+        let path = scratch.join(name);
+        File::create(&path)
+            .map(|file| (path.clone(), file))
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))
+    };
+    let (_, stdout) = log("stdout.log")?;
+    let (stderr_path, stderr) = log("stderr.log")?;
+
+    let mut env = confined.env.clone();
+    env.retain(|(key, _)| !TEMP_VARS.iter().any(|t| key.eq_ignore_ascii_case(t)));
+    for key in TEMP_VARS {
+        env.push((key.to_string(), work.to_string_lossy().into_owned()));
+    }
+    let mut writable = confined.writable.clone();
+    writable.push(scratch.to_path_buf());
+
+    let status = spawn_and_wait(confined, &env, &writable, work, stdout, stderr).await?;
+    Ok(match status {
+        Some((success, status)) => Finished::Exited {
+            success,
+            status,
+            stderr: read_tail(&stderr_path),
+        },
+        None => Finished::TimedOut,
+    })
+}
+
+// Pointed at the scratch folder, so the program's temp files go with it.
+const TEMP_VARS: [&str; 3] = ["TEMP", "TMP", "TMPDIR"];
+
+fn read_tail(path: &Path) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    if let Ok(mut file) = File::open(path) {
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let _ = file.seek(SeekFrom::Start(len.saturating_sub(STDERR_TAIL)));
+        let _ = file.read_to_end(&mut bytes);
+    }
+    bytes
+}
+
+// Some((success, status)) once it exits, None when it ran out of time.
+#[cfg(unix)]
+async fn spawn_and_wait(
+    confined: &Confined,
+    env: &[(String, String)],
+    writable: &[PathBuf],
+    work: &Path,
+    stdout: File,
+    stderr: File,
+) -> Result<Option<(bool, String)>, String> {
+    use std::process::Stdio;
+
+    let mut cmd = confine(&confined.program, writable)?;
+    cmd.args(&confined.args)
+        .env_clear()
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .current_dir(work)
+        .stdin(Stdio::null())
+        .stdout(stdout)
+        .stderr(stderr)
+        .process_group(0)
+        .kill_on_drop(true);
+    let mut child = cmd.spawn().map_err(|e| match e.raw_os_error() {
+        Some(libc::ENOSYS) => "Hooks need Landlock (Linux 5.13 or later) to run confined, and this kernel doesn't offer it".to_string(),
+        _ => format!("Failed to run {}: {}", confined.program, e),
+    })?;
+    let group = child.id().map(|pid| pid as libc::pid_t);
+    let waited = tokio::time::timeout(confined.timeout, child.wait()).await;
+    // Whatever it left running goes with it, like the job object on Windows.
+    if let Some(group) = group {
+        unsafe {
+            libc::kill(-group, libc::SIGKILL);
+        }
+    }
+    match waited {
+        Ok(status) => {
+            let status = status.map_err(|e| format!("{} failed: {}", confined.program, e))?;
+            Ok(Some((status.success(), status.to_string())))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+// Landlock rules are built here and only applied in the forked child,
+// where nothing but async-signal-safe calls may run.
+#[cfg(target_os = "linux")]
+fn confine(program: &str, writable: &[PathBuf]) -> Result<tokio::process::Command, String> {
+    use landlock::{
+        ABI, Access, AccessFs, Ruleset, RulesetAttr, RulesetCreatedAttr, RulesetStatus,
+        path_beneath_rules,
+    };
+
+    let abi = ABI::V5;
+    let ruleset = Ruleset::default()
+        .handle_access(AccessFs::from_all(abi))
+        .and_then(|ruleset| ruleset.create())
+        .and_then(|ruleset| ruleset.add_rules(path_beneath_rules(["/"], AccessFs::from_read(abi))))
+        .and_then(|ruleset| {
+            ruleset.add_rules(path_beneath_rules(
+                writable
+                    .iter()
+                    .map(PathBuf::as_path)
+                    .chain([Path::new("/dev/null")]),
+                AccessFs::from_all(abi),
+            ))
+        })
+        .map_err(|e| format!("Failed to set up the hook's sandbox: {}", e))?;
+
+    let mut ruleset = Some(ruleset);
+    let parent = std::process::id() as libc::pid_t;
+    let mut cmd = tokio::process::Command::new(program);
+    unsafe {
+        cmd.pre_exec(move || {
+            // The same death signal as the backend (child_guard.rs).
+            libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+            if libc::getppid() != parent {
+                libc::_exit(1);
+            }
+            let enforced = ruleset
+                .take()
+                .and_then(|ruleset| ruleset.restrict_self().ok())
+                .is_some_and(|status| status.ruleset != RulesetStatus::NotEnforced);
+            if !enforced {
+                return Err(std::io::Error::from_raw_os_error(libc::ENOSYS));
+            }
+            Ok(())
+        });
+    }
+    Ok(cmd)
+}
+
+#[cfg(target_os = "macos")]
+fn confine(program: &str, writable: &[PathBuf]) -> Result<tokio::process::Command, String> {
+    // Seatbelt matches real paths, and TMPDIR is under the /var symlink.
+    let writable: Vec<PathBuf> = writable
+        .iter()
+        .filter_map(|path| crate::paths::canonical(path).ok())
+        .collect();
+    let mut cmd = tokio::process::Command::new("/usr/bin/sandbox-exec");
+    cmd.arg("-p").arg(seatbelt_profile(&writable)).arg(program);
+    Ok(cmd)
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+fn confine(_program: &str, _writable: &[PathBuf]) -> Result<tokio::process::Command, String> {
+    Err("Hooks can't run confined on this system".to_string())
+}
+
+// Everything allowed but writing, which only goes to `writable`.
+#[cfg(any(target_os = "macos", test))]
+fn seatbelt_profile(writable: &[PathBuf]) -> String {
+    let quote = |path: &Path| {
+        path.to_string_lossy()
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+    };
+    let mut profile = String::from("(version 1)\n(allow default)\n(deny file-write*)\n");
+    profile.push_str("(allow file-write* (literal \"/dev/null\")");
+    for path in writable {
+        profile.push_str(&format!(" (subpath \"{}\")", quote(path)));
+    }
+    profile.push_str(")\n");
+    profile
+}
+
+#[cfg(windows)]
+async fn spawn_and_wait(
+    confined: &Confined,
+    env: &[(String, String)],
+    _writable: &[PathBuf],
+    work: &Path,
+    stdout: File,
+    stderr: File,
+) -> Result<Option<(bool, String)>, String> {
+    let line = command_line(&confined.program, &confined.args);
+    let env = env.to_vec();
+    let work = work.to_path_buf();
+    let timeout = confined.timeout;
+    let program = confined.program.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        run_restricted(&line, &env, &work, stdout, stderr, timeout)
+            .map_err(|e| format!("Failed to run {}: {}", program, e))
+    })
+    .await
+    .map_err(|e| format!("Hook task failed: {}", e))?
+}
+
+// CreateProcess hands the child one string; this quotes each word the way
+// the C runtime splits it again.
+#[cfg(any(windows, test))]
+fn command_line(program: &str, args: &[String]) -> String {
+    let mut line = String::new();
+    for word in std::iter::once(program).chain(args.iter().map(String::as_str)) {
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        if !word.is_empty() && !word.contains([' ', '\t', '\n', '\u{b}', '"']) {
+            line.push_str(word);
+            continue;
+        }
+        line.push('"');
+        let mut backslashes = 0;
+        for c in word.chars() {
+            match c {
+                '\\' => backslashes += 1,
+                // Backslashes only escape when a quote follows.
+                '"' => {
+                    line.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                    line.push('"');
+                    backslashes = 0;
+                }
+                c => {
+                    line.extend(std::iter::repeat_n('\\', backslashes));
+                    line.push(c);
+                    backslashes = 0;
+                }
+            }
+        }
+        line.extend(std::iter::repeat_n('\\', backslashes * 2));
+        line.push('"');
+    }
+    line
+}
+
+#[cfg(windows)]
+fn run_restricted(
+    line: &str,
+    env: &[(String, String)],
+    work: &Path,
+    stdout: File,
+    stderr: File,
+    timeout: Duration,
+) -> windows::core::Result<Option<(bool, String)>> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::{
+        CloseHandle, HANDLE, HANDLE_FLAG_INHERIT, SetHandleInformation, WAIT_TIMEOUT,
+    };
+    use windows::Win32::Security::{
+        CreateRestrictedToken, CreateWellKnownSid, DISABLE_MAX_PRIVILEGE, PSID,
+        SECURITY_MAX_SID_SIZE, SID_AND_ATTRIBUTES, TOKEN_ASSIGN_PRIMARY, TOKEN_DUPLICATE,
+        TOKEN_QUERY, WinBuiltinAdministratorsSid,
+    };
+    use windows::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JOB_OBJECT_LIMIT_DIE_ON_UNHANDLED_EXCEPTION,
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE, JOB_OBJECT_UILIMIT_DESKTOP,
+        JOB_OBJECT_UILIMIT_DISPLAYSETTINGS, JOB_OBJECT_UILIMIT_EXITWINDOWS,
+        JOB_OBJECT_UILIMIT_GLOBALATOMS, JOB_OBJECT_UILIMIT_HANDLES,
+        JOB_OBJECT_UILIMIT_READCLIPBOARD, JOB_OBJECT_UILIMIT_SYSTEMPARAMETERS,
+        JOB_OBJECT_UILIMIT_WRITECLIPBOARD, JOBOBJECT_BASIC_UI_RESTRICTIONS,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JobObjectBasicUIRestrictions,
+        JobObjectExtendedLimitInformation, SetInformationJobObject, TerminateJobObject,
+    };
+    use windows::Win32::System::Threading::{
+        CREATE_NO_WINDOW, CREATE_SUSPENDED, CREATE_UNICODE_ENVIRONMENT, CreateProcessAsUserW,
+        DeleteProcThreadAttributeList, EXTENDED_STARTUPINFO_PRESENT, GetCurrentProcess,
+        GetExitCodeProcess, InitializeProcThreadAttributeList, LPPROC_THREAD_ATTRIBUTE_LIST,
+        OpenProcessToken, PROC_THREAD_ATTRIBUTE_HANDLE_LIST, PROCESS_INFORMATION, ResumeThread,
+        STARTF_USESTDHANDLES, STARTUPINFOEXW, TerminateProcess, WaitForSingleObject,
+    };
+    use windows::core::{PCWSTR, PWSTR};
+
+    // Closes what it holds however this returns.
+    struct Owned(HANDLE);
+    impl Drop for Owned {
+        fn drop(&mut self) {
+            if !self.0.is_invalid() {
+                unsafe {
+                    let _ = CloseHandle(self.0);
+                }
+            }
+        }
+    }
+
+    let wide = |text: &std::ffi::OsStr| -> Vec<u16> { text.encode_wide().chain([0]).collect() };
+    let stdin = File::open("NUL").map_err(windows::core::Error::from)?;
+    let handles = [
+        HANDLE(stdin.as_raw_handle()),
+        HANDLE(stdout.as_raw_handle()),
+        HANDLE(stderr.as_raw_handle()),
+    ];
+
+    unsafe {
+        // The user's own token, minus admin rights and every privilege.
+        let mut token = HANDLE::default();
+        OpenProcessToken(
+            GetCurrentProcess(),
+            TOKEN_DUPLICATE | TOKEN_ASSIGN_PRIMARY | TOKEN_QUERY,
+            &mut token,
+        )?;
+        let token = Owned(token);
+        let mut sid = [0u8; SECURITY_MAX_SID_SIZE as usize];
+        let mut sid_len = sid.len() as u32;
+        let admins = PSID(sid.as_mut_ptr().cast());
+        CreateWellKnownSid(
+            WinBuiltinAdministratorsSid,
+            None,
+            Some(admins),
+            &mut sid_len,
+        )?;
+        let mut restricted = HANDLE::default();
+        CreateRestrictedToken(
+            token.0,
+            DISABLE_MAX_PRIVILEGE,
+            Some(&[SID_AND_ATTRIBUTES {
+                Sid: admins,
+                Attributes: 0,
+            }]),
+            None,
+            None,
+            &mut restricted,
+        )?;
+        let restricted = Owned(restricted);
+
+        let job = Owned(CreateJobObjectW(None, PCWSTR::null())?);
+        let mut limits = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+        limits.BasicLimitInformation.LimitFlags =
+            JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE | JOB_OBJECT_LIMIT_DIE_ON_UNHANDLED_EXCEPTION;
+        SetInformationJobObject(
+            job.0,
+            JobObjectExtendedLimitInformation,
+            &limits as *const _ as *const std::ffi::c_void,
+            std::mem::size_of_val(&limits) as u32,
+        )?;
+        let ui = JOBOBJECT_BASIC_UI_RESTRICTIONS {
+            UIRestrictionsClass: JOB_OBJECT_UILIMIT_DESKTOP
+                | JOB_OBJECT_UILIMIT_DISPLAYSETTINGS
+                | JOB_OBJECT_UILIMIT_EXITWINDOWS
+                | JOB_OBJECT_UILIMIT_GLOBALATOMS
+                | JOB_OBJECT_UILIMIT_HANDLES
+                | JOB_OBJECT_UILIMIT_READCLIPBOARD
+                | JOB_OBJECT_UILIMIT_SYSTEMPARAMETERS
+                | JOB_OBJECT_UILIMIT_WRITECLIPBOARD,
+        };
+        SetInformationJobObject(
+            job.0,
+            JobObjectBasicUIRestrictions,
+            &ui as *const _ as *const std::ffi::c_void,
+            std::mem::size_of_val(&ui) as u32,
+        )?;
+
+        // Only these three handles are inherited, whatever else is open.
+        for handle in handles {
+            SetHandleInformation(handle, HANDLE_FLAG_INHERIT.0, HANDLE_FLAG_INHERIT)?;
+        }
+        let mut size = 0;
+        let _ = InitializeProcThreadAttributeList(None, 1, None, &mut size);
+        let mut list_buffer = vec![0u8; size];
+        let list = LPPROC_THREAD_ATTRIBUTE_LIST(list_buffer.as_mut_ptr().cast());
+        InitializeProcThreadAttributeList(Some(list), 1, None, &mut size)?;
+        struct List(LPPROC_THREAD_ATTRIBUTE_LIST);
+        impl Drop for List {
+            fn drop(&mut self) {
+                unsafe { DeleteProcThreadAttributeList(self.0) }
+            }
+        }
+        let list = List(list);
+        windows::Win32::System::Threading::UpdateProcThreadAttribute(
+            list.0,
+            0,
+            PROC_THREAD_ATTRIBUTE_HANDLE_LIST as usize,
+            Some(handles.as_ptr().cast()),
+            std::mem::size_of_val(&handles),
+            None,
+            None,
+        )?;
+
+        let mut startup = STARTUPINFOEXW::default();
+        startup.StartupInfo.cb = std::mem::size_of::<STARTUPINFOEXW>() as u32;
+        startup.StartupInfo.dwFlags = STARTF_USESTDHANDLES;
+        startup.StartupInfo.hStdInput = handles[0];
+        startup.StartupInfo.hStdOutput = handles[1];
+        startup.StartupInfo.hStdError = handles[2];
+        startup.lpAttributeList = list.0;
+
+        // Sorted, as Windows expects of an environment block.
+        let mut env = env.to_vec();
+        env.sort_by_key(|(key, _)| key.to_uppercase());
+        let mut block: Vec<u16> = env
+            .iter()
+            .flat_map(|(key, value)| {
+                format!("{}={}", key, value)
+                    .encode_utf16()
+                    .chain([0])
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        block.push(0);
+        let mut line: Vec<u16> = line.encode_utf16().chain([0]).collect();
+        let work = wide(work.as_os_str());
+
+        // Suspended until it's in the job, so nothing it starts escapes it.
+        let mut process = PROCESS_INFORMATION::default();
+        CreateProcessAsUserW(
+            Some(restricted.0),
+            PCWSTR::null(),
+            Some(PWSTR(line.as_mut_ptr())),
+            None,
+            None,
+            true,
+            CREATE_SUSPENDED
+                | CREATE_UNICODE_ENVIRONMENT
+                | CREATE_NO_WINDOW
+                | EXTENDED_STARTUPINFO_PRESENT,
+            Some(block.as_ptr().cast()),
+            PCWSTR(work.as_ptr()),
+            &startup.StartupInfo,
+            &mut process,
+        )?;
+        let child = Owned(process.hProcess);
+        let thread = Owned(process.hThread);
+        if let Err(e) = AssignProcessToJobObject(job.0, child.0) {
+            let _ = TerminateProcess(child.0, 1);
+            return Err(e);
+        }
+        ResumeThread(thread.0);
+
+        let millis = u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX - 1);
+        if WaitForSingleObject(child.0, millis) == WAIT_TIMEOUT {
+            let _ = TerminateJobObject(job.0, 1);
+            return Ok(None);
+        }
+        let mut code = 0u32;
+        GetExitCodeProcess(child.0, &mut code)?;
+        // Dropping the job kills anything it left running.
+        Ok(Some((code == 0, format!("exit code: {}", code))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn plain_words_stay_as_they_are() {
+        assert_eq!(
+            command_line("ffmpeg", &args(&["-i", r"C:\out\loop_001.wav"])),
+            r"ffmpeg -i C:\out\loop_001.wav"
+        );
+    }
+
+    #[test]
+    fn spaces_and_empty_words_are_quoted() {
+        assert_eq!(
+            command_line(r"C:\Program Files\x.exe", &args(&["two words", ""])),
+            r#""C:\Program Files\x.exe" "two words" """#
+        );
+    }
+
+    #[test]
+    fn quotes_and_their_backslashes_are_escaped() {
+        assert_eq!(
+            command_line("echo", &args(&[r#"say "hi""#, r#"a\"b"#])),
+            r#"echo "say \"hi\"" "a\\\"b""#
+        );
+        // A trailing backslash would otherwise escape the closing quote.
+        assert_eq!(
+            command_line("copy", &args(&[r"C:\My Sounds\"])),
+            r#"copy "C:\My Sounds\\""#
+        );
+    }
+
+    #[test]
+    fn seatbelt_only_allows_writes_to_the_given_folders() {
+        let profile = seatbelt_profile(&[
+            PathBuf::from("/Users/me/Music/Noises"),
+            PathBuf::from("/tmp/odd \"name\""),
+        ]);
+        assert!(profile.contains("(deny file-write*)"));
+        assert!(profile.contains("(subpath \"/Users/me/Music/Noises\")"));
+        assert!(profile.contains("(subpath \"/tmp/odd \\\"name\\\"\")"));
+    }
+}
@@ -0,0 +1,99 @@
+use serde::Deserialize;
+use tauri::AppHandle;
+
+// Tokens go to the OS credential store (Windows Credential Manager, the macOS
+// keychain, Secret Service on Linux) under the app identifier, never to a file.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Secret {
+    // Needed for gated checkpoints such as Stable Audio Open.
+    HuggingfaceToken,
+    // For the local HTTP API (http_api.rs).
+    ApiToken,
+    // The S3 secret key or WebDAV password for library sync (sync.rs).
+    SyncPassword,
+    // Where share_to_discord posts (discord.rs); the URL is its own token.
+    DiscordWebhook,
+    // OAuth tokens for uploads (freesound.rs), as JSON.
+    FreesoundToken,
+    // API keys for remote generation providers (providers.rs).
+    ReplicateToken,
+    RunpodApiKey,
+}
+
+impl Secret {
+    fn account(self) -> &'static str {
+        match self {
+            Secret::HuggingfaceToken => "huggingface-token",
+            Secret::ApiToken => "api-token",
+            Secret::SyncPassword => "sync-password",
+            Secret::DiscordWebhook => "discord-webhook",
+            Secret::FreesoundToken => "freesound-token",
+            Secret::ReplicateToken => "replicate-token",
+            Secret::RunpodApiKey => "runpod-api-key",
+        }
+    }
+}
+
+fn entry(app: &AppHandle, secret: Secret) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(&app.config().identifier, secret.account())
+        .map_err(|e| format!("Failed to open the credential store: {}", e))
+}
+
+// The Secret Service backend drives its own async runtime, so store calls get
+// a thread to themselves instead of running on one of ours.
+fn on_own_thread<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    std::thread::scope(|scope| scope.spawn(f).join())
+        .unwrap_or_else(|e| std::panic::resume_unwind(e))
+}
+
+pub fn get(app: &AppHandle, secret: Secret) -> Result<Option<String>, String> {
+    let entry = entry(app, secret)?;
+    on_own_thread(move || match entry.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", secret.account(), e)),
+    })
+}
+
+// For callers that just go without when there's no token or no store.
+pub fn get_or_none(app: &AppHandle, secret: Secret) -> Option<String> {
+    get(app, secret).unwrap_or_else(|e| {
+        eprintln!("[Secrets] {}", e);
+        None
+    })
+}
+
+#[tauri::command]
+pub async fn get_secret(app: AppHandle, key: Secret) -> Result<Option<String>, String> {
+    get(&app, key)
+}
+
+// An empty value removes the secret.
+pub fn set(app: &AppHandle, secret: Secret, value: Option<String>) -> Result<(), String> {
+    let entry = entry(app, secret)?;
+    let value = value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    on_own_thread(move || match value {
+        Some(value) => entry.set_password(&value),
+        None => match entry.delete_credential() {
+            Err(keyring::Error::NoEntry) => Ok(()),
+            result => result,
+        },
+    })
+    .map_err(|e| format!("Failed to save {}: {}", secret.account(), e))?;
+    println!("[Secrets] Updated {}", secret.account());
+    Ok(())
+}
+
+// The backend reads its token at spawn, so a change reaches it on the next
+// start. The API token has its own command (reset_api_token), since the
+// running server caches it.
+#[tauri::command]
+pub async fn set_secret(app: AppHandle, key: Secret, value: Option<String>) -> Result<(), String> {
+    if matches!(key, Secret::ApiToken) {
+        return Err("Use reset_api_token to change the API token".to_string());
+    }
+    set(&app, key, value)
+}
@@ -0,0 +1,314 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{Value, json};
+
+use crate::backend;
+use crate::jobs::{self, Job};
+use crate::models;
+use crate::paths;
+use crate::tasks::Task;
+
+// Each model has a longest clip it makes in one go (models.rs). A request
+// past that is made as overlapping segments, one after another, and the
+// overlaps are crossfaded into one file. The overlap is longer than the
+// fade-out the backend puts on every clip, so that's never heard.
+const OVERLAP: f64 = 4.0;
+
+// Beyond this it's more likely a typo (an hour of loop) than a request.
+const MAX_SEGMENTS: usize = 16;
+
+fn length(config: &Value) -> Option<f64> {
+    config.get("length").and_then(|l| l.as_f64())
+}
+
+fn max_length(config: &Value) -> f64 {
+    models::max_length(
+        config
+            .get("type")
+            .and_then(|t| t.as_str())
+            .unwrap_or_default(),
+    )
+}
+
+pub fn is_long(config: &Value) -> bool {
+    length(config).is_some_and(|length| length > max_length(config))
+}
+
+// Equal segments, as few as fit: n of them cover n * length - (n - 1) * OVERLAP.
+fn plan(total: f64, max: f64) -> Result<Vec<f64>, String> {
+    let count = ((total - OVERLAP) / (max - OVERLAP)).ceil().max(1.0) as usize;
+    if count > MAX_SEGMENTS {
+        return Err(format!(
+            "{:.0} s is too long; at most {:.0} s",
+            total,
+            MAX_SEGMENTS as f64 * (max - OVERLAP) + OVERLAP
+        ));
+    }
+    let each = (total + (count - 1) as f64 * OVERLAP) / count as f64;
+    Ok(vec![each; count])
+}
+
+// The segments made so far. Whatever's left in here when it's dropped (a
+// failure, a cancel, or the parts after stitching) is deleted.
+struct Parts(Vec<PathBuf>);
+
+impl Drop for Parts {
+    fn drop(&mut self) {
+        for path in &self.0 {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+// Makes a long request as segments with consecutive seeds, through the job
+// (so a backend crash re-sends only the segment it hit), and answers like
+// the backend does for a single clip.
+pub async fn generate(job: &Job, task: &Task, config: &Value) -> Result<Value, String> {
+    let total = length(config).unwrap_or_default();
+    let lengths = plan(total, max_length(config))?;
+    let seed = match config.get("seed").and_then(|s| s.as_u64()) {
+        Some(seed) => seed,
+        None => jobs::random_seed()?,
+    };
+    println!(
+        "[Segments] \"{}\": {:.0} s as {} segments of {:.1} s",
+        job.prompt(),
+        total,
+        lengths.len(),
+        lengths[0]
+    );
+
+    let mut parts = Parts(Vec::with_capacity(lengths.len()));
+    for (i, length) in lengths.iter().enumerate() {
+        task.progress(i as u64, Some(lengths.len() as u64));
+        let mut segment = config.clone();
+        segment["length"] = json!(length);
+        segment["seed"] = json!(seed.wrapping_add(i as u64));
+        segment["variations"] = json!(1);
+        let result = job.run_on_backend(|| backend::generate(&segment)).await?;
+        let path = result
+            .get("files")
+            .and_then(|f| f.get(0))
+            .and_then(|f| f.get("path"))
+            .and_then(|p| p.as_str())
+            .map(PathBuf::from)
+            .ok_or("The backend answered without a file")?;
+        parts.0.push(path);
+    }
+    task.progress(lengths.len() as u64, Some(lengths.len() as u64));
+
+    // The result takes the first segment's name.
+    let output = parts.0[0].clone();
+    let temp = paths::temp_path(&output);
+    let sources = parts.0.clone();
+    let stitched = {
+        let temp = temp.clone();
+        tauri::async_runtime::spawn_blocking(move || stitch(&sources, &temp))
+            .await
+            .map_err(|e| format!("Stitching failed: {}", e))?
+    };
+    let spec = match stitched.and_then(|spec| {
+        std::fs::rename(&temp, &output)
+            .map(|()| spec)
+            .map_err(|e| format!("Failed to save {}: {}", output.display(), e))
+    }) {
+        Ok(spec) => spec,
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp);
+            return Err(e);
+        }
+    };
+    parts.0.remove(0);
+
+    let (frames, sample_rate) = spec;
+    let file = json!({
+        "file": output.file_name().map(|n| n.to_string_lossy().into_owned()),
+        "path": output,
+        "duration": frames as f64 / sample_rate as f64,
+        "sample_rate": sample_rate,
+    });
+    Ok(json!({ "status": "success", "files": [file], "path": output }))
+}
+
+fn read(path: &Path) -> Result<(hound::WavSpec, Vec<f32>), String> {
+    let mut reader = hound::WavReader::open(path)
+        .map_err(|e| format!("Failed to read segment {}: {}", path.display(), e))?;
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>(),
+        hound::SampleFormat::Int => {
+            let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 * scale))
+                .collect()
+        }
+    }
+    .map_err(|e| format!("Failed to read segment {}: {}", path.display(), e))?;
+    Ok((spec, samples))
+}
+
+// Writes the segments to `to` one after another, each overlap an
+// equal-power crossfade. Only one segment is in memory at a time. Returns
+// the frame count and sample rate.
+fn stitch(parts: &[PathBuf], to: &Path) -> Result<(usize, u32), String> {
+    let (spec, first) = read(&parts[0])?;
+    let channels = spec.channels.max(1) as usize;
+    let overlap = (OVERLAP * spec.sample_rate as f64) as usize * channels;
+    let mut writer = hound::WavWriter::create(to, spec)
+        .map_err(|e| format!("Failed to write {}: {}", to.display(), e))?;
+    let peak = (1i64 << (spec.bits_per_sample - 1)) as f32 - 1.0;
+    let mut write = |samples: &[f32]| -> Result<(), String> {
+        for &s in samples {
+            match spec.sample_format {
+                hound::SampleFormat::Float => writer.write_sample(s),
+                hound::SampleFormat::Int => {
+                    writer.write_sample((s.clamp(-1.0, 1.0) * peak).round() as i32)
+                }
+            }
+            .map_err(|e| format!("Failed to write {}: {}", to.display(), e))?;
+        }
+        Ok(())
+    };
+
+    let mut written = 0;
+    // The end of the previous segment, waiting to be faded into the next.
+    let mut tail: Vec<f32> = Vec::new();
+    let mut next = Some(first);
+    for (i, path) in parts.iter().enumerate() {
+        let samples = match next.take() {
+            Some(samples) => samples,
+            None => {
+                let (segment_spec, samples) = read(path)?;
+                if segment_spec.sample_rate != spec.sample_rate
+                    || segment_spec.channels != spec.channels
+                {
+                    return Err("The segments came back in different formats".to_string());
+                }
+                samples
+            }
+        };
+
+        // Whole frames only, so channels stay in step.
+        let fade = tail.len().min(samples.len()) / channels * channels;
+        let frames = fade / channels;
+        let faded: Vec<f32> = (0..fade)
+            .map(|n| {
+                let t = (n / channels) as f32 / frames.max(1) as f32;
+                let angle = t * std::f32::consts::FRAC_PI_2;
+                tail[n] * angle.cos() + samples[n] * angle.sin()
+            })
+            .collect();
+        write(&faded)?;
+
+        let keep = if i + 1 < parts.len() {
+            overlap.min(samples.len() - fade) / channels * channels
+        } else {
+            0
+        };
+        let body_end = samples.len() - keep;
+        write(&samples[fade..body_end])?;
+        written += body_end;
+        tail = samples[body_end..].to_vec();
+    }
+    writer
+        .finalize()
+        .map_err(|e| format!("Failed to write {}: {}", to.display(), e))?;
+    Ok((written / channels, spec.sample_rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn covered(lengths: &[f64]) -> f64 {
+        lengths.iter().sum::<f64>() - (lengths.len() - 1) as f64 * OVERLAP
+    }
+
+    #[test]
+    fn short_requests_are_one_segment() {
+        assert_eq!(plan(30.0, 47.0).unwrap(), [30.0]);
+        assert_eq!(plan(47.0, 47.0).unwrap(), [47.0]);
+    }
+
+    #[test]
+    fn segments_fit_and_cover_the_length() {
+        for total in [48.0, 100.0, 240.0, 600.0] {
+            let lengths = plan(total, 47.0).unwrap();
+            assert!(
+                lengths.iter().all(|l| *l <= 47.0),
+                "{}: {:?}",
+                total,
+                lengths
+            );
+            assert!(
+                (covered(&lengths) - total).abs() < 1e-9,
+                "{}: {:?}",
+                total,
+                lengths
+            );
+        }
+        assert_eq!(plan(100.0, 47.0).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn too_many_segments_is_an_error() {
+        let longest = MAX_SEGMENTS as f64 * (47.0 - OVERLAP) + OVERLAP;
+        assert_eq!(plan(longest, 47.0).unwrap().len(), MAX_SEGMENTS);
+        assert!(plan(longest + 1.0, 47.0).is_err());
+    }
+
+    // Mono float WAVs; at 100 Hz the overlap is 400 samples.
+    fn write_segment(name: &str, sample_rate: u32, samples: &[f32]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("noises-segment-{}.wav", name));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for &s in samples {
+            writer.write_sample(s).unwrap();
+        }
+        writer.finalize().unwrap();
+        path
+    }
+
+    #[test]
+    fn stitch_crossfades_the_overlap() {
+        let parts = [
+            write_segment("a", 100, &[1.0; 1000]),
+            write_segment("b", 100, &[0.5; 1000]),
+        ];
+        let to = std::env::temp_dir().join("noises-segment-stitched.wav");
+        let stitched = stitch(&parts, &to);
+        let (_, samples) = read(&to).unwrap();
+        for path in parts.iter().chain([&to]) {
+            let _ = std::fs::remove_file(path);
+        }
+
+        assert_eq!(stitched.unwrap(), (1600, 100));
+        assert_eq!(samples.len(), 1600);
+        assert_eq!(samples[599], 1.0);
+        // Starts as the first segment, ends as the second, never dips.
+        assert!((samples[600] - 1.0).abs() < 1e-6);
+        assert!((samples[999] - 0.5).abs() < 0.01);
+        assert!(samples[600..1000].iter().all(|s| *s >= 0.5 - 1e-6));
+        assert_eq!(samples[1599], 0.5);
+    }
+
+    #[test]
+    fn stitch_rejects_mixed_formats() {
+        let parts = [
+            write_segment("c", 100, &[0.0; 1000]),
+            write_segment("d", 200, &[0.0; 1000]),
+        ];
+        let to = std::env::temp_dir().join("noises-segment-mixed.wav");
+        let stitched = stitch(&parts, &to);
+        for path in parts.iter().chain([&to]) {
+            let _ = std::fs::remove_file(path);
+        }
+        assert!(stitched.is_err());
+    }
+}
@@ -0,0 +1,299 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, Url};
+
+use crate::http_api;
+use crate::jobs::JobManager;
+use crate::midi::{self, MidiMapping};
+use crate::models;
+use crate::osc;
+use crate::paths;
+use crate::playback;
+use crate::providers::RemoteProvider;
+use crate::proxy::ProxyMode;
+use crate::sidecar::{self, ComputeDevice};
+use crate::sync::SyncRemote;
+use crate::telemetry;
+use crate::tray;
+use crate::updater::{self, Channel};
+use crate::watch_folder;
+
+// Everything the Rust side remembers between launches, in settings.json in the
+// app config dir. Hotkeys keep their own file (hotkeys.json), and start at
+// login lives with the OS.
+//
+// Keys are the field names. A missing or unknown field falls back to its
+// default, so older and newer versions can share a file.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // Closing the main window hides it to the tray instead of quitting.
+    pub close_to_tray: bool,
+    // Don't spawn the backend (and load models onto the GPU) until the first
+    // generation asks for it. Takes effect on the next launch.
+    pub lazy_backend_start: bool,
+    pub update_channel: Channel,
+    // Model id (models.rs) used when a generation doesn't say which.
+    pub default_model: String,
+    // Refuse every request that would leave the machine (proxy.rs), and keep
+    // the backend from fetching checkpoints. Changing it restarts the backend.
+    pub offline: bool,
+    pub proxy_mode: ProxyMode,
+    // Only used with proxy_mode "manual".
+    pub proxy_url: Option<Url>,
+    // Where the backend runs the models; changing it restarts the backend.
+    pub compute_device: ComputeDevice,
+    // Spawn the backend with memory-saving flags and cap request sizes
+    // (low_vram.rs), for GPUs around 6 GB. Changing it restarts the backend.
+    pub low_vram: bool,
+    // GPU temperature (°C) at which the queue pauses until it has cooled
+    // down (thermal.rs). None leaves it alone.
+    pub thermal_limit: Option<u32>,
+    // Name generations after their prompt ("Rain on a tin roof.wav") instead
+    // of the backend's loop_001 / song_001 (filenames.rs).
+    pub prompt_file_names: bool,
+    // A second output device previews are mirrored to (playback.rs), e.g.
+    // headphones while the default device feeds a stream.
+    pub cue_device: Option<String>,
+    // The program "Open in editor" uses (editor.rs). None means the system's
+    // own choice for the file.
+    pub external_editor: Option<PathBuf>,
+    // Where a generation marked "render_target" is copied when it's done
+    // (render_target.rs), e.g. a game's Assets/Audio/latest.wav.
+    pub render_target: Option<PathBuf>,
+    // Sounds exported into a Godot or Unity project get a companion file
+    // with import settings for that engine (game_engines.rs).
+    pub engine_metadata: bool,
+    // The local HTTP API for other tools (http_api.rs), on 127.0.0.1 only.
+    pub api_enabled: bool,
+    pub api_port: u16,
+    // The OSC listener for live triggering (osc.rs), also 127.0.0.1 only.
+    pub osc_enabled: bool,
+    pub osc_port: u16,
+    // MIDI triggers (midi.rs). No input means every input.
+    pub midi_enabled: bool,
+    pub midi_input: Option<String>,
+    pub midi_mappings: Vec<MidiMapping>,
+    // Prompt files dropped here are generated next to themselves
+    // (watch_folder.rs).
+    pub watch_folder: Option<PathBuf>,
+    // Where the library syncs to (sync.rs). None means no sync.
+    pub sync: Option<SyncRemote>,
+    // Where a request with a "provider" of that name is sent instead of the
+    // local backend (providers.rs).
+    pub remote_providers: Vec<RemoteProvider>,
+    // A command run after every successful generation (hooks.rs), and how
+    // long it may take before it's stopped.
+    pub post_generation_hook: Option<String>,
+    pub hook_timeout_seconds: u64,
+    // Send anonymous usage counters (telemetry.rs). Off unless the user opts in.
+    pub telemetry: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            close_to_tray: false,
+            lazy_backend_start: false,
+            update_channel: Channel::default(),
+            default_model: models::DEFAULT_MODEL.to_string(),
+            offline: false,
+            proxy_mode: ProxyMode::default(),
+            proxy_url: None,
+            compute_device: ComputeDevice::default(),
+            low_vram: false,
+            thermal_limit: None,
+            prompt_file_names: false,
+            cue_device: None,
+            external_editor: None,
+            render_target: None,
+            engine_metadata: false,
+            api_enabled: false,
+            api_port: 8765,
+            osc_enabled: false,
+            osc_port: 9000,
+            midi_enabled: false,
+            midi_input: None,
+            midi_mappings: Vec::new(),
+            watch_folder: None,
+            sync: None,
+            remote_providers: Vec::new(),
+            post_generation_hook: None,
+            hook_timeout_seconds: 60,
+            telemetry: false,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct SettingChanged {
+    key: String,
+    value: Value,
+}
+
+pub struct SettingsStore {
+    settings: Mutex<Settings>,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve config directory: {}", e))?;
+    Ok(dir.join("settings.json"))
+}
+
+impl SettingsStore {
+    pub fn load(app: &AppHandle) -> Self {
+        let path = settings_path(app).ok();
+        let saved = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok());
+        let settings = match saved {
+            Some(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                eprintln!("[Settings] Ignoring unreadable settings.json: {}", e);
+                Settings::default()
+            }),
+            None => migrate(app),
+        };
+        SettingsStore {
+            settings: Mutex::new(settings),
+        }
+    }
+
+    fn save(&self, app: &AppHandle) -> Result<(), String> {
+        let path = settings_path(app)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create config folder: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(&*self.settings.lock().unwrap())
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        paths::write_atomic(path, json).map_err(|e| format!("Failed to save settings: {}", e))
+    }
+}
+
+// Before settings.json, lazy start and the update channel had a file each.
+// Fold them in on the first launch that has settings, then drop them.
+fn migrate(app: &AppHandle) -> Settings {
+    #[derive(Default, Deserialize)]
+    struct BackendJson {
+        #[serde(default)]
+        lazy_start: bool,
+    }
+    #[derive(Default, Deserialize)]
+    struct UpdaterJson {
+        #[serde(default)]
+        channel: Channel,
+    }
+
+    let Ok(dir) = app.path().app_config_dir() else {
+        return Settings::default();
+    };
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).ok();
+    let backend: Option<BackendJson> =
+        read("backend.json").and_then(|json| serde_json::from_str(&json).ok());
+    let updater: Option<UpdaterJson> =
+        read("updater.json").and_then(|json| serde_json::from_str(&json).ok());
+    if backend.is_none() && updater.is_none() {
+        return Settings::default();
+    }
+
+    let settings = Settings {
+        lazy_backend_start: backend.unwrap_or_default().lazy_start,
+        update_channel: updater.unwrap_or_default().channel,
+        ..Settings::default()
+    };
+    let store = SettingsStore {
+        settings: Mutex::new(settings.clone()),
+    };
+    match store.save(app) {
+        Ok(()) => {
+            let _ = std::fs::remove_file(dir.join("backend.json"));
+            let _ = std::fs::remove_file(dir.join("updater.json"));
+            println!("[Settings] Migrated backend.json and updater.json");
+        }
+        Err(e) => eprintln!("[Settings] Failed to migrate old settings: {}", e),
+    }
+    settings
+}
+
+pub fn get(app: &AppHandle) -> Settings {
+    app.state::<SettingsStore>()
+        .settings
+        .lock()
+        .unwrap()
+        .clone()
+}
+
+// Checks the value against the key's type (by round-tripping through
+// Settings), saves, and tells everyone with `settings-changed`.
+pub fn set(app: &AppHandle, key: &str, value: Value) -> Result<(), String> {
+    let store = app.state::<SettingsStore>();
+    {
+        let mut settings = store.settings.lock().unwrap();
+        let mut fields = match serde_json::to_value(&*settings) {
+            Ok(Value::Object(fields)) => fields,
+            _ => return Err("Failed to read settings".to_string()),
+        };
+        match fields.get(key) {
+            None => return Err(format!("Unknown setting: {}", key)),
+            Some(current) if *current == value => return Ok(()),
+            Some(_) => {}
+        }
+        fields.insert(key.to_string(), value.clone());
+        *settings = serde_json::from_value(Value::Object(fields))
+            .map_err(|e| format!("Invalid value for {}: {}", key, e))?;
+    }
+    store.save(app)?;
+    println!("[Settings] {} = {}", key, value);
+
+    apply(app, key);
+    let _ = app.emit(
+        "settings-changed",
+        SettingChanged {
+            key: key.to_string(),
+            value,
+        },
+    );
+    Ok(())
+}
+
+// Settings that something is already acting on get passed along here.
+fn apply(app: &AppHandle, key: &str) {
+    match key {
+        "close_to_tray" => tray::refresh(app, &app.state::<JobManager>().status()),
+        "update_channel" => updater::channel_changed(app),
+        "compute_device" | "low_vram" | "offline" => sidecar::restart(app),
+        "telemetry" => telemetry::setting_changed(app),
+        "api_enabled" | "api_port" => http_api::restart(app),
+        "osc_enabled" | "osc_port" => osc::restart(app),
+        "midi_enabled" | "midi_input" => midi::restart(app),
+        "watch_folder" => watch_folder::restart(app),
+        "cue_device" => playback::cue_device_changed(app),
+        _ => {}
+    }
+}
+
+#[tauri::command]
+pub fn get_setting(app: AppHandle, key: String) -> Result<Value, String> {
+    let settings =
+        serde_json::to_value(get(&app)).map_err(|e| format!("Failed to read settings: {}", e))?;
+    settings
+        .get(&key)
+        .cloned()
+        .ok_or_else(|| format!("Unknown setting: {}", key))
+}
+
+#[tauri::command]
+pub fn set_setting(app: AppHandle, key: String, value: Value) -> Result<(), String> {
+    set(&app, &key, value)
+}
+
+#[tauri::command]
+pub fn get_all_settings(app: AppHandle) -> Settings {
+    get(&app)
+}
@@ -0,0 +1,167 @@
+use tauri::{AppHandle, Manager};
+use tokio::sync::oneshot;
+
+use crate::paths;
+use crate::telemetry;
+
+// Opens the OS share sheet for a sound (Windows Share UI with Mail, Nearby
+// Sharing, ...; the macOS picker with Messages, Mail, AirDrop, ...). Linux has
+// no equivalent, so there it's an error the frontend can show.
+#[tauri::command]
+pub async fn share_file(app: AppHandle, path: String) -> Result<(), String> {
+    telemetry::record_feature(&app, "share");
+    let path = paths::normalize(&path);
+    if !path.is_file() {
+        return Err(format!("File not found: {}", path.display()));
+    }
+    let window = app
+        .get_webview_window("main")
+        .ok_or("Main window is not available")?;
+
+    #[cfg(target_os = "windows")]
+    let file = {
+        let path = path.clone();
+        tauri::async_runtime::spawn_blocking(move || windows_share::storage_file(&path))
+            .await
+            .map_err(|e| format!("Failed to prepare share: {}", e))??
+    };
+
+    // Both share UIs belong to the window's thread.
+    let (tx, rx) = oneshot::channel();
+    app.run_on_main_thread(move || {
+        #[cfg(target_os = "windows")]
+        let result = windows_share::show(&window, file, &path);
+        #[cfg(target_os = "macos")]
+        let result = macos_share::show(&window, &path);
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        let result = {
+            let _ = (&window, &path);
+            Err("Sharing isn't supported on this platform".to_string())
+        };
+        let _ = tx.send(result);
+    })
+    .map_err(|e| format!("Failed to open share sheet: {}", e))?;
+
+    rx.await
+        .map_err(|_| "Share sheet was interrupted".to_string())?
+}
+
+#[cfg(target_os = "windows")]
+mod windows_share {
+    use std::cell::Cell;
+    use std::path::Path;
+
+    use tauri::WebviewWindow;
+    use windows::ApplicationModel::DataTransfer::{DataRequestedEventArgs, DataTransferManager};
+    use windows::Foundation::TypedEventHandler;
+    use windows::Storage::{IStorageItem, StorageFile};
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Shell::IDataTransferManagerInterop;
+    use windows::core::{AgileReference, HSTRING, Interface, Ref};
+    use windows_collections::IIterable;
+
+    thread_local! {
+        // The manager is per window, so each share replaces the previous
+        // DataRequested handler instead of stacking another one.
+        static HANDLER_TOKEN: Cell<Option<i64>> = const { Cell::new(None) };
+    }
+
+    // Resolving the StorageFile blocks on an async WinRT call, so this runs
+    // off the UI thread before the sheet is shown. COM pointers aren't Send,
+    // so it crosses back as an agile reference.
+    pub fn storage_file(path: &Path) -> Result<AgileReference<IStorageItem>, String> {
+        StorageFile::GetFileFromPathAsync(&HSTRING::from(path.as_os_str()))
+            .and_then(|op| op.join())
+            .and_then(|file| file.cast::<IStorageItem>())
+            .and_then(|item| AgileReference::new(&item))
+            .map_err(|e| format!("Failed to open {} for sharing: {}", path.display(), e))
+    }
+
+    pub fn show(
+        window: &WebviewWindow,
+        file: AgileReference<IStorageItem>,
+        path: &Path,
+    ) -> Result<(), String> {
+        let hwnd = window
+            .hwnd()
+            .map_err(|e| format!("Failed to get window handle: {}", e))?;
+        let hwnd = HWND(hwnd.0);
+        let title = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        share(hwnd, file, title).map_err(|e| format!("Failed to open share sheet: {}", e))
+    }
+
+    fn share(
+        hwnd: HWND,
+        file: AgileReference<IStorageItem>,
+        title: String,
+    ) -> windows::core::Result<()> {
+        let interop = windows::core::factory::<DataTransferManager, IDataTransferManagerInterop>()?;
+        let manager: DataTransferManager = unsafe { interop.GetForWindow(hwnd)? };
+
+        if let Some(token) = HANDLER_TOKEN.take() {
+            let _ = manager.RemoveDataRequested(token);
+        }
+        let handler = TypedEventHandler::new(
+            move |_: Ref<DataTransferManager>, args: Ref<DataRequestedEventArgs>| {
+                let Some(args) = args.as_ref() else {
+                    return Ok(());
+                };
+                let data = args.Request()?.Data()?;
+                data.Properties()?
+                    .SetTitle(&HSTRING::from(title.as_str()))?;
+                data.SetStorageItemsReadOnly(&IIterable::from(vec![Some(file.resolve()?)]))
+            },
+        );
+        HANDLER_TOKEN.set(Some(manager.DataRequested(&handler)?));
+
+        unsafe { interop.ShowShareUIForWindow(hwnd) }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos_share {
+    use std::cell::RefCell;
+    use std::path::Path;
+
+    use objc2::AllocAnyThread;
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2_app_kit::{NSRectEdge, NSSharingServicePicker, NSView};
+    use objc2_foundation::{NSArray, NSPoint, NSRect, NSSize, NSString, NSURL};
+    use tauri::WebviewWindow;
+
+    thread_local! {
+        // Kept alive while it's on screen; AppKit doesn't hold on to it for us.
+        static PICKER: RefCell<Option<Retained<NSSharingServicePicker>>> = const { RefCell::new(None) };
+    }
+
+    pub fn show(window: &WebviewWindow, path: &Path) -> Result<(), String> {
+        let view = window
+            .ns_view()
+            .map_err(|e| format!("Failed to get window view: {}", e))?;
+        // SAFETY: tauri hands us the window's content view, and we're on the main thread.
+        let view: &NSView = unsafe { &*(view as *const NSView) };
+
+        let url = NSURL::fileURLWithPath(&NSString::from_str(&path.to_string_lossy()));
+        // SAFETY: an NSURL is an object; the picker takes an untyped array.
+        let item: Retained<AnyObject> = unsafe { Retained::cast_unchecked(url) };
+        let items = NSArray::from_retained_slice(&[item]);
+        let picker = unsafe {
+            NSSharingServicePicker::initWithItems(NSSharingServicePicker::alloc(), &items)
+        };
+
+        // Anchor the picker in the middle of the window.
+        let bounds = view.bounds();
+        let anchor = NSRect::new(
+            NSPoint::new(bounds.size.width / 2.0, bounds.size.height / 2.0),
+            NSSize::new(1.0, 1.0),
+        );
+        picker.showRelativeToRect_ofView_preferredEdge(anchor, view, NSRectEdge::MinY);
+        PICKER.set(Some(picker));
+        Ok(())
+    }
+}
@@ -0,0 +1,645 @@
+use std::process::{Child, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+#[cfg(not(debug_assertions))]
+use tauri_plugin_shell::ShellExt;
+
+use crate::backend;
+use crate::child_guard;
+use crate::crash;
+use crate::gpu;
+use crate::jobs::{JobManager, QueueStatus};
+use crate::proxy;
+use crate::secrets::{self, Secret};
+use crate::settings;
+
+// How long a lazily started backend gets to load its models before the
+// generation that woke it gives up.
+const WARM_UP_TIMEOUT: Duration = Duration::from_secs(180);
+const CPU_WARM_UP_TIMEOUT: Duration = Duration::from_secs(360);
+
+// The backend we spawned: the venv's Python in dev, the bundled sidecar in
+// release. Both are plain std processes so child_guard.rs can tie them to us.
+#[derive(Default)]
+struct BackendState {
+    process: Option<Child>,
+}
+
+impl BackendState {
+    fn is_spawned(&self) -> bool {
+        self.process.is_some()
+    }
+
+    fn kill(&mut self) {
+        if let Some(mut child) = self.process.take() {
+            child_guard::kill(&mut child);
+            println!("[Tauri] Killed backend");
+        }
+    }
+}
+
+// "auto", "cpu", "mps" or "cuda:<index>". Anything else is rejected when the
+// setting is changed, so the backend only ever sees a device torch understands.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ComputeDevice(String);
+
+impl Default for ComputeDevice {
+    fn default() -> Self {
+        ComputeDevice("auto".to_string())
+    }
+}
+
+impl TryFrom<String> for ComputeDevice {
+    type Error = String;
+
+    fn try_from(device: String) -> Result<Self, String> {
+        let valid = match device.strip_prefix("cuda:") {
+            Some(index) => index.parse::<u32>().is_ok(),
+            None => matches!(device.as_str(), "auto" | "cpu" | "mps"),
+        };
+        if valid {
+            Ok(ComputeDevice(device))
+        } else {
+            Err(format!(
+                "Unknown device \"{}\" (expected auto, cpu, mps or cuda:N)",
+                device
+            ))
+        }
+    }
+}
+
+impl From<ComputeDevice> for String {
+    fn from(device: ComputeDevice) -> String {
+        device.0
+    }
+}
+
+impl ComputeDevice {
+    fn as_arg(&self) -> Option<&str> {
+        (self.0 != "auto").then_some(self.0.as_str())
+    }
+
+    // The card the backend runs on, as NVML counts them (gpu.rs): the one
+    // picked, or the first with "auto". None off the GPU.
+    pub fn gpu_index(&self) -> Option<u32> {
+        match self.0.strip_prefix("cuda:") {
+            Some(index) => index.parse().ok(),
+            None => (self.0 == "auto").then_some(0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendStage {
+    // Not spawned yet; only possible with lazy start.
+    Idle,
+    Starting,
+    Running,
+}
+
+#[derive(Default)]
+pub struct Sidecar {
+    // An async lock: the commands and the restart task wait for it without
+    // holding up a runtime thread. spawn() holds it until the new backend is
+    // running, and only the exit paths (outside the runtime) block on it.
+    state: tokio::sync::Mutex<BackendState>,
+    // Set when the device or low-VRAM mode changed mid-generation; the restart
+    // waits for the queue to drain.
+    restart_pending: AtomicBool,
+    // Serializes warm-ups so two generations arriving together spawn one backend.
+    warming: tokio::sync::Mutex<()>,
+}
+
+impl Sidecar {
+    // Kills whichever backend we spawned. Called on exit and from the command
+    // line, both outside the async runtime; async code uses stop().
+    pub fn kill(&self) {
+        self.state.blocking_lock().kill();
+    }
+
+    async fn stop(&self) {
+        self.state.lock().await.kill();
+    }
+
+    // kill() for the panic hook: the panicking thread may be the one holding
+    // the lock, so don't wait for it.
+    pub fn kill_after_crash(&self) {
+        if let Ok(mut state) = self.state.try_lock() {
+            state.kill();
+        }
+    }
+}
+
+// How many times a backend we spawned has exited on its own, and a wake-up
+// for whoever waits on that: generations in flight (jobs.rs) use it to tell
+// a dead backend from a failed request.
+static EXITS: AtomicU64 = AtomicU64::new(0);
+static EXITED: tokio::sync::Notify = tokio::sync::Notify::const_new();
+
+pub fn exits() -> u64 {
+    EXITS.load(Ordering::Relaxed)
+}
+
+// Resolves once the backend has exited since exits() returned `seen`.
+pub async fn exited_since(seen: u64) {
+    loop {
+        // Created before the check, so an exit in between isn't missed.
+        let notified = EXITED.notified();
+        if exits() != seen {
+            return;
+        }
+        notified.await;
+    }
+}
+
+// Called when the backend's output closes. If it's really gone (and not
+// already replaced), the dead process is dropped so the next spawn() starts a
+// new one instead of thinking it's still running. The pipe can close a
+// moment before the process is done exiting, hence the few tries.
+fn on_output_closed(app: &AppHandle, pid: u32) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let sidecar = app.state::<Sidecar>();
+        for _ in 0..10 {
+            {
+                let mut state = sidecar.state.lock().await;
+                let Some(child) = state.process.as_mut().filter(|c| c.id() == pid) else {
+                    // Killed on purpose, or already replaced.
+                    return;
+                };
+                if let Ok(Some(status)) = child.try_wait() {
+                    eprintln!("[Tauri] Backend exited unexpectedly ({})", status);
+                    state.process = None;
+                    EXITS.fetch_add(1, Ordering::Relaxed);
+                    EXITED.notify_waiters();
+                    return;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    });
+}
+
+// Called from setup: starts the backend right away unless lazy start is on.
+pub fn init(app: &AppHandle) {
+    if settings::get(app).lazy_backend_start {
+        println!("[Tauri] Lazy start enabled, backend will start on first generation");
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = spawn(&app).await {
+            eprintln!("[Tauri] {}", e);
+        }
+    });
+}
+
+// Starts the backend if it isn't already running. Doesn't wait for it to come up.
+async fn spawn(app: &AppHandle) -> Result<(), String> {
+    let sidecar = app.state::<Sidecar>();
+    let mut state = sidecar.state.lock().await;
+    if state.is_spawned() {
+        return Ok(());
+    }
+
+    let pid = std::process::id();
+    let env = backend_env(app);
+    let port = choose_port(app).await;
+    backend::set_port(port);
+
+    #[cfg(debug_assertions)]
+    let mut cmd = {
+        // Use the venv Python for dev mode
+        let venv_python = std::path::Path::new("../.venv312/Scripts/python.exe");
+        let python_cmd = if venv_python.exists() {
+            venv_python.to_str().unwrap().to_string()
+        } else {
+            "python".to_string()
+        };
+        let mut cmd = std::process::Command::new(&python_cmd);
+        cmd.arg("../backend/main.py");
+        cmd
+    };
+
+    // The shell plugin only resolves the bundled binary (and sets
+    // CREATE_NO_WINDOW); spawning it ourselves is what lets child_guard set
+    // it up before it runs.
+    #[cfg(not(debug_assertions))]
+    let mut cmd: std::process::Command = app
+        .shell()
+        .sidecar("backend")
+        .map_err(|e| format!("Failed to resolve sidecar: {}", e))?
+        .into();
+    #[cfg(not(debug_assertions))]
+    verify_binary(app, std::path::Path::new(cmd.get_program())).await?;
+
+    cmd.arg("--parent-pid");
+    cmd.arg(pid.to_string());
+    cmd.arg("--port");
+    cmd.arg(port.to_string());
+    if cpu_fallback(app) {
+        cmd.arg("--device");
+        cmd.arg("cpu");
+    }
+    cmd.envs(env);
+    cmd.stdin(Stdio::piped());
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+    child_guard::tie_to_app(&mut cmd);
+
+    match cmd.spawn() {
+        Ok(mut child) => {
+            child_guard::adopt(&child);
+            println!("[Tauri] Backend started with PID: {}", child.id());
+            if let Some(stdout) = child.stdout.take() {
+                forward_output(app, child.id(), stdout, false);
+            }
+            if let Some(stderr) = child.stderr.take() {
+                forward_output(app, child.id(), stderr, true);
+            }
+            state.process = Some(child);
+            Ok(())
+        }
+        Err(e) => Err(format!("Failed to spawn backend: {}", e)),
+    }
+}
+
+#[cfg(not(debug_assertions))]
+#[derive(Clone, Serialize)]
+struct IntegrityFailed {
+    path: String,
+    expected: String,
+    found: String,
+}
+
+// Checks the sidecar against the SHA-256 build.rs baked in, so a tampered or
+// half-updated backend is never run; the UI hears about it as
+// `backend-integrity-failed`. Builds without a digest (the sidecar was a
+// placeholder) skip it. If the release pipeline ever signs the sidecar, it
+// has to do so before the app is built, or the digest won't match.
+#[cfg(not(debug_assertions))]
+async fn verify_binary(app: &AppHandle, path: &std::path::Path) -> Result<(), String> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    let Some(expected) = option_env!("NOISES_BACKEND_SHA256") else {
+        return Ok(());
+    };
+    let hash_path = path.to_path_buf();
+    let found = tauri::async_runtime::spawn_blocking(move || {
+        let mut file = std::fs::File::open(&hash_path)?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0u8; 1 << 20];
+        loop {
+            match file.read(&mut buffer)? {
+                0 => break,
+                n => hasher.update(&buffer[..n]),
+            }
+        }
+        Ok::<String, std::io::Error>(
+            hasher
+                .finalize()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to check the backend: {}", e))?
+    .unwrap_or_else(|e| format!("unreadable ({})", e));
+    if found == expected {
+        return Ok(());
+    }
+    eprintln!(
+        "[Tauri] {} doesn't match this build (expected {}, found {})",
+        path.display(),
+        expected,
+        found
+    );
+    let _ = app.emit(
+        "backend-integrity-failed",
+        IntegrityFailed {
+            path: path.to_string_lossy().into_owned(),
+            expected: expected.to_string(),
+            found,
+        },
+    );
+    Err(
+        "The AI engine's files are damaged or from another version. Reinstall Noises to fix them"
+            .to_string(),
+    )
+}
+
+#[derive(Clone, Serialize)]
+struct PortInUse {
+    port: u16,
+    // "noises" for a backend left over from an earlier run, "other" for
+    // anything else.
+    owner: &'static str,
+    // Where the backend runs now.
+    used: u16,
+}
+
+// The port for a backend about to be spawned. A leftover backend of ours is
+// asked to exit, so the new one (with the current settings, and tied to us)
+// takes its place; anything else keeps the port and the backend moves to a
+// free one. Either way the UI hears about it as `backend-port-in-use`.
+async fn choose_port(app: &AppHandle) -> u16 {
+    let port = backend::DEFAULT_PORT;
+    let owner = match backend::probe(port).await {
+        backend::Probe::Closed => return port,
+        backend::Probe::Healthy => {
+            println!(
+                "[Tauri] A backend from an earlier run still has port {}, stopping it",
+                port
+            );
+            backend::request_shutdown(port).await;
+            let started = tokio::time::Instant::now();
+            while started.elapsed() < SHUTDOWN_TIMEOUT {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                if backend::probe(port).await == backend::Probe::Closed {
+                    let _ = app.emit(
+                        "backend-port-in-use",
+                        PortInUse {
+                            port,
+                            owner: "noises",
+                            used: port,
+                        },
+                    );
+                    return port;
+                }
+            }
+            eprintln!("[Tauri] The old backend didn't exit");
+            "noises"
+        }
+        backend::Probe::Unresponsive | backend::Probe::Foreign => "other",
+    };
+
+    let free = std::net::TcpListener::bind(("127.0.0.1", 0))
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port());
+    match free {
+        Ok(used) => {
+            println!("[Tauri] Port {} is taken, using {}", port, used);
+            let _ = app.emit("backend-port-in-use", PortInUse { port, owner, used });
+            used
+        }
+        Err(e) => {
+            eprintln!("[Tauri] Failed to find a free port: {}", e);
+            port
+        }
+    }
+}
+
+// With the device on "auto" and no GPU driver to be found (gpu.rs), the
+// backend goes straight to the CPU, generations get longer timeouts, and the
+// UI is told with `cpu-fallback` so it can say why it's slow. The event is
+// sent on every spawn; cpu_fallback_active answers for a UI that loaded
+// later.
+fn cpu_fallback(app: &AppHandle) -> bool {
+    let fallback =
+        settings::get(app).compute_device.as_arg().is_none() && !gpu::has_supported_gpu();
+    backend::set_cpu_fallback(fallback);
+    if fallback {
+        println!("[Tauri] No supported GPU found, starting the backend on the CPU");
+        let _ = app.emit("cpu-fallback", ());
+    }
+    fallback
+}
+
+#[tauri::command]
+pub fn cpu_fallback_active() -> bool {
+    backend::is_cpu_fallback()
+}
+
+// Settings and secrets the backend reads from its environment at startup.
+fn backend_env(app: &AppHandle) -> Vec<(&'static str, String)> {
+    let mut env = Vec::new();
+    // Picked up by huggingface_hub for gated checkpoints.
+    if let Some(token) = secrets::get_or_none(app, Secret::HuggingfaceToken) {
+        env.push(("HF_TOKEN", token));
+    }
+    // Read by backend/config.py; unset lets the backend pick.
+    if let Some(device) = settings::get(app).compute_device.as_arg() {
+        env.push(("NOISES_DEVICE", device.to_string()));
+    }
+    // CUDA numbers the fastest card first, NVML by PCI slot; this makes
+    // cuda:1 the card gpu.rs reads as 1.
+    env.push(("CUDA_DEVICE_ORDER", "PCI_BUS_ID".to_string()));
+    // huggingface_hub and diffusers then only look in the local cache.
+    if proxy::is_offline(app) {
+        env.push(("HF_HUB_OFFLINE", "1".to_string()));
+    }
+    if settings::get(app).low_vram {
+        env.push(("NOISES_LOW_VRAM", "1".to_string()));
+        // Less fragmentation, so a nearly full card still finds room.
+        env.push((
+            "PYTORCH_CUDA_ALLOC_CONF",
+            "expandable_segments:True".to_string(),
+        ));
+    }
+    env
+}
+
+// Keeps the tail of the backend's output for crash reports, and echoes it to
+// the console in dev builds.
+fn forward_output(
+    app: &AppHandle,
+    pid: u32,
+    pipe: impl std::io::Read + Send + 'static,
+    is_stderr: bool,
+) {
+    use std::io::BufRead;
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for line in std::io::BufReader::new(pipe).lines().map_while(Result::ok) {
+            if cfg!(debug_assertions) {
+                if is_stderr {
+                    eprintln!("{}", line);
+                } else {
+                    println!("{}", line);
+                }
+            }
+            crash::record_backend_line(&line);
+        }
+        if !is_stderr {
+            crash::record_backend_line("[backend exited]");
+            on_output_closed(&app, pid);
+        }
+    });
+}
+
+// How long a killed backend gets to stop answering before we move on.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Kills the backend and waits until it's really gone (port and files free).
+pub async fn shut_down(app: &AppHandle) {
+    app.state::<Sidecar>().stop().await;
+    let started = tokio::time::Instant::now();
+    while backend::is_healthy().await {
+        if started.elapsed() > SHUTDOWN_TIMEOUT {
+            eprintln!("[Tauri] Backend is still answering after being killed");
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+// Called by settings.rs when something the backend reads at startup changes.
+// Running generations finish on the old backend first.
+pub fn restart(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let sidecar = app.state::<Sidecar>();
+        if !sidecar.state.lock().await.is_spawned() {
+            // Not started yet (lazy start); it'll pick the change up when it is.
+            return;
+        }
+        if !app.state::<JobManager>().status().active.is_empty() {
+            println!("[Tauri] Backend restart waits for the running generations");
+            sidecar.restart_pending.store(true, Ordering::Relaxed);
+            return;
+        }
+        sidecar.restart_pending.store(false, Ordering::Relaxed);
+
+        println!("[Tauri] Restarting backend");
+        let _ = app.emit("backend-restarting", ());
+        shut_down(&app).await;
+        if settings::get(&app).lazy_backend_start {
+            return;
+        }
+        if let Err(e) = spawn(&app).await {
+            eprintln!("[Tauri] {}", e);
+            return;
+        }
+        match wait_until_healthy(&app).await {
+            Ok(()) => {
+                let _ = app.emit("backend-ready", ());
+            }
+            Err(e) => eprintln!("[Tauri] Restarted backend didn't come up: {}", e),
+        }
+    });
+}
+
+// On a timeout, what the port looked like last says why: nothing listening
+// means the backend died (or never got going), an open port that doesn't
+// answer means it's hung.
+async fn wait_until_healthy(app: &AppHandle) -> Result<(), String> {
+    let started = tokio::time::Instant::now();
+    let timeout = if backend::is_cpu_fallback() {
+        CPU_WARM_UP_TIMEOUT
+    } else {
+        WARM_UP_TIMEOUT
+    };
+    let mut status = backend::Probe::Closed;
+    while started.elapsed() < timeout {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        status = backend::probe(backend::port()).await;
+        backend::announce(app, status);
+        if status == backend::Probe::Healthy {
+            return Ok(());
+        }
+    }
+    Err(match status {
+        backend::Probe::Unresponsive => {
+            "The AI engine is running but stopped responding".to_string()
+        }
+        backend::Probe::Foreign => format!(
+            "Another program answered on the AI engine's port ({})",
+            backend::port()
+        ),
+        _ => "The AI engine took too long to start".to_string(),
+    })
+}
+
+// Spawns the backend whatever the lazy start setting says and waits for it.
+// For the command line (cli.rs), which has no setup to start it early.
+pub async fn start(app: &AppHandle) -> Result<(), String> {
+    if backend::is_healthy().await {
+        return Ok(());
+    }
+    spawn(app).await?;
+    wait_until_healthy(app).await
+}
+
+// Called by jobs.rs whenever the queue changes.
+pub fn on_jobs_changed(app: &AppHandle, status: &QueueStatus) {
+    let Some(sidecar) = app.try_state::<Sidecar>() else {
+        return;
+    };
+    if status.active.is_empty() && sidecar.restart_pending.load(Ordering::Relaxed) {
+        restart(app);
+    }
+}
+
+// Makes sure a backend is up before a request is sent to it. With lazy start
+// this is where it gets spawned, and either way where one that died is
+// started again, announced by `backend-warming-up` and then `backend-ready`
+// once /health answers.
+pub async fn ensure_running(app: &AppHandle) -> Result<(), String> {
+    if backend::is_healthy().await {
+        return Ok(());
+    }
+    let sidecar = app.state::<Sidecar>();
+    // Without lazy start, one that's still starting is the UI's to wait for.
+    if !settings::get(app).lazy_backend_start && sidecar.state.lock().await.is_spawned() {
+        return Ok(());
+    }
+
+    let _warming = sidecar.warming.lock().await;
+    if backend::is_healthy().await {
+        return Ok(());
+    }
+    println!("[Tauri] Warming up backend for the generation");
+    let _ = app.emit("backend-warming-up", ());
+    spawn(app).await?;
+
+    wait_until_healthy(app).await?;
+    let _ = app.emit("backend-ready", ());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn backend_stage(app: AppHandle) -> BackendStage {
+    let status = backend::probe(backend::port()).await;
+    backend::announce(&app, status);
+    if status == backend::Probe::Healthy {
+        return BackendStage::Running;
+    }
+    let spawned = app.state::<Sidecar>().state.lock().await.is_spawned();
+    if spawned {
+        BackendStage::Starting
+    } else {
+        BackendStage::Idle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(name: &str) -> ComputeDevice {
+        ComputeDevice::try_from(name.to_string()).unwrap()
+    }
+
+    #[test]
+    fn gpu_index_follows_the_device() {
+        assert_eq!(device("cuda:1").gpu_index(), Some(1));
+        assert_eq!(device("cuda:0").gpu_index(), Some(0));
+        assert_eq!(device("auto").gpu_index(), Some(0));
+        assert_eq!(device("cpu").gpu_index(), None);
+        assert_eq!(device("mps").gpu_index(), None);
+    }
+
+    #[test]
+    fn rejects_unknown_devices() {
+        assert!(ComputeDevice::try_from("cuda:x".to_string()).is_err());
+        assert!(ComputeDevice::try_from("gpu".to_string()).is_err());
+    }
+}
@@ -0,0 +1,426 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::disk;
+use crate::filenames;
+use crate::history;
+use crate::import::{self, ImportedFile};
+use crate::library;
+use crate::paths;
+use crate::telemetry;
+use crate::workspaces;
+
+// A .noisespack is a zip with the sounds under sounds/ and a pack.json
+// describing them: who made the pack, under what license, and for every
+// sound the prompt, model and settings it was generated with. Stored, not
+// compressed; audio hardly shrinks and this way a pack opens instantly.
+pub const EXTENSION: &str = "noisespack";
+const MANIFEST: &str = "pack.json";
+const SOUNDS_DIR: &str = "sounds/";
+// Marks the manifest as ours, like profile.rs does.
+const FORMAT: &str = "noises-pack";
+// Bump when a field changes meaning; newer packs are refused.
+const VERSION: u32 = 1;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PackInfo {
+    pub name: String,
+    pub author: Option<String>,
+    // Free text or an SPDX id; what the recipients may do with the sounds.
+    pub license: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackSound {
+    // The name inside sounds/.
+    file: String,
+    sha256: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    // From the generation that made it, when there was one.
+    #[serde(default)]
+    prompt: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    params: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    format: String,
+    version: u32,
+    #[serde(flatten)]
+    info: PackInfo,
+    #[serde(default)]
+    app_version: String,
+    #[serde(default)]
+    created_at: String,
+    sounds: Vec<PackSound>,
+}
+
+#[derive(Clone, Default, Serialize)]
+pub struct PackImportReport {
+    pub name: String,
+    pub imported: Vec<ImportedFile>,
+    // Already in the library (same contents), so not copied again.
+    pub duplicates: usize,
+    // Entries that couldn't be imported, with the reason.
+    pub skipped: Vec<String>,
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Copies everything from `from` to `to`, returning the SHA-256 of what went
+// through.
+fn copy_hashed(from: &mut impl Read, to: &mut impl Write) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1 << 20];
+    loop {
+        let n = from.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        to.write_all(&buffer[..n])?;
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+fn hash_file(path: &Path) -> std::io::Result<String> {
+    copy_hashed(&mut File::open(path)?, &mut std::io::sink())
+}
+
+// Writes <name>.noisespack to `dir` (<name>-1.noisespack, ... when taken)
+// and returns its path.
+pub fn export(
+    app: &AppHandle,
+    sounds: &[PathBuf],
+    info: PackInfo,
+    dir: &Path,
+) -> Result<PathBuf, String> {
+    if sounds.is_empty() {
+        return Err("Pick at least one sound for the pack".to_string());
+    }
+    if let Some(missing) = sounds.iter().find(|s| !s.is_file()) {
+        return Err(format!("File not found: {}", missing.display()));
+    }
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+    disk::ensure_space_for_files(dir, sounds)?;
+    let name = match info.name.trim() {
+        "" => "Noises pack",
+        name => name,
+    };
+    let path = filenames::unique(dir, &filenames::sanitize(name), EXTENSION);
+    let temp = paths::temp_path(&path);
+    let written = write_pack(app, sounds, info, &temp)
+        .and_then(|()| std::fs::rename(&temp, &path).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&temp);
+        return Err(format!("Failed to write sound pack: {}", e));
+    }
+    println!(
+        "[SoundPack] Exported {} sounds to {}",
+        sounds.len(),
+        path.display()
+    );
+    Ok(path)
+}
+
+fn write_pack(
+    app: &AppHandle,
+    sounds: &[PathBuf],
+    info: PackInfo,
+    to: &Path,
+) -> Result<(), String> {
+    let tags = workspaces::tags(app);
+    let mut zip = zip::ZipWriter::new(File::create(to).map_err(|e| e.to_string())?);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Stored)
+        .large_file(true);
+
+    let mut names = HashSet::new();
+    let mut manifest_sounds = Vec::with_capacity(sounds.len());
+    for sound in sounds {
+        // Two sounds can share a name when they come from different folders.
+        let file = unique_name(&mut names, sound);
+        zip.start_file(format!("{}{}", SOUNDS_DIR, file), options)
+            .map_err(|e| e.to_string())?;
+        let mut source =
+            File::open(sound).map_err(|e| format!("Failed to read {}: {}", sound.display(), e))?;
+        let sha256 = copy_hashed(&mut source, &mut zip)
+            .map_err(|e| format!("Failed to read {}: {}", sound.display(), e))?;
+        let generation = history::find_output(app, &sound.to_string_lossy());
+        manifest_sounds.push(PackSound {
+            file,
+            sha256,
+            tags: tags.get(sound).cloned().unwrap_or_default(),
+            prompt: generation.as_ref().map(|g| g.prompt.clone()),
+            model: generation.as_ref().map(|g| g.model.clone()),
+            params: generation.map(|g| g.params),
+        });
+    }
+
+    let manifest = Manifest {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        info,
+        app_version: app.package_info().version.to_string(),
+        created_at: time::OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap_or_default(),
+        sounds: manifest_sounds,
+    };
+    let json = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    zip.start_file(MANIFEST, options)
+        .map_err(|e| e.to_string())?;
+    zip.write_all(&json).map_err(|e| e.to_string())?;
+    let file = zip.finish().map_err(|e| e.to_string())?;
+    file.sync_all().map_err(|e| e.to_string())
+}
+
+// "kick.wav", then "kick (2).wav", ...
+fn unique_name(taken: &mut HashSet<String>, path: &Path) -> String {
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    let name = std::iter::once(name)
+        .chain((2..).map(|n| format!("{} ({}){}", stem, n, ext)))
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap();
+    taken.insert(name.clone());
+    name
+}
+
+// Library files by size, hashed only when a pack brings a sound of the same
+// size, so importing doesn't read the whole library.
+struct Library {
+    by_size: HashMap<u64, Vec<PathBuf>>,
+    hashes: HashMap<PathBuf, String>,
+}
+
+impl Library {
+    fn new(app: &AppHandle) -> Self {
+        let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+        for item in library::scan(app) {
+            by_size
+                .entry(item.size)
+                .or_default()
+                .push(PathBuf::from(item.path));
+        }
+        Library {
+            by_size,
+            hashes: HashMap::new(),
+        }
+    }
+
+    fn contains(&mut self, size: u64, sha256: &str) -> bool {
+        let Some(candidates) = self.by_size.get(&size) else {
+            return false;
+        };
+        for path in candidates {
+            let hash = match self.hashes.get(path) {
+                Some(hash) => hash.clone(),
+                None => match hash_file(path) {
+                    Ok(hash) => {
+                        self.hashes.insert(path.clone(), hash.clone());
+                        hash
+                    }
+                    Err(_) => continue,
+                },
+            };
+            if hash == sha256 {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn add(&mut self, path: PathBuf, size: u64, sha256: String) {
+        self.by_size.entry(size).or_default().push(path.clone());
+        self.hashes.insert(path, sha256);
+    }
+}
+
+// Copies the pack's sounds into the library's imports folder with their
+// tags, plus one for the pack's name. Sounds the library already has (same
+// contents, whatever the name) are skipped.
+pub fn import(app: &AppHandle, pack: &Path) -> Result<PackImportReport, String> {
+    telemetry::record_feature(app, "sound-pack");
+    let file = File::open(pack).map_err(|e| format!("Failed to open {}: {}", pack.display(), e))?;
+    let mut zip = zip::ZipArchive::new(file).map_err(|_| "Not a sound pack".to_string())?;
+    let manifest: Manifest = {
+        let mut entry = zip
+            .by_name(MANIFEST)
+            .map_err(|_| "Not a sound pack (no pack.json)".to_string())?;
+        let mut json = String::new();
+        entry
+            .read_to_string(&mut json)
+            .map_err(|e| format!("Failed to read pack.json: {}", e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Not a valid pack.json: {}", e))?
+    };
+    if manifest.format != FORMAT {
+        return Err("Not a Noises sound pack".to_string());
+    }
+    if manifest.version > VERSION {
+        return Err(format!(
+            "This pack was made by a newer version of Noises (pack format {})",
+            manifest.version
+        ));
+    }
+
+    let dir = paths::imports_dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create imports folder: {}", e))?;
+    let mut library = Library::new(app);
+    let mut report = PackImportReport {
+        name: manifest.info.name.clone(),
+        ..Default::default()
+    };
+    let pack_tag = manifest.info.name.trim().to_string();
+
+    for sound in &manifest.sounds {
+        // Names come from someone else's file: no folders, only our formats.
+        let name = Path::new(&sound.file);
+        let plain = name.file_name().is_some_and(|n| n == name.as_os_str());
+        if !plain || !import::is_audio_file(name) {
+            report
+                .skipped
+                .push(format!("{}: not a supported sound", sound.file));
+            continue;
+        }
+        let mut entry = match zip.by_name(&format!("{}{}", SOUNDS_DIR, sound.file)) {
+            Ok(entry) => entry,
+            Err(_) => {
+                report
+                    .skipped
+                    .push(format!("{}: missing from the pack", sound.file));
+                continue;
+            }
+        };
+        let size = entry.size();
+
+        let target = import::unique_path(&dir, name);
+        let temp = paths::temp_path(&target);
+        let copied = File::create(&temp).and_then(|mut out| {
+            let hash = copy_hashed(&mut entry, &mut out)?;
+            out.sync_all()?;
+            Ok(hash)
+        });
+        let sha256 = match copied {
+            Ok(hash) => hash,
+            Err(e) => {
+                let _ = std::fs::remove_file(&temp);
+                report.skipped.push(format!("{}: {}", sound.file, e));
+                continue;
+            }
+        };
+        if !sound.sha256.is_empty() && sha256 != sound.sha256 {
+            let _ = std::fs::remove_file(&temp);
+            report
+                .skipped
+                .push(format!("{}: damaged (checksum mismatch)", sound.file));
+            continue;
+        }
+        if library.contains(size, &sha256) {
+            let _ = std::fs::remove_file(&temp);
+            report.duplicates += 1;
+            continue;
+        }
+        if let Err(e) = std::fs::rename(&temp, &target) {
+            let _ = std::fs::remove_file(&temp);
+            report.skipped.push(format!("{}: {}", sound.file, e));
+            continue;
+        }
+        library.add(target.clone(), size, sha256);
+
+        let mut tags = sound.tags.clone();
+        if !pack_tag.is_empty() {
+            tags.push(pack_tag.clone());
+        }
+        if let Err(e) = workspaces::add_tags(app, &target, &tags) {
+            eprintln!("[SoundPack] {}", e);
+        }
+        report.imported.push(ImportedFile {
+            file: target
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path: target.to_string_lossy().into_owned(),
+        });
+    }
+    println!(
+        "[SoundPack] Imported {} from {} ({} already in the library)",
+        report.imported.len(),
+        pack.display(),
+        report.duplicates
+    );
+    Ok(report)
+}
+
+pub fn is_sound_pack(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(EXTENSION))
+}
+
+// "Open with Noises" on a pack. Runs in the background and reports to the
+// frontend when it's done.
+pub fn open(app: &AppHandle, pack: PathBuf) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || match import(&app, &pack) {
+        Ok(report) => {
+            let _ = app.emit("sound-pack-imported", report);
+        }
+        Err(e) => {
+            eprintln!("[SoundPack] {}: {}", pack.display(), e);
+            let _ = app.emit(
+                "sound-pack-failed",
+                serde_json::json!({ "path": pack.to_string_lossy(), "error": e }),
+            );
+        }
+    });
+}
+
+// Saves to the Downloads folder.
+#[tauri::command]
+pub async fn export_sound_pack(
+    app: AppHandle,
+    paths: Vec<String>,
+    info: PackInfo,
+) -> Result<String, String> {
+    telemetry::record_feature(&app, "sound-pack");
+    let sounds: Vec<PathBuf> = paths.iter().map(|p| paths::normalize(p)).collect();
+    let dir = app
+        .path()
+        .download_dir()
+        .map_err(|e| format!("Failed to resolve Downloads folder: {}", e))?;
+    tauri::async_runtime::spawn_blocking(move || export(&app, &sounds, info, &dir))
+        .await
+        .map_err(|e| format!("Export task failed: {}", e))?
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+pub async fn import_sound_pack(app: AppHandle, path: String) -> Result<PackImportReport, String> {
+    let pack = paths::normalize(&path);
+    tauri::async_runtime::spawn_blocking(move || import(&app, &pack))
+        .await
+        .map_err(|e| format!("Import task failed: {}", e))?
+}
@@ -0,0 +1,377 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{Value, json};
+use tauri::{AppHandle, Manager};
+
+use crate::backend;
+use crate::disk;
+use crate::filenames;
+use crate::paths;
+use crate::telemetry;
+use crate::workspaces;
+
+// A backend output can come as stems (layered SFX variants, say), announced
+// by a "stems" list in its file entry, either as
+//   - names: the file holds one channel group per name, interleaved (four
+//     channels and ["low", "high"] is two stereo stems), or
+//   - {"name", "path"} objects: each stem already written as its own file.
+// Either way every stem ends up as its own library item next to the sound,
+// named <sound>-<stem>.wav (-1, -2, ... when taken) and linked to it in the
+// workspace, and the sound itself is the mix of them.
+pub fn settle(app: &AppHandle, path: &Path, file: &mut Value) -> Result<(), String> {
+    let Some(stems) = file.get("stems").and_then(|s| s.as_array()).cloned() else {
+        return Ok(());
+    };
+    if stems.is_empty() {
+        return Ok(());
+    }
+    let settled = if stems.iter().all(|s| s.is_string()) {
+        let names: Vec<String> = stems
+            .iter()
+            .filter_map(|s| s.as_str())
+            .map(str::to_string)
+            .collect();
+        split(path, &names)?
+    } else {
+        adopt(path, &stems)?
+    };
+
+    let stem_paths: Vec<PathBuf> = settled.iter().map(|(_, p)| p.clone()).collect();
+    workspaces::set_stems(app, path, &stem_paths)?;
+    println!(
+        "[Stems] {} has {} stems",
+        path.file_name().unwrap_or_default().to_string_lossy(),
+        settled.len()
+    );
+    file["stems"] = settled
+        .iter()
+        .map(|(name, p)| json!({ "name": name, "path": p.to_string_lossy() }))
+        .collect();
+    Ok(())
+}
+
+// "Low Layer!" -> "low-layer".
+fn clean_name(name: &str, index: usize) -> String {
+    let slug = name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        format!("stem-{}", index + 1)
+    } else {
+        slug
+    }
+}
+
+// Where a stem of `sound` goes: beside it, with the stem's name appended.
+// `claimed` are the names of stems that aren't written yet.
+fn stem_path(sound: &Path, name: &str, extension: &str, claimed: &[PathBuf]) -> PathBuf {
+    let dir = sound.parent().unwrap_or(Path::new("."));
+    let base = sound.file_stem().unwrap_or_default().to_string_lossy();
+    filenames::unique_among(dir, &format!("{}-{}", base, name), extension, claimed)
+}
+
+// Files written under temporary names, (temporary, final), that get their
+// names all at once in commit. Until then dropping it deletes them.
+struct Pending(Vec<(PathBuf, PathBuf)>);
+
+impl Pending {
+    fn targets(&self) -> Vec<PathBuf> {
+        self.0.iter().map(|(_, target)| target.clone()).collect()
+    }
+
+    // Flushes every file, then renames them in order. A rename that fails
+    // takes back the ones before it.
+    fn commit(mut self) -> Result<(), String> {
+        let pending = std::mem::take(&mut self.0);
+        let flushed = pending.iter().try_for_each(|(temp, target)| {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(temp)
+                .and_then(|file| file.sync_all())
+                .map_err(|e| format!("Failed to save {}: {}", target.display(), e))
+        });
+        if let Err(e) = flushed {
+            self.0 = pending;
+            return Err(e);
+        }
+        for (i, (temp, target)) in pending.iter().enumerate() {
+            if let Err(e) = std::fs::rename(temp, target) {
+                for (_, done) in &pending[..i] {
+                    let _ = std::fs::remove_file(done);
+                }
+                self.0 = pending[i..].to_vec();
+                return Err(format!("Failed to save {}: {}", target.display(), e));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        for (temp, _) in &self.0 {
+            let _ = std::fs::remove_file(temp);
+        }
+    }
+}
+
+// Stems the backend wrote itself: checked like any output and moved beside
+// the sound. Names it leaves out are numbered.
+fn adopt(sound: &Path, stems: &[Value]) -> Result<Vec<(String, PathBuf)>, String> {
+    let mut settled = Vec::with_capacity(stems.len());
+    for (i, stem) in stems.iter().enumerate() {
+        let source = stem
+            .get("path")
+            .and_then(|p| p.as_str())
+            .map(PathBuf::from)
+            .ok_or("The backend answered with a stem without a path")?;
+        backend::check_output(&source)?;
+        let name = clean_name(
+            stem.get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or_default(),
+            i,
+        );
+        let extension = source
+            .extension()
+            .map(|e| e.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "wav".to_string());
+        let target = stem_path(sound, &name, &extension, &[]);
+        paths::move_file(&source, &target)?;
+        settled.push((name, target));
+    }
+    Ok(settled)
+}
+
+// Cuts a multi-channel WAV into its channel groups, one file each, and
+// rewrites the sound as their mix. Nothing takes its name until everything
+// is written, the mix last, so a split that fails leaves the sound as it was
+// and no stems beside it.
+fn split(sound: &Path, names: &[String]) -> Result<Vec<(String, PathBuf)>, String> {
+    let mut reader = hound::WavReader::open(sound)
+        .map_err(|e| format!("Failed to read {}: {}", sound.display(), e))?;
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+    if !channels.is_multiple_of(names.len()) {
+        return Err(format!(
+            "The backend sent {} stems in a file with {} channels",
+            names.len(),
+            channels
+        ));
+    }
+    let per_stem = channels / names.len();
+    // Samples as f32 whatever the format, for mixing; written back as it was.
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>(),
+        hound::SampleFormat::Int => {
+            let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 * scale))
+                .collect()
+        }
+    }
+    .map_err(|e| format!("Failed to read {}: {}", sound.display(), e))?;
+    // Closed, so the mix can be renamed over it on Windows.
+    drop(reader);
+
+    let stem_spec = hound::WavSpec {
+        channels: per_stem as u16,
+        ..spec
+    };
+    let frames = samples.len() / channels;
+    let mut settled = Vec::with_capacity(names.len());
+    let mut pending = Pending(Vec::with_capacity(names.len() + 1));
+    let mut mix = vec![0.0f32; frames * per_stem];
+    for (i, name) in names.iter().enumerate() {
+        let name = clean_name(name, i);
+        let target = stem_path(sound, &name, "wav", &pending.targets());
+        let stem: Vec<f32> = (0..frames)
+            .flat_map(|f| {
+                let start = f * channels + i * per_stem;
+                samples[start..start + per_stem].iter().copied()
+            })
+            .collect();
+        for (m, s) in mix.iter_mut().zip(&stem) {
+            *m += s;
+        }
+        let temp = paths::temp_path(&target);
+        pending.0.push((temp.clone(), target.clone()));
+        write(&temp, stem_spec, &stem)?;
+        settled.push((name, target));
+    }
+
+    // Layers summed can go past full scale; brought back under it if so.
+    let peak = mix.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    if peak > 1.0 {
+        mix.iter_mut().for_each(|s| *s /= peak);
+    }
+    let temp = paths::temp_path(sound);
+    pending.0.push((temp.clone(), sound.to_path_buf()));
+    write(&temp, stem_spec, &mix)?;
+    pending.commit()?;
+    Ok(settled)
+}
+
+fn write(path: &Path, spec: hound::WavSpec, samples: &[f32]) -> Result<(), String> {
+    let fail = |e: hound::Error| format!("Failed to write {}: {}", path.display(), e);
+    let mut writer = hound::WavWriter::create(path, spec).map_err(fail)?;
+    let peak = (1i64 << (spec.bits_per_sample - 1)) as f32 - 1.0;
+    for &s in samples {
+        match spec.sample_format {
+            hound::SampleFormat::Float => writer.write_sample(s),
+            hound::SampleFormat::Int => {
+                writer.write_sample((s.clamp(-1.0, 1.0) * peak).round() as i32)
+            }
+        }
+        .map_err(fail)?;
+    }
+    writer.finalize().map_err(fail)
+}
+
+// Copies a sound's stems, and the mix, into <sound>_stems/ in `dir` and
+// returns that folder.
+pub fn export(app: &AppHandle, sound: &Path, dir: &Path) -> Result<PathBuf, String> {
+    let stems = workspaces::stems(app)
+        .remove(sound)
+        .filter(|stems| !stems.is_empty())
+        .ok_or_else(|| format!("{} has no stems", sound.display()))?;
+    let mut files = vec![sound.to_path_buf()];
+    files.extend(stems);
+    if let Some(missing) = files.iter().find(|f| !f.is_file()) {
+        return Err(format!("File not found: {}", missing.display()));
+    }
+
+    let base = sound.file_stem().unwrap_or_default().to_string_lossy();
+    let folder = filenames::unique(dir, &format!("{}_stems", base), "");
+    disk::ensure_space_for_files(dir, &files)?;
+    std::fs::create_dir_all(&folder).map_err(|e| format!("Failed to create folder: {}", e))?;
+    for file in &files {
+        let target = folder.join(file.file_name().unwrap_or_default());
+        if let Err(e) = paths::copy_atomic(file, &target) {
+            let _ = std::fs::remove_dir_all(&folder);
+            return Err(format!("Failed to copy {}: {}", file.display(), e));
+        }
+    }
+    println!(
+        "[Stems] Exported {} files to {}",
+        files.len(),
+        folder.display()
+    );
+    Ok(folder)
+}
+
+// "Export stems as folder", into Downloads.
+#[tauri::command]
+pub async fn export_stems(app: AppHandle, path: String) -> Result<String, String> {
+    telemetry::record_feature(&app, "stems");
+    let sound = paths::normalize(&path);
+    let dir = app
+        .path()
+        .download_dir()
+        .map_err(|e| format!("Failed to resolve Downloads folder: {}", e))?;
+    tauri::async_runtime::spawn_blocking(move || export(&app, &sound, &dir))
+        .await
+        .map_err(|e| format!("Export task failed: {}", e))?
+        .map(|folder| folder.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("noises-stems-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // Four channels, 16-bit: two stereo stems at 0.5 and 0.25.
+    fn four_channel_sound(path: &Path) {
+        let spec = hound::WavSpec {
+            channels: 4,
+            sample_rate: 48000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        write(path, spec, &[0.5, 0.5, 0.25, 0.25].repeat(100)).unwrap();
+    }
+
+    fn names_in(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn splits_into_stems_and_a_mix() {
+        let dir = scratch("split");
+        let sound = dir.join("hit.wav");
+        four_channel_sound(&sound);
+
+        let stems = split(&sound, &["Low".to_string(), "High".to_string()]).unwrap();
+        let names: Vec<&str> = stems.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["low", "high"]);
+        assert_eq!(names_in(&dir), ["hit-high.wav", "hit-low.wav", "hit.wav"]);
+        let mix = hound::WavReader::open(&sound).unwrap();
+        assert_eq!(mix.spec().channels, 2);
+        assert_eq!(mix.len(), 200);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    // The second stem of a name can't take the first one's, which isn't on
+    // disk yet when it's picked.
+    #[test]
+    fn same_names_are_numbered() {
+        let dir = scratch("same");
+        let sound = dir.join("hit.wav");
+        four_channel_sound(&sound);
+
+        split(&sound, &["layer".to_string(), "layer".to_string()]).unwrap();
+        assert_eq!(
+            names_in(&dir),
+            ["hit-layer-1.wav", "hit-layer.wav", "hit.wav"]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn uncommitted_files_are_deleted() {
+        let dir = scratch("pending");
+        let temp = paths::temp_path(&dir.join("hit-low.wav"));
+        std::fs::write(&temp, b"stem").unwrap();
+        drop(Pending(vec![(temp, dir.join("hit-low.wav"))]));
+        assert!(names_in(&dir).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    // A rename that fails (here: onto a folder) takes back the ones before.
+    #[test]
+    fn a_failed_commit_takes_back_the_renamed() {
+        let dir = scratch("commit");
+        let first = dir.join("hit-low.wav");
+        let second = dir.join("hit");
+        std::fs::create_dir_all(second.join("inside")).unwrap();
+        let pending: Vec<(PathBuf, PathBuf)> = [&first, &second]
+            .iter()
+            .map(|target| {
+                let temp = paths::temp_path(target);
+                std::fs::write(&temp, b"stem").unwrap();
+                (temp, target.to_path_buf())
+            })
+            .collect();
+
+        assert!(Pending(pending).commit().is_err());
+        assert_eq!(names_in(&dir), ["hit"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
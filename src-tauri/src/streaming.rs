@@ -0,0 +1,215 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::http::{StatusCode, header};
+use tauri::{AppHandle, Emitter, State};
+use tokio::io::AsyncWriteExt;
+
+use crate::backend;
+use crate::disk;
+use crate::jobs::{self, JobManager};
+use crate::low_vram;
+use crate::paths;
+use crate::power::PowerGuard;
+use crate::providers;
+use crate::segments;
+use crate::tasks::{self, TaskKind};
+use crate::telemetry;
+use crate::workspaces;
+
+// How often we tell the UI the partial file grew. Each event makes the player
+// re-request the file, so there's no point doing it per chunk.
+const EMIT_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Serialize)]
+struct PartialAudio {
+    stream_id: String,
+    path: String,
+    bytes: u64,
+    complete: bool,
+}
+
+// Like generate_audio, but if the backend streams audio (chunked `audio/*` from
+// /generate/stream) the bytes are written to a growing temp file and announced
+// with `partial-audio-available` events, so the first seconds of a long render
+// can be heard before it finishes. The bundled sidecar's models make a clip in
+// one pass, so its stream starts once sampling is done and mostly saves the
+// JSON round trip; a backend that renders incrementally streams as it goes.
+// Backends without the endpoint fall back to the normal request. The result
+// has the same shape as generate_audio.
+#[tauri::command]
+pub async fn generate_audio_streaming(
+    app: AppHandle,
+    config: serde_json::Value,
+    stream_id: String,
+    power: State<'_, PowerGuard>,
+    jobs: State<'_, JobManager>,
+) -> Result<serde_json::Value, String> {
+    backend::validate_config(&config)?;
+    // Stitched from segments, or made remotely, so there's nothing to stream
+    // until the end.
+    if segments::is_long(&config) || providers::is_remote(&config) {
+        return jobs::generate(&app, config).await;
+    }
+    let _awake = power.acquire();
+    telemetry::record_feature(&app, "streaming");
+    let job = jobs.begin(&app, &config);
+    let task = tasks::begin(&app, TaskKind::Generation, job.prompt());
+    let config = workspaces::with_output_dir(&app, low_vram::clamp(&app, config));
+
+    let result = task
+        .run(async {
+            disk::ensure_space_for_generation(&app, &config)?;
+            job.run_on_backend(|| stream_generation(&app, &config, &stream_id))
+                .await
+        })
+        .await
+        .and_then(|result| backend::settle_outputs(&app, &config, result));
+    job.end(&task, &result);
+    result
+}
+
+async fn stream_generation(
+    app: &AppHandle,
+    config: &serde_json::Value,
+    stream_id: &str,
+) -> Result<serde_json::Value, String> {
+    let client = backend::generate_client()?;
+    let mut res = client
+        .post(format!("{}/generate/stream", backend::url()))
+        .timeout(backend::generate_timeout())
+        .json(config)
+        .send()
+        .await
+        .map_err(|e| format!("Backend request failed: {}", e))?;
+
+    if matches!(
+        res.status(),
+        StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+    ) {
+        return backend::generate(config).await;
+    }
+    if !res.status().is_success() {
+        return Err(backend::error_from_response(res).await);
+    }
+
+    let is_audio = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("audio/"));
+    if !is_audio {
+        // The backend decided not to stream this one and answered with the usual JSON.
+        return res
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e));
+    }
+
+    // The backend names (and saves) the final library file up front.
+    let final_path = res
+        .headers()
+        .get("x-noises-output")
+        .and_then(|v| v.to_str().ok())
+        .map(PathBuf::from);
+
+    let dir = paths::partial_dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create temp folder: {}", e))?;
+    let partial = dir.join(format!("{}.wav", sanitize_id(stream_id)));
+    let partial_str = partial.to_string_lossy().into_owned();
+
+    let mut file = tokio::fs::File::create(&partial)
+        .await
+        .map_err(|e| format!("Failed to create temp file: {}", e))?;
+    let mut written = 0u64;
+    let mut last_emit: Option<Instant> = None;
+
+    while let Some(chunk) = res
+        .chunk()
+        .await
+        .map_err(|e| format!("Audio stream interrupted: {}", e))?
+    {
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write temp file: {}", e))?;
+        written += chunk.len() as u64;
+
+        if last_emit.is_none_or(|t| t.elapsed() >= EMIT_INTERVAL) {
+            // Flush so whatever the player reads next is actually on disk.
+            let _ = file.flush().await;
+            emit_partial(app, stream_id, &partial_str, written, false);
+            last_emit = Some(Instant::now());
+        }
+    }
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to write temp file: {}", e))?;
+    drop(file);
+    emit_partial(app, stream_id, &partial_str, written, true);
+
+    let output = match final_path {
+        Some(path) if path.exists() => path,
+        // The backend didn't keep a copy, so the streamed file becomes the result.
+        _ => {
+            let is_loop = config
+                .get("type")
+                .and_then(|t| t.as_str())
+                .is_some_and(|t| t.contains("loop"));
+            let (dir, prefix) = if is_loop {
+                (paths::loops_dir(app)?, "loop")
+            } else {
+                (paths::oneshots_dir(app)?, "song")
+            };
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create output folder: {}", e))?;
+            let target = dir.join(paths::next_filename(&dir, prefix, ".wav"));
+            paths::move_file(&partial, &target)?;
+            target
+        }
+    };
+
+    let file_name = output
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let path = output.to_string_lossy().into_owned();
+    Ok(serde_json::json!({
+        "status": "success",
+        "files": [{ "file": file_name, "path": path }],
+        "path": path,
+    }))
+}
+
+// Leftovers from a previous session are never needed again.
+pub fn clear_partials(app: &AppHandle) {
+    if let Ok(dir) = paths::partial_dir(app) {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
+
+fn emit_partial(app: &AppHandle, stream_id: &str, path: &str, bytes: u64, complete: bool) {
+    let _ = app.emit(
+        "partial-audio-available",
+        PartialAudio {
+            stream_id: stream_id.to_string(),
+            path: path.to_string(),
+            bytes,
+            complete,
+        },
+    );
+}
+
+// The id comes from the frontend and ends up in a file name.
+fn sanitize_id(id: &str) -> String {
+    let clean: String = id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .take(64)
+        .collect();
+    if clean.is_empty() {
+        "stream".to_string()
+    } else {
+        clean
+    }
+}
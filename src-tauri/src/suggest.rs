@@ -0,0 +1,445 @@
+use std::cell::Cell;
+
+use serde::Serialize;
+
+use crate::templates::{self, Part};
+
+// "Surprise me" prompts, made up offline from the word lists below. Each
+// category has a few sentence patterns whose {slots} are filled with a random
+// word from the list of that name.
+struct Category {
+    id: &'static str,
+    label: &'static str,
+    // Picked when no category is asked for. The music ones aren't, since they
+    // need the matching model.
+    sfx: bool,
+    patterns: &'static [&'static str],
+}
+
+const CATEGORIES: &[Category] = &[
+    Category {
+        id: "foley",
+        label: "Foley",
+        sfx: true,
+        patterns: &[
+            "{footwear} footsteps on {surface}, {pace}, {perspective}",
+            "{object} {handling} on a {furniture}, {perspective}",
+            "{fabric} rustle, {pace} movement, {perspective}",
+            "{door} {door_action} in {room}",
+        ],
+    },
+    Category {
+        id: "ambience",
+        label: "Ambience",
+        sfx: true,
+        patterns: &[
+            "{place} ambience, {time}, {ambient_detail}",
+            "{weather} over {landscape}, {ambient_detail}, {perspective}",
+            "room tone of {room}, {ambient_detail}",
+        ],
+    },
+    Category {
+        id: "impact",
+        label: "Impacts",
+        sfx: true,
+        patterns: &[
+            "{weight} {material} impact, {tail}",
+            "{material} crash with {debris}, {tail}",
+            "{weight} punch hit, {character}, {tail}",
+        ],
+    },
+    Category {
+        id: "ui",
+        label: "UI",
+        sfx: true,
+        patterns: &[
+            "{ui_mood} UI {ui_event} sound, {ui_texture}, short",
+            "{ui_texture} notification chime, {ui_mood}",
+            "{ui_mood} menu {ui_event}, {ui_texture} click",
+        ],
+    },
+    Category {
+        id: "creature",
+        label: "Creatures",
+        sfx: true,
+        patterns: &[
+            "{size} {creature} {vocal}, {character}",
+            "{creature} {vocal} in {landscape}, {perspective}",
+        ],
+    },
+    Category {
+        id: "vehicle",
+        label: "Vehicles",
+        sfx: true,
+        patterns: &[
+            "{vehicle} {vehicle_action}, {perspective}",
+            "{vehicle} engine {engine_state}, {character}",
+        ],
+    },
+    Category {
+        id: "scifi",
+        label: "Sci-fi",
+        sfx: true,
+        patterns: &[
+            "{scifi_source} {scifi_event}, {scifi_texture}",
+            "{scifi_texture} {scifi_source} hum, {tail}",
+        ],
+    },
+    Category {
+        id: "loop",
+        label: "Music loops",
+        sfx: false,
+        patterns: &[
+            "{genre} {loop_part} loop, {loop_feel}",
+            "{loop_feel} {instrument} {loop_part}, {genre}",
+        ],
+    },
+    Category {
+        id: "song",
+        label: "Songs",
+        sfx: false,
+        patterns: &[
+            "{genre} song, {song_mood}, {vocals}, {instrument}",
+            "{song_mood} {genre} track with {instrument}, {vocals}",
+        ],
+    },
+];
+
+fn words(slot: &str) -> &'static [&'static str] {
+    match slot {
+        "footwear" => &[
+            "boot",
+            "high heel",
+            "sneaker",
+            "bare",
+            "leather shoe",
+            "rubber boot",
+        ],
+        "surface" => &[
+            "gravel",
+            "wet pavement",
+            "creaky wood",
+            "fresh snow",
+            "dry leaves",
+            "metal grating",
+            "marble",
+        ],
+        "pace" => &["slow", "hurried", "running", "cautious", "shuffling"],
+        "perspective" => &[
+            "close up",
+            "distant",
+            "mid distance",
+            "off to the left",
+            "passing by",
+        ],
+        "object" => &[
+            "ceramic cup",
+            "set of keys",
+            "glass bottle",
+            "paper stack",
+            "coin",
+            "metal spoon",
+        ],
+        "handling" => &["set down", "dropped", "slid", "tapped", "rattled"],
+        "furniture" => &["wooden table", "metal shelf", "stone counter", "glass desk"],
+        "fabric" => &[
+            "leather jacket",
+            "nylon backpack",
+            "wool coat",
+            "silk curtain",
+        ],
+        "door" => &[
+            "heavy wooden door",
+            "car door",
+            "screen door",
+            "steel hatch",
+            "sliding door",
+        ],
+        "door_action" => &[
+            "creaking open",
+            "slamming shut",
+            "closing softly",
+            "rattling in its frame",
+        ],
+        "room" => &[
+            "a small bathroom",
+            "an empty warehouse",
+            "a stone cellar",
+            "a carpeted office",
+            "a tiled hallway",
+        ],
+        "place" => &[
+            "busy market",
+            "night forest",
+            "subway platform",
+            "harbor",
+            "city rooftop",
+            "hospital corridor",
+            "cafe",
+        ],
+        "time" => &["early morning", "midday", "late night", "at dusk"],
+        "ambient_detail" => &[
+            "distant traffic",
+            "birds chirping",
+            "light wind",
+            "murmuring crowd",
+            "buzzing lights",
+            "dripping water",
+        ],
+        "weather" => &[
+            "light rain",
+            "heavy thunderstorm",
+            "howling wind",
+            "hail",
+            "drizzle",
+        ],
+        "landscape" => &[
+            "an open field",
+            "a pine forest",
+            "a canyon",
+            "a coastal cliff",
+            "a city street",
+        ],
+        "weight" => &["heavy", "light", "massive", "sharp", "dull"],
+        "material" => &["metal", "wood", "glass", "stone", "plastic", "concrete"],
+        "debris" => &[
+            "scattering debris",
+            "falling shards",
+            "rolling pebbles",
+            "dust",
+        ],
+        "tail" => &[
+            "short tail",
+            "long reverb tail",
+            "dry",
+            "echoing",
+            "cinematic boom",
+        ],
+        "character" => &["gritty", "punchy", "realistic", "exaggerated", "cartoonish"],
+        "ui_mood" => &["friendly", "futuristic", "minimal", "playful", "soft"],
+        "ui_event" => &["confirm", "error", "hover", "open", "close", "success"],
+        "ui_texture" => &[
+            "glassy",
+            "plucky",
+            "bubbly",
+            "digital",
+            "woody",
+            "synthetic",
+        ],
+        "size" => &["tiny", "huge", "small", "giant"],
+        "creature" => &["wolf", "dragon", "owl", "frog", "alien", "bear", "crow"],
+        "vocal" => &["growl", "howl", "screech", "chirp", "roar", "hiss"],
+        "vehicle" => &[
+            "motorcycle",
+            "old truck",
+            "sports car",
+            "scooter",
+            "steam train",
+            "helicopter",
+        ],
+        "vehicle_action" => &[
+            "passing by fast",
+            "starting up",
+            "braking hard",
+            "idling",
+            "driving off",
+        ],
+        "engine_state" => &[
+            "revving",
+            "idling",
+            "sputtering",
+            "roaring at full throttle",
+        ],
+        "scifi_source" => &[
+            "laser",
+            "force field",
+            "teleporter",
+            "spaceship",
+            "robot",
+            "reactor",
+        ],
+        "scifi_event" => &[
+            "charging up",
+            "powering down",
+            "firing",
+            "malfunctioning",
+            "activating",
+        ],
+        "scifi_texture" => &["glitchy", "shimmering", "metallic", "pulsing", "granular"],
+        "genre" => &[
+            "lo-fi hip hop",
+            "house",
+            "drum and bass",
+            "ambient",
+            "funk",
+            "trap",
+            "synthwave",
+            "jazz",
+        ],
+        "loop_part" => &["drum", "bass", "chord", "melody", "percussion", "arpeggio"],
+        "loop_feel" => &[
+            "groovy",
+            "laid back",
+            "driving",
+            "swung",
+            "dreamy",
+            "punchy",
+        ],
+        "instrument" => &[
+            "electric piano",
+            "acoustic guitar",
+            "analog synth",
+            "strings",
+            "saxophone",
+            "808 bass",
+        ],
+        "song_mood" => &["upbeat", "melancholic", "epic", "chill", "dark", "hopeful"],
+        "vocals" => &["female vocals", "male vocals", "choir", "instrumental"],
+        _ => &[],
+    }
+}
+
+thread_local! {
+    // xorshift64*, seeded from the OS once per thread. Plenty for word picks.
+    static STATE: Cell<u64> = Cell::new({
+        let mut seed = [0u8; 8];
+        let _ = getrandom::fill(&mut seed);
+        u64::from_le_bytes(seed) | 1
+    });
+}
+
+fn random_below(n: usize) -> usize {
+    STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        (x.wrapping_mul(0x2545_f491_4f6c_dd1d) % n.max(1) as u64) as usize
+    })
+}
+
+fn pick<'a>(items: &[&'a str]) -> &'a str {
+    items
+        .get(random_below(items.len()))
+        .copied()
+        .unwrap_or_default()
+}
+
+fn expand(pattern: &str) -> String {
+    let mut prompt = String::new();
+    for part in templates::parse(pattern) {
+        match part {
+            Part::Text(text) => prompt.push_str(text),
+            Part::Variable(slot) => prompt.push_str(pick(words(slot))),
+        }
+    }
+    prompt
+}
+
+#[derive(Serialize)]
+pub struct PromptCategory {
+    pub id: &'static str,
+    pub label: &'static str,
+}
+
+#[tauri::command]
+pub fn list_prompt_categories() -> Vec<PromptCategory> {
+    CATEGORIES
+        .iter()
+        .map(|c| PromptCategory {
+            id: c.id,
+            label: c.label,
+        })
+        .collect()
+}
+
+// Without a category it's any of the sound effect ones.
+#[tauri::command]
+pub fn suggest_prompt(category: Option<String>) -> Result<String, String> {
+    let category = match category.as_deref() {
+        Some(id) => CATEGORIES
+            .iter()
+            .find(|c| c.id == id)
+            .ok_or_else(|| format!("Unknown prompt category: {}", id))?,
+        None => {
+            let sfx: Vec<&Category> = CATEGORIES.iter().filter(|c| c.sfx).collect();
+            sfx[random_below(sfx.len())]
+        }
+    };
+    Ok(expand(pick(category.patterns)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A slot with no words would leave a hole in the prompt.
+    #[test]
+    fn every_slot_has_words() {
+        for category in CATEGORIES {
+            for pattern in category.patterns {
+                for part in templates::parse(pattern) {
+                    if let Part::Variable(slot) = part {
+                        assert!(!words(slot).is_empty(), "{}: {{{}}}", category.id, slot);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn prompts_are_filled_in() {
+        for category in CATEGORIES {
+            for _ in 0..20 {
+                let prompt = suggest_prompt(Some(category.id.to_string())).unwrap();
+                assert!(!prompt.contains(['{', '}']), "{}", prompt);
+                assert!(
+                    !prompt.contains("  ") && !prompt.contains(" ,"),
+                    "{}",
+                    prompt
+                );
+                assert_eq!(prompt.trim(), prompt);
+            }
+        }
+    }
+
+    // Every music pattern says loop, song or track; no sound effect word does.
+    #[test]
+    fn no_category_means_sound_effects() {
+        for _ in 0..200 {
+            let prompt = suggest_prompt(None).unwrap();
+            assert!(
+                !["loop", "song", "track"]
+                    .iter()
+                    .any(|word| prompt.contains(word)),
+                "{}",
+                prompt
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_categories_are_refused() {
+        assert_eq!(
+            suggest_prompt(Some("polka".to_string())).unwrap_err(),
+            "Unknown prompt category: polka"
+        );
+    }
+
+    #[test]
+    fn picks_stay_in_range() {
+        for n in [1, 2, 7, 100] {
+            assert!((0..1000).all(|_| random_below(n) < n));
+        }
+        assert_eq!(random_below(0), 0);
+        assert_eq!(pick(&[]), "");
+    }
+
+    #[test]
+    fn categories_are_listed_in_order() {
+        let ids: Vec<&str> = list_prompt_categories().iter().map(|c| c.id).collect();
+        assert_eq!(ids.first(), Some(&"foley"));
+        assert_eq!(ids.len(), CATEGORIES.len());
+    }
+}
@@ -0,0 +1,976 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use hmac::{Hmac, Mac};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager, Url};
+
+use crate::import;
+use crate::library;
+use crate::paths;
+use crate::proxy;
+use crate::secrets::{self, Secret};
+use crate::settings;
+use crate::tasks::{self, TaskKind};
+use crate::workspaces::WorkspaceStore;
+
+// Everything but the unreserved characters, as S3 signing wants it.
+const ENCODE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+// Where the `sync` setting points the library. The secret half (S3 secret key
+// or WebDAV password) is in the credential store as sync-password.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SyncRemote {
+    // AWS or anything that speaks its API (R2, MinIO, B2, ...), with
+    // path-style URLs: endpoint/bucket/prefix/samples/loops/loop_001.wav.
+    S3 {
+        endpoint: Url,
+        region: String,
+        bucket: String,
+        #[serde(default)]
+        prefix: String,
+        access_key_id: String,
+    },
+    // Nextcloud, ownCloud, most NAS boxes. The URL is the folder to sync into.
+    Webdav {
+        url: Url,
+        username: String,
+    },
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Direction {
+    Upload,
+    Download,
+}
+
+#[derive(Clone, Serialize)]
+struct SyncProgress {
+    direction: Direction,
+    file: String,
+    done: usize,
+    total: usize,
+}
+
+#[derive(Default, Serialize)]
+pub struct SyncReport {
+    pub uploaded: usize,
+    pub downloaded: usize,
+    // Changed on both machines since the last sync. The local file wins and is
+    // uploaded; the remote one is saved next to it with ".remote" before the
+    // extension, which the other machine then gets too.
+    pub conflicts: Vec<String>,
+}
+
+// One sync at a time, whether from the button or after a generation.
+static RUNNING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+// Paths are relative to the workspace root with forward slashes, e.g.
+// samples/loops/loop_001.wav. Only the library folders (library.rs) are
+// synced, and deleting a file is never synced: it comes back from the other
+// side on the next sync.
+type Files<T> = BTreeMap<String, T>;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct LocalFile {
+    size: u64,
+    // Unix time in milliseconds, as library.rs has it.
+    modified: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct RemoteFile {
+    size: u64,
+    // The ETag, or the last-modified time from a WebDAV server without
+    // them. None when an upload's reply didn't say.
+    version: Option<String>,
+}
+
+// Both sides of a file as they were when it was last in sync.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Synced {
+    local: LocalFile,
+    remote: RemoteFile,
+}
+
+#[derive(Debug, PartialEq)]
+enum Plan {
+    Keep,
+    Upload,
+    Download,
+    Conflict,
+}
+
+// What to do with a file that's on both sides. With nothing recorded (the
+// first sync), equal sizes are taken as the same sound.
+fn plan(local: &LocalFile, remote: &RemoteFile, last: Option<&Synced>) -> Plan {
+    let Some(last) = last else {
+        return if local.size == remote.size {
+            Plan::Keep
+        } else {
+            Plan::Conflict
+        };
+    };
+    let local_changed = *local != last.local;
+    let remote_changed = match (&remote.version, &last.remote.version) {
+        (Some(now), Some(then)) => now != then,
+        _ => remote.size != last.remote.size,
+    };
+    match (local_changed, remote_changed) {
+        (false, false) => Plan::Keep,
+        (true, false) => Plan::Upload,
+        (false, true) => Plan::Download,
+        (true, true) => Plan::Conflict,
+    }
+}
+
+fn local_file(path: &Path) -> Option<LocalFile> {
+    let meta = std::fs::metadata(path).ok()?;
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    Some(LocalFile {
+        size: meta.len(),
+        modified,
+    })
+}
+
+fn relative(root: &Path, path: &Path) -> Option<String> {
+    let rel = path.strip_prefix(root).ok()?;
+    Some(rel.to_string_lossy().replace('\\', "/"))
+}
+
+fn local_files(app: &AppHandle) -> Result<(PathBuf, Files<LocalFile>), String> {
+    let root = paths::output_dir(app)?;
+    let files = library::scan(app)
+        .into_iter()
+        .filter_map(|item| {
+            let file = LocalFile {
+                size: item.size,
+                modified: item.modified,
+            };
+            Some((relative(&root, Path::new(&item.path))?, file))
+        })
+        .collect();
+    Ok((root, files))
+}
+
+// Rejects anything from the remote that would land outside the library
+// folders.
+fn local_path(app: &AppHandle, root: &Path, rel: &str) -> Option<PathBuf> {
+    let path = root.join(rel);
+    let safe = Path::new(rel)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)));
+    let folders = library::folders(app);
+    let in_library = folders
+        .iter()
+        .any(|(_, dir)| path.parent() == Some(dir.as_path()));
+    (safe && in_library && import::is_audio_file(&path)).then_some(path)
+}
+
+// What both sides looked like after the last sync, so a difference can be
+// told apart as an edit on one side or a conflict. Per workspace, next to its
+// history.
+fn state_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let id = app
+        .try_state::<WorkspaceStore>()
+        .and_then(|store| store.active())
+        .map(|w| w.id)
+        .ok_or("No active workspace")?;
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve data directory: {}", e))?;
+    Ok(dir.join("workspaces").join(format!("{}.sync.json", id)))
+}
+
+// A state file from before versions were kept doesn't parse, and that sync
+// goes as a first one.
+fn load_state(app: &AppHandle) -> Files<Synced> {
+    state_path(app)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(app: &AppHandle, state: &Files<Synced>) -> Result<(), String> {
+    let path = state_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create data folder: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(state)
+        .map_err(|e| format!("Failed to serialize sync state: {}", e))?;
+    paths::write_atomic(path, json).map_err(|e| format!("Failed to save sync state: {}", e))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any size");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+// The text of every element with this local name (any namespace prefix), in
+// order. Enough for ListObjectsV2 and PROPFIND replies; entities other than
+// the five predefined ones don't show up in either.
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<std::borrow::Cow<'a, str>> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let end = rest
+            .find(['>', ' ', '/', '\t', '\r', '\n'])
+            .unwrap_or(rest.len());
+        let tag = &rest[..end];
+        let local = tag.rsplit(':').next().unwrap_or(tag);
+        if local != name || tag.starts_with(['/', '?', '!']) {
+            continue;
+        }
+        let Some(open_end) = rest.find('>') else {
+            break;
+        };
+        if rest[..open_end].ends_with('/') {
+            found.push("".into());
+            continue;
+        }
+        let body = &rest[open_end + 1..];
+        let Some(close) = body.find(&format!("</{}>", tag)) else {
+            break;
+        };
+        let text = &body[..close];
+        found.push(if text.contains('&') {
+            text.replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&")
+                .into()
+        } else {
+            text.into()
+        });
+    }
+    found
+}
+
+// Sorted and encoded the way the signature wants, which is also how it's
+// sent.
+fn canonical_query(query: &[(&str, &str)]) -> String {
+    let mut query: Vec<String> = query
+        .iter()
+        .map(|(k, v)| {
+            format!(
+                "{}={}",
+                utf8_percent_encode(k, ENCODE),
+                utf8_percent_encode(v, ENCODE)
+            )
+        })
+        .collect();
+    query.sort();
+    query.join("&")
+}
+
+fn scope(timestamp: &str, region: &str) -> String {
+    format!("{}/{}/s3/aws4_request", &timestamp[..8], region)
+}
+
+// What an S3 request's Signature Version 4 covers: these and the three
+// headers, with the payload hash in one of them.
+struct Signed<'a> {
+    method: &'a str,
+    host: &'a str,
+    uri: &'a str,
+    query: &'a str,
+    payload_hash: &'a str,
+    // 20130524T000000Z
+    timestamp: &'a str,
+}
+
+impl Signed<'_> {
+    fn signature(&self, secret: &str, region: &str) -> String {
+        let canonical = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            self.method,
+            self.uri,
+            self.query,
+            self.host,
+            self.payload_hash,
+            self.timestamp,
+            self.payload_hash
+        );
+        let to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            self.timestamp,
+            scope(self.timestamp, region),
+            hex(&Sha256::digest(canonical.as_bytes()))
+        );
+        let mut signing_key = hmac(format!("AWS4{}", secret).as_bytes(), &self.timestamp[..8]);
+        for part in [region, "s3", "aws4_request"] {
+            signing_key = hmac(&signing_key, part);
+        }
+        hex(&hmac(&signing_key, &to_sign))
+    }
+}
+
+struct Remote {
+    client: reqwest::Client,
+    target: SyncRemote,
+    secret: String,
+}
+
+impl Remote {
+    fn open(app: &AppHandle) -> Result<Self, String> {
+        let target = settings::get(app).sync.ok_or("Sync isn't set up")?;
+        let secret = secrets::get(app, Secret::SyncPassword)?
+            .ok_or("The sync password or secret key is missing")?;
+        let client = proxy::client_builder(app)?
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        Ok(Remote {
+            client,
+            target,
+            secret,
+        })
+    }
+
+    // Signed with the payload hash in a header, which S3 and the compatible
+    // services all accept.
+    fn s3_request(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> reqwest::RequestBuilder {
+        let SyncRemote::S3 {
+            endpoint,
+            region,
+            bucket,
+            access_key_id,
+            ..
+        } = &self.target
+        else {
+            unreachable!("s3_request on a WebDAV remote");
+        };
+        let host = match endpoint.port() {
+            Some(port) => format!("{}:{}", endpoint.host_str().unwrap_or_default(), port),
+            None => endpoint.host_str().unwrap_or_default().to_string(),
+        };
+        let mut uri = format!("/{}", utf8_percent_encode(bucket, ENCODE));
+        if !key.is_empty() {
+            for segment in key.split('/') {
+                uri.push('/');
+                uri.extend(utf8_percent_encode(segment, ENCODE));
+            }
+        }
+        let query = canonical_query(query);
+
+        let now = time::OffsetDateTime::now_utc();
+        let timestamp = format!(
+            "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+            now.year(),
+            now.month() as u8,
+            now.day(),
+            now.hour(),
+            now.minute(),
+            now.second()
+        );
+        let payload_hash = hex(&Sha256::digest(&body));
+        let signed = Signed {
+            method: method.as_str(),
+            host: &host,
+            uri: &uri,
+            query: &query,
+            payload_hash: &payload_hash,
+            timestamp: &timestamp,
+        };
+        let signature = signed.signature(&self.secret, region);
+        let scope = scope(&timestamp, region);
+
+        let mut url = format!("{}://{}{}", endpoint.scheme(), host, uri);
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(&query);
+        }
+        self.client
+            .request(method, url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", timestamp)
+            .header(
+                "Authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+                    access_key_id, scope, signature
+                ),
+            )
+            .body(body)
+    }
+
+    fn webdav_request(&self, method: reqwest::Method, rel: &str) -> reqwest::RequestBuilder {
+        let SyncRemote::Webdav { url, username } = &self.target else {
+            unreachable!("webdav_request on an S3 remote");
+        };
+        let mut url = url.as_str().trim_end_matches('/').to_string();
+        for segment in rel.split('/').filter(|s| !s.is_empty()) {
+            url.push('/');
+            url.extend(utf8_percent_encode(segment, ENCODE));
+        }
+        self.client
+            .request(method, url)
+            .basic_auth(username, Some(&self.secret))
+    }
+
+    async fn send(
+        request: reqwest::RequestBuilder,
+        what: &str,
+    ) -> Result<reqwest::Response, String> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to {}: {}", what, e))?;
+        Self::check(response, what).await
+    }
+
+    async fn check(response: reqwest::Response, what: &str) -> Result<reqwest::Response, String> {
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let message = elements(&body, "Message").into_iter().next();
+            return Err(format!(
+                "Failed to {}: {}{}",
+                what,
+                status,
+                message.map(|m| format!(" ({})", m)).unwrap_or_default()
+            ));
+        }
+        Ok(response)
+    }
+
+    async fn list(&self, app: &AppHandle, root: &Path) -> Result<Files<RemoteFile>, String> {
+        let mut files = BTreeMap::new();
+        match &self.target {
+            SyncRemote::S3 { prefix, .. } => {
+                let prefix = prefix.trim_matches('/');
+                let key_prefix = if prefix.is_empty() {
+                    String::new()
+                } else {
+                    format!("{}/", prefix)
+                };
+                let mut token: Option<String> = None;
+                loop {
+                    let mut query = vec![("list-type", "2"), ("prefix", key_prefix.as_str())];
+                    if let Some(token) = &token {
+                        query.push(("continuation-token", token));
+                    }
+                    let request = self.s3_request(reqwest::Method::GET, "", &query, Vec::new());
+                    let xml = Self::send(request, "list the bucket")
+                        .await?
+                        .text()
+                        .await
+                        .map_err(|e| format!("Failed to list the bucket: {}", e))?;
+                    for contents in elements(&xml, "Contents") {
+                        let key = elements(&contents, "Key").into_iter().next();
+                        let size = elements(&contents, "Size").into_iter().next();
+                        let version = elements(&contents, "ETag").into_iter().next();
+                        if let (Some(key), Some(size)) = (key, size)
+                            && let Some(rel) = key.strip_prefix(&key_prefix)
+                            && let Ok(size) = size.parse()
+                        {
+                            let version = version.map(|v| v.trim().to_string());
+                            files.insert(rel.to_string(), RemoteFile { size, version });
+                        }
+                    }
+                    token = elements(&xml, "NextContinuationToken")
+                        .into_iter()
+                        .next()
+                        .map(|t| t.into_owned());
+                    if token.is_none() {
+                        break;
+                    }
+                }
+            }
+            // Depth: infinity is off on most servers, so one PROPFIND per
+            // library folder.
+            SyncRemote::Webdav { url, .. } => {
+                let propfind = reqwest::Method::from_bytes(b"PROPFIND").expect("valid method");
+                let base = percent_decode_str(url.path().trim_end_matches('/'))
+                    .decode_utf8_lossy()
+                    .into_owned();
+                for (_, dir) in library::folders(app) {
+                    let Some(folder) = relative(root, &dir) else {
+                        continue;
+                    };
+                    let request = self
+                        .webdav_request(propfind.clone(), &folder)
+                        .header("Depth", "1")
+                        .header("Content-Type", "application/xml")
+                        .body(
+                            r#"<?xml version="1.0"?><propfind xmlns="DAV:"><prop><getcontentlength/><getetag/><getlastmodified/><resourcetype/></prop></propfind>"#,
+                        );
+                    let what = format!("list {}", folder);
+                    let response = request
+                        .send()
+                        .await
+                        .map_err(|e| format!("Failed to {}: {}", what, e))?;
+                    // Folders that don't exist there yet.
+                    if response.status() == reqwest::StatusCode::NOT_FOUND {
+                        continue;
+                    }
+                    let xml = Self::check(response, &what)
+                        .await?
+                        .text()
+                        .await
+                        .map_err(|e| format!("Failed to {}: {}", what, e))?;
+                    for entry in elements(&xml, "response") {
+                        if !elements(&entry, "collection").is_empty() {
+                            continue;
+                        }
+                        let href = elements(&entry, "href").into_iter().next();
+                        let size = elements(&entry, "getcontentlength").into_iter().next();
+                        let version = elements(&entry, "getetag")
+                            .into_iter()
+                            .chain(elements(&entry, "getlastmodified"))
+                            .map(|v| v.trim().to_string())
+                            .find(|v| !v.is_empty());
+                        let (Some(href), Some(size)) = (href, size) else {
+                            continue;
+                        };
+                        // Hrefs are either absolute URLs or absolute paths.
+                        let path = Url::parse(&href)
+                            .map(|u| u.path().to_string())
+                            .unwrap_or_else(|_| href.into_owned());
+                        let path = percent_decode_str(&path).decode_utf8_lossy().into_owned();
+                        if let Some(rel) = path.strip_prefix(&base)
+                            && let Ok(size) = size.trim().parse()
+                        {
+                            files.insert(
+                                rel.trim_start_matches('/').to_string(),
+                                RemoteFile { size, version },
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    // Returns the remote file as the server has it now.
+    async fn upload(&self, rel: &str, path: &Path) -> Result<RemoteFile, String> {
+        let body = tokio::fs::read(path)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let size = body.len() as u64;
+        let what = format!("upload {}", rel);
+        let response = match &self.target {
+            SyncRemote::S3 { prefix, .. } => {
+                let prefix = prefix.trim_matches('/');
+                let key = if prefix.is_empty() {
+                    rel.to_string()
+                } else {
+                    format!("{}/{}", prefix, rel)
+                };
+                Self::send(
+                    self.s3_request(reqwest::Method::PUT, &key, &[], body),
+                    &what,
+                )
+                .await?
+            }
+            SyncRemote::Webdav { .. } => {
+                // PUT doesn't create folders. MKCOL on one that exists fails
+                // with 405, which is fine.
+                let mkcol = reqwest::Method::from_bytes(b"MKCOL").expect("valid method");
+                let segments: Vec<&str> = rel.split('/').collect();
+                for depth in 1..segments.len() {
+                    let folder = segments[..depth].join("/");
+                    let _ = self.webdav_request(mkcol.clone(), &folder).send().await;
+                }
+                let request = self.webdav_request(reqwest::Method::PUT, rel).body(body);
+                Self::send(request, &what).await?
+            }
+        };
+        let version = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().to_string());
+        Ok(RemoteFile { size, version })
+    }
+
+    async fn download(&self, rel: &str, to: &Path) -> Result<(), String> {
+        let what = format!("download {}", rel);
+        let request = match &self.target {
+            SyncRemote::S3 { prefix, .. } => {
+                let prefix = prefix.trim_matches('/');
+                let key = if prefix.is_empty() {
+                    rel.to_string()
+                } else {
+                    format!("{}/{}", prefix, rel)
+                };
+                self.s3_request(reqwest::Method::GET, &key, &[], Vec::new())
+            }
+            SyncRemote::Webdav { .. } => self.webdav_request(reqwest::Method::GET, rel),
+        };
+        let bytes = Self::send(request, &what)
+            .await?
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to {}: {}", what, e))?;
+        if let Some(dir) = to.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+        }
+        // Atomic, so a broken download never looks like a finished sound to
+        // the library.
+        paths::write_atomic(to, &bytes).map_err(|e| format!("Failed to save {}: {}", rel, e))
+    }
+}
+
+// samples/loops/loop_001.wav -> samples/loops/loop_001.remote.wav
+fn conflict_name(rel: &str) -> String {
+    let (dir, name) = rel.rsplit_once('/').unwrap_or(("", rel));
+    let name = match name.rsplit_once('.') {
+        Some((stem, ext)) => format!("{}.remote.{}", stem, ext),
+        None => format!("{}.remote", name),
+    };
+    if dir.is_empty() {
+        name
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+// Uploads what's only here, downloads what's only there, and for files on
+// both sides takes the side that changed since the last sync, going by size
+// and mtime here and the ETag there. Reports `sync-progress` for every file.
+pub async fn sync(app: &AppHandle) -> Result<SyncReport, String> {
+    let _running = RUNNING.lock().await;
+    let remote = Remote::open(app)?;
+    let scan_app = app.clone();
+    let (root, local) = tauri::async_runtime::spawn_blocking(move || local_files(&scan_app))
+        .await
+        .map_err(|e| format!("Library scan failed: {}", e))??;
+    let remote_files = remote.list(app, &root).await?;
+    let mut state = load_state(app);
+
+    let mut uploads = Vec::new();
+    let mut downloads = Vec::new();
+    let mut conflicts = Vec::new();
+    for (rel, file) in &local {
+        let Some(theirs) = remote_files.get(rel) else {
+            uploads.push(rel.clone());
+            continue;
+        };
+        match plan(file, theirs, state.get(rel)) {
+            Plan::Keep => {
+                let synced = Synced {
+                    local: file.clone(),
+                    remote: theirs.clone(),
+                };
+                state.insert(rel.clone(), synced);
+            }
+            Plan::Upload => uploads.push(rel.clone()),
+            Plan::Download => downloads.push(rel.clone()),
+            Plan::Conflict => conflicts.push(rel.clone()),
+        }
+    }
+    for rel in remote_files.keys() {
+        if !local.contains_key(rel) {
+            downloads.push(rel.clone());
+        }
+    }
+
+    let mut report = SyncReport::default();
+    // A conflict is a download of the remote copy and an upload.
+    let total = uploads.len() + downloads.len() + 2 * conflicts.len();
+    let task = tasks::begin(app, TaskKind::Sync, "Library sync");
+    let mut done = 0;
+    let mut progress = |direction, file: &str| {
+        done += 1;
+        task.progress(done as u64, Some(total as u64));
+        let _ = app.emit(
+            "sync-progress",
+            SyncProgress {
+                direction,
+                file: file.to_string(),
+                done,
+                total,
+            },
+        );
+    };
+    // The state is saved even when a transfer fails or the sync is cancelled,
+    // so what did get across isn't compared again.
+    let result = task
+        .run(async {
+            for rel in uploads {
+                let uploaded = remote.upload(&rel, &root.join(&rel)).await?;
+                let synced = Synced {
+                    local: local[&rel].clone(),
+                    remote: uploaded,
+                };
+                state.insert(rel.clone(), synced);
+                report.uploaded += 1;
+                progress(Direction::Upload, &rel);
+            }
+            for rel in downloads {
+                let Some(to) = local_path(app, &root, &rel) else {
+                    eprintln!(
+                        "[Sync] Skipping {}, which isn't a sound in a library folder",
+                        rel
+                    );
+                    progress(Direction::Download, &rel);
+                    continue;
+                };
+                remote.download(&rel, &to).await?;
+                if let Some(file) = local_file(&to) {
+                    let synced = Synced {
+                        local: file,
+                        remote: remote_files[&rel].clone(),
+                    };
+                    state.insert(rel.clone(), synced);
+                }
+                report.downloaded += 1;
+                progress(Direction::Download, &rel);
+            }
+            // The remote version comes down as the .remote copy, which the
+            // next sync uploads like any new file, and the local one goes up
+            // in its place, so both machines end up with both versions.
+            for rel in conflicts {
+                let copy = conflict_name(&rel);
+                if let Some(to) = local_path(app, &root, &copy) {
+                    remote.download(&rel, &to).await?;
+                    report.downloaded += 1;
+                }
+                progress(Direction::Download, &rel);
+                let uploaded = remote.upload(&rel, &root.join(&rel)).await?;
+                let synced = Synced {
+                    local: local[&rel].clone(),
+                    remote: uploaded,
+                };
+                state.insert(rel.clone(), synced);
+                report.uploaded += 1;
+                report.conflicts.push(rel.clone());
+                progress(Direction::Upload, &rel);
+            }
+            Ok(())
+        })
+        .await;
+    save_state(app, &state)?;
+    result?;
+
+    println!(
+        "[Sync] Uploaded {}, downloaded {}, {} conflicts",
+        report.uploaded,
+        report.downloaded,
+        report.conflicts.len()
+    );
+    Ok(report)
+}
+
+// Called by jobs.rs after a generation, so a new sound reaches the other
+// machine without anyone pressing Sync. Does nothing when sync isn't set up.
+pub fn upload_new(app: &AppHandle, outputs: Vec<String>) {
+    if settings::get(app).sync.is_none() || outputs.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = async {
+            let _running = RUNNING.lock().await;
+            let remote = Remote::open(&app)?;
+            let root = paths::output_dir(&app)?;
+            let mut state = load_state(&app);
+            for output in outputs {
+                let path = Path::new(&output);
+                let Some(rel) = relative(&root, path) else {
+                    continue;
+                };
+                let uploaded = remote.upload(&rel, path).await?;
+                if let Some(file) = local_file(path) {
+                    let synced = Synced {
+                        local: file,
+                        remote: uploaded,
+                    };
+                    state.insert(rel.clone(), synced);
+                }
+                println!("[Sync] Uploaded {}", rel);
+            }
+            save_state(&app, &state)
+        }
+        .await;
+        if let Err(e) = result {
+            eprintln!("[Sync] {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn sync_now(app: AppHandle) -> Result<SyncReport, String> {
+    sync(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // From the examples in AWS's "Signature Calculations for the
+    // Authorization Header" for S3.
+    const SECRET: &str = "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY";
+    const EMPTY_HASH: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    fn aws_example(query: &str) -> String {
+        Signed {
+            method: "GET",
+            host: "examplebucket.s3.amazonaws.com",
+            uri: "/",
+            query,
+            payload_hash: EMPTY_HASH,
+            timestamp: "20130524T000000Z",
+        }
+        .signature(SECRET, "us-east-1")
+    }
+
+    #[test]
+    fn signs_the_aws_bucket_lifecycle_example() {
+        assert_eq!(
+            aws_example(&canonical_query(&[("lifecycle", "")])),
+            "fea454ca298b7da1c68078a5d1bdbfbbe0d65c699e0f91ac7a200a0136783543"
+        );
+    }
+
+    #[test]
+    fn signs_the_aws_list_objects_example() {
+        let query = canonical_query(&[("prefix", "J"), ("max-keys", "2")]);
+        assert_eq!(query, "max-keys=2&prefix=J");
+        assert_eq!(
+            aws_example(&query),
+            "34b48302e7b5fa45bde8084f4b7868a86f0a534bc59db6670ed5711ef69dc6f7"
+        );
+    }
+
+    #[test]
+    fn query_values_are_encoded() {
+        assert_eq!(
+            canonical_query(&[("prefix", "a b/c"), ("continuation-token", "x+y=")]),
+            "continuation-token=x%2By%3D&prefix=a%20b%2Fc"
+        );
+    }
+
+    #[test]
+    fn finds_elements_by_local_name() {
+        let xml = r#"<?xml version="1.0"?><ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+            <Contents><Key>samples/loops/a.wav</Key><ETag>&quot;abc&quot;</ETag></Contents>
+            <Contents><Key>samples/loops/b &amp; c.wav</Key></Contents>
+            <KeyCount>2</KeyCount></ListBucketResult>"#;
+        let contents = elements(xml, "Contents");
+        assert_eq!(contents.len(), 2);
+        assert_eq!(elements(&contents[0], "Key"), ["samples/loops/a.wav"]);
+        assert_eq!(elements(&contents[0], "ETag"), ["\"abc\""]);
+        assert_eq!(elements(&contents[1], "Key"), ["samples/loops/b & c.wav"]);
+        // KeyCount isn't Key.
+        assert_eq!(elements(xml, "Key").len(), 2);
+    }
+
+    #[test]
+    fn finds_prefixed_and_empty_elements() {
+        let xml = r#"<d:multistatus xmlns:d="DAV:"><d:response><d:href>/dav/a.wav</d:href>
+            <d:propstat><d:prop><d:getetag>"1"</d:getetag><d:resourcetype/></d:prop></d:propstat>
+            </d:response></d:multistatus>"#;
+        assert_eq!(elements(xml, "href"), ["/dav/a.wav"]);
+        assert_eq!(elements(xml, "getetag"), ["\"1\""]);
+        assert_eq!(elements(xml, "resourcetype"), [""]);
+        assert!(elements(xml, "collection").is_empty());
+    }
+
+    fn local(size: u64, modified: u64) -> LocalFile {
+        LocalFile { size, modified }
+    }
+
+    fn remote(size: u64, version: &str) -> RemoteFile {
+        RemoteFile {
+            size,
+            version: Some(version.to_string()),
+        }
+    }
+
+    fn last() -> Synced {
+        Synced {
+            local: local(100, 1),
+            remote: remote(100, "v1"),
+        }
+    }
+
+    #[test]
+    fn takes_the_side_that_changed() {
+        let last = Some(last());
+        assert_eq!(
+            plan(&local(100, 1), &remote(100, "v1"), last.as_ref()),
+            Plan::Keep
+        );
+        // Same size, new mtime: an edit here.
+        assert_eq!(
+            plan(&local(100, 2), &remote(100, "v1"), last.as_ref()),
+            Plan::Upload
+        );
+        // Same size, new ETag: an edit there.
+        assert_eq!(
+            plan(&local(100, 1), &remote(100, "v2"), last.as_ref()),
+            Plan::Download
+        );
+        assert_eq!(
+            plan(&local(100, 2), &remote(100, "v2"), last.as_ref()),
+            Plan::Conflict
+        );
+    }
+
+    #[test]
+    fn falls_back_to_size_without_versions() {
+        let last = Synced {
+            remote: RemoteFile {
+                size: 100,
+                version: None,
+            },
+            ..last()
+        };
+        assert_eq!(
+            plan(&local(100, 1), &remote(100, "v9"), Some(&last)),
+            Plan::Keep
+        );
+        assert_eq!(
+            plan(&local(100, 1), &remote(120, "v9"), Some(&last)),
+            Plan::Download
+        );
+    }
+
+    #[test]
+    fn first_sync_compares_sizes() {
+        assert_eq!(plan(&local(100, 5), &remote(100, "v1"), None), Plan::Keep);
+        assert_eq!(
+            plan(&local(100, 5), &remote(101, "v1"), None),
+            Plan::Conflict
+        );
+    }
+
+    #[test]
+    fn conflict_copies_keep_the_extension() {
+        assert_eq!(
+            conflict_name("samples/loops/loop_001.wav"),
+            "samples/loops/loop_001.remote.wav"
+        );
+        assert_eq!(conflict_name("noext"), "noext.remote");
+    }
+}
@@ -0,0 +1,40 @@
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::{AppHandle, Manager};
+
+use crate::jobs::QueueStatus;
+
+// Mirrors the job queue on the Windows taskbar button / macOS dock tile, so a
+// long batch can be followed without switching to the window.
+pub fn update(app: &AppHandle, status: &QueueStatus) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+
+    // A single generation has no progress to report, so it just pulses.
+    let state = if status.active.is_empty() {
+        ProgressBarState {
+            status: Some(ProgressBarStatus::None),
+            progress: None,
+        }
+    } else if status.batch_total <= 1 {
+        ProgressBarState {
+            status: Some(ProgressBarStatus::Indeterminate),
+            progress: None,
+        }
+    } else {
+        ProgressBarState {
+            status: Some(ProgressBarStatus::Normal),
+            progress: Some((status.batch_done * 100 / status.batch_total) as u64),
+        }
+    };
+    if let Err(e) = window.set_progress_bar(state) {
+        eprintln!("[Taskbar] Failed to set progress: {}", e);
+    }
+
+    // Number of sounds still rendering on the dock icon. Windows has no badge.
+    #[cfg(target_os = "macos")]
+    {
+        let count = status.active.len() as i64;
+        let _ = window.set_badge_count((count > 0).then_some(count));
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio_util::sync::CancellationToken;
+
+// What a cancelled task's command returns, so the UI can tell it apart from a
+// real failure.
+pub const CANCELLED: &str = "Cancelled";
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskKind {
+    Generation,
+    Processing,
+    Download,
+    Sync,
+}
+
+#[derive(Clone, Serialize)]
+pub struct TaskInfo {
+    pub id: String,
+    pub kind: TaskKind,
+    pub label: String,
+    // In whatever unit the task counts (bytes, files); total is None when
+    // there's nothing to count, e.g. a generation.
+    pub done: u64,
+    pub total: Option<u64>,
+}
+
+#[derive(Clone, Serialize)]
+struct TaskEnded {
+    id: String,
+    kind: TaskKind,
+}
+
+// Every long-running operation (generations, plugin processing, downloads,
+// sync) registers here with its own cancellation token, so one command can
+// stop any of them. Listeners get:
+//   task-progress   TaskInfo, when a task starts and as it moves along
+//   task-finished   {id, kind} when it ends on its own (success or error)
+//   task-cancelled  {id, kind} when it ends because of cancel_task
+#[derive(Default)]
+pub struct TaskRegistry {
+    tasks: Mutex<HashMap<String, (TaskInfo, CancellationToken)>>,
+    next_id: AtomicU64,
+}
+
+// Returned by begin(). Dropping it ends the task.
+pub struct Task {
+    app: AppHandle,
+    info: TaskInfo,
+    token: CancellationToken,
+}
+
+// Works without a registry too (the command line doesn't manage one); the
+// task then just can't be cancelled from outside.
+pub fn begin(app: &AppHandle, kind: TaskKind, label: &str) -> Task {
+    let registry = app.try_state::<TaskRegistry>();
+    let number = registry
+        .as_ref()
+        .map(|r| r.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        .unwrap_or_default();
+    let task = Task {
+        app: app.clone(),
+        info: TaskInfo {
+            id: format!("task-{}", number),
+            kind,
+            label: label.to_string(),
+            done: 0,
+            total: None,
+        },
+        token: CancellationToken::new(),
+    };
+    if let Some(registry) = registry {
+        registry.tasks.lock().unwrap().insert(
+            task.info.id.clone(),
+            (task.info.clone(), task.token.clone()),
+        );
+        let _ = app.emit("task-progress", &task.info);
+    }
+    task
+}
+
+impl Task {
+    // For code that checks for cancellation itself (select! on cancelled()).
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    pub fn progress(&self, done: u64, total: Option<u64>) {
+        let Some(registry) = self.app.try_state::<TaskRegistry>() else {
+            return;
+        };
+        let info = {
+            let mut tasks = registry.tasks.lock().unwrap();
+            let Some((info, _)) = tasks.get_mut(&self.info.id) else {
+                return;
+            };
+            info.done = done;
+            info.total = total;
+            info.clone()
+        };
+        let _ = self.app.emit("task-progress", info);
+    }
+
+    // Runs the work until it finishes or the task is cancelled. Cancelling
+    // drops the future, which closes its requests and kills its child
+    // processes (they're all kill_on_drop).
+    pub async fn run<T>(&self, work: impl Future<Output = Result<T, String>>) -> Result<T, String> {
+        tokio::select! {
+            _ = self.token.cancelled() => Err(CANCELLED.to_string()),
+            result = work => result,
+        }
+    }
+}
+
+impl Drop for Task {
+    fn drop(&mut self) {
+        let Some(registry) = self.app.try_state::<TaskRegistry>() else {
+            return;
+        };
+        registry.tasks.lock().unwrap().remove(&self.info.id);
+        let event = if self.token.is_cancelled() {
+            "task-cancelled"
+        } else {
+            "task-finished"
+        };
+        let _ = self.app.emit(
+            event,
+            TaskEnded {
+                id: self.info.id.clone(),
+                kind: self.info.kind,
+            },
+        );
+    }
+}
+
+#[tauri::command]
+pub fn list_tasks(registry: State<'_, TaskRegistry>) -> Vec<TaskInfo> {
+    let tasks = registry.tasks.lock().unwrap();
+    let mut list: Vec<TaskInfo> = tasks.values().map(|(info, _)| info.clone()).collect();
+    // Oldest first (task-9 before task-10).
+    list.sort_by(|a, b| a.id.len().cmp(&b.id.len()).then(a.id.cmp(&b.id)));
+    list
+}
+
+#[tauri::command]
+pub fn cancel_task(registry: State<'_, TaskRegistry>, task_id: String) -> Result<(), String> {
+    let tasks = registry.tasks.lock().unwrap();
+    let (info, token) = tasks
+        .get(&task_id)
+        .ok_or_else(|| format!("Unknown task: {}", task_id))?;
+    println!("[Tasks] Cancelling {} ({})", info.id, info.label);
+    token.cancel();
+    Ok(())
+}
@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::paths;
+use crate::proxy;
+use crate::settings;
+
+// Set at build time by release builds. Without it nothing is counted or sent.
+const ENDPOINT: Option<&str> = option_env!("NOISES_TELEMETRY_URL");
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Bump when the batch shape changes.
+const SCHEMA: u32 = 1;
+
+// Strictly opt-in (the `telemetry` setting, off by default). Only counters go
+// out: generations per model, how long they took, which kinds of errors came
+// back and which features got used. No prompts, paths, file names or
+// anything that identifies the machine or the user.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Counters {
+    // By model id.
+    generations: BTreeMap<String, GenerationCounters>,
+    // By error code (see error_code).
+    errors: BTreeMap<String, u64>,
+    features: BTreeMap<String, u64>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct GenerationCounters {
+    count: u64,
+    failed: u64,
+    total_seconds: f64,
+}
+
+impl Counters {
+    fn is_empty(&self) -> bool {
+        self.generations.is_empty() && self.errors.is_empty() && self.features.is_empty()
+    }
+}
+
+#[derive(Serialize)]
+struct Batch<'a> {
+    schema: u32,
+    app_version: String,
+    os: &'static str,
+    arch: &'static str,
+    counters: &'a Counters,
+}
+
+#[derive(Default)]
+pub struct Telemetry {
+    counters: Mutex<Counters>,
+}
+
+// Counters not sent yet survive a restart here, in the app data dir.
+fn pending_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve data directory: {}", e))?;
+    Ok(dir.join("telemetry.json"))
+}
+
+fn enabled(app: &AppHandle) -> bool {
+    ENDPOINT.is_some() && settings::get(app).telemetry && !proxy::is_offline(app)
+}
+
+fn update(app: &AppHandle, f: impl FnOnce(&mut Counters)) {
+    if !enabled(app) {
+        return;
+    }
+    if let Some(telemetry) = app.try_state::<Telemetry>() {
+        f(&mut telemetry.counters.lock().unwrap());
+    }
+}
+
+// Buckets an error message into something that says nothing about the user.
+fn error_code(error: &str) -> &'static str {
+    let error = error.to_lowercase();
+    if error.contains("out of memory") {
+        "out-of-memory"
+    } else if error.contains("took too long to start") {
+        "backend-start-timeout"
+    } else if error.contains("timed out") || error.contains("timeout") {
+        "timeout"
+    } else if error.contains("backend request failed") {
+        "backend-unreachable"
+    } else if error.contains("backend error") {
+        "backend-error"
+    } else {
+        "other"
+    }
+}
+
+pub fn record_generation(app: &AppHandle, model: &str, duration: Duration, error: Option<&String>) {
+    update(app, |counters| {
+        let entry = counters.generations.entry(model.to_string()).or_default();
+        entry.count += 1;
+        entry.total_seconds += duration.as_secs_f64();
+        if let Some(error) = error {
+            entry.failed += 1;
+            *counters
+                .errors
+                .entry(error_code(error).to_string())
+                .or_default() += 1;
+        }
+    });
+}
+
+pub fn record_feature(app: &AppHandle, feature: &str) {
+    update(app, |counters| {
+        *counters.features.entry(feature.to_string()).or_default() += 1;
+    });
+}
+
+// Called from setup: picks up what the last session didn't send and starts
+// the hourly flush.
+pub fn init(app: &AppHandle) {
+    let pending: Option<Counters> = pending_path(app)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok());
+    if enabled(app)
+        && let Some(pending) = pending
+    {
+        *app.state::<Telemetry>().counters.lock().unwrap() = pending;
+    }
+    if let Ok(path) = pending_path(app) {
+        let _ = std::fs::remove_file(path);
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            if let Err(e) = flush(&app).await {
+                eprintln!("[Telemetry] {}", e);
+            }
+        }
+    });
+}
+
+async fn flush(app: &AppHandle) -> Result<(), String> {
+    let Some(endpoint) = ENDPOINT.filter(|_| enabled(app)) else {
+        return Ok(());
+    };
+    let telemetry = app.state::<Telemetry>();
+    let counters = std::mem::take(&mut *telemetry.counters.lock().unwrap());
+    if counters.is_empty() {
+        return Ok(());
+    }
+
+    let batch = Batch {
+        schema: SCHEMA,
+        app_version: app.package_info().version.to_string(),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        counters: &counters,
+    };
+    let result = async {
+        let client = proxy::client_builder(app)?
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        let res = client
+            .post(endpoint)
+            .json(&batch)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send usage counters: {}", e))?;
+        if !res.status().is_success() {
+            return Err(format!("Usage counters rejected ({})", res.status()));
+        }
+        Ok(())
+    }
+    .await;
+
+    // Put them back for the next try, merged with anything counted meanwhile.
+    if result.is_err() {
+        let mut current = telemetry.counters.lock().unwrap();
+        merge(&mut current, counters);
+    }
+    result
+}
+
+fn merge(into: &mut Counters, from: Counters) {
+    for (model, counts) in from.generations {
+        let entry = into.generations.entry(model).or_default();
+        entry.count += counts.count;
+        entry.failed += counts.failed;
+        entry.total_seconds += counts.total_seconds;
+    }
+    for (code, count) in from.errors {
+        *into.errors.entry(code).or_default() += count;
+    }
+    for (feature, count) in from.features {
+        *into.features.entry(feature).or_default() += count;
+    }
+}
+
+// On exit, so a session shorter than the flush interval still counts.
+pub fn save_pending(app: &AppHandle) -> Result<(), String> {
+    if !enabled(app) {
+        return Ok(());
+    }
+    let telemetry = app.state::<Telemetry>();
+    let counters = telemetry.counters.lock().unwrap();
+    if counters.is_empty() {
+        return Ok(());
+    }
+    let path = pending_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create data folder: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&*counters)
+        .map_err(|e| format!("Failed to serialize usage counters: {}", e))?;
+    paths::write_atomic(path, json).map_err(|e| format!("Failed to save usage counters: {}", e))
+}
+
+// Opting out drops whatever was counted but not sent.
+pub fn setting_changed(app: &AppHandle) {
+    if !settings::get(app).telemetry
+        && let Some(telemetry) = app.try_state::<Telemetry>()
+    {
+        *telemetry.counters.lock().unwrap() = Counters::default();
+        if let Ok(path) = pending_path(app) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::backend;
+use crate::history;
+use crate::paths;
+
+// A matrix multiplies out fast (4 surfaces x 3 intensities x 5 shoes); past
+// this many configs it's more likely a mistake than a batch anyone wants.
+const MAX_MATRIX: usize = 100;
+
+// A reusable prompt with {variables}, e.g. "footsteps on {surface},
+// {intensity}". Kept in templates.json in the app data dir, shared by all
+// workspaces.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub prompt: String,
+    // The rest of the request (type, length, steps, ...). {variables} work in
+    // its strings too (negative_prompt, lyrics).
+    #[serde(default)]
+    pub config: Value,
+    // Used for variables a caller leaves out.
+    #[serde(default)]
+    pub defaults: BTreeMap<String, String>,
+    // Every {variable} in the template, in order of appearance. Filled in
+    // when listing.
+    #[serde(default, skip_deserializing)]
+    pub variables: Vec<String>,
+}
+
+fn templates_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve data directory: {}", e))?;
+    Ok(dir.join("templates.json"))
+}
+
+fn load(app: &AppHandle) -> Vec<PromptTemplate> {
+    templates_path(app)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save(app: &AppHandle, templates: &[PromptTemplate]) -> Result<(), String> {
+    let path = templates_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(templates)
+        .map_err(|e| format!("Failed to serialize templates: {}", e))?;
+    paths::write_atomic(path, json).map_err(|e| format!("Failed to save templates: {}", e))
+}
+
+fn find(app: &AppHandle, id: &str) -> Result<PromptTemplate, String> {
+    load(app)
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| format!("Unknown template: {}", id))
+}
+
+// A piece of a template string: literal text or a variable.
+pub enum Part<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+}
+
+// "{{" and "}}" are a literal brace; a "{" that never closes is text.
+// suggest.rs fills its patterns with this too.
+pub fn parse(template: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find(['{', '}']) {
+        let (text, tail) = rest.split_at(open);
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            parts.push(Part::Text(&tail[..1]));
+            rest = &tail[2..];
+        } else if let Some(close) = tail.find('}')
+            && tail.starts_with('{')
+            && !tail[1..close].trim().is_empty()
+            && !tail[1..close].contains('{')
+        {
+            parts.push(Part::Variable(tail[1..close].trim()));
+            rest = &tail[close + 1..];
+        } else {
+            parts.push(Part::Text(&tail[..1]));
+            rest = &tail[1..];
+        }
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Text(rest));
+    }
+    parts
+}
+
+fn collect_variables<'a>(value: &'a Value, found: &mut Vec<&'a str>) {
+    match value {
+        Value::String(s) => {
+            for part in parse(s) {
+                if let Part::Variable(name) = part
+                    && !found.contains(&name)
+                {
+                    found.push(name);
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_variables(v, found)),
+        Value::Object(map) => map.values().for_each(|v| collect_variables(v, found)),
+        _ => {}
+    }
+}
+
+impl PromptTemplate {
+    fn variables(&self) -> Vec<String> {
+        let mut found = Vec::new();
+        let prompt = Value::String(self.prompt.clone());
+        collect_variables(&prompt, &mut found);
+        collect_variables(&self.config, &mut found);
+        found.into_iter().map(str::to_string).collect()
+    }
+
+    // One concrete config: the template's with every variable filled in and
+    // the prompt on top.
+    fn expand(&self, vars: &BTreeMap<String, String>) -> Result<Value, String> {
+        let mut config = match &self.config {
+            Value::Object(map) => map.clone(),
+            Value::Null => serde_json::Map::new(),
+            _ => return Err(format!("Template {} has a broken config", self.name)),
+        };
+        config.insert("prompt".to_string(), Value::String(self.prompt.clone()));
+        let mut config = Value::Object(config);
+        self.substitute(&mut config, vars)?;
+        backend::validate_config(&config)?;
+        Ok(config)
+    }
+
+    fn substitute(&self, value: &mut Value, vars: &BTreeMap<String, String>) -> Result<(), String> {
+        match value {
+            Value::String(s) => {
+                let mut out = String::with_capacity(s.len());
+                for part in parse(s) {
+                    match part {
+                        Part::Text(text) => out.push_str(text),
+                        Part::Variable(name) => {
+                            let value = vars
+                                .get(name)
+                                .or_else(|| self.defaults.get(name))
+                                .ok_or_else(|| format!("No value for {{{}}}", name))?;
+                            out.push_str(value);
+                        }
+                    }
+                }
+                *s = out;
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.substitute(item, vars)?;
+                }
+            }
+            Value::Object(map) => {
+                for item in map.values_mut() {
+                    self.substitute(item, vars)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // Every combination of the given values, the first variable changing
+    // slowest. Variables without a list take their default.
+    fn expand_matrix(&self, vars: &BTreeMap<String, Vec<String>>) -> Result<Vec<Value>, String> {
+        let axes: Vec<(String, &Vec<String>)> = self
+            .variables()
+            .into_iter()
+            .filter_map(|name| {
+                let values = vars.get(&name).filter(|v| !v.is_empty())?;
+                Some((name, values))
+            })
+            .collect();
+        let total = axes
+            .iter()
+            .try_fold(1usize, |n, (_, values)| n.checked_mul(values.len()))
+            .filter(|n| *n <= MAX_MATRIX)
+            .ok_or_else(|| format!("Too many combinations; at most {} at once", MAX_MATRIX))?;
+
+        let mut configs = Vec::with_capacity(total);
+        for index in 0..total {
+            let mut chosen = BTreeMap::new();
+            let mut rest = index;
+            for (name, values) in axes.iter().rev() {
+                chosen.insert(name.clone(), values[rest % values.len()].clone());
+                rest /= values.len();
+            }
+            configs.push(self.expand(&chosen)?);
+        }
+        Ok(configs)
+    }
+}
+
+#[tauri::command]
+pub fn list_templates(app: AppHandle) -> Vec<PromptTemplate> {
+    load(&app)
+        .into_iter()
+        .map(|mut t| {
+            t.variables = t.variables();
+            t
+        })
+        .collect()
+}
+
+// Adds a template, or replaces the one with the same id. Returns it with its
+// id (new templates get one) and variables.
+#[tauri::command]
+pub fn save_template(app: AppHandle, template: PromptTemplate) -> Result<PromptTemplate, String> {
+    let mut template = template;
+    if template.name.trim().is_empty() || template.prompt.trim().is_empty() {
+        return Err("A template needs a name and a prompt".to_string());
+    }
+    if !matches!(template.config, Value::Object(_) | Value::Null) {
+        return Err("A template's config has to be an object".to_string());
+    }
+    let mut templates = load(&app);
+    if template.id.is_empty() {
+        template.id = format!("template-{}", history::now_millis());
+    }
+    match templates.iter_mut().find(|t| t.id == template.id) {
+        Some(existing) => *existing = template.clone(),
+        None => templates.push(template.clone()),
+    }
+    save(&app, &templates)?;
+    template.variables = template.variables();
+    Ok(template)
+}
+
+#[tauri::command]
+pub fn delete_template(app: AppHandle, id: String) -> Result<(), String> {
+    let mut templates = load(&app);
+    let before = templates.len();
+    templates.retain(|t| t.id != id);
+    if templates.len() == before {
+        return Err(format!("Unknown template: {}", id));
+    }
+    save(&app, &templates)
+}
+
+// A config ready for generate_audio (or enqueue).
+#[tauri::command]
+pub fn expand_template(
+    app: AppHandle,
+    id: String,
+    vars: BTreeMap<String, String>,
+) -> Result<Value, String> {
+    find(&app, &id)?.expand(&vars)
+}
+
+// One config per combination: {"surface": ["gravel", "snow"], "intensity":
+// ["soft", "running"]} gives four.
+#[tauri::command]
+pub fn expand_template_matrix(
+    app: AppHandle,
+    id: String,
+    vars: BTreeMap<String, Vec<String>>,
+) -> Result<Vec<Value>, String> {
+    find(&app, &id)?.expand_matrix(&vars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Variables in angle brackets, so the test strings stay readable.
+    fn render(template: &str) -> String {
+        parse(template)
+            .into_iter()
+            .map(|part| match part {
+                Part::Text(text) => text.to_string(),
+                Part::Variable(name) => format!("<{}>", name),
+            })
+            .collect()
+    }
+
+    fn template(prompt: &str, config: Value) -> PromptTemplate {
+        PromptTemplate {
+            id: String::new(),
+            name: "test".to_string(),
+            prompt: prompt.to_string(),
+            config,
+            defaults: BTreeMap::from([("intensity".to_string(), "soft".to_string())]),
+            variables: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn parses_variables() {
+        assert_eq!(
+            render("steps on {surface}, { intensity }"),
+            "steps on <surface>, <intensity>"
+        );
+        assert_eq!(render("{a}{b}"), "<a><b>");
+        assert_eq!(render("no variables"), "no variables");
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        assert_eq!(render("{{surface}}"), "{surface}");
+        assert_eq!(render("a }} b {{"), "a } b {");
+    }
+
+    #[test]
+    fn broken_braces_are_text() {
+        assert_eq!(render("open {surface"), "open {surface");
+        assert_eq!(render("empty {} and { }"), "empty {} and { }");
+        assert_eq!(render("stray } here"), "stray } here");
+        assert_eq!(render("{outer {inner}"), "{outer <inner>");
+        assert_eq!(render("{"), "{");
+    }
+
+    #[test]
+    fn variables_come_from_prompt_and_config_once() {
+        let t = template(
+            "{surface}, {intensity}",
+            json!({ "negative_prompt": "no {surface}", "lyrics": ["{shoe}"] }),
+        );
+        assert_eq!(t.variables(), ["surface", "intensity", "shoe"]);
+    }
+
+    #[test]
+    fn expands_with_defaults() {
+        let t = template("{surface}, {intensity}", json!({ "type": "oneshot" }));
+        let vars = BTreeMap::from([("surface".to_string(), "gravel".to_string())]);
+        let config = t.expand(&vars).unwrap();
+        assert_eq!(config["prompt"], "gravel, soft");
+        assert!(
+            t.expand(&BTreeMap::new())
+                .unwrap_err()
+                .contains("{surface}")
+        );
+    }
+
+    #[test]
+    fn matrix_varies_the_first_variable_slowest() {
+        let t = template("{surface} {intensity}", json!({ "type": "oneshot" }));
+        let vars = BTreeMap::from([
+            (
+                "surface".to_string(),
+                vec!["gravel".to_string(), "snow".to_string()],
+            ),
+            (
+                "intensity".to_string(),
+                vec!["soft".to_string(), "hard".to_string()],
+            ),
+        ]);
+        let prompts: Vec<Value> = t
+            .expand_matrix(&vars)
+            .unwrap()
+            .into_iter()
+            .map(|config| config["prompt"].clone())
+            .collect();
+        assert_eq!(
+            prompts,
+            ["gravel soft", "gravel hard", "snow soft", "snow hard"]
+        );
+    }
+
+    #[test]
+    fn matrix_has_a_limit() {
+        let t = template("{a} {b} {c}", json!({ "type": "oneshot" }));
+        let many: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let vars = BTreeMap::from([
+            ("a".to_string(), many.clone()),
+            ("b".to_string(), many.clone()),
+            ("c".to_string(), many),
+        ]);
+        assert!(t.expand_matrix(&vars).is_err());
+    }
+}
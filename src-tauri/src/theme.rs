@@ -0,0 +1,93 @@
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Theme, Window, WindowEvent};
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct SystemTheme {
+    // "dark" or "light"
+    theme: &'static str,
+    // "#rrggbb", or None where the OS has no accent color we can read (Linux).
+    accent: Option<String>,
+}
+
+// The last theme sent out as `system-theme-changed`, so re-checks don't spam
+// the frontend.
+#[derive(Default)]
+pub struct ThemeState {
+    last: Mutex<Option<SystemTheme>>,
+}
+
+fn read(window: &Window, theme: Option<Theme>) -> SystemTheme {
+    let theme = theme.or_else(|| window.theme().ok());
+    SystemTheme {
+        theme: match theme {
+            Some(Theme::Light) => "light",
+            _ => "dark",
+        },
+        accent: accent_color(),
+    }
+}
+
+#[tauri::command]
+pub fn get_system_theme(window: Window, state: tauri::State<'_, ThemeState>) -> SystemTheme {
+    let current = read(&window, None);
+    *state.last.lock().unwrap() = Some(current.clone());
+    current
+}
+
+// Hooked up in main.rs via on_window_event. Dark/light switches arrive as
+// ThemeChanged; accent color changes don't have an event we get to see, so
+// they're picked up the next time a window gains focus (i.e. when the user
+// comes back from the settings app).
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    let theme = match event {
+        WindowEvent::ThemeChanged(theme) => Some(*theme),
+        WindowEvent::Focused(true) => None,
+        _ => return,
+    };
+    let current = read(window, theme);
+    notify(window.app_handle(), current);
+}
+
+fn notify(app: &AppHandle, current: SystemTheme) {
+    let Some(state) = app.try_state::<ThemeState>() else {
+        return;
+    };
+    let mut last = state.last.lock().unwrap();
+    if last.as_ref() == Some(&current) {
+        return;
+    }
+    *last = Some(current.clone());
+    let _ = app.emit("system-theme-changed", current);
+}
+
+#[cfg(target_os = "windows")]
+fn accent_color() -> Option<String> {
+    use windows::UI::ViewManagement::{UIColorType, UISettings};
+
+    let color = UISettings::new()
+        .and_then(|settings| settings.GetColorValue(UIColorType::Accent))
+        .ok()?;
+    Some(format!("#{:02x}{:02x}{:02x}", color.R, color.G, color.B))
+}
+
+#[cfg(target_os = "macos")]
+fn accent_color() -> Option<String> {
+    use objc2_app_kit::{NSColor, NSColorSpace};
+
+    let color =
+        NSColor::controlAccentColor().colorUsingColorSpace(&NSColorSpace::sRGBColorSpace())?;
+    let channel = |c: f64| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Some(format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.redComponent()),
+        channel(color.greenComponent()),
+        channel(color.blueComponent())
+    ))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn accent_color() -> Option<String> {
+    None
+}
@@ -0,0 +1,114 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::gpu;
+use crate::settings;
+
+// Once paused, the queue waits until the GPU is this far under the limit, so
+// it doesn't flip on and off around it.
+const COOLDOWN: u32 = 10;
+
+// How often a paused queue looks at the temperature again.
+pub const POLL: Duration = Duration::from_secs(15);
+
+// Whether the queue is paused for heat, shared by every waiting job.
+static HOT: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Serialize)]
+struct ThermalStatus {
+    paused: bool,
+    temperature: Option<u32>,
+    limit: Option<u32>,
+}
+
+// Hot from the limit up; once hot, until more than COOLDOWN degrees under it.
+// No limit or no reading is never hot.
+fn next_hot(was_hot: bool, limit: Option<u32>, temperature: Option<u32>) -> bool {
+    match (limit, temperature) {
+        (Some(limit), Some(t)) if was_hot => t > limit.saturating_sub(COOLDOWN),
+        (Some(limit), Some(t)) => t >= limit,
+        _ => false,
+    }
+}
+
+// With a thermal limit set, true while new jobs should be held back: from
+// when the GPU reaches the limit until it's COOLDOWN degrees under it.
+// Pausing and resuming are announced as `thermal-status`. Without NVML (no
+// NVIDIA GPU) there's nothing to read and the queue runs as usual.
+pub fn too_hot(app: &AppHandle) -> bool {
+    let settings = settings::get(app);
+    let limit = settings.thermal_limit;
+    let temperature = limit
+        .and(settings.compute_device.gpu_index())
+        .and_then(gpu::temperature);
+    let was_hot = HOT.load(Ordering::SeqCst);
+    let hot = next_hot(was_hot, limit, temperature);
+    if hot != was_hot {
+        HOT.store(hot, Ordering::SeqCst);
+        match (hot, temperature) {
+            (true, Some(t)) => println!("[Thermal] GPU at {} °C, pausing the queue", t),
+            (false, Some(t)) => println!("[Thermal] GPU down to {} °C, resuming the queue", t),
+            _ => println!("[Thermal] Resuming the queue"),
+        }
+        let _ = app.emit(
+            "thermal-status",
+            ThermalStatus {
+                paused: hot,
+                temperature,
+                limit,
+            },
+        );
+    }
+    hot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pauses_at_the_limit() {
+        assert!(!next_hot(false, Some(80), Some(79)));
+        assert!(next_hot(false, Some(80), Some(80)));
+        assert!(next_hot(false, Some(80), Some(95)));
+    }
+
+    #[test]
+    fn stays_paused_until_cooled_down() {
+        assert!(next_hot(true, Some(80), Some(80)));
+        assert!(next_hot(true, Some(80), Some(79)));
+        assert!(next_hot(true, Some(80), Some(71)));
+        assert!(!next_hot(true, Some(80), Some(70)));
+        assert!(!next_hot(true, Some(80), Some(40)));
+    }
+
+    // Between the two thresholds it keeps whatever it was, both ways.
+    #[test]
+    fn holds_between_the_thresholds() {
+        let readings = [75, 80, 75, 71, 70, 75, 79, 80];
+        let states: Vec<bool> = readings
+            .iter()
+            .scan(false, |hot, &t| {
+                *hot = next_hot(*hot, Some(80), Some(t));
+                Some(*hot)
+            })
+            .collect();
+        assert_eq!(states, [false, true, true, true, false, false, false, true]);
+    }
+
+    #[test]
+    fn low_limits_resume_at_zero() {
+        assert!(next_hot(true, Some(5), Some(1)));
+        assert!(!next_hot(true, Some(5), Some(0)));
+    }
+
+    #[test]
+    fn needs_a_limit_and_a_reading() {
+        assert!(!next_hot(false, None, Some(100)));
+        assert!(!next_hot(true, None, Some(100)));
+        assert!(!next_hot(true, Some(80), None));
+    }
+}
@@ -0,0 +1,159 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::file_lock;
+use crate::filenames;
+use crate::history;
+use crate::paths;
+
+// Deleted files are moved to a holding folder instead of removed, so a
+// slip can be undone. Only the last few are kept; the folder is emptied at
+// startup, so nothing is held past the session.
+const MAX_HELD: usize = 20;
+
+struct Held {
+    id: String,
+    original: PathBuf,
+    held: PathBuf,
+}
+
+#[derive(Default)]
+pub struct Trash {
+    // Oldest first.
+    held: Mutex<Vec<Held>>,
+}
+
+#[derive(Clone, Serialize)]
+struct UndoAvailable {
+    id: String,
+    file: String,
+    path: String,
+}
+
+fn trash_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let cache = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to resolve cache directory: {}", e))?;
+    Ok(cache.join("trash"))
+}
+
+pub fn clear(app: &AppHandle) {
+    if let Ok(dir) = trash_dir(app) {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
+
+// rename() where it can; across volumes (the cache dir vs. a library on
+// another drive) a copy and a delete, the delete being what fails while
+// another program has the file open. The copy of a long recording takes a
+// while, so it all runs on the blocking pool.
+async fn move_across(from: &Path, to: &Path) -> std::io::Result<()> {
+    let (from, to) = (from.to_path_buf(), to.to_path_buf());
+    tauri::async_runtime::spawn_blocking(move || move_across_blocking(&from, &to))
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+fn move_across_blocking(from: &Path, to: &Path) -> std::io::Result<()> {
+    match std::fs::rename(from, to) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            paths::copy_atomic(from, to)?;
+            std::fs::remove_file(from).inspect_err(|_| {
+                let _ = std::fs::remove_file(to);
+            })
+        }
+        result => result,
+    }
+}
+
+// Moves the file to the holding folder and announces it as `undo-available`
+// with the id restore_deleted takes.
+pub async fn delete(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let dir = trash_dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create trash folder: {}", e))?;
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let id = format!(
+        "deleted-{}-{}",
+        history::now_millis(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    let file = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let held = dir.join(format!("{}-{}", id, file));
+    file_lock::retry_while_in_use(path, "delete", || move_across(path, &held)).await?;
+
+    let dropped = {
+        let trash = app.state::<Trash>();
+        let mut items = trash.held.lock().unwrap();
+        items.push(Held {
+            id: id.clone(),
+            original: path.to_path_buf(),
+            held,
+        });
+        let excess = items.len().saturating_sub(MAX_HELD);
+        items.drain(..excess).collect::<Vec<_>>()
+    };
+    for item in dropped {
+        let _ = std::fs::remove_file(item.held);
+    }
+    let _ = app.emit(
+        "undo-available",
+        UndoAvailable {
+            id,
+            file,
+            path: path.to_string_lossy().into_owned(),
+        },
+    );
+    Ok(())
+}
+
+// Puts a deleted file back where it was (with a -1 suffix if that name has
+// been taken since) and returns its path.
+#[tauri::command]
+pub async fn restore_deleted(
+    app: AppHandle,
+    id: String,
+    trash: State<'_, Trash>,
+) -> Result<String, String> {
+    let item = {
+        let mut items = trash.held.lock().unwrap();
+        let index = items
+            .iter()
+            .position(|item| item.id == id)
+            .ok_or("That file can't be restored any more")?;
+        items.remove(index)
+    };
+    let dir = item.original.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+    let target = if item.original.exists() {
+        let stem = item
+            .original
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let extension = item
+            .original
+            .extension()
+            .unwrap_or_default()
+            .to_string_lossy();
+        filenames::unique(dir, &stem, &extension)
+    } else {
+        item.original.clone()
+    };
+    if let Err(e) = move_across(&item.held, &target).await {
+        trash.held.lock().unwrap().push(item);
+        return Err(format!("Failed to restore file: {}", e));
+    }
+    println!("[Trash] Restored {}", target.display());
+    let path = target.to_string_lossy().into_owned();
+    let _ = app.emit("sound-restored", &path);
+    Ok(path)
+}
@@ -0,0 +1,227 @@
+use std::path::Path;
+
+use serde_json::json;
+use tauri::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Window, WindowEvent, Wry};
+
+use crate::autostart;
+use crate::clipboard;
+use crate::jobs::{JobManager, QueueStatus};
+use crate::library_window;
+use crate::mini_player;
+use crate::playback::PlaybackEngine;
+use crate::settings;
+
+const TRAY_ID: &str = "main";
+
+// Menu item ids. Recent outputs are "recent:<path>".
+const GENERATE_CLIPBOARD: &str = "generate-clipboard";
+const IMPORT_CLIPBOARD: &str = "import-clipboard";
+const PLAY_LAST: &str = "play-last";
+const SHOW: &str = "show";
+const MINI_PLAYER: &str = "mini-player";
+const LIBRARY: &str = "library";
+const CLOSE_TO_TRAY: &str = "close-to-tray";
+const START_AT_LOGIN: &str = "start-at-login";
+const QUIT: &str = "quit";
+const RECENT_PREFIX: &str = "recent:";
+
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let status = app.state::<JobManager>().status();
+    let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(tooltip(&status))
+        .menu(&build_menu(app, &status)?)
+        .show_menu_on_left_click(true)
+        .on_menu_event(on_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+    Ok(())
+}
+
+// Rebuilt whenever the job list changes; the menu is small enough that this is cheap.
+pub fn refresh(app: &AppHandle, status: &QueueStatus) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    match build_menu(app, status) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => eprintln!("[Tray] Failed to rebuild menu: {}", e),
+    }
+    let _ = tray.set_tooltip(Some(tooltip(status)));
+}
+
+// Hooked up in main.rs via on_window_event.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    if let WindowEvent::CloseRequested { api, .. } = event
+        && window.label() == "main"
+        && closes_to_tray(window.app_handle())
+    {
+        api.prevent_close();
+        let _ = window.hide();
+        println!("[Tray] Window hidden, still running in the tray");
+    }
+}
+
+// Whether closing the main window hides it instead of quitting. The backend
+// and any running generations keep going; "Quit" in the tray really exits.
+pub fn closes_to_tray(app: &AppHandle) -> bool {
+    settings::get(app).close_to_tray
+}
+
+#[tauri::command]
+pub fn set_close_to_tray(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set(&app, "close_to_tray", json!(enabled))
+}
+
+fn tooltip(status: &QueueStatus) -> String {
+    match status.active.as_slice() {
+        [] => "Noises".to_string(),
+        [job] => format!("Noises - generating \"{}\"", truncate(&job.prompt, 40)),
+        jobs => format!("Noises - generating {} sounds", jobs.len()),
+    }
+}
+
+fn build_menu(app: &AppHandle, status: &QueueStatus) -> tauri::Result<Menu<Wry>> {
+    let queue_label = match status.active.as_slice() {
+        [] => "Idle".to_string(),
+        [job] => format!("Generating: {}", truncate(&job.prompt, 40)),
+        jobs => format!("Generating {} sounds", jobs.len()),
+    };
+    let queue = MenuItem::with_id(app, "queue-status", queue_label, false, None::<&str>)?;
+    let close_to_tray = closes_to_tray(app);
+
+    let recent = Submenu::with_id(app, "recent", "Recent", !status.recent.is_empty())?;
+    for path in &status.recent {
+        let name = Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.clone());
+        recent.append(&MenuItem::with_id(
+            app,
+            format!("{}{}", RECENT_PREFIX, path),
+            name,
+            true,
+            None::<&str>,
+        )?)?;
+    }
+
+    Menu::with_items(
+        app,
+        &[
+            &MenuItem::with_id(
+                app,
+                GENERATE_CLIPBOARD,
+                "Generate from clipboard prompt",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app,
+                IMPORT_CLIPBOARD,
+                "Import copied files",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app,
+                PLAY_LAST,
+                "Play last result",
+                !status.recent.is_empty(),
+                None::<&str>,
+            )?,
+            &recent,
+            &PredefinedMenuItem::separator(app)?,
+            &queue,
+            &PredefinedMenuItem::separator(app)?,
+            &CheckMenuItem::with_id(
+                app,
+                CLOSE_TO_TRAY,
+                "Keep running when closed",
+                true,
+                close_to_tray,
+                None::<&str>,
+            )?,
+            &CheckMenuItem::with_id(
+                app,
+                START_AT_LOGIN,
+                "Start at login",
+                true,
+                autostart::is_enabled(app),
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(app, MINI_PLAYER, "Mini player", true, None::<&str>)?,
+            &MenuItem::with_id(app, LIBRARY, "Library", true, None::<&str>)?,
+            &MenuItem::with_id(app, SHOW, "Show Noises", true, None::<&str>)?,
+            &MenuItem::with_id(app, QUIT, "Quit", true, None::<&str>)?,
+        ],
+    )
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    let id = event.id().as_ref();
+    match id {
+        GENERATE_CLIPBOARD => clipboard::generate_from_clipboard(app),
+        IMPORT_CLIPBOARD => clipboard::import_from_clipboard(app),
+        PLAY_LAST => {
+            if let Some(path) = app.state::<JobManager>().last_output() {
+                play(app, path);
+            }
+        }
+        CLOSE_TO_TRAY => {
+            // The check mark has already toggled itself; just mirror it.
+            if let Err(e) = settings::set(app, "close_to_tray", json!(!closes_to_tray(app))) {
+                eprintln!("[Tray] {}", e);
+            }
+        }
+        START_AT_LOGIN => autostart::toggle(app),
+        MINI_PLAYER => {
+            if let Err(e) = mini_player::open(app) {
+                eprintln!("[Tray] {}", e);
+            }
+        }
+        LIBRARY => {
+            if let Err(e) = library_window::open(app) {
+                eprintln!("[Tray] {}", e);
+            }
+        }
+        SHOW => show_main_window(app),
+        // The Exit handler in main.rs shuts the backend down.
+        QUIT => app.exit(0),
+        _ => {
+            if let Some(path) = id.strip_prefix(RECENT_PREFIX) {
+                play(app, path.to_string());
+            }
+        }
+    }
+}
+
+pub fn play(app: &AppHandle, path: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = app.state::<PlaybackEngine>().play(path).await {
+            eprintln!("[Tray] Playback failed: {}", e);
+        }
+    });
+}
+
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(max).collect();
+    short.push('…');
+    short
+}
@@ -0,0 +1,165 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Emitter, Manager, Runtime, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use crate::jobs::JobManager;
+use crate::proxy;
+use crate::settings;
+use crate::sidecar;
+use crate::window_state;
+
+// Stable builds are the "latest" GitHub release; betas are prereleases that
+// also upload their manifest to the rolling `beta` release.
+const STABLE_ENDPOINT: &str =
+    "https://github.com/callei/Noises/releases/latest/download/latest.json";
+const BETA_ENDPOINT: &str = "https://github.com/callei/Noises/releases/download/beta/latest.json";
+
+// The minisign public key releases are checked against, baked in by a
+// release build (see the README). Builds without one don't update.
+const PUBKEY: Option<&str> = option_env!("NOISES_UPDATER_PUBKEY");
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl Channel {
+    fn endpoint(self) -> &'static str {
+        match self {
+            Channel::Stable => STABLE_ENDPOINT,
+            Channel::Beta => BETA_ENDPOINT,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct UpdateInfo {
+    version: String,
+    current_version: String,
+    notes: Option<String>,
+    // RFC 3339, when the release says.
+    date: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct DownloadProgress {
+    downloaded: u64,
+    // None when the server doesn't send a length.
+    total: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct UpdaterState {
+    // The update found by the last check, waiting for install_update.
+    pending: Mutex<Option<Update>>,
+}
+
+pub fn plugin<R: Runtime>() -> TauriPlugin<R, tauri_plugin_updater::Config> {
+    let builder = tauri_plugin_updater::Builder::new();
+    match PUBKEY {
+        Some(pubkey) => builder.pubkey(pubkey),
+        None => builder,
+    }
+    .build()
+}
+
+#[tauri::command]
+pub async fn check_for_updates(app: AppHandle) -> Result<Option<UpdateInfo>, String> {
+    if PUBKEY.is_none() {
+        println!("[Updater] Built without an update key, not checking");
+        return Ok(None);
+    }
+    let state = app.state::<UpdaterState>();
+    let channel = settings::get(&app).update_channel;
+    let endpoint =
+        Url::parse(channel.endpoint()).map_err(|e| format!("Invalid update endpoint: {}", e))?;
+    let update = proxy::apply_to_updater(&app, app.updater_builder())?
+        .endpoints(vec![endpoint])
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Failed to set up updater: {}", e))?
+        .check()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+
+    let info = update.as_ref().map(|update| UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        notes: update.body.clone(),
+        date: update.date.and_then(|date| {
+            date.format(&time::format_description::well_known::Rfc3339)
+                .ok()
+        }),
+    });
+    match &info {
+        Some(info) => println!("[Updater] {} available", info.version),
+        None => println!("[Updater] Up to date"),
+    }
+    *state.pending.lock().unwrap() = update;
+    Ok(info)
+}
+
+// Downloads the update found by check_for_updates, reporting
+// `update-download-progress` along the way, then shuts the backend down so the
+// installer can replace it and restarts into the new version.
+#[tauri::command]
+pub async fn install_update(app: AppHandle) -> Result<(), String> {
+    if !app.state::<JobManager>().status().active.is_empty() {
+        return Err("Wait for the running generations to finish first".to_string());
+    }
+    let update = app
+        .state::<UpdaterState>()
+        .pending
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or("No update to install, check for updates first")?;
+
+    println!("[Updater] Downloading {}", update.version);
+    let mut downloaded = 0u64;
+    let mut last_percent = None;
+    let bytes = update
+        .download(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                // One event per percent is plenty for a progress bar.
+                let percent = total.map(|total| downloaded * 100 / total.max(1));
+                if percent.is_some() && percent == last_percent {
+                    return;
+                }
+                last_percent = percent;
+                let _ = app.emit(
+                    "update-download-progress",
+                    DownloadProgress { downloaded, total },
+                );
+            },
+            || println!("[Updater] Download finished"),
+        )
+        .await
+        .map_err(|e| format!("Failed to download update: {}", e))?;
+    let _ = app.emit("update-downloaded", ());
+
+    // The installer can't overwrite the backend binary while it's running.
+    sidecar::shut_down(&app).await;
+    // On Windows the installer takes over and ends the process without the
+    // Exit handler running, so save what it would have.
+    if let Err(e) = window_state::save(&app) {
+        eprintln!("[Updater] Failed to save window state: {}", e);
+    }
+
+    println!("[Updater] Installing {}", update.version);
+    update
+        .install(bytes)
+        .map_err(|e| format!("Failed to install update: {}", e))?;
+    app.restart()
+}
+
+// Called by settings.rs: an update found on the old channel is dropped.
+pub fn channel_changed(app: &AppHandle) {
+    *app.state::<UpdaterState>().pending.lock().unwrap() = None;
+}
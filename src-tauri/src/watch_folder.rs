@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use serde_json::{Value, json};
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+
+use crate::import;
+use crate::jobs;
+use crate::models;
+use crate::paths;
+use crate::settings;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+// A file still being written shouldn't be picked up half done.
+const SETTLE_TIME: Duration = Duration::from_secs(1);
+
+// A folder (the `watch_folder` setting) where dropping a prompt file queues a
+// generation, for build pipelines and scripts that can't do more than write
+// files:
+//
+//   rain.txt    the whole file is the prompt
+//   rain.json   an object with the generate_audio fields ({"prompt": ...,
+//               "type": ..., "length": ..., "seed": ...})
+//
+// The result lands next to it as rain.wav (rain_2.wav, ... for variations);
+// a failure is written to rain.error.log instead. A prompt file counts as
+// done once either is newer than it, so editing it generates again.
+#[derive(Default)]
+pub struct WatchFolder {
+    running: Mutex<Option<CancellationToken>>,
+}
+
+// Called from setup and whenever watch_folder changes.
+pub fn restart(app: &AppHandle) {
+    let watch = app.state::<WatchFolder>();
+    if let Some(running) = watch.running.lock().unwrap().take() {
+        running.cancel();
+    }
+    let Some(dir) = settings::get(app).watch_folder else {
+        return;
+    };
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        eprintln!("[WatchFolder] Failed to create {}: {}", dir.display(), e);
+        return;
+    }
+
+    let cancel = CancellationToken::new();
+    *watch.running.lock().unwrap() = Some(cancel.clone());
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        println!("[WatchFolder] Watching {}", dir.display());
+        let busy: Arc<Mutex<HashSet<PathBuf>>> = Arc::default();
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            }
+            for path in pending(&dir) {
+                if !busy.lock().unwrap().insert(path.clone()) {
+                    continue;
+                }
+                let app = app.clone();
+                let busy = busy.clone();
+                tauri::async_runtime::spawn(async move {
+                    process(&app, &path).await;
+                    busy.lock().unwrap().remove(&path);
+                });
+            }
+        }
+        println!("[WatchFolder] Stopped");
+    });
+}
+
+fn is_prompt_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("txt") || ext.eq_ignore_ascii_case("json"))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn error_log(path: &Path) -> PathBuf {
+    path.with_extension("error.log")
+}
+
+// Prompt files that have settled and have no result (or error) newer than
+// themselves.
+fn pending(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let files: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    let now = SystemTime::now();
+    files
+        .iter()
+        .filter(|path| path.is_file() && is_prompt_file(path))
+        .filter(|path| {
+            let Some(written) = modified(path) else {
+                return false;
+            };
+            if now.duration_since(written).unwrap_or_default() < SETTLE_TIME {
+                return false;
+            }
+            let stem = path.file_stem();
+            let newer = |other: &Path| modified(other).is_some_and(|t| t >= written);
+            let has_result = files.iter().any(|other| {
+                other.file_stem() == stem && import::is_audio_file(other) && newer(other)
+            });
+            !has_result && !newer(&error_log(path))
+        })
+        .cloned()
+        .collect()
+}
+
+fn read_prompt(app: &AppHandle, path: &Path) -> Result<Value, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let is_json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let mut config = if is_json {
+        match serde_json::from_str(&contents) {
+            Ok(config @ Value::Object(_)) => config,
+            Ok(_) => return Err("Expected a JSON object".to_string()),
+            Err(e) => return Err(format!("Not valid JSON: {}", e)),
+        }
+    } else {
+        json!({ "prompt": contents.trim() })
+    };
+
+    if config
+        .get("prompt")
+        .and_then(|p| p.as_str())
+        .is_none_or(|p| p.trim().is_empty())
+    {
+        return Err("The prompt is empty".to_string());
+    }
+    if config.get("type").is_none() {
+        config["type"] = json!(models::default_generation_type(app));
+    }
+    if config.get("variations").is_none() {
+        config["variations"] = json!(1);
+    }
+    Ok(config)
+}
+
+async fn process(app: &AppHandle, path: &Path) {
+    println!("[WatchFolder] Generating from {}", path.display());
+    let result = async {
+        let config = read_prompt(app, path)?;
+        let result = jobs::generate(app, config).await?;
+        place_results(path, &result)
+    }
+    .await;
+
+    match result {
+        Ok(files) => {
+            let _ = std::fs::remove_file(error_log(path));
+            for file in files {
+                println!("[WatchFolder] Wrote {}", file.display());
+            }
+        }
+        Err(e) => {
+            eprintln!("[WatchFolder] {}: {}", path.display(), e);
+            if let Err(e) = paths::write_atomic(error_log(path), format!("{}\n", e)) {
+                eprintln!("[WatchFolder] Failed to write the error log: {}", e);
+            }
+        }
+    }
+}
+
+// Copies the outputs next to the prompt file, named after it. The library
+// keeps its copy, like a generation from the app.
+fn place_results(path: &Path, result: &Value) -> Result<Vec<PathBuf>, String> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let outputs = result
+        .get("files")
+        .and_then(|f| f.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let mut placed = Vec::new();
+    for (i, file) in outputs.iter().enumerate() {
+        let Some(output) = file.get("path").and_then(|p| p.as_str()).map(Path::new) else {
+            continue;
+        };
+        let extension = output
+            .extension()
+            .map(|ext| ext.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "wav".to_string());
+        let name = match i {
+            0 => format!("{}.{}", stem, extension),
+            _ => format!("{}_{}.{}", stem, i + 1, extension),
+        };
+        let target = dir.join(name);
+        paths::copy_atomic(output, &target)
+            .map_err(|e| format!("Failed to copy to {}: {}", target.display(), e))?;
+        placed.push(target);
+    }
+    if placed.is_empty() {
+        return Err("The backend returned no files".to_string());
+    }
+    Ok(placed)
+}
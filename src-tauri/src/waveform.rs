@@ -0,0 +1,436 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use memmap2::Mmap;
+use rodio::{Decoder, Source};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::oneshot;
+
+use crate::paths;
+
+// We first reduce the decoded audio to min/max per block of this many frames,
+// then resample that to whatever resolution was asked for. Keeps memory flat
+// regardless of file length.
+const BLOCK_FRAMES: usize = 1024;
+
+// Past this many blocks (about 12 minutes at 44.1 kHz) neighbouring blocks are
+// merged and the block size doubles, so an hour-long render costs the same
+// memory as a short one. Still 4x the most buckets anyone can ask for.
+const MAX_BLOCKS: usize = 4 * MAX_BUCKETS;
+
+// For files that can't be mapped (see AudioReader): big reads keep decoding
+// cheap on slow disks without holding more than this much of the file.
+const READ_BUFFER: usize = 1024 * 1024;
+
+// Decoding is CPU-bound, so a few threads at most; the UI stays responsive
+// and a library full of new files doesn't pin every core.
+const MAX_WORKERS: usize = 4;
+
+pub const DEFAULT_BUCKETS: usize = 1024;
+pub const MAX_BUCKETS: usize = 8192;
+
+// Same purple as the UI's primary colour.
+pub const WAVE_COLOR: [u8; 4] = [168, 85, 247, 255];
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Peaks {
+    pub duration: f64,
+    pub sample_rate: u32,
+    pub channels: u16,
+    // One entry per bucket, mono mix, -1.0..=1.0.
+    pub min: Vec<f32>,
+    pub max: Vec<f32>,
+}
+
+// Key for the on-disk caches. A changed file (size or mtime) gets a new key,
+// and the key doubles as the HTTP ETag.
+pub fn cache_key(path: &Path, variant: &str) -> Result<String, String> {
+    let meta = std::fs::metadata(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    meta.len().hash(&mut hasher);
+    meta.modified().ok().hash(&mut hasher);
+    variant.hash(&mut hasher);
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+fn cache_dir(app: &AppHandle, kind: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to resolve cache directory: {}", e))?
+        .join(kind);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create cache folder: {}", e))?;
+    Ok(dir)
+}
+
+// Peaks JSON for a file, computed once and then served from the cache.
+pub fn peaks_json(
+    app: &AppHandle,
+    path: &Path,
+    buckets: usize,
+) -> Result<(String, Vec<u8>), String> {
+    let key = cache_key(path, &format!("peaks:{}", buckets))?;
+    let cached = cache_dir(app, "peaks")?.join(format!("{}.json", key));
+    if let Ok(bytes) = std::fs::read(&cached) {
+        return Ok((key, bytes));
+    }
+
+    let peaks = compute_peaks(path, buckets)?;
+    let bytes = serde_json::to_vec(&peaks).map_err(|e| format!("Failed to encode peaks: {}", e))?;
+    let _ = paths::write_atomic(&cached, &bytes);
+    Ok((key, bytes))
+}
+
+// Same cache, decoded, for callers that draw the waveform themselves.
+pub fn cached_peaks(app: &AppHandle, path: &Path, buckets: usize) -> Result<Peaks, String> {
+    let (_, bytes) = peaks_json(app, path, buckets)?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("Failed to read cached peaks: {}", e))
+}
+
+// PNG waveform thumbnail, cached the same way.
+pub fn thumbnail_png(
+    app: &AppHandle,
+    path: &Path,
+    width: u32,
+    height: u32,
+) -> Result<(String, Vec<u8>), String> {
+    let key = cache_key(path, &format!("thumb:{}x{}", width, height))?;
+    let cached = cache_dir(app, "thumbs")?.join(format!("{}.png", key));
+    if let Ok(bytes) = std::fs::read(&cached) {
+        return Ok((key, bytes));
+    }
+
+    let peaks = compute_peaks(path, width as usize)?;
+    let bytes = render_png(&peaks, width, height)?;
+    let _ = paths::write_atomic(&cached, &bytes);
+    Ok((key, bytes))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Render {
+    Peaks { buckets: usize },
+    Thumbnail { width: u32, height: u32 },
+}
+
+type Rendered = Result<(String, Vec<u8>), String>;
+
+impl Render {
+    fn run(self, app: &AppHandle, path: &Path) -> Rendered {
+        match self {
+            Render::Peaks { buckets } => peaks_json(app, path, buckets),
+            Render::Thumbnail { width, height } => thumbnail_png(app, path, width, height),
+        }
+    }
+
+    // Only what's already on disk; None means it has to be rendered.
+    fn cached(self, app: &AppHandle, path: &Path) -> Option<(String, Vec<u8>)> {
+        let (kind, variant, extension) = match self {
+            Render::Peaks { buckets } => ("peaks", format!("peaks:{}", buckets), "json"),
+            Render::Thumbnail { width, height } => {
+                ("thumbs", format!("thumb:{}x{}", width, height), "png")
+            }
+        };
+        let key = cache_key(path, &variant).ok()?;
+        let bytes = std::fs::read(
+            cache_dir(app, kind)
+                .ok()?
+                .join(format!("{}.{}", key, extension)),
+        )
+        .ok()?;
+        Some((key, bytes))
+    }
+}
+
+struct Task {
+    path: PathBuf,
+    render: Render,
+    // Everyone who asked for this while it was queued.
+    waiters: Vec<oneshot::Sender<Rendered>>,
+}
+
+#[derive(Default)]
+struct Queue {
+    tasks: VecDeque<Task>,
+    // The frontend's viewport hint (set_visible_sounds); these jump the queue.
+    visible: HashSet<PathBuf>,
+    workers: usize,
+}
+
+// Peaks and thumbnails that aren't cached yet are rendered here, on a few
+// threads of our own, rather than on the runtime or one blocking thread per
+// request. Sounds on screen go first, then the order they were asked for.
+#[derive(Default)]
+pub struct WaveformWorkers {
+    queue: Mutex<Queue>,
+}
+
+impl WaveformWorkers {
+    pub async fn render(&self, app: &AppHandle, path: PathBuf, render: Render) -> Rendered {
+        // Cache hits don't wait behind files that still need decoding.
+        let lookup = {
+            let (app, path) = (app.clone(), path.clone());
+            tauri::async_runtime::spawn_blocking(move || render.cached(&app, &path))
+        };
+        if let Ok(Some(hit)) = lookup.await {
+            return Ok(hit);
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let spawn_worker = {
+            let mut queue = self.queue.lock().unwrap();
+            match queue
+                .tasks
+                .iter_mut()
+                .find(|t| t.path == path && t.render == render)
+            {
+                Some(task) => task.waiters.push(tx),
+                None => queue.tasks.push_back(Task {
+                    path,
+                    render,
+                    waiters: vec![tx],
+                }),
+            }
+            let spawn = queue.workers < MAX_WORKERS.min(queue.tasks.len());
+            if spawn {
+                queue.workers += 1;
+            }
+            spawn
+        };
+        if spawn_worker {
+            let app = app.clone();
+            std::thread::spawn(move || work(&app));
+        }
+        rx.await
+            .map_err(|_| "Waveform worker stopped".to_string())?
+    }
+
+    fn next(&self) -> Option<Task> {
+        let mut queue = self.queue.lock().unwrap();
+        let at = queue
+            .tasks
+            .iter()
+            .position(|t| queue.visible.contains(&t.path))
+            .unwrap_or(0);
+        let task = queue.tasks.remove(at);
+        if task.is_none() {
+            queue.workers -= 1;
+        }
+        task
+    }
+}
+
+// Runs until the queue is empty; render() starts more as work comes in.
+fn work(app: &AppHandle) {
+    let workers = app.state::<WaveformWorkers>();
+    while let Some(task) = workers.next() {
+        let result = task.render.run(app, &task.path);
+        for waiter in task.waiters {
+            let _ = waiter.send(result.clone());
+        }
+    }
+}
+
+// What the library has on screen right now, so those waveforms render first.
+// Paths that aren't queued are just remembered for when they are.
+#[tauri::command]
+pub fn set_visible_sounds(workers: State<'_, WaveformWorkers>, paths: Vec<String>) {
+    let mut queue = workers.queue.lock().unwrap();
+    queue.visible = paths
+        .into_iter()
+        .map(|p| {
+            let path = paths::normalize(&p);
+            paths::canonical(&path).unwrap_or(path)
+        })
+        .collect();
+}
+
+// An audio file for the decoder, memory-mapped so reading an hour-long
+// render goes through pages the OS loads and drops as it likes rather than
+// through our own buffers. A file that can't be mapped (some network
+// drives) is read the usual way.
+pub enum AudioReader {
+    Mapped(Cursor<Mmap>),
+    Buffered(BufReader<File>),
+}
+
+impl AudioReader {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the map is read-only and the app only ever replaces library
+        // files by renaming over them, which leaves this one's pages alone.
+        // Another program truncating it mid-decode would fault, as it would
+        // for any reader of a mapped file.
+        match unsafe { Mmap::map(&file) } {
+            Ok(map) => Ok(AudioReader::Mapped(Cursor::new(map))),
+            Err(_) => Ok(AudioReader::Buffered(BufReader::with_capacity(
+                READ_BUFFER,
+                file,
+            ))),
+        }
+    }
+}
+
+impl Read for AudioReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            AudioReader::Mapped(map) => map.read(buf),
+            AudioReader::Buffered(file) => file.read(buf),
+        }
+    }
+}
+
+impl Seek for AudioReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        match self {
+            AudioReader::Mapped(map) => map.seek(pos),
+            AudioReader::Buffered(file) => file.seek(pos),
+        }
+    }
+}
+
+pub fn compute_peaks(path: &Path, buckets: usize) -> Result<Peaks, String> {
+    let reader =
+        AudioReader::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+    let source = Decoder::new(reader)
+        .map_err(|e| format!("Failed to decode audio file: {}", e))?
+        .convert_samples::<f32>();
+
+    let channels = source.channels().max(1);
+    let sample_rate = source.sample_rate();
+
+    // Pass 1: stream through the file keeping only per-block min/max.
+    let mut blocks_min = Vec::new();
+    let mut blocks_max = Vec::new();
+    let (mut lo, mut hi) = (f32::MAX, f32::MIN);
+    let mut frame_sum = 0.0f32;
+    let mut channel = 0u16;
+    let mut frames_in_block = 0usize;
+    let mut block_frames = BLOCK_FRAMES;
+    let mut total_frames = 0usize;
+
+    for sample in source {
+        frame_sum += sample;
+        channel += 1;
+        if channel < channels {
+            continue;
+        }
+        let mono = frame_sum / channels as f32;
+        frame_sum = 0.0;
+        channel = 0;
+
+        lo = lo.min(mono);
+        hi = hi.max(mono);
+        frames_in_block += 1;
+        total_frames += 1;
+        if frames_in_block == block_frames {
+            blocks_min.push(lo);
+            blocks_max.push(hi);
+            (lo, hi) = (f32::MAX, f32::MIN);
+            frames_in_block = 0;
+            if blocks_min.len() == MAX_BLOCKS {
+                merge_pairs(&mut blocks_min, f32::min);
+                merge_pairs(&mut blocks_max, f32::max);
+                block_frames *= 2;
+            }
+        }
+    }
+    if frames_in_block > 0 {
+        blocks_min.push(lo);
+        blocks_max.push(hi);
+    }
+
+    // Pass 2: resample the blocks to the requested number of buckets.
+    let buckets = buckets.clamp(1, MAX_BUCKETS);
+    let mut min = vec![0.0; buckets];
+    let mut max = vec![0.0; buckets];
+    if !blocks_min.is_empty() {
+        for b in 0..buckets {
+            let start = b * blocks_min.len() / buckets;
+            let end = ((b + 1) * blocks_min.len() / buckets).max(start + 1);
+            let end = end.min(blocks_min.len());
+            if start >= end {
+                continue;
+            }
+            min[b] = blocks_min[start..end]
+                .iter()
+                .copied()
+                .fold(f32::MAX, f32::min);
+            max[b] = blocks_max[start..end]
+                .iter()
+                .copied()
+                .fold(f32::MIN, f32::max);
+        }
+    }
+
+    Ok(Peaks {
+        duration: total_frames as f64 / sample_rate.max(1) as f64,
+        sample_rate,
+        channels,
+        min,
+        max,
+    })
+}
+
+// Halves the block list in place: each pair of blocks becomes one.
+fn merge_pairs(blocks: &mut Vec<f32>, merge: fn(f32, f32) -> f32) {
+    let merged: Vec<f32> = blocks
+        .chunks(2)
+        .map(|pair| pair.iter().copied().reduce(merge).unwrap_or(0.0))
+        .collect();
+    *blocks = merged;
+}
+
+fn render_png(peaks: &Peaks, width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let (w, h) = (width as usize, height as usize);
+    let mut pixels = vec![0u8; w * h * 4];
+    draw_peaks(&mut pixels, w, (0, 0, w, h), peaks, WAVE_COLOR);
+    encode_png(&pixels, width, height)
+}
+
+// Draws the waveform into `rect` (x, y, w, h) of an RGBA canvas `canvas_width` pixels wide.
+// Peaks are expected to have one bucket per column.
+pub fn draw_peaks(
+    pixels: &mut [u8],
+    canvas_width: usize,
+    rect: (usize, usize, usize, usize),
+    peaks: &Peaks,
+    color: [u8; 4],
+) {
+    let (x0, y0, w, h) = rect;
+    if h == 0 {
+        return;
+    }
+    let mid = (h as f32 - 1.0) / 2.0;
+
+    for x in 0..w.min(peaks.min.len()) {
+        let top = (mid - peaks.max[x].clamp(-1.0, 1.0) * mid).round() as usize;
+        let bottom = (mid - peaks.min[x].clamp(-1.0, 1.0) * mid).round() as usize;
+        for y in top.min(h - 1)..=bottom.min(h - 1) {
+            let i = ((y0 + y) * canvas_width + x0 + x) * 4;
+            pixels[i..i + 4].copy_from_slice(&color);
+        }
+    }
+}
+
+pub fn encode_png(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut out, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| format!("Failed to encode image: {}", e))?;
+        writer
+            .write_image_data(pixels)
+            .map_err(|e| format!("Failed to encode image: {}", e))?;
+    }
+    Ok(out)
+}
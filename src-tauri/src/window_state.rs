@@ -0,0 +1,159 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, Window, WindowEvent};
+
+use crate::paths;
+
+// Where the main window was last time, in physical pixels. Only the normal
+// (un-maximized) bounds are kept so un-maximizing after a restore lands where
+// the user left it.
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct Bounds {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    maximized: bool,
+    monitor: Option<String>,
+}
+
+// Tracked in memory as the window moves and written to window-state.json in
+// the app data dir when it closes or the app exits.
+#[derive(Default)]
+pub struct WindowStateStore {
+    bounds: Mutex<Option<Bounds>>,
+}
+
+fn state_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    Ok(dir.join("window-state.json"))
+}
+
+fn load(app: &AppHandle) -> Option<Bounds> {
+    let json = std::fs::read_to_string(state_path(app).ok()?).ok()?;
+    match serde_json::from_str(&json) {
+        Ok(bounds) => Some(bounds),
+        Err(e) => {
+            eprintln!("[WindowState] Ignoring unreadable window-state.json: {}", e);
+            None
+        }
+    }
+}
+
+pub fn save(app: &AppHandle) -> Result<(), String> {
+    let Some(bounds) = app
+        .state::<WindowStateStore>()
+        .bounds
+        .lock()
+        .unwrap()
+        .clone()
+    else {
+        return Ok(());
+    };
+    let path = state_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create app data folder: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&bounds)
+        .map_err(|e| format!("Failed to serialize window state: {}", e))?;
+    paths::write_atomic(path, json).map_err(|e| format!("Failed to save window state: {}", e))
+}
+
+// Called from setup, before the (initially hidden) main window is shown. A
+// monitor that's gone or has moved leaves the window at its default spot.
+pub fn restore(app: &AppHandle) {
+    let store = WindowStateStore::default();
+    let saved = load(app);
+    *store.bounds.lock().unwrap() = saved.clone();
+    app.manage(store);
+
+    let (Some(bounds), Some(window)) = (saved, app.get_webview_window("main")) else {
+        return;
+    };
+    let monitors = window.available_monitors().unwrap_or_default();
+    let target = monitors
+        .iter()
+        .find(|m| bounds.monitor.is_some() && m.name() == bounds.monitor.as_ref())
+        .filter(|m| contains(m, bounds.x, bounds.y))
+        .or_else(|| monitors.iter().find(|m| contains(m, bounds.x, bounds.y)));
+
+    let Some(monitor) = target else {
+        println!("[WindowState] Saved monitor is not connected, using the default position");
+        return;
+    };
+    let area = monitor.work_area();
+    // Zero when the window was only ever seen maximized.
+    if bounds.width > 0 && bounds.height > 0 {
+        let width = bounds.width.min(area.size.width);
+        let height = bounds.height.min(area.size.height);
+        let _ = window.set_size(PhysicalSize::new(width, height));
+    }
+    let _ = window.set_position(PhysicalPosition::new(bounds.x, bounds.y));
+    if bounds.maximized {
+        let _ = window.maximize();
+    }
+}
+
+// Whether the title bar area near (x, y) would be on this monitor, so the
+// window can still be grabbed and moved.
+fn contains(monitor: &Monitor, x: i32, y: i32) -> bool {
+    let pos = monitor.position();
+    let size = monitor.size();
+    let (grab_x, grab_y) = (x + 40, y + 10);
+    grab_x >= pos.x
+        && grab_y >= pos.y
+        && grab_x < pos.x + size.width as i32
+        && grab_y < pos.y + size.height as i32
+}
+
+// Hooked up in main.rs via on_window_event.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    if window.label() != "main" {
+        return;
+    }
+    match event {
+        WindowEvent::Moved(_) | WindowEvent::Resized(_) => track(window),
+        WindowEvent::CloseRequested { .. } => {
+            if let Err(e) = save(window.app_handle()) {
+                eprintln!("[WindowState] {}", e);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn track(window: &Window) {
+    // Minimized windows report a bogus position (-32000 on Windows).
+    if window.is_minimized().unwrap_or(false) || !window.is_visible().unwrap_or(false) {
+        return;
+    }
+    let Some(store) = window.try_state::<WindowStateStore>() else {
+        return;
+    };
+    let maximized = window.is_maximized().unwrap_or(false);
+    let monitor = window
+        .current_monitor()
+        .ok()
+        .flatten()
+        .and_then(|m| m.name().cloned());
+
+    let mut bounds = store.bounds.lock().unwrap();
+    let entry = bounds.get_or_insert_with(Bounds::default);
+    entry.maximized = maximized;
+    if maximized {
+        return;
+    }
+    if let (Ok(pos), Ok(size)) = (window.outer_position(), window.inner_size()) {
+        entry.x = pos.x;
+        entry.y = pos.y;
+        entry.width = size.width;
+        entry.height = size.height;
+        entry.monitor = monitor;
+    }
+}
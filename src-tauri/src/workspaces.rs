@@ -0,0 +1,402 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::jobs::{self, JobManager};
+use crate::library;
+use crate::paths;
+
+// The workspace that uses the original ~/Music/Noises library.
+const DEFAULT_ID: &str = "default";
+
+// A separate library: outputs, recordings and imports go under `root`, and the
+// history, presets and tags only cover that workspace.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    pub root: PathBuf,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WorkspaceList {
+    pub active: String,
+    pub workspaces: Vec<Workspace>,
+}
+
+// The parts of a workspace that aren't audio files, in
+// workspaces/<id>.json in the app data dir.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct WorkspaceData {
+    // The frontend's preset objects, stored as-is. None until it first saves,
+    // so it can tell "never saved" from "deleted them all".
+    presets: Option<Vec<Value>>,
+    // Keyed by path relative to the workspace root, so moving the folder
+    // doesn't lose them.
+    tags: BTreeMap<String, Vec<String>>,
+    // A sound's stems (stems.rs), keyed and listed the same way.
+    stems: BTreeMap<String, Vec<String>>,
+}
+
+pub struct WorkspaceStore {
+    list: Mutex<WorkspaceList>,
+    // Only the active workspace's.
+    data: Mutex<WorkspaceData>,
+}
+
+fn list_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve config directory: {}", e))?;
+    Ok(dir.join("workspaces.json"))
+}
+
+fn data_path(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve data directory: {}", e))?;
+    Ok(dir.join("workspaces").join(format!("{}.json", id)))
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize workspace: {}", e))?;
+    paths::write_atomic(path, json).map_err(|e| format!("Failed to save workspace: {}", e))
+}
+
+fn load_data(app: &AppHandle, id: &str) -> WorkspaceData {
+    data_path(app, id)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+impl WorkspaceStore {
+    pub fn load(app: &AppHandle) -> Self {
+        let saved: Option<WorkspaceList> = list_path(app)
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok());
+        let mut list = saved.unwrap_or_else(|| WorkspaceList {
+            active: DEFAULT_ID.to_string(),
+            workspaces: Vec::new(),
+        });
+        if !list.workspaces.iter().any(|w| w.id == DEFAULT_ID)
+            && let Ok(root) = paths::default_output_dir(app)
+        {
+            list.workspaces.insert(
+                0,
+                Workspace {
+                    id: DEFAULT_ID.to_string(),
+                    name: "Default".to_string(),
+                    root,
+                },
+            );
+        }
+        if !list.workspaces.iter().any(|w| w.id == list.active) {
+            list.active = DEFAULT_ID.to_string();
+        }
+
+        let data = load_data(app, &list.active);
+        WorkspaceStore {
+            list: Mutex::new(list),
+            data: Mutex::new(data),
+        }
+    }
+
+    fn save_list(&self, app: &AppHandle) -> Result<(), String> {
+        write_json(&list_path(app)?, &*self.list.lock().unwrap())
+    }
+
+    fn save_data(&self, app: &AppHandle) -> Result<(), String> {
+        let id = self.list.lock().unwrap().active.clone();
+        write_json(&data_path(app, &id)?, &*self.data.lock().unwrap())
+    }
+
+    pub fn active(&self) -> Option<Workspace> {
+        let list = self.list.lock().unwrap();
+        list.workspaces
+            .iter()
+            .find(|w| w.id == list.active)
+            .cloned()
+    }
+}
+
+// What paths::output_dir resolves to. None before the store is managed.
+pub fn active_root(app: &AppHandle) -> Option<PathBuf> {
+    app.try_state::<WorkspaceStore>()?.active().map(|w| w.root)
+}
+
+// Tells the backend where to save, since it only knows the default library.
+pub fn with_output_dir(app: &AppHandle, mut config: Value) -> Value {
+    if let (Some(fields), Ok(root)) = (config.as_object_mut(), paths::output_dir(app)) {
+        fields.insert(
+            "output_dir".to_string(),
+            Value::String(root.to_string_lossy().into_owned()),
+        );
+    }
+    config
+}
+
+fn tag_key(app: &AppHandle, path: &Path) -> String {
+    let relative =
+        active_root(app).and_then(|root| path.strip_prefix(root).ok().map(Path::to_path_buf));
+    relative
+        .as_deref()
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+// Tags for every sound in the active workspace, keyed by absolute path.
+pub fn tags(app: &AppHandle) -> BTreeMap<PathBuf, Vec<String>> {
+    let Some(store) = app.try_state::<WorkspaceStore>() else {
+        return BTreeMap::new();
+    };
+    let root = active_root(app).unwrap_or_default();
+    store
+        .data
+        .lock()
+        .unwrap()
+        .tags
+        .iter()
+        .map(|(key, tags)| (root.join(key), tags.clone()))
+        .collect()
+}
+
+// Every sound's stems in the active workspace, keyed by the sound's absolute
+// path.
+pub fn stems(app: &AppHandle) -> BTreeMap<PathBuf, Vec<PathBuf>> {
+    let Some(store) = app.try_state::<WorkspaceStore>() else {
+        return BTreeMap::new();
+    };
+    let root = active_root(app).unwrap_or_default();
+    store
+        .data
+        .lock()
+        .unwrap()
+        .stems
+        .iter()
+        .map(|(key, stems)| (root.join(key), stems.iter().map(|s| root.join(s)).collect()))
+        .collect()
+}
+
+// Links the stems to the sound they were split from.
+pub fn set_stems(app: &AppHandle, path: &Path, stems: &[PathBuf]) -> Result<(), String> {
+    let store = app.state::<WorkspaceStore>();
+    let key = tag_key(app, path);
+    let stems = stems.iter().map(|stem| tag_key(app, stem)).collect();
+    store.data.lock().unwrap().stems.insert(key, stems);
+    store.save_data(app)
+}
+
+// Moves a renamed sound's tags and stem links over to its new path.
+pub fn move_sound(app: &AppHandle, from: &Path, to: &Path) -> Result<(), String> {
+    let store = app.state::<WorkspaceStore>();
+    let (from, to) = (tag_key(app, from), tag_key(app, to));
+    {
+        let mut data = store.data.lock().unwrap();
+        if let Some(tags) = data.tags.remove(&from) {
+            data.tags.insert(to.clone(), tags);
+        }
+        if let Some(stems) = data.stems.remove(&from) {
+            data.stems.insert(to.clone(), stems);
+        }
+        for stem in data.stems.values_mut().flatten() {
+            if *stem == from {
+                *stem = to.clone();
+            }
+        }
+    }
+    store.save_data(app)
+}
+
+// Lowercase, letters, digits and dashes; falls back to "workspace".
+fn slug(name: &str) -> String {
+    let slug = name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "workspace".to_string()
+    } else {
+        slug
+    }
+}
+
+pub fn presets(app: &AppHandle) -> Vec<Value> {
+    let store = app.state::<WorkspaceStore>();
+    let presets = store.data.lock().unwrap().presets.clone();
+    presets.unwrap_or_default()
+}
+
+pub fn set_presets(app: &AppHandle, presets: Vec<Value>) -> Result<(), String> {
+    let store = app.state::<WorkspaceStore>();
+    store.data.lock().unwrap().presets = Some(presets);
+    store.save_data(app)
+}
+
+#[tauri::command]
+pub fn list_workspaces(store: State<'_, WorkspaceStore>) -> WorkspaceList {
+    store.list.lock().unwrap().clone()
+}
+
+// New workspaces get a folder next to the default library unless a root is
+// given. An existing folder is fine; its sounds show up in the library.
+#[tauri::command]
+pub fn create_workspace(
+    app: AppHandle,
+    name: String,
+    root: Option<String>,
+    store: State<'_, WorkspaceStore>,
+) -> Result<Workspace, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Workspace name can't be empty".to_string());
+    }
+
+    let workspace = {
+        let mut list = store.list.lock().unwrap();
+        let base = slug(&name);
+        let mut id = base.clone();
+        let mut n = 1;
+        while list.workspaces.iter().any(|w| w.id == id) {
+            n += 1;
+            id = format!("{}-{}", base, n);
+        }
+        let root = match root.filter(|r| !r.trim().is_empty()) {
+            Some(root) => PathBuf::from(root),
+            None => {
+                let default = paths::default_output_dir(&app)?;
+                let parent = default.parent().unwrap_or(&default);
+                parent.join("Noises Workspaces").join(&id)
+            }
+        };
+        if list.workspaces.iter().any(|w| w.root == root) {
+            return Err("Another workspace already uses that folder".to_string());
+        }
+        std::fs::create_dir_all(&root)
+            .map_err(|e| format!("Failed to create workspace folder: {}", e))?;
+
+        let workspace = Workspace { id, name, root };
+        list.workspaces.push(workspace.clone());
+        workspace
+    };
+    store.save_list(&app)?;
+    println!(
+        "[Workspaces] Created {} at {}",
+        workspace.id,
+        workspace.root.display()
+    );
+    Ok(workspace)
+}
+
+// Refused while generating: a job that's already running would otherwise
+// finish into the new workspace's history.
+#[tauri::command]
+pub fn switch_workspace(
+    app: AppHandle,
+    id: String,
+    store: State<'_, WorkspaceStore>,
+    jobs: State<'_, JobManager>,
+) -> Result<Workspace, String> {
+    if !jobs.status().active.is_empty() {
+        return Err("Wait for the current generations to finish first".to_string());
+    }
+    let workspace = {
+        let mut list = store.list.lock().unwrap();
+        let workspace = list
+            .workspaces
+            .iter()
+            .find(|w| w.id == id)
+            .cloned()
+            .ok_or_else(|| format!("Unknown workspace: {}", id))?;
+        if list.active == id {
+            return Ok(workspace);
+        }
+        list.active = id.clone();
+        workspace
+    };
+    store.save_list(&app)?;
+    *store.data.lock().unwrap() = load_data(&app, &id);
+    println!("[Workspaces] Switched to {}", id);
+
+    jobs::reload_recent(&app);
+    library::watch(&app);
+    let _ = app.emit("workspace-changed", &workspace);
+    Ok(workspace)
+}
+
+#[tauri::command]
+pub fn get_workspace_presets(store: State<'_, WorkspaceStore>) -> Option<Vec<Value>> {
+    store.data.lock().unwrap().presets.clone()
+}
+
+#[tauri::command]
+pub fn save_workspace_presets(app: AppHandle, presets: Vec<Value>) -> Result<(), String> {
+    set_presets(&app, presets)
+}
+
+// Replaces the sound's tags; an empty list clears them.
+#[tauri::command]
+pub fn set_sound_tags(
+    app: AppHandle,
+    path: String,
+    tags: Vec<String>,
+    store: State<'_, WorkspaceStore>,
+) -> Result<(), String> {
+    let tags = clean_tags(&tags);
+    let key = tag_key(&app, &paths::normalize(&path));
+    {
+        let mut data = store.data.lock().unwrap();
+        if tags.is_empty() {
+            data.tags.remove(&key);
+        } else {
+            data.tags.insert(key, tags);
+        }
+    }
+    store.save_data(&app)
+}
+
+// Adds to the sound's tags, keeping the ones it already has. Used when an
+// import brings its own (a sound pack).
+pub fn add_tags(app: &AppHandle, path: &Path, tags: &[String]) -> Result<(), String> {
+    let store = app.state::<WorkspaceStore>();
+    let key = tag_key(app, path);
+    {
+        let mut data = store.data.lock().unwrap();
+        let mut merged = data.tags.get(&key).cloned().unwrap_or_default();
+        merged.extend_from_slice(tags);
+        let merged = clean_tags(&merged);
+        if merged.is_empty() {
+            return Ok(());
+        }
+        data.tags.insert(key, merged);
+    }
+    store.save_data(app)
+}
+
+// Trimmed, sorted, no blanks or repeats.
+fn clean_tags(tags: &[String]) -> Vec<String> {
+    let mut tags: Vec<String> = tags
+        .iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::backend;
+
+#[derive(Clone, Serialize)]
+struct WsMessage {
+    id: String,
+    channel: String,
+    // Binary frames are base64 encoded so they survive the JSON event payload.
+    data: String,
+    binary: bool,
+}
+
+#[derive(Clone, Serialize)]
+struct WsClosed {
+    id: String,
+    channel: String,
+    reason: Option<String>,
+}
+
+// Generic relay between the frontend and the backend's `/ws/<channel>` sockets.
+// Incoming frames become `ws-message` events and `ws_send` writes outgoing ones,
+// so realtime endpoints don't need their own Rust plumbing. The sidecar serves
+// `progress`, the running generation's events as JSON (backend/main.py).
+#[derive(Default)]
+pub struct WsBridge {
+    connections: Mutex<HashMap<String, UnboundedSender<Message>>>,
+    next_id: AtomicU64,
+}
+
+fn channel_url(channel: &str) -> Result<String, String> {
+    let channel = channel.trim_matches('/');
+    if channel.is_empty() || channel.contains("..") || channel.contains("://") {
+        return Err(format!("Invalid channel: {}", channel));
+    }
+    Ok(format!(
+        "{}/ws/{}",
+        backend::url().replacen("http", "ws", 1),
+        channel
+    ))
+}
+
+#[tauri::command]
+pub async fn ws_connect(
+    app: AppHandle,
+    bridge: State<'_, WsBridge>,
+    channel: String,
+) -> Result<String, String> {
+    let url = channel_url(&channel)?;
+    let (socket, _) = tokio_tungstenite::connect_async(url.as_str())
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", url, e))?;
+
+    let id = format!("ws-{}", bridge.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+    bridge.connections.lock().unwrap().insert(id.clone(), tx);
+    println!("[WsBridge] {} connected to {}", id, url);
+
+    let task_id = id.clone();
+    tauri::async_runtime::spawn(async move {
+        let (mut sink, mut stream) = socket.split();
+        let reason = loop {
+            tokio::select! {
+                outgoing = rx.recv() => match outgoing {
+                    Some(message) => {
+                        if let Err(e) = sink.send(message).await {
+                            break Some(format!("Failed to send: {}", e));
+                        }
+                    }
+                    // ws_close dropped the sender.
+                    None => {
+                        let _ = sink.send(Message::Close(None)).await;
+                        break None;
+                    }
+                },
+                incoming = stream.next() => match incoming {
+                    Some(Ok(Message::Text(text))) => {
+                        let _ = app.emit("ws-message", WsMessage {
+                            id: task_id.clone(),
+                            channel: channel.clone(),
+                            data: text.to_string(),
+                            binary: false,
+                        });
+                    }
+                    Some(Ok(Message::Binary(bytes))) => {
+                        let _ = app.emit("ws-message", WsMessage {
+                            id: task_id.clone(),
+                            channel: channel.clone(),
+                            data: BASE64.encode(&bytes),
+                            binary: true,
+                        });
+                    }
+                    Some(Ok(Message::Close(frame))) => {
+                        break frame.map(|f| f.reason.to_string()).filter(|r| !r.is_empty());
+                    }
+                    // Pings are answered by tungstenite itself.
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => break Some(e.to_string()),
+                    None => break None,
+                },
+            }
+        };
+
+        app.state::<WsBridge>()
+            .connections
+            .lock()
+            .unwrap()
+            .remove(&task_id);
+        println!("[WsBridge] {} closed", task_id);
+        let _ = app.emit(
+            "ws-closed",
+            WsClosed {
+                id: task_id,
+                channel,
+                reason,
+            },
+        );
+    });
+
+    Ok(id)
+}
+
+// `binary` means `data` is base64 and is sent as a binary frame.
+#[tauri::command]
+pub fn ws_send(
+    bridge: State<'_, WsBridge>,
+    id: String,
+    data: String,
+    binary: Option<bool>,
+) -> Result<(), String> {
+    let message = if binary.unwrap_or(false) {
+        let bytes = BASE64
+            .decode(data)
+            .map_err(|e| format!("Failed to decode binary message: {}", e))?;
+        Message::Binary(bytes.into())
+    } else {
+        Message::Text(data.into())
+    };
+
+    let connections = bridge.connections.lock().unwrap();
+    let tx = connections
+        .get(&id)
+        .ok_or_else(|| format!("No open connection {}", id))?;
+    tx.send(message)
+        .map_err(|_| format!("Connection {} is closing", id))
+}
+
+#[tauri::command]
+pub fn ws_close(bridge: State<'_, WsBridge>, id: String) -> Result<(), String> {
+    // Dropping the sender makes the relay task send a close frame and exit.
+    bridge
+        .connections
+        .lock()
+        .unwrap()
+        .remove(&id)
+        .map(|_| ())
+        .ok_or_else(|| format!("No open connection {}", id))
+}